@@ -0,0 +1,43 @@
+#[macro_use]
+extern crate criterion;
+extern crate noise;
+
+use criterion::{black_box, Criterion};
+use noise::{LatticeOrientation, NoiseFn, OpenSimplex2F, OpenSimplex2S};
+
+criterion_group!(
+    open_simplex2,
+    bench_open_simplex2f2,
+    bench_open_simplex2f3,
+    bench_open_simplex2f4,
+    bench_open_simplex2s3_improve_xy
+);
+criterion_main!(open_simplex2);
+
+fn bench_open_simplex2f2(c: &mut Criterion) {
+    let open_simplex2f = OpenSimplex2F::new();
+    c.bench_function("open simplex 2f 2d", |b| {
+        b.iter(|| open_simplex2f.get(black_box([42.0_f64, 37.0])))
+    });
+}
+
+fn bench_open_simplex2f3(c: &mut Criterion) {
+    let open_simplex2f = OpenSimplex2F::new();
+    c.bench_function("open simplex 2f 3d", |b| {
+        b.iter(|| open_simplex2f.get(black_box([42.0_f64, 37.0, 26.0])))
+    });
+}
+
+fn bench_open_simplex2f4(c: &mut Criterion) {
+    let open_simplex2f = OpenSimplex2F::new();
+    c.bench_function("open simplex 2f 4d", |b| {
+        b.iter(|| open_simplex2f.get(black_box([42.0_f64, 37.0, 26.0, 128.0])))
+    });
+}
+
+fn bench_open_simplex2s3_improve_xy(c: &mut Criterion) {
+    let open_simplex2s = OpenSimplex2S::new().set_orientation(LatticeOrientation::ImproveXY);
+    c.bench_function("open simplex 2s 3d (improve xy)", |b| {
+        b.iter(|| open_simplex2s.get(black_box([42.0_f64, 37.0, 26.0])))
+    });
+}
@@ -61,7 +61,7 @@ use noise::{utils::*, *};
 #[allow(non_snake_case)]
 fn main() {
     /// Planet seed. Change this to generate a different planet.
-    const CURRENT_SEED: u32 = 0;
+    const CURRENT_SEED: u64 = 0;
 
     /// Frequency of the planet's continents. Higher frequency produces
     /// smaller, more numerous continents. This value is measured in radians.
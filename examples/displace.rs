@@ -7,7 +7,7 @@ fn main() {
     let constant = Constant::new(0.0);
     let cylinders = Cylinders::new();
     let perlin = Perlin::default();
-    let displace = Displace::new(cylinders, cboard, perlin, constant, constant);
+    let displace = Displace::new(cylinders, (cboard, perlin, constant));
 
     PlaneMapBuilder::new(&displace)
         .build()
@@ -0,0 +1,13 @@
+//! An example of using the `ErodedFbm` noise function
+
+extern crate noise;
+
+use noise::{utils::*, ErodedFbm};
+
+fn main() {
+    let eroded_fbm = ErodedFbm::new();
+
+    PlaneMapBuilder::new(&eroded_fbm)
+        .build()
+        .write_to_file("eroded_fbm.png");
+}
@@ -2,7 +2,7 @@
 
 extern crate noise;
 
-use noise::{utils::*, Fbm};
+use noise::{utils::*, Fbm, MultiFractal, OctaveRotation};
 
 fn main() {
     let fbm = Fbm::new();
@@ -13,4 +13,15 @@ fn main() {
         .set_y_bounds(-5.0, 5.0)
         .build()
         .write_to_file("fbm.png");
+
+    let rotated = Fbm::new()
+        .set_lacunarity(1.5)
+        .set_rotation(OctaveRotation::Seeded);
+
+    PlaneMapBuilder::new(&rotated)
+        .set_size(1000, 1000)
+        .set_x_bounds(-5.0, 5.0)
+        .set_y_bounds(-5.0, 5.0)
+        .build()
+        .write_to_file("fbm_rotated.png");
 }
@@ -0,0 +1,86 @@
+//! An example of using OpenSimplex2S and OpenSimplex2F noise
+
+extern crate noise;
+
+use noise::{utils::*, LatticeOrientation, OpenSimplex2F, OpenSimplex2S, Seedable};
+
+fn main() {
+    // OpenSimplex2S uses the same lattice lookups as Super Simplex, which the super_simplex example
+    // generates. OpenSimplex2F visits the same 4D lattice points, but its radius of influence is
+    // only sqrt(3/5), so fewer of them can be reached from each half of the simplex cube. A
+    // lattice point is listed for such a region when its distance to the region in real space is
+    // less than the radius, which projected gradient descent finds as in the super_simplex example.
+    let to_real = |point: [f64; 4]| {
+        let offset = point.iter().sum::<f64>() * (1.0 / 5f64.sqrt() - 1.0) / 4.0;
+        point.map(|v| v + offset)
+    };
+    let mut lookup_4d: Vec<[i8; 4]> = Vec::new();
+    let mut regions_4d = vec![0];
+    for region in 0..16 {
+        let min = [0, 1, 2, 3].map(|axis| 0.5 * (region >> axis & 1) as f64);
+        for i in 0..256 {
+            let lattice_point = [0, 1, 2, 3].map(|axis| (i >> (axis * 2) & 3) as i8 - 1);
+            let delta = |point: [f64; 4]| {
+                to_real([0, 1, 2, 3].map(|axis| point[axis] - lattice_point[axis] as f64))
+            };
+
+            let mut point = min.map(|v| v + 0.25);
+            for _ in 0..1000 {
+                let gradient = to_real(delta(point));
+                point = [0, 1, 2, 3].map(|axis| {
+                    (point[axis] - 0.5 * gradient[axis]).clamp(min[axis], min[axis] + 0.5)
+                });
+            }
+
+            // Lattice points that only touch the region never contribute to it.
+            if delta(point).iter().map(|v| v * v).sum::<f64>() < 0.6 - 1e-9 {
+                lookup_4d.push(lattice_point);
+            }
+        }
+        regions_4d.push(lookup_4d.len());
+    }
+
+    print!("lookup_4d = [");
+    for x in lookup_4d.iter() {
+        print!("[{}, {}, {}, {}],", x[0], x[1], x[2], x[3]);
+    }
+    println!("\x08]");
+    println!("regions_4d = {:?}", regions_4d);
+
+    // The maxima of OpenSimplex2F are found the same way as the 4D maximum of Super Simplex: every
+    // point within reach of a location in a cell is given whichever gradient points most directly
+    // towards that location, and the sum of their contributions is maximized numerically over the
+    // cell. In 2D the cell is a triangle of the simplex grid, with a radius of influence of
+    // sqrt(1/2); in 3D it is a cube of either of the two cubic lattices, with the same radius; and
+    // in 4D it is the simplex cube, with a radius of sqrt(3/5), where the maximum lies at its
+    // center, [1/2,1/2,1/2,1/2]. In Mathematica, for 4D:
+    // Clear["Global`*"];
+    // skew4d = (1/Sqrt[4 + 1] - 1)/4;
+    // toReal[p_] := p + Total[p]*skew4d;
+    // grads = Normalize /@ Select[Tuples[{-1, 0, 1}, 4], Count[#, 0] <= 1 &];
+    // eq4d[p_, l_] := With[{d = toReal[p - l]},
+    //    If[d.d < 3/5, (3/5 - d.d)^4*Max[grads.d], 0]];
+    // eq4dsp[p_] := Total[eq4d[p, #] & /@ Tuples[{-1, 0, 1, 2}, 4]];
+    // NMaximize[{eq4dsp[{x, y, z, w}], 0 <= x <= 1 && 0 <= y <= 1 && 0 <= z <= 1 && 0 <= w <= 1}, {x, y, z, w}]
+
+    let open_simplex2s = OpenSimplex2S::new();
+
+    PlaneMapBuilder::new(&open_simplex2s)
+        .build()
+        .write_to_file("open_simplex2s.png");
+
+    let open_simplex2f = OpenSimplex2F::new();
+
+    PlaneMapBuilder::new(&open_simplex2f)
+        .build()
+        .write_to_file("open_simplex2f.png");
+
+    // With the XY plane improved, horizontal slices of 3D noise look like good 2D noise.
+    let open_simplex2s = open_simplex2s
+        .set_orientation(LatticeOrientation::ImproveXY)
+        .set_seed(1);
+
+    PlaneMapBuilder::new(&open_simplex2s)
+        .build()
+        .write_to_file("open_simplex2s_improve_xy.png");
+}
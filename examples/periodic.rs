@@ -0,0 +1,30 @@
+//! An example of generating textures that tile exactly with periodic noise
+
+extern crate noise;
+
+use noise::{utils::*, Fbm, MultiFractal, Periodic, Perlin};
+
+fn main() {
+    // Every octave repeats every 4 lattice cells. With a lacunarity of 2, the
+    // finer octaves repeat a whole number of times within that, so the fractal
+    // repeats every 4 units.
+    let fbm = Fbm::<Perlin>::new().set_lacunarity(2.0).set_period([4, 4]);
+
+    PlaneMapBuilder::new(&fbm)
+        .set_size(256, 256)
+        .set_x_bounds(0.0, 4.0)
+        .set_y_bounds(0.0, 4.0)
+        .build()
+        .write_to_file("periodic.png");
+
+    // The seamless blend also tiles, but loses contrast in the middle.
+    let fbm = Fbm::<Perlin>::new().set_lacunarity(2.0);
+
+    PlaneMapBuilder::new(&fbm)
+        .set_size(256, 256)
+        .set_x_bounds(0.0, 4.0)
+        .set_y_bounds(0.0, 4.0)
+        .set_is_seamless(true)
+        .build()
+        .write_to_file("periodic_blended.png");
+}
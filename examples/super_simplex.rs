@@ -116,6 +116,47 @@ fn main() {
     }
     println!("\x08]");
 
+    // In 4D, the points that can be reached depend on the half of the simplex cube the point is
+    // in along each axis. A lattice point is listed for such a region when its distance to the
+    // region in real space is less than the radius of influence, sqrt(4/5). The distance is a
+    // convex function of the point in the region, so projected gradient descent finds it.
+    let to_real = |point: [f64; 4]| {
+        let offset = point.iter().sum::<f64>() * (1.0 / 5f64.sqrt() - 1.0) / 4.0;
+        point.map(|v| v + offset)
+    };
+    let mut lookup_4d: Vec<[i8; 4]> = Vec::new();
+    let mut regions_4d = vec![0];
+    for region in 0..16 {
+        let min = [0, 1, 2, 3].map(|axis| 0.5 * (region >> axis & 1) as f64);
+        for i in 0..256 {
+            let lattice_point = [0, 1, 2, 3].map(|axis| (i >> (axis * 2) & 3) as i8 - 1);
+            let delta = |point: [f64; 4]| {
+                to_real([0, 1, 2, 3].map(|axis| point[axis] - lattice_point[axis] as f64))
+            };
+
+            let mut point = min.map(|v| v + 0.25);
+            for _ in 0..1000 {
+                let gradient = to_real(delta(point));
+                point = [0, 1, 2, 3].map(|axis| {
+                    (point[axis] - 0.5 * gradient[axis]).clamp(min[axis], min[axis] + 0.5)
+                });
+            }
+
+            // Lattice points that only touch the region never contribute to it.
+            if delta(point).iter().map(|v| v * v).sum::<f64>() < 0.8 - 1e-9 {
+                lookup_4d.push(lattice_point);
+            }
+        }
+        regions_4d.push(lookup_4d.len());
+    }
+
+    print!("lookup_4d = [");
+    for x in lookup_4d.iter() {
+        print!("[{}, {}, {}, {}],", x[0], x[1], x[2], x[3]);
+    }
+    println!("\x08]");
+    println!("regions_4d = {:?}", regions_4d);
+
     // Calculation of maximum value:
     // x => real_rel_coords[0], y => real_rel_coords[1]
     // a-h, components of gradient vectors for 4 closest points
@@ -198,6 +239,20 @@ fn main() {
     // {xout, yout, zout} = P0;
     // eq3dsp[xout, yout, zout]
 
+    // In 4D, there are too many points and gradients for the maximum to be found by hand like
+    // this. Instead, every point within reach of a location in the simplex cube is given whichever
+    // gradient points most directly towards that location, and the sum of their contributions is
+    // maximized numerically over the cube. This reproduces the 2D maximum exactly, and in 4D the
+    // maximum lies at the center of the simplex cube, [1/2,1/2,1/2,1/2]. In Mathematica:
+    // Clear["Global`*"];
+    // skew4d = (1/Sqrt[4 + 1] - 1)/4;
+    // toReal[p_] := p + Total[p]*skew4d;
+    // grads = Normalize /@ Select[Tuples[{-1, 0, 1}, 4], Count[#, 0] <= 1 &];
+    // eq4d[p_, l_] := With[{d = toReal[p - l]},
+    //    If[d.d < 4/5, (4/5 - d.d)^4*Max[grads.d], 0]];
+    // eq4dsp[p_] := Total[eq4d[p, #] & /@ Tuples[{-1, 0, 1, 2}, 4]];
+    // NMaximize[{eq4dsp[{x, y, z, w}], 0 <= x <= 1 && 0 <= y <= 1 && 0 <= z <= 1 && 0 <= w <= 1}, {x, y, z, w}]
+
     let super_simplex = SuperSimplex::new();
 
     PlaneMapBuilder::new(&super_simplex)
@@ -1,6 +1,6 @@
 extern crate noise;
 
-use noise::{distance_functions::*, utils::*, ReturnType, Worley};
+use noise::{utils::*, DistanceFunction, ReturnType, SeedPoints, Worley};
 
 fn main() {
     PlaneMapBuilder::new(&Worley::default())
@@ -11,19 +11,21 @@ fn main() {
         .build()
         .write_to_file("worley_distance.png");
 
-    PlaneMapBuilder::new(&Worley::default().set_distance_function(euclidean_squared))
-        .build()
-        .write_to_file("worley_squared.png");
+    PlaneMapBuilder::new(
+        &Worley::default().set_distance_function(DistanceFunction::EuclideanSquared),
+    )
+    .build()
+    .write_to_file("worley_squared.png");
 
     PlaneMapBuilder::new(
         &Worley::default()
             .set_return_type(ReturnType::Distance)
-            .set_distance_function(euclidean_squared),
+            .set_distance_function(DistanceFunction::EuclideanSquared),
     )
     .build()
     .write_to_file("worley_squared_distance.png");
 
-    PlaneMapBuilder::new(&Worley::default().set_distance_function(manhattan))
+    PlaneMapBuilder::new(&Worley::default().set_distance_function(DistanceFunction::Manhattan))
         .build()
         .write_to_file("worley_manhattan.png");
 
@@ -31,15 +33,43 @@ fn main() {
         .build()
         .write_to_file("worley_manhattan_distance.png");
 
-    PlaneMapBuilder::new(&Worley::default().set_distance_function(chebyshev))
+    PlaneMapBuilder::new(&Worley::default().set_distance_function(DistanceFunction::Chebyshev))
         .build()
         .write_to_file("worley_chebyshev.png");
 
     PlaneMapBuilder::new(
         &Worley::default()
             .set_return_type(ReturnType::Distance)
-            .set_distance_function(chebyshev),
+            .set_distance_function(DistanceFunction::Chebyshev),
     )
     .build()
     .write_to_file("worley_chebyshev_distance.png");
+
+    PlaneMapBuilder::new(
+        &Worley::default()
+            .set_return_type(ReturnType::Distance)
+            .set_distance_function(DistanceFunction::Minkowski(3.0)),
+    )
+    .build()
+    .write_to_file("worley_minkowski_distance.png");
+
+    PlaneMapBuilder::new(&Worley::default().set_return_type(ReturnType::Distance2))
+        .build()
+        .write_to_file("worley_distance2.png");
+
+    PlaneMapBuilder::new(&Worley::default().set_return_type(ReturnType::Distance2Sub))
+        .build()
+        .write_to_file("worley_distance2_sub.png");
+
+    PlaneMapBuilder::new(&Worley::default().set_return_type(ReturnType::Distance2Mul))
+        .build()
+        .write_to_file("worley_distance2_mul.png");
+
+    PlaneMapBuilder::new(&Worley::default().set_jitter(0.3))
+        .build()
+        .write_to_file("worley_jitter.png");
+
+    PlaneMapBuilder::new(&Worley::default().set_seed_points(SeedPoints::Poisson(3.0)))
+        .build()
+        .write_to_file("worley_poisson.png");
 }
@@ -0,0 +1,591 @@
+//! Noise graphs that are put together at runtime, for editors and other tools
+//! that don't know the shape of the graph at compile time.
+//!
+//! A [`Graph`] holds nodes, each of which wraps one of the noise functions of
+//! this crate. Nodes are connected to the inputs of other nodes, their
+//! parameters can be listed and changed, and [`Graph::build`] turns any node
+//! into a [`DynNoise`] that can be evaluated like any other noise function.
+//!
+//! ```
+//! use noise::{
+//!     graph::{Graph, NodeKind, ParamValue},
+//!     NoiseFn,
+//! };
+//!
+//! let mut graph = Graph::new();
+//! let fbm = graph.add_node(NodeKind::Fbm);
+//! let clamp = graph.add_node(NodeKind::Clamp);
+//! graph.connect(fbm, clamp, 0).unwrap();
+//! graph
+//!     .set_param(clamp, "upper_bound", ParamValue::Float(0.5))
+//!     .unwrap();
+//!
+//! let noise = graph.build::<3>(clamp).unwrap();
+//! assert!(noise.get([1.0, 2.0, 3.0]) <= 0.5);
+//! ```
+
+use crate::noise_fns::NoiseFn;
+use std::{error, fmt, sync::Arc};
+
+pub use self::node::*;
+
+use self::module::Module;
+
+mod module;
+mod node;
+
+/// Identifies a node within a [`Graph`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+#[derive(Clone, Debug)]
+struct Node {
+    kind: NodeKind,
+    params: Vec<ParamValue>,
+    inputs: Vec<Option<NodeId>>,
+}
+
+/// A noise graph that can be changed at runtime.
+#[derive(Clone, Debug, Default)]
+pub struct Graph {
+    nodes: Vec<Option<Node>>,
+}
+
+impl Graph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a node with default parameters and no connected inputs.
+    pub fn add_node(&mut self, kind: NodeKind) -> NodeId {
+        self.nodes.push(Some(Node {
+            kind,
+            params: kind.params().iter().map(ParamInfo::default_value).collect(),
+            inputs: vec![None; kind.inputs().len()],
+        }));
+
+        NodeId(self.nodes.len() - 1)
+    }
+
+    /// Removes a node, disconnecting it from every input it was connected to.
+    pub fn remove_node(&mut self, id: NodeId) -> Result<NodeKind, GraphError> {
+        let kind = self.node(id)?.kind;
+        self.nodes[id.0] = None;
+
+        for node in self.nodes.iter_mut().flatten() {
+            for input in &mut node.inputs {
+                if *input == Some(id) {
+                    *input = None;
+                }
+            }
+        }
+
+        Ok(kind)
+    }
+
+    /// Returns the ids of the nodes in the graph, in the order they were added.
+    pub fn nodes(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.is_some())
+            .map(|(index, _)| NodeId(index))
+    }
+
+    pub fn node_kind(&self, id: NodeId) -> Result<NodeKind, GraphError> {
+        Ok(self.node(id)?.kind)
+    }
+
+    /// Connects the output of `from` to the input of `to` at index `input`,
+    /// replacing whatever was connected there. See [`NodeKind::inputs`].
+    ///
+    /// Fails without changing the graph if the connection would create a
+    /// cycle.
+    pub fn connect(&mut self, from: NodeId, to: NodeId, input: usize) -> Result<(), GraphError> {
+        self.node(from)?;
+        self.check_input(to, input)?;
+
+        if self.depends_on(from, to) {
+            return Err(GraphError::Cycle(to));
+        }
+
+        self.node_mut(to)?.inputs[input] = Some(from);
+
+        Ok(())
+    }
+
+    /// Disconnects the input of `to` at index `input`, returning the node
+    /// that was connected to it.
+    pub fn disconnect(&mut self, to: NodeId, input: usize) -> Result<Option<NodeId>, GraphError> {
+        self.check_input(to, input)?;
+
+        Ok(self.node_mut(to)?.inputs[input].take())
+    }
+
+    /// Returns the node connected to the input of `id` at index `input`.
+    pub fn input(&self, id: NodeId, input: usize) -> Result<Option<NodeId>, GraphError> {
+        self.check_input(id, input)?;
+
+        Ok(self.node(id)?.inputs[input])
+    }
+
+    pub fn param(&self, id: NodeId, name: &str) -> Result<&ParamValue, GraphError> {
+        let node = self.node(id)?;
+        let index = param_index(id, node.kind, name)?;
+
+        Ok(&node.params[index])
+    }
+
+    /// Changes a parameter of a node, if `value` has the parameter's type and
+    /// lies within its range. See [`NodeKind::params`].
+    pub fn set_param(
+        &mut self,
+        id: NodeId,
+        name: &str,
+        value: ParamValue,
+    ) -> Result<(), GraphError> {
+        let node = self.node_mut(id)?;
+        let index = param_index(id, node.kind, name)?;
+        let info = &node.kind.params()[index];
+
+        info.check(&value)
+            .map_err(|reason| GraphError::InvalidParam {
+                node: id,
+                name: info.name,
+                reason,
+            })?;
+        node.params[index] = value;
+
+        Ok(())
+    }
+
+    /// Builds the noise function of `output` and everything it depends on,
+    /// for evaluating at `DIM` dimensional points.
+    ///
+    /// The graph is copied into the result, so later changes to the graph
+    /// don't affect it. Nodes used by several others are only built once.
+    pub fn build<const DIM: usize>(&self, output: NodeId) -> Result<DynNoise<DIM>, GraphError> {
+        let mut built = vec![None; self.nodes.len()];
+        let mut visiting = vec![false; self.nodes.len()];
+        let root = self.build_node(output, DIM, &mut built, &mut visiting)?;
+
+        Ok(DynNoise { root })
+    }
+
+    fn build_node(
+        &self,
+        id: NodeId,
+        dimension: usize,
+        built: &mut Vec<Option<Arc<Module>>>,
+        visiting: &mut Vec<bool>,
+    ) -> Result<Arc<Module>, GraphError> {
+        let node = self.node(id)?;
+        if let Some(module) = &built[id.0] {
+            return Ok(Arc::clone(module));
+        }
+        if visiting[id.0] {
+            return Err(GraphError::Cycle(id));
+        }
+        if !node.kind.supports_dimension(dimension) {
+            return Err(GraphError::UnsupportedDimension {
+                node: id,
+                dimension,
+            });
+        }
+
+        visiting[id.0] = true;
+        let required = node.kind.required_inputs(dimension);
+        let inputs = node
+            .inputs
+            .iter()
+            .zip(node.kind.inputs())
+            .enumerate()
+            .map(|(index, (input, &name))| match *input {
+                _ if index >= required => Ok(None),
+                Some(input) => self.build_node(input, dimension, built, visiting).map(Some),
+                None => Err(GraphError::MissingInput {
+                    node: id,
+                    input: name,
+                }),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        visiting[id.0] = false;
+
+        let module = Arc::new(Module::build(id, node.kind, &node.params, inputs)?);
+        built[id.0] = Some(Arc::clone(&module));
+
+        Ok(module)
+    }
+
+    /// Returns whether `id` is `dependency` or uses it, directly or through
+    /// other nodes.
+    fn depends_on(&self, id: NodeId, dependency: NodeId) -> bool {
+        let mut visited = vec![false; self.nodes.len()];
+        let mut stack = vec![id];
+
+        while let Some(id) = stack.pop() {
+            if id == dependency {
+                return true;
+            }
+            if visited[id.0] {
+                continue;
+            }
+            visited[id.0] = true;
+
+            if let Some(node) = &self.nodes[id.0] {
+                stack.extend(node.inputs.iter().flatten());
+            }
+        }
+
+        false
+    }
+
+    fn node(&self, id: NodeId) -> Result<&Node, GraphError> {
+        self.nodes
+            .get(id.0)
+            .and_then(Option::as_ref)
+            .ok_or(GraphError::UnknownNode(id))
+    }
+
+    fn node_mut(&mut self, id: NodeId) -> Result<&mut Node, GraphError> {
+        self.nodes
+            .get_mut(id.0)
+            .and_then(Option::as_mut)
+            .ok_or(GraphError::UnknownNode(id))
+    }
+
+    fn check_input(&self, id: NodeId, input: usize) -> Result<(), GraphError> {
+        if input < self.node(id)?.inputs.len() {
+            Ok(())
+        } else {
+            Err(GraphError::UnknownInput { node: id, input })
+        }
+    }
+}
+
+fn param_index(id: NodeId, kind: NodeKind, name: &str) -> Result<usize, GraphError> {
+    kind.params()
+        .iter()
+        .position(|param| param.name == name)
+        .ok_or_else(|| GraphError::UnknownParam {
+            node: id,
+            name: name.to_string(),
+        })
+}
+
+/// An error from changing or building a [`Graph`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum GraphError {
+    /// The node doesn't exist, or has been removed.
+    UnknownNode(NodeId),
+    /// The node has no parameter with this name.
+    UnknownParam { node: NodeId, name: String },
+    /// The parameter value has the wrong type, is out of range, or doesn't
+    /// fit with the other parameters of the node.
+    InvalidParam {
+        node: NodeId,
+        name: &'static str,
+        reason: String,
+    },
+    /// The node has no input at this index.
+    UnknownInput { node: NodeId, input: usize },
+    /// The node depends on itself.
+    Cycle(NodeId),
+    /// Nothing is connected to this input of the node.
+    MissingInput { node: NodeId, input: &'static str },
+    /// The node can't be evaluated at points of this dimension.
+    UnsupportedDimension { node: NodeId, dimension: usize },
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphError::UnknownNode(node) => write!(f, "node {} doesn't exist", node.0),
+            GraphError::UnknownParam { node, name } => {
+                write!(f, "node {} has no parameter `{}`", node.0, name)
+            }
+            GraphError::InvalidParam { node, name, reason } => {
+                write!(f, "invalid `{}` for node {}: {}", name, node.0, reason)
+            }
+            GraphError::UnknownInput { node, input } => {
+                write!(f, "node {} has no input {}", node.0, input)
+            }
+            GraphError::Cycle(node) => write!(f, "node {} would depend on itself", node.0),
+            GraphError::MissingInput { node, input } => {
+                write!(f, "input `{}` of node {} is not connected", input, node.0)
+            }
+            GraphError::UnsupportedDimension { node, dimension } => write!(
+                f,
+                "node {} doesn't support {} dimensional points",
+                node.0, dimension
+            ),
+        }
+    }
+}
+
+impl error::Error for GraphError {}
+
+/// A noise function built from a [`Graph`].
+#[derive(Clone, Debug)]
+pub struct DynNoise<const DIM: usize> {
+    root: Arc<Module>,
+}
+
+macro_rules! impl_dyn_noise {
+    ($($dim:literal),+) => {$(
+        impl NoiseFn<f64, $dim> for DynNoise<$dim> {
+            fn get(&self, point: [f64; $dim]) -> f64 {
+                self.root.get(point)
+            }
+
+            fn get_batch(&self, points: &[[f64; $dim]], output: &mut [f64]) {
+                self.root.get_batch(points, output)
+            }
+
+            fn get_filtered(&self, point: [f64; $dim], width: f64) -> f64 {
+                self.root.get_filtered(point, width)
+            }
+
+            fn get_batch_filtered(&self, points: &[[f64; $dim]], width: f64, output: &mut [f64]) {
+                self.root.get_batch_filtered(points, width, output)
+            }
+        }
+    )+};
+}
+
+impl_dyn_noise!(1, 2, 3, 4);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::noise_fns::{
+        generators::perlin::Perlin, Fbm, MultiFractal, NoiseFnExt, ReturnType, SeedPoints,
+        Seedable, Worley,
+    };
+
+    #[test]
+    fn test_matches_static_graph() {
+        let mut graph = Graph::new();
+        let fbm = graph.add_node(NodeKind::Fbm);
+        let perlin = graph.add_node(NodeKind::Perlin);
+        let add = graph.add_node(NodeKind::Add);
+        let scale_bias = graph.add_node(NodeKind::ScaleBias);
+        graph.connect(fbm, add, 0).unwrap();
+        graph.connect(perlin, add, 1).unwrap();
+        graph.connect(add, scale_bias, 0).unwrap();
+        graph.set_param(fbm, "seed", ParamValue::Seed(3)).unwrap();
+        graph.set_param(fbm, "octaves", ParamValue::Int(4)).unwrap();
+        graph
+            .set_param(perlin, "seed", ParamValue::Seed(7))
+            .unwrap();
+        graph
+            .set_param(scale_bias, "scale", ParamValue::Float(0.5))
+            .unwrap();
+
+        let expected =
+            (Fbm::new().set_seed(3).set_octaves(4) + Perlin::new(7)).scale_bias(0.5, 0.0);
+        let noise = graph.build::<3>(scale_bias).unwrap();
+        for &point in &[[0.1, 0.2, 0.3], [-4.5, 1.25, 9.75]] {
+            assert_eq!(noise.get(point), expected.get(point));
+        }
+
+        let noise = graph.build::<1>(scale_bias).unwrap();
+        for &point in &[[0.1], [-4.5]] {
+            assert_eq!(noise.get(point), expected.get(point));
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut graph = Graph::new();
+        let abs = graph.add_node(NodeKind::Abs);
+        let negate = graph.add_node(NodeKind::Negate);
+        graph.connect(abs, negate, 0).unwrap();
+
+        assert_eq!(graph.connect(negate, abs, 0), Err(GraphError::Cycle(abs)));
+        assert_eq!(
+            graph.build::<2>(negate).unwrap_err(),
+            GraphError::MissingInput {
+                node: abs,
+                input: "source"
+            }
+        );
+
+        let select = graph.add_node(NodeKind::Select);
+        assert!(matches!(
+            graph.set_param(select, "falloff", ParamValue::Float(-1.0)),
+            Err(GraphError::InvalidParam {
+                name: "falloff",
+                ..
+            })
+        ));
+        assert!(matches!(
+            graph.set_param(select, "falloff", ParamValue::Int(1)),
+            Err(GraphError::InvalidParam {
+                name: "falloff",
+                ..
+            })
+        ));
+        assert!(matches!(
+            graph.set_param(select, "octaves", ParamValue::Int(1)),
+            Err(GraphError::UnknownParam { .. })
+        ));
+
+        let perlin = graph.add_node(NodeKind::Perlin);
+        assert!(matches!(
+            graph.set_param(perlin, "seed", ParamValue::Int(-1)),
+            Err(GraphError::InvalidParam { name: "seed", .. })
+        ));
+        assert!(graph
+            .set_param(perlin, "seed", ParamValue::Seed(u64::MAX))
+            .is_ok());
+
+        let super_simplex = graph.add_node(NodeKind::SuperSimplex);
+        assert!(graph.build::<4>(super_simplex).is_ok());
+        assert!(matches!(
+            graph.build::<1>(super_simplex),
+            Err(GraphError::UnsupportedDimension { dimension: 1, .. })
+        ));
+
+        let rotate_point = graph.add_node(NodeKind::RotatePoint);
+        graph.connect(super_simplex, rotate_point, 0).unwrap();
+        assert!(graph.build::<3>(rotate_point).is_ok());
+        assert!(matches!(
+            graph.build::<4>(rotate_point),
+            Err(GraphError::UnsupportedDimension { dimension: 4, .. })
+        ));
+    }
+
+    #[test]
+    fn test_displace_axes() {
+        let mut graph = Graph::new();
+        let perlin = graph.add_node(NodeKind::Perlin);
+        let constant = graph.add_node(NodeKind::Constant);
+        let displace = graph.add_node(NodeKind::Displace);
+        graph
+            .set_param(constant, "value", ParamValue::Float(0.25))
+            .unwrap();
+        for input in 0..3 {
+            graph.connect(perlin, displace, input).unwrap();
+        }
+
+        let noise = graph.build::<2>(displace).unwrap();
+        let perlin = Perlin::new(0);
+        let point = [0.3, -1.7];
+        let moved = perlin.get(point);
+        assert_eq!(
+            noise.get(point),
+            perlin.get([point[0] + moved, point[1] + moved])
+        );
+
+        assert_eq!(
+            graph.build::<3>(displace).unwrap_err(),
+            GraphError::MissingInput {
+                node: displace,
+                input: "z_displace"
+            }
+        );
+        graph.connect(constant, displace, 3).unwrap();
+        assert!(graph.build::<3>(displace).is_ok());
+        assert!(matches!(
+            graph.build::<4>(displace),
+            Err(GraphError::MissingInput {
+                input: "u_displace",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_worley_params() {
+        let mut graph = Graph::new();
+        let worley = graph.add_node(NodeKind::Worley);
+        let return_type = NodeKind::Worley
+            .params()
+            .iter()
+            .find(|param| param.name == "return_type");
+        let return_types = match return_type.unwrap().kind {
+            ParamKind::Choice { choices, .. } => choices,
+            kind => panic!("return_type is not a choice: {:?}", kind),
+        };
+        let weighted = return_types
+            .iter()
+            .position(|&choice| choice == "WeightedDistances")
+            .unwrap();
+        graph
+            .set_param(worley, "return_type", ParamValue::Choice(weighted))
+            .unwrap();
+        assert!(matches!(
+            graph.build::<2>(worley),
+            Err(GraphError::InvalidParam {
+                name: "weights",
+                ..
+            })
+        ));
+
+        graph
+            .set_param(worley, "weights", ParamValue::List(vec![-1.0, 1.0]))
+            .unwrap();
+        graph
+            .set_param(worley, "seed_points", ParamValue::Choice(1))
+            .unwrap();
+        graph
+            .set_param(worley, "poisson_mean", ParamValue::Float(0.5))
+            .unwrap();
+        assert!(matches!(
+            graph.set_param(worley, "poisson_mean", ParamValue::Float(0.0)),
+            Err(GraphError::InvalidParam {
+                name: "poisson_mean",
+                ..
+            })
+        ));
+
+        let expected = Worley::new(0)
+            .set_return_type(ReturnType::WeightedDistances([-1.0, 1.0, 0.0, 0.0]))
+            .set_seed_points(SeedPoints::Poisson(0.5));
+        let noise = graph.build::<3>(worley).unwrap();
+        for &point in &[[0.1, 0.2, 0.3], [-4.5, 1.25, 9.75]] {
+            assert_eq!(noise.get(point), expected.get(point));
+        }
+    }
+
+    #[test]
+    fn test_params() {
+        let mut graph = Graph::new();
+        let ridged = graph.add_node(NodeKind::RidgedMulti);
+
+        let names: Vec<_> = NodeKind::RidgedMulti
+            .params()
+            .iter()
+            .map(|param| param.name)
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "seed",
+                "octaves",
+                "frequency",
+                "lacunarity",
+                "persistence",
+                "rotation",
+                "rotation_angle",
+                "attenuation"
+            ]
+        );
+        assert_eq!(
+            graph.param(ridged, "attenuation"),
+            Ok(&ParamValue::Float(2.0))
+        );
+
+        for &kind in NodeKind::ALL {
+            let id = graph.add_node(kind);
+            for param in kind.params() {
+                assert_eq!(graph.param(id, param.name), Ok(&param.default_value()));
+                assert_eq!(param.check(&param.default_value()), Ok(()));
+                if param.name == "seed" {
+                    assert_eq!(param.check(&ParamValue::Seed(u64::MAX)), Ok(()));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,474 @@
+use super::{GraphError, NodeId, NodeKind, ParamKind, ParamValue};
+use crate::noise_fns::{
+    generators::perlin::Perlin, Abs, Add, BasicMulti, Billow, Blend, Checkerboard, Clamp, Constant,
+    Curve, Cylinders, Displace, DistanceFunction, Divide, ErodedFbm, Exponent, Fbm, HybridMulti,
+    LatticeOrientation, Max, Min, MultiFractal, Multiply, Negate, NoiseFn, NoiseFnVector,
+    OctaveRotation, OpenSimplex, OpenSimplex2F, OpenSimplex2S, Power, ReturnType, RidgedMulti,
+    RotatePoint, ScaleBias, ScalePoint, SeedPoints, Seedable, Select, Simplex, SuperSimplex,
+    Terrace, TranslatePoint, Turbulence, Value, Worley, NEAREST_SEED_POINTS,
+};
+use std::sync::Arc;
+
+/// A built node, shared by every node that uses it as an input.
+pub(crate) type Input = Arc<Module>;
+
+/// One of the noise functions of this crate, with its inputs built.
+#[derive(Clone, Debug)]
+pub(crate) enum Module {
+    Constant(Constant),
+    Checkerboard(Checkerboard),
+    Cylinders(Cylinders),
+    Perlin(Perlin),
+    Simplex(Simplex),
+    OpenSimplex(OpenSimplex),
+    SuperSimplex(SuperSimplex),
+    OpenSimplex2S(OpenSimplex2S),
+    OpenSimplex2F(OpenSimplex2F),
+    Value(Value),
+    Worley(Worley),
+    Fbm(Fbm),
+    Billow(Billow),
+    BasicMulti(BasicMulti),
+    HybridMulti(HybridMulti),
+    RidgedMulti(RidgedMulti),
+    ErodedFbm(ErodedFbm),
+    Abs(Abs<Input>),
+    Clamp(Clamp<Input>),
+    Curve(Curve<Input>),
+    Exponent(Exponent<Input>),
+    Negate(Negate<Input>),
+    ScaleBias(ScaleBias<Input>),
+    Terrace(Terrace<Input>),
+    Add(Add<Input, Input>),
+    Divide(Divide<Input, Input>),
+    Max(Max<Input, Input>),
+    Min(Min<Input, Input>),
+    Multiply(Multiply<Input, Input>),
+    Power(Power<Input, Input>),
+    Blend(Blend<Input, Input, Input>),
+    Select(Select<Input, Input, Input>),
+    Displace(Displace<Input, Axes>),
+    RotatePoint(RotatePoint<Input>),
+    ScalePoint(ScalePoint<Input>),
+    TranslatePoint(TranslatePoint<Input>),
+    // Boxed, as its four distortion fractals make it much larger than the rest.
+    Turbulence(Box<Turbulence<Input>>),
+}
+
+/// The displacement functions of a `Displace` node, one per axis. Points
+/// with fewer than four coordinates leave the last ones unused, so they may be
+/// missing.
+#[derive(Clone, Debug)]
+pub(crate) struct Axes([Option<Input>; 4]);
+
+impl<const DIM: usize> NoiseFnVector<f64, DIM, DIM> for Axes
+where
+    Module: NoiseFn<f64, DIM>,
+{
+    fn get_vector(&self, point: [f64; DIM]) -> [f64; DIM] {
+        let mut result = [0.0; DIM];
+        for (value, source) in result.iter_mut().zip(self.0.iter()) {
+            *value = source
+                .as_ref()
+                .expect("the axes of the built dimension are connected")
+                .get(point);
+        }
+        result
+    }
+}
+
+/// Implements `NoiseFn` for `Module` at one dimension, forwarding to the
+/// listed variants. The others don't support that dimension, which
+/// [`NodeKind::supports_dimension`] stops them from being built for.
+macro_rules! impl_noise_fn {
+    ($dim:literal: $($variant:ident),+ $(,)?) => {
+        impl NoiseFn<f64, $dim> for Module {
+            fn get(&self, point: [f64; $dim]) -> f64 {
+                match self {
+                    $(Module::$variant(module) => module.get(point),)+
+                    #[allow(unreachable_patterns)]
+                    _ => unreachable!("graph nodes are only built for dimensions they support"),
+                }
+            }
+
+            fn get_batch(&self, points: &[[f64; $dim]], output: &mut [f64]) {
+                match self {
+                    $(Module::$variant(module) => module.get_batch(points, output),)+
+                    #[allow(unreachable_patterns)]
+                    _ => unreachable!("graph nodes are only built for dimensions they support"),
+                }
+            }
+
+            fn get_filtered(&self, point: [f64; $dim], width: f64) -> f64 {
+                match self {
+                    $(Module::$variant(module) => module.get_filtered(point, width),)+
+                    #[allow(unreachable_patterns)]
+                    _ => unreachable!("graph nodes are only built for dimensions they support"),
+                }
+            }
+
+            fn get_batch_filtered(&self, points: &[[f64; $dim]], width: f64, output: &mut [f64]) {
+                match self {
+                    $(Module::$variant(module) => module.get_batch_filtered(points, width, output),)+
+                    #[allow(unreachable_patterns)]
+                    _ => unreachable!("graph nodes are only built for dimensions they support"),
+                }
+            }
+        }
+    };
+}
+
+impl_noise_fn!(
+    1: Constant, Checkerboard, Cylinders, Perlin, Simplex, Value, Worley, Fbm, Billow, BasicMulti,
+    HybridMulti, RidgedMulti, ErodedFbm, Abs, Clamp, Curve, Exponent, Negate, ScaleBias, Terrace,
+    Add, Divide, Max, Min, Multiply, Power, Blend, Select, Displace,
+);
+
+impl_noise_fn!(
+    2: Constant, Checkerboard, Cylinders, Perlin, Simplex, OpenSimplex, SuperSimplex, OpenSimplex2S,
+    OpenSimplex2F, Value,
+    Worley, Fbm, Billow, BasicMulti, HybridMulti, RidgedMulti, ErodedFbm, Abs, Clamp, Curve,
+    Exponent, Negate, ScaleBias, Terrace, Add, Divide, Max, Min, Multiply, Power, Blend, Select,
+    Displace, RotatePoint, ScalePoint, TranslatePoint, Turbulence,
+);
+
+impl_noise_fn!(
+    3: Constant, Checkerboard, Cylinders, Perlin, Simplex, OpenSimplex, SuperSimplex, OpenSimplex2S,
+    OpenSimplex2F, Value,
+    Worley, Fbm, Billow, BasicMulti, HybridMulti, RidgedMulti, ErodedFbm, Abs, Clamp, Curve,
+    Exponent, Negate, ScaleBias, Terrace, Add, Divide, Max, Min, Multiply, Power, Blend, Select,
+    Displace, RotatePoint, ScalePoint, TranslatePoint, Turbulence,
+);
+
+impl_noise_fn!(
+    4: Constant, Checkerboard, Cylinders, Perlin, Simplex, OpenSimplex, SuperSimplex, OpenSimplex2S,
+    OpenSimplex2F, Value,
+    Worley, Fbm, Billow, BasicMulti, HybridMulti, RidgedMulti, ErodedFbm, Abs, Clamp, Curve,
+    Exponent, Negate, ScaleBias, Terrace, Add, Divide, Max, Min, Multiply, Power, Blend, Select,
+    Displace, ScalePoint, TranslatePoint, Turbulence,
+);
+
+/// Reads the parameters of a node by name.
+struct Params<'a> {
+    node: NodeId,
+    kind: NodeKind,
+    values: &'a [ParamValue],
+}
+
+impl Params<'_> {
+    fn get(&self, name: &str) -> &ParamValue {
+        let index = self
+            .kind
+            .params()
+            .iter()
+            .position(|param| param.name == name)
+            .expect("node kinds read only their own parameters");
+
+        &self.values[index]
+    }
+
+    fn float(&self, name: &str) -> f64 {
+        match *self.get(name) {
+            ParamValue::Float(value) => value,
+            ref value => unreachable!("{} is not a float: {:?}", name, value),
+        }
+    }
+
+    fn int(&self, name: &str) -> i64 {
+        match *self.get(name) {
+            ParamValue::Int(value) => value,
+            ref value => unreachable!("{} is not an integer: {:?}", name, value),
+        }
+    }
+
+    fn seed(&self) -> u64 {
+        match *self.get("seed") {
+            ParamValue::Seed(value) => value,
+            ref value => unreachable!("seed is not a seed: {:?}", value),
+        }
+    }
+
+    fn bool(&self, name: &str) -> bool {
+        match *self.get(name) {
+            ParamValue::Bool(value) => value,
+            ref value => unreachable!("{} is not a bool: {:?}", name, value),
+        }
+    }
+
+    fn choice(&self, name: &str) -> &'static str {
+        let info = self
+            .kind
+            .params()
+            .iter()
+            .find(|param| param.name == name)
+            .expect("node kinds read only their own parameters");
+
+        match (info.kind, self.get(name)) {
+            (ParamKind::Choice { choices, .. }, &ParamValue::Choice(index)) => choices[index],
+            (_, value) => unreachable!("{} is not a choice: {:?}", name, value),
+        }
+    }
+
+    fn list(&self, name: &str) -> &[f64] {
+        match self.get(name) {
+            ParamValue::List(values) => values,
+            value => unreachable!("{} is not a list: {:?}", name, value),
+        }
+    }
+
+    fn invalid(&self, name: &'static str, reason: String) -> GraphError {
+        GraphError::InvalidParam {
+            node: self.node,
+            name,
+            reason,
+        }
+    }
+
+    fn bounds(&self) -> Result<(f64, f64), GraphError> {
+        let (lower, upper) = (self.float("lower_bound"), self.float("upper_bound"));
+        if lower > upper {
+            return Err(self.invalid(
+                "lower_bound",
+                format!("{} is larger than the upper bound {}", lower, upper),
+            ));
+        }
+
+        Ok((lower, upper))
+    }
+}
+
+impl Module {
+    /// Builds the noise function of a node from its parameters and its built
+    /// inputs, which are in the order of [`NodeKind::inputs`]. Only the
+    /// optional inputs may be missing.
+    ///
+    /// The parameters have already been checked individually, so this only
+    /// checks how they relate to each other.
+    pub(crate) fn build(
+        node: NodeId,
+        kind: NodeKind,
+        values: &[ParamValue],
+        inputs: Vec<Option<Input>>,
+    ) -> Result<Self, GraphError> {
+        let p = Params { node, kind, values };
+        let mut inputs = inputs.into_iter();
+        let mut input = || {
+            inputs
+                .next()
+                .flatten()
+                .expect("every required input has been built")
+        };
+
+        let module = match kind {
+            NodeKind::Constant => Module::Constant(Constant::new(p.float("value"))),
+            NodeKind::Checkerboard => {
+                Module::Checkerboard(Checkerboard::new(p.int("size") as usize))
+            }
+            NodeKind::Cylinders => {
+                Module::Cylinders(Cylinders::new().set_frequency(p.float("frequency")))
+            }
+            NodeKind::Perlin => Module::Perlin(Perlin::new(p.seed())),
+            NodeKind::Simplex => Module::Simplex(Simplex::new(p.seed())),
+            NodeKind::OpenSimplex => Module::OpenSimplex(OpenSimplex::new().set_seed(p.seed())),
+            NodeKind::SuperSimplex => Module::SuperSimplex(SuperSimplex::new().set_seed(p.seed())),
+            NodeKind::OpenSimplex2S => Module::OpenSimplex2S(
+                OpenSimplex2S::new()
+                    .set_seed(p.seed())
+                    .set_orientation(orientation(&p)),
+            ),
+            NodeKind::OpenSimplex2F => Module::OpenSimplex2F(
+                OpenSimplex2F::new()
+                    .set_seed(p.seed())
+                    .set_orientation(orientation(&p)),
+            ),
+            NodeKind::Value => Module::Value(Value::new().set_seed(p.seed())),
+            NodeKind::Worley => {
+                let return_type = match p.choice("return_type") {
+                    "Distance" => ReturnType::Distance,
+                    "Distance2" => ReturnType::Distance2,
+                    "Distance2Sub" => ReturnType::Distance2Sub,
+                    "Distance2Mul" => ReturnType::Distance2Mul,
+                    "WeightedDistances" => {
+                        let list = p.list("weights");
+                        if list.is_empty() || list.len() > NEAREST_SEED_POINTS {
+                            return Err(p.invalid(
+                                "weights",
+                                format!(
+                                    "expected between 1 and {} weights, got {}",
+                                    NEAREST_SEED_POINTS,
+                                    list.len()
+                                ),
+                            ));
+                        }
+
+                        let mut weights = [0.0; NEAREST_SEED_POINTS];
+                        weights[..list.len()].copy_from_slice(list);
+                        ReturnType::WeightedDistances(weights)
+                    }
+                    _ => ReturnType::Value,
+                };
+                let distance_function = match p.choice("distance_function") {
+                    "EuclideanSquared" => DistanceFunction::EuclideanSquared,
+                    "Manhattan" => DistanceFunction::Manhattan,
+                    "Chebyshev" => DistanceFunction::Chebyshev,
+                    "Quadratic" => DistanceFunction::Quadratic,
+                    "Minkowski" => DistanceFunction::Minkowski(p.float("minkowski_exponent")),
+                    _ => DistanceFunction::Euclidean,
+                };
+                let seed_points = match p.choice("seed_points") {
+                    "Poisson" => SeedPoints::Poisson(p.float("poisson_mean")),
+                    _ => SeedPoints::One,
+                };
+
+                Module::Worley(
+                    Worley::new(p.seed())
+                        .set_frequency(p.float("frequency"))
+                        .set_jitter(p.float("jitter"))
+                        .set_return_type(return_type)
+                        .set_distance_function(distance_function)
+                        .set_seed_points(seed_points),
+                )
+            }
+            NodeKind::Fbm => Module::Fbm(fractal(&p, Fbm::new())),
+            NodeKind::Billow => Module::Billow(fractal(&p, Billow::new())),
+            NodeKind::BasicMulti => Module::BasicMulti(fractal(&p, BasicMulti::new())),
+            NodeKind::HybridMulti => Module::HybridMulti(fractal(&p, HybridMulti::new())),
+            NodeKind::RidgedMulti => Module::RidgedMulti(
+                fractal(&p, RidgedMulti::new()).set_attenuation(p.float("attenuation")),
+            ),
+            NodeKind::ErodedFbm => Module::ErodedFbm(
+                fractal(&p, ErodedFbm::new()).set_gradient_influence(p.float("gradient_influence")),
+            ),
+            NodeKind::Abs => Module::Abs(Abs::new(input())),
+            NodeKind::Clamp => {
+                let (lower, upper) = p.bounds()?;
+                Module::Clamp(Clamp::new(input()).set_bounds(lower, upper))
+            }
+            NodeKind::Curve => {
+                let points = p.list("control_points");
+                let pairs = points.chunks_exact(2);
+                if !pairs.remainder().is_empty() {
+                    return Err(p.invalid(
+                        "control_points",
+                        "expected an input and an output value for each control point".to_string(),
+                    ));
+                }
+                if points.len() < 8 {
+                    return Err(p.invalid(
+                        "control_points",
+                        format!(
+                            "expected at least 4 control points, got {}",
+                            points.len() / 2
+                        ),
+                    ));
+                }
+
+                Module::Curve(pairs.fold(Curve::new(input()), |curve, point| {
+                    curve.add_control_point(point[0], point[1])
+                }))
+            }
+            NodeKind::Exponent => {
+                Module::Exponent(Exponent::new(input()).set_exponent(p.float("exponent")))
+            }
+            NodeKind::Negate => Module::Negate(Negate::new(input())),
+            NodeKind::ScaleBias => Module::ScaleBias(
+                ScaleBias::new(input())
+                    .set_scale(p.float("scale"))
+                    .set_bias(p.float("bias")),
+            ),
+            NodeKind::Terrace => {
+                let points = p.list("control_points");
+                if points.len() < 2 {
+                    return Err(p.invalid(
+                        "control_points",
+                        format!("expected at least 2 control points, got {}", points.len()),
+                    ));
+                }
+
+                let terrace = points
+                    .iter()
+                    .fold(Terrace::new(input()), |terrace, &point| {
+                        terrace.add_control_point(point)
+                    });
+
+                Module::Terrace(terrace.invert_terraces(p.bool("invert_terraces")))
+            }
+            NodeKind::Add => Module::Add(Add::new(input(), input())),
+            NodeKind::Divide => Module::Divide(Divide::new(input(), input())),
+            NodeKind::Max => Module::Max(Max::new(input(), input())),
+            NodeKind::Min => Module::Min(Min::new(input(), input())),
+            NodeKind::Multiply => Module::Multiply(Multiply::new(input(), input())),
+            NodeKind::Power => Module::Power(Power::new(input(), input())),
+            NodeKind::Blend => Module::Blend(Blend::new(input(), input(), input())),
+            NodeKind::Select => {
+                let (lower, upper) = p.bounds()?;
+                Module::Select(
+                    Select::new(input(), input(), input())
+                        .set_bounds(lower, upper)
+                        .set_falloff(p.float("falloff")),
+                )
+            }
+            NodeKind::Displace => {
+                let source = input();
+                let mut axis = || inputs.next().expect("every input has a slot");
+                Module::Displace(Displace::new(
+                    source,
+                    Axes([axis(), axis(), axis(), axis()]),
+                ))
+            }
+            NodeKind::RotatePoint => Module::RotatePoint(RotatePoint::new(input()).set_angles(
+                p.float("x_angle"),
+                p.float("y_angle"),
+                p.float("z_angle"),
+                p.float("u_angle"),
+            )),
+            NodeKind::ScalePoint => Module::ScalePoint(ScalePoint::new(input()).set_all_scales(
+                p.float("x_scale"),
+                p.float("y_scale"),
+                p.float("z_scale"),
+                p.float("u_scale"),
+            )),
+            NodeKind::TranslatePoint => {
+                Module::TranslatePoint(TranslatePoint::new(input()).set_all_translations(
+                    p.float("x_translation"),
+                    p.float("y_translation"),
+                    p.float("z_translation"),
+                    p.float("u_translation"),
+                ))
+            }
+            NodeKind::Turbulence => Module::Turbulence(Box::new(
+                Turbulence::new(input())
+                    .set_seed(p.seed())
+                    .set_frequency(p.float("frequency"))
+                    .set_power(p.float("power"))
+                    .set_roughness(p.int("roughness") as usize),
+            )),
+        };
+
+        Ok(module)
+    }
+}
+
+fn orientation(p: &Params<'_>) -> LatticeOrientation {
+    match p.choice("orientation") {
+        "ImproveXY" => LatticeOrientation::ImproveXY,
+        "ImproveXZ" => LatticeOrientation::ImproveXZ,
+        _ => LatticeOrientation::Classic,
+    }
+}
+
+fn fractal<F: MultiFractal + Seedable>(p: &Params<'_>, fractal: F) -> F {
+    let rotation = match p.choice("rotation") {
+        "Fixed" => OctaveRotation::Fixed(p.float("rotation_angle")),
+        "Seeded" => OctaveRotation::Seeded,
+        _ => OctaveRotation::None,
+    };
+
+    fractal
+        .set_seed(p.seed())
+        .set_octaves(p.int("octaves") as usize)
+        .set_frequency(p.float("frequency"))
+        .set_lacunarity(p.float("lacunarity"))
+        .set_persistence(p.float("persistence"))
+        .set_rotation(rotation)
+}
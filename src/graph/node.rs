@@ -0,0 +1,526 @@
+use crate::noise_fns::{
+    BasicMulti, Billow, Cylinders, ErodedFbm, Fbm, HybridMulti, RidgedMulti, Turbulence, Worley,
+};
+
+/// Expands to a `'static` slice of parameters, which can't be borrowed
+/// directly from a function body because they're built by `const fn`s.
+macro_rules! params {
+    ($($param:expr),* $(,)?) => {{
+        const PARAMS: &[ParamInfo] = &[$($param),*];
+        PARAMS
+    }};
+}
+
+/// The type of a node in a [`Graph`](super::Graph), which is one of the noise
+/// functions of this crate.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NodeKind {
+    // Generators
+    Constant,
+    Checkerboard,
+    Cylinders,
+    Perlin,
+    Simplex,
+    OpenSimplex,
+    SuperSimplex,
+    OpenSimplex2S,
+    OpenSimplex2F,
+    Value,
+    Worley,
+    Fbm,
+    Billow,
+    BasicMulti,
+    HybridMulti,
+    RidgedMulti,
+    ErodedFbm,
+    // Modifiers
+    Abs,
+    Clamp,
+    Curve,
+    Exponent,
+    Negate,
+    ScaleBias,
+    Terrace,
+    // Combiners
+    Add,
+    Divide,
+    Max,
+    Min,
+    Multiply,
+    Power,
+    // Selectors
+    Blend,
+    Select,
+    // Transformers
+    Displace,
+    RotatePoint,
+    ScalePoint,
+    TranslatePoint,
+    Turbulence,
+}
+
+impl NodeKind {
+    /// Every kind of node, in declaration order.
+    pub const ALL: &'static [NodeKind] = &[
+        NodeKind::Constant,
+        NodeKind::Checkerboard,
+        NodeKind::Cylinders,
+        NodeKind::Perlin,
+        NodeKind::Simplex,
+        NodeKind::OpenSimplex,
+        NodeKind::SuperSimplex,
+        NodeKind::OpenSimplex2S,
+        NodeKind::OpenSimplex2F,
+        NodeKind::Value,
+        NodeKind::Worley,
+        NodeKind::Fbm,
+        NodeKind::Billow,
+        NodeKind::BasicMulti,
+        NodeKind::HybridMulti,
+        NodeKind::RidgedMulti,
+        NodeKind::ErodedFbm,
+        NodeKind::Abs,
+        NodeKind::Clamp,
+        NodeKind::Curve,
+        NodeKind::Exponent,
+        NodeKind::Negate,
+        NodeKind::ScaleBias,
+        NodeKind::Terrace,
+        NodeKind::Add,
+        NodeKind::Divide,
+        NodeKind::Max,
+        NodeKind::Min,
+        NodeKind::Multiply,
+        NodeKind::Power,
+        NodeKind::Blend,
+        NodeKind::Select,
+        NodeKind::Displace,
+        NodeKind::RotatePoint,
+        NodeKind::ScalePoint,
+        NodeKind::TranslatePoint,
+        NodeKind::Turbulence,
+    ];
+
+    /// Returns the names of the inputs of this kind of node. The inputs that
+    /// are [required](Self::required_inputs) must be connected before the node
+    /// can be built.
+    pub fn inputs(self) -> &'static [&'static str] {
+        use NodeKind::*;
+
+        match self {
+            Constant | Checkerboard | Cylinders | Perlin | Simplex | OpenSimplex | SuperSimplex
+            | OpenSimplex2S | OpenSimplex2F | Value | Worley | Fbm | Billow | BasicMulti
+            | HybridMulti | RidgedMulti | ErodedFbm => &[],
+            Abs | Clamp | Curve | Exponent | Negate | ScaleBias | Terrace | RotatePoint
+            | ScalePoint | TranslatePoint | Turbulence => &["source"],
+            Add | Divide | Max | Min | Multiply | Power => &["source1", "source2"],
+            Blend | Select => &["source1", "source2", "control"],
+            Displace => &[
+                "source",
+                "x_displace",
+                "y_displace",
+                "z_displace",
+                "u_displace",
+            ],
+        }
+    }
+
+    /// Returns how many of the [inputs](Self::inputs), from the first, must be
+    /// connected to build this kind of node for `dimension` dimensional points.
+    ///
+    /// Only `Displace` has optional inputs: the displacements of the axes that
+    /// points of `dimension` dimensions don't have, which are left unused.
+    pub fn required_inputs(self, dimension: usize) -> usize {
+        match self {
+            NodeKind::Displace => 1 + dimension,
+            _ => self.inputs().len(),
+        }
+    }
+
+    /// Returns the parameters of this kind of node.
+    pub fn params(self) -> &'static [ParamInfo] {
+        match self {
+            NodeKind::Constant => params![ParamInfo::float("value", 0.0, f64::MIN, f64::MAX)],
+            NodeKind::Checkerboard => params![ParamInfo::int("size", 0, 0, 16)],
+            NodeKind::Cylinders => params![ParamInfo::float(
+                "frequency",
+                Cylinders::DEFAULT_FREQUENCY,
+                0.0,
+                f64::MAX,
+            )],
+            NodeKind::Perlin
+            | NodeKind::Simplex
+            | NodeKind::OpenSimplex
+            | NodeKind::SuperSimplex
+            | NodeKind::Value => params![SEED],
+            NodeKind::OpenSimplex2S | NodeKind::OpenSimplex2F => params![
+                SEED,
+                ParamInfo::choice("orientation", 0, &["Classic", "ImproveXY", "ImproveXZ"]),
+            ],
+            NodeKind::Worley => params![
+                SEED,
+                ParamInfo::float("frequency", Worley::DEFAULT_FREQUENCY, 0.0, f64::MAX),
+                ParamInfo::float("jitter", Worley::DEFAULT_JITTER, 0.0, 1.0),
+                ParamInfo::choice(
+                    "return_type",
+                    0,
+                    &[
+                        "Value",
+                        "Distance",
+                        "Distance2",
+                        "Distance2Sub",
+                        "Distance2Mul",
+                        "WeightedDistances",
+                    ],
+                ),
+                ParamInfo::list("weights"),
+                ParamInfo::choice(
+                    "distance_function",
+                    0,
+                    &[
+                        "Euclidean",
+                        "EuclideanSquared",
+                        "Manhattan",
+                        "Chebyshev",
+                        "Quadratic",
+                        "Minkowski",
+                    ],
+                ),
+                ParamInfo::float("minkowski_exponent", 3.0, 1.0, f64::MAX),
+                ParamInfo::choice("seed_points", 0, &["One", "Poisson"]),
+                ParamInfo::float("poisson_mean", 3.0, f64::MIN_POSITIVE, f64::MAX),
+            ],
+            NodeKind::Fbm => params![
+                SEED,
+                octaves(Fbm::DEFAULT_OCTAVE_COUNT, Fbm::MAX_OCTAVES),
+                frequency(Fbm::DEFAULT_FREQUENCY),
+                lacunarity(Fbm::DEFAULT_LACUNARITY),
+                persistence(Fbm::DEFAULT_PERSISTENCE),
+                ROTATION,
+                ROTATION_ANGLE,
+            ],
+            NodeKind::Billow => params![
+                SEED,
+                octaves(Billow::DEFAULT_OCTAVE_COUNT, Billow::MAX_OCTAVES),
+                frequency(Billow::DEFAULT_FREQUENCY),
+                lacunarity(Billow::DEFAULT_LACUNARITY),
+                persistence(Billow::DEFAULT_PERSISTENCE),
+                ROTATION,
+                ROTATION_ANGLE,
+            ],
+            NodeKind::BasicMulti => params![
+                SEED,
+                octaves(BasicMulti::DEFAULT_OCTAVES, BasicMulti::MAX_OCTAVES),
+                frequency(BasicMulti::DEFAULT_FREQUENCY),
+                lacunarity(BasicMulti::DEFAULT_LACUNARITY),
+                persistence(BasicMulti::DEFAULT_PERSISTENCE),
+                ROTATION,
+                ROTATION_ANGLE,
+            ],
+            NodeKind::HybridMulti => params![
+                SEED,
+                octaves(HybridMulti::DEFAULT_OCTAVES, HybridMulti::MAX_OCTAVES),
+                frequency(HybridMulti::DEFAULT_FREQUENCY),
+                lacunarity(HybridMulti::DEFAULT_LACUNARITY),
+                persistence(HybridMulti::DEFAULT_PERSISTENCE),
+                ROTATION,
+                ROTATION_ANGLE,
+            ],
+            NodeKind::RidgedMulti => params![
+                SEED,
+                octaves(RidgedMulti::DEFAULT_OCTAVE_COUNT, RidgedMulti::MAX_OCTAVES),
+                frequency(RidgedMulti::DEFAULT_FREQUENCY),
+                lacunarity(RidgedMulti::DEFAULT_LACUNARITY),
+                persistence(RidgedMulti::DEFAULT_PERSISTENCE),
+                ROTATION,
+                ROTATION_ANGLE,
+                ParamInfo::float(
+                    "attenuation",
+                    RidgedMulti::DEFAULT_ATTENUATION,
+                    f64::MIN,
+                    f64::MAX,
+                ),
+            ],
+            NodeKind::ErodedFbm => params![
+                SEED,
+                octaves(ErodedFbm::DEFAULT_OCTAVE_COUNT, ErodedFbm::MAX_OCTAVES),
+                frequency(ErodedFbm::DEFAULT_FREQUENCY),
+                lacunarity(ErodedFbm::DEFAULT_LACUNARITY),
+                persistence(ErodedFbm::DEFAULT_PERSISTENCE),
+                ROTATION,
+                ROTATION_ANGLE,
+                ParamInfo::float(
+                    "gradient_influence",
+                    ErodedFbm::DEFAULT_GRADIENT_INFLUENCE,
+                    0.0,
+                    f64::MAX,
+                ),
+            ],
+            NodeKind::Abs
+            | NodeKind::Negate
+            | NodeKind::Add
+            | NodeKind::Divide
+            | NodeKind::Max
+            | NodeKind::Min
+            | NodeKind::Multiply
+            | NodeKind::Power
+            | NodeKind::Blend
+            | NodeKind::Displace => &[],
+            NodeKind::Clamp => params![
+                ParamInfo::float("lower_bound", -1.0, f64::MIN, f64::MAX),
+                ParamInfo::float("upper_bound", 1.0, f64::MIN, f64::MAX),
+            ],
+            NodeKind::Curve => params![ParamInfo::list("control_points")],
+            NodeKind::Exponent => params![ParamInfo::float("exponent", 1.0, f64::MIN, f64::MAX)],
+            NodeKind::ScaleBias => params![
+                ParamInfo::float("scale", 1.0, f64::MIN, f64::MAX),
+                ParamInfo::float("bias", 0.0, f64::MIN, f64::MAX),
+            ],
+            NodeKind::Terrace => params![
+                ParamInfo::list("control_points"),
+                ParamInfo::bool("invert_terraces", false),
+            ],
+            NodeKind::Select => params![
+                ParamInfo::float("lower_bound", 0.0, f64::MIN, f64::MAX),
+                ParamInfo::float("upper_bound", 1.0, f64::MIN, f64::MAX),
+                ParamInfo::float("falloff", 0.0, 0.0, f64::MAX),
+            ],
+            NodeKind::RotatePoint => params![
+                ParamInfo::float("x_angle", 0.0, -360.0, 360.0),
+                ParamInfo::float("y_angle", 0.0, -360.0, 360.0),
+                ParamInfo::float("z_angle", 0.0, -360.0, 360.0),
+                ParamInfo::float("u_angle", 0.0, -360.0, 360.0),
+            ],
+            NodeKind::ScalePoint => params![
+                ParamInfo::float("x_scale", 1.0, f64::MIN, f64::MAX),
+                ParamInfo::float("y_scale", 1.0, f64::MIN, f64::MAX),
+                ParamInfo::float("z_scale", 1.0, f64::MIN, f64::MAX),
+                ParamInfo::float("u_scale", 1.0, f64::MIN, f64::MAX),
+            ],
+            NodeKind::TranslatePoint => params![
+                ParamInfo::float("x_translation", 0.0, f64::MIN, f64::MAX),
+                ParamInfo::float("y_translation", 0.0, f64::MIN, f64::MAX),
+                ParamInfo::float("z_translation", 0.0, f64::MIN, f64::MAX),
+                ParamInfo::float("u_translation", 0.0, f64::MIN, f64::MAX),
+            ],
+            NodeKind::Turbulence => params![
+                SEED,
+                ParamInfo::float(
+                    "frequency",
+                    Turbulence::<()>::DEFAULT_FREQUENCY,
+                    0.0,
+                    f64::MAX,
+                ),
+                ParamInfo::float("power", Turbulence::<()>::DEFAULT_POWER, f64::MIN, f64::MAX,),
+                ParamInfo::int(
+                    "roughness",
+                    Turbulence::<()>::DEFAULT_ROUGHNESS as i64,
+                    1,
+                    Fbm::MAX_OCTAVES as i64,
+                ),
+            ],
+        }
+    }
+
+    /// Returns whether this kind of node can be evaluated at `dimension`
+    /// dimensional points.
+    pub fn supports_dimension(self, dimension: usize) -> bool {
+        match self {
+            NodeKind::OpenSimplex
+            | NodeKind::SuperSimplex
+            | NodeKind::OpenSimplex2S
+            | NodeKind::OpenSimplex2F
+            | NodeKind::ScalePoint
+            | NodeKind::TranslatePoint
+            | NodeKind::Turbulence => (2..=4).contains(&dimension),
+            NodeKind::RotatePoint => (2..=3).contains(&dimension),
+            _ => (1..=4).contains(&dimension),
+        }
+    }
+}
+
+const SEED: ParamInfo = ParamInfo::seed("seed", 0);
+
+const ROTATION: ParamInfo = ParamInfo::choice("rotation", 0, &["None", "Fixed", "Seeded"]);
+
+const ROTATION_ANGLE: ParamInfo = ParamInfo::float("rotation_angle", 0.5, f64::MIN, f64::MAX);
+
+const fn octaves(default: usize, max: usize) -> ParamInfo {
+    ParamInfo::int("octaves", default as i64, 1, max as i64)
+}
+
+const fn frequency(default: f64) -> ParamInfo {
+    ParamInfo::float("frequency", default, 0.0, f64::MAX)
+}
+
+const fn lacunarity(default: f64) -> ParamInfo {
+    ParamInfo::float("lacunarity", default, f64::MIN, f64::MAX)
+}
+
+const fn persistence(default: f64) -> ParamInfo {
+    ParamInfo::float("persistence", default, f64::MIN, f64::MAX)
+}
+
+/// Describes a parameter of a kind of node.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParamInfo {
+    /// The name of the parameter, which matches the field or setter of the
+    /// wrapped noise function.
+    pub name: &'static str,
+
+    /// The type of the parameter, with its default and valid range.
+    pub kind: ParamKind,
+}
+
+impl ParamInfo {
+    const fn float(name: &'static str, default: f64, min: f64, max: f64) -> Self {
+        Self {
+            name,
+            kind: ParamKind::Float { default, min, max },
+        }
+    }
+
+    const fn int(name: &'static str, default: i64, min: i64, max: i64) -> Self {
+        Self {
+            name,
+            kind: ParamKind::Int { default, min, max },
+        }
+    }
+
+    const fn seed(name: &'static str, default: u64) -> Self {
+        Self {
+            name,
+            kind: ParamKind::Seed { default },
+        }
+    }
+
+    const fn bool(name: &'static str, default: bool) -> Self {
+        Self {
+            name,
+            kind: ParamKind::Bool { default },
+        }
+    }
+
+    const fn choice(name: &'static str, default: usize, choices: &'static [&'static str]) -> Self {
+        Self {
+            name,
+            kind: ParamKind::Choice { default, choices },
+        }
+    }
+
+    const fn list(name: &'static str) -> Self {
+        Self {
+            name,
+            kind: ParamKind::List,
+        }
+    }
+
+    /// Returns the value the parameter has when a node is added to a graph.
+    pub fn default_value(&self) -> ParamValue {
+        match self.kind {
+            ParamKind::Float { default, .. } => ParamValue::Float(default),
+            ParamKind::Int { default, .. } => ParamValue::Int(default),
+            ParamKind::Seed { default } => ParamValue::Seed(default),
+            ParamKind::Bool { default } => ParamValue::Bool(default),
+            ParamKind::Choice { default, .. } => ParamValue::Choice(default),
+            ParamKind::List => ParamValue::List(Vec::new()),
+        }
+    }
+
+    /// Checks that `value` has the type of this parameter and lies within its
+    /// range, returning a description of the problem if not.
+    pub(crate) fn check(&self, value: &ParamValue) -> Result<(), String> {
+        match (self.kind, value) {
+            (ParamKind::Float { min, max, .. }, &ParamValue::Float(value)) => {
+                if value >= min && value <= max {
+                    Ok(())
+                } else {
+                    Err(format!("{} is outside the range {}..={}", value, min, max))
+                }
+            }
+            (ParamKind::Int { min, max, .. }, &ParamValue::Int(value)) => {
+                if value >= min && value <= max {
+                    Ok(())
+                } else {
+                    Err(format!("{} is outside the range {}..={}", value, min, max))
+                }
+            }
+            (ParamKind::Seed { .. }, ParamValue::Seed(_)) => Ok(()),
+            (ParamKind::Bool { .. }, ParamValue::Bool(_)) => Ok(()),
+            (ParamKind::Choice { choices, .. }, &ParamValue::Choice(index)) => {
+                if index < choices.len() {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "choice {} is out of range, there are {} choices",
+                        index,
+                        choices.len()
+                    ))
+                }
+            }
+            (ParamKind::List, ParamValue::List(values)) => {
+                match values.iter().find(|value| !value.is_finite()) {
+                    Some(value) => Err(format!("the list contains {}", value)),
+                    None => Ok(()),
+                }
+            }
+            (kind, value) => Err(format!("expected {}, got {:?}", kind.type_name(), value)),
+        }
+    }
+}
+
+/// The type of a parameter, with its default and valid range.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParamKind {
+    Float {
+        default: f64,
+        min: f64,
+        max: f64,
+    },
+    Int {
+        default: i64,
+        min: i64,
+        max: i64,
+    },
+    /// A seed, which may be any `u64`.
+    Seed {
+        default: u64,
+    },
+    Bool {
+        default: bool,
+    },
+    /// One of a fixed set of named options, chosen by index.
+    Choice {
+        default: usize,
+        choices: &'static [&'static str],
+    },
+    /// A list of numbers, empty by default. For `Curve` it holds the input
+    /// and output value of each control point in turn, and for `Worley` the
+    /// weights of up to
+    /// [`NEAREST_SEED_POINTS`](crate::NEAREST_SEED_POINTS) distances.
+    List,
+}
+
+impl ParamKind {
+    fn type_name(self) -> &'static str {
+        match self {
+            ParamKind::Float { .. } => "a float",
+            ParamKind::Int { .. } => "an integer",
+            ParamKind::Seed { .. } => "a seed",
+            ParamKind::Bool { .. } => "a bool",
+            ParamKind::Choice { .. } => "a choice",
+            ParamKind::List => "a list",
+        }
+    }
+}
+
+/// The value of a parameter.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParamValue {
+    Float(f64),
+    Int(i64),
+    Seed(u64),
+    Bool(bool),
+    Choice(usize),
+    List(Vec<f64>),
+}
@@ -11,9 +11,13 @@
 
 #![deny(missing_copy_implementations)]
 
-pub use crate::noise_fns::*;
+pub use crate::{
+    noise_fns::*,
+    permutationtable::{IntegerHasher, NoiseHasher, PermutationTable, SeedableHasher},
+};
 
 mod gradient;
+pub mod graph;
 mod math;
 mod noise_fns;
 mod permutationtable;
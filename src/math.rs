@@ -3,7 +3,9 @@
 
 use std::ops::{Add, Mul, Sub};
 
+pub(crate) mod dual;
 pub(crate) mod interpolate;
+pub(crate) mod lanes;
 pub(crate) mod s_curve;
 
 /// Cast a numeric type without having to unwrap - we don't expect any overflow
@@ -211,21 +213,6 @@ pub(crate) fn const4<T: Copy>(x: T) -> [T; 4] {
     [x, x, x, x]
 }
 
-#[inline]
-pub(crate) fn one2<T: Copy + From<i8>>() -> [T; 2] {
-    cast2(const2(1))
-}
-
-#[inline]
-pub(crate) fn one3<T: Copy + From<i8>>() -> [T; 3] {
-    cast3(const3(1))
-}
-
-#[inline]
-pub(crate) fn one4<T: Copy + From<i8>>() -> [T; 4] {
-    cast4(const4(1))
-}
-
 #[inline]
 pub(crate) fn cast2<T, U>(x: [T; 2]) -> [U; 2]
 where
@@ -0,0 +1,228 @@
+//! A value along with its gradient, for computing analytic derivatives.
+//!
+//! Arithmetic on a [`Dual`] applies the chain rule to the gradient, so running the same
+//! expression on duals as on plain `f64`s yields the derivatives along with the value. The value
+//! is always computed with exactly the same operations as the `f64` version, so the two agree bit
+//! for bit.
+
+use crate::math::{self, s_curve::quintic::Quintic};
+use num_traits::MulAdd;
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Dual<const DIM: usize> {
+    pub(crate) value: f64,
+    pub(crate) gradient: [f64; DIM],
+}
+
+impl<const DIM: usize> Dual<DIM> {
+    #[inline(always)]
+    pub(crate) fn new(value: f64, gradient: [f64; DIM]) -> Self {
+        Self { value, gradient }
+    }
+
+    /// A value that doesn't depend on the point.
+    #[inline(always)]
+    pub(crate) fn constant(value: f64) -> Self {
+        Self::new(value, [0.0; DIM])
+    }
+
+    #[inline(always)]
+    pub(crate) fn into_tuple(self) -> (f64, [f64; DIM]) {
+        (self.value, self.gradient)
+    }
+
+    #[inline(always)]
+    fn map_gradient<F>(self, value: f64, f: F) -> Self
+    where
+        F: Fn(f64) -> f64,
+    {
+        let mut gradient = self.gradient;
+        for component in gradient.iter_mut() {
+            *component = f(*component);
+        }
+        Self::new(value, gradient)
+    }
+
+    #[inline(always)]
+    fn zip_gradient<F>(self, other: Self, value: f64, f: F) -> Self
+    where
+        F: Fn(f64, f64) -> f64,
+    {
+        let mut gradient = self.gradient;
+        for (component, &other) in gradient.iter_mut().zip(other.gradient.iter()) {
+            *component = f(*component, other);
+        }
+        Self::new(value, gradient)
+    }
+
+    /// Multiplies the gradient alone by `factor`, as for a function evaluated at a point that was
+    /// scaled by `factor`.
+    #[inline(always)]
+    pub(crate) fn scale_gradient(self, factor: f64) -> Self {
+        self.map_gradient(self.value, |g| g * factor)
+    }
+
+    #[inline(always)]
+    pub(crate) fn abs(self) -> Self {
+        let sign = if self.value < 0.0 { -1.0 } else { 1.0 };
+        self.map_gradient(self.value.abs(), |g| g * sign)
+    }
+
+    /// Like [`f64::max`]. Where `other` is chosen, the gradient is zero.
+    #[inline(always)]
+    pub(crate) fn max(self, other: f64) -> Self {
+        if self.value < other {
+            Self::constant(self.value.max(other))
+        } else {
+            self
+        }
+    }
+
+    /// Like [`f64::clamp`]. Where the value is clamped, the gradient is zero.
+    #[inline(always)]
+    pub(crate) fn clamp(self, min: f64, max: f64) -> Self {
+        if self.value < min || self.value > max {
+            Self::constant(self.value.clamp(min, max))
+        } else {
+            self
+        }
+    }
+
+    /// Like [`math::scale_shift`].
+    #[inline(always)]
+    pub(crate) fn scale_shift(self, n: f64) -> Self {
+        let value = math::scale_shift(self.value, n);
+        self.abs().map_gradient(value, |g| g * n)
+    }
+}
+
+/// Maps each coordinate of `distance` onto the quintic S-curve, like
+/// [`Quintic::map_quintic`], with its derivative along the matching axis.
+#[inline(always)]
+pub(crate) fn quintic<const DIM: usize>(distance: [f64; DIM]) -> [Dual<DIM>; DIM] {
+    let mut weights = [Dual::constant(0.0); DIM];
+    for (axis, (weight, &t)) in weights.iter_mut().zip(distance.iter()).enumerate() {
+        weight.value = t.map_quintic();
+        weight.gradient[axis] = 30.0 * t * t * (t - 1.0) * (t - 1.0);
+    }
+    weights
+}
+
+/// The contribution `attn⁴ (g · d)` of a lattice point to simplex-type noise, where `d` is the
+/// offset of the point from the lattice point, `g` is the gradient of the lattice point, `attn` is
+/// the radius of influence minus `d · d`, and `dot` is `g · d`.
+#[inline(always)]
+pub(crate) fn surflet<const DIM: usize>(
+    attn: f64,
+    dot: f64,
+    gradient: [f64; DIM],
+    offset: [f64; DIM],
+) -> Dual<DIM> {
+    let attn3 = attn * attn * attn;
+    let mut result = [0.0; DIM];
+    for axis in 0..DIM {
+        result[axis] = attn3 * (attn * gradient[axis] - 8.0 * dot * offset[axis]);
+    }
+    Dual::new(attn.powi(4) * dot, result)
+}
+
+impl<const DIM: usize> From<(f64, [f64; DIM])> for Dual<DIM> {
+    #[inline(always)]
+    fn from((value, gradient): (f64, [f64; DIM])) -> Self {
+        Self::new(value, gradient)
+    }
+}
+
+impl<const DIM: usize> Add for Dual<DIM> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn add(self, other: Self) -> Self {
+        self.zip_gradient(other, self.value + other.value, |a, b| a + b)
+    }
+}
+
+impl<const DIM: usize> AddAssign for Dual<DIM> {
+    #[inline(always)]
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl<const DIM: usize> Sub for Dual<DIM> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn sub(self, other: Self) -> Self {
+        self.zip_gradient(other, self.value - other.value, |a, b| a - b)
+    }
+}
+
+#[allow(clippy::suspicious_arithmetic_impl)]
+impl<const DIM: usize> Mul for Dual<DIM> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn mul(self, other: Self) -> Self {
+        let (a, b) = (self.value, other.value);
+        self.zip_gradient(other, a * b, |da, db| da * b + a * db)
+    }
+}
+
+impl<const DIM: usize> Mul<f64> for Dual<DIM> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn mul(self, other: f64) -> Self {
+        self.map_gradient(self.value * other, |g| g * other)
+    }
+}
+
+#[allow(clippy::suspicious_arithmetic_impl)]
+impl<const DIM: usize> Div for Dual<DIM> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn div(self, other: Self) -> Self {
+        let (a, b) = (self.value, other.value);
+        self.zip_gradient(other, a / b, |da, db| (da * b - a * db) / (b * b))
+    }
+}
+
+impl<const DIM: usize> Div<f64> for Dual<DIM> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn div(self, other: f64) -> Self {
+        self.map_gradient(self.value / other, |g| g / other)
+    }
+}
+
+impl<const DIM: usize> Neg for Dual<DIM> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn neg(self) -> Self {
+        self.map_gradient(-self.value, |g| -g)
+    }
+}
+
+impl<const DIM: usize> MulAdd for Dual<DIM> {
+    type Output = Self;
+
+    #[inline(always)]
+    fn mul_add(self, a: Self, b: Self) -> Self {
+        let value = self.value.mul_add(a.value, b.value);
+        let (x, y) = (self.value, a.value);
+        let mut gradient = b.gradient;
+        for ((component, &da), &db) in gradient
+            .iter_mut()
+            .zip(self.gradient.iter())
+            .zip(a.gradient.iter())
+        {
+            *component += da * y + x * db;
+        }
+        Self::new(value, gradient)
+    }
+}
@@ -0,0 +1,169 @@
+//! A minimal vector type for the vectorized noise kernels.
+//!
+//! These kernels evaluate `LANES` points at a time and hold each quantity in a [`Lanes`], with one
+//! element per point. Every operation on it is a plain loop over the lanes, which the compiler
+//! turns into a handful of vector instructions.
+
+use crate::math::interpolate;
+use num_traits::MulAdd;
+use std::ops::{Add, Index, IndexMut, Mul, Sub};
+
+/// Number of points evaluated together by the vectorized noise kernels.
+pub(crate) const LANES: usize = 4;
+
+/// One `f64` per lane.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Lanes(pub(crate) [f64; LANES]);
+
+impl Lanes {
+    #[inline(always)]
+    pub(crate) fn map<F>(self, f: F) -> Self
+    where
+        F: Fn(f64) -> f64,
+    {
+        let mut result = self.0;
+        for value in result.iter_mut() {
+            *value = f(*value);
+        }
+        Lanes(result)
+    }
+
+    #[inline(always)]
+    pub(crate) fn zip_with<F>(self, other: Self, f: F) -> Self
+    where
+        F: Fn(f64, f64) -> f64,
+    {
+        let mut result = self.0;
+        for (value, other) in result.iter_mut().zip(other.0.iter()) {
+            *value = f(*value, *other);
+        }
+        Lanes(result)
+    }
+}
+
+impl Index<usize> for Lanes {
+    type Output = f64;
+
+    #[inline(always)]
+    fn index(&self, lane: usize) -> &f64 {
+        &self.0[lane]
+    }
+}
+
+impl IndexMut<usize> for Lanes {
+    #[inline(always)]
+    fn index_mut(&mut self, lane: usize) -> &mut f64 {
+        &mut self.0[lane]
+    }
+}
+
+impl Add for Lanes {
+    type Output = Self;
+
+    #[inline(always)]
+    fn add(self, other: Self) -> Self {
+        self.zip_with(other, |a, b| a + b)
+    }
+}
+
+impl Sub for Lanes {
+    type Output = Self;
+
+    #[inline(always)]
+    fn sub(self, other: Self) -> Self {
+        self.zip_with(other, |a, b| a - b)
+    }
+}
+
+impl Mul for Lanes {
+    type Output = Self;
+
+    #[inline(always)]
+    fn mul(self, other: Self) -> Self {
+        self.zip_with(other, |a, b| a * b)
+    }
+}
+
+impl Mul<f64> for Lanes {
+    type Output = Self;
+
+    #[inline(always)]
+    fn mul(self, other: f64) -> Self {
+        self.map(|a| a * other)
+    }
+}
+
+impl MulAdd for Lanes {
+    type Output = Self;
+
+    #[inline(always)]
+    fn mul_add(self, a: Self, b: Self) -> Self {
+        let mut result = self.0;
+        for lane in 0..LANES {
+            result[lane] = result[lane].mul_add(a[lane], b[lane]);
+        }
+        Lanes(result)
+    }
+}
+
+/// Returns the offset of corner `index` of a unit hypercube from its origin.
+///
+/// Bit `n` of `index` is the offset along axis `n`, so consecutive pairs of corners differ only
+/// along the first axis.
+#[inline(always)]
+pub(crate) fn corner_offset<const DIM: usize>(index: usize) -> [isize; DIM] {
+    let mut offset = [0; DIM];
+    for (axis, offset) in offset.iter_mut().enumerate() {
+        *offset = ((index >> axis) & 1) as isize;
+    }
+    offset
+}
+
+/// Splits each point into the lattice cell containing it and its position within that cell.
+#[inline(always)]
+pub(crate) fn split_cells<const DIM: usize>(
+    points: &[[f64; DIM]; LANES],
+) -> ([[isize; DIM]; LANES], [Lanes; DIM]) {
+    let mut cells = [[0; DIM]; LANES];
+    let mut distance = [Lanes::default(); DIM];
+
+    for (lane, (point, cell)) in points.iter().zip(cells.iter_mut()).enumerate() {
+        for axis in 0..DIM {
+            let floored = point[axis].floor();
+            cell[axis] = floored as isize;
+            distance[axis][lane] = point[axis] - floored;
+        }
+    }
+
+    (cells, distance)
+}
+
+/// Maps every lane onto the quintic S-curve, like
+/// [`Quintic::map_quintic`](crate::math::s_curve::quintic::Quintic::map_quintic).
+#[inline(always)]
+pub(crate) fn quintic(x: Lanes) -> Lanes {
+    x.map(|x| x * x * x * (x * (x * 6.0 - 15.0) + 10.0))
+}
+
+/// Linearly interpolates the values at the corners of the unit hypercube, ordered as by
+/// [`corner_offset`], one axis at a time.
+///
+/// `CORNERS` must equal `2^DIM`.
+#[inline(always)]
+pub(crate) fn interpolate_corners<T, const DIM: usize, const CORNERS: usize>(
+    mut values: [T; CORNERS],
+    weights: [T; DIM],
+) -> T
+where
+    T: Copy + MulAdd<Output = T> + Sub<Output = T>,
+{
+    let mut len = CORNERS;
+    for &weight in weights.iter() {
+        len /= 2;
+        for index in 0..len {
+            values[index] = interpolate::linear(values[2 * index], values[2 * index + 1], weight);
+        }
+    }
+
+    values[0]
+}
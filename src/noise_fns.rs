@@ -1,10 +1,14 @@
+use crate::math::lanes::{Lanes, LANES};
+use std::{convert::TryInto, sync::Arc};
+
 pub use self::{
-    cache::*, combiners::*, generators::*, modifiers::*, selectors::*, transformers::*,
+    cache::*, combiners::*, compose::*, generators::*, modifiers::*, selectors::*, transformers::*,
 };
 
 mod cache;
 mod combiners;
-mod generators;
+mod compose;
+pub(crate) mod generators;
 mod modifiers;
 mod selectors;
 mod transformers;
@@ -18,26 +22,561 @@ mod transformers;
 /// value. Some of these methods include:
 ///
 /// * Calculating a value using a coherent-noise function or some other
-///     mathematical function.
+///   mathematical function.
 /// * Mathematically changing the output value from another noise function
-///     in various ways.
+///   in various ways.
 /// * Combining the output values from two noise functions in various ways.
+///
+/// Every noise function accepts both `f64` and `f32` coordinates. The
+/// generators always compute in `f64`, so an `f32` point gives exactly the
+/// same value as the equivalent `f64` point.
 pub trait NoiseFn<T, const DIM: usize> {
     fn get(&self, point: [T; DIM]) -> f64;
+
+    /// Evaluates the function at every point in `points`, storing each result
+    /// in the matching element of `output`.
+    ///
+    /// The results match calling [`get`](Self::get) once per point.
+    /// Generators override this to share work between neighbouring points, so
+    /// it is much faster when the points lie close together, such as a row of
+    /// a densely sampled grid.
+    ///
+    /// `Perlin`, `Simplex` and `Value` evaluate several points at once with
+    /// vectorized kernels when the CPU supports them. These perform the same
+    /// floating point operations in the same order as `get`, so the results
+    /// are identical on every CPU.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `points` and `output` have different lengths.
+    fn get_batch(&self, points: &[[T; DIM]], output: &mut [f64])
+    where
+        T: Copy,
+    {
+        evaluate_batch(points, output, |point| self.get(point));
+    }
+
+    /// Returns the value at `point` as seen by a sample that covers `width`
+    /// units around it, such as a pixel of an image.
+    ///
+    /// Functions that add up detail at many frequencies, like the fractals,
+    /// fade out the detail that is too fine for the sample to resolve, which
+    /// would otherwise alias. A `width` of zero gives the same value as
+    /// [`get`](Self::get), which is what functions that don't filter return
+    /// for any `width`.
+    ///
+    /// Modifiers, combiners, selectors and caches pass the width on to their
+    /// sources. Transformers pass it on scaled by how much they stretch the
+    /// space around the point, so a fractal keeps filtering when it is
+    /// wrapped in a `ScalePoint` or `Turbulence`.
+    fn get_filtered(&self, point: [T; DIM], width: f64) -> f64 {
+        let _ = width;
+        self.get(point)
+    }
+
+    /// Evaluates [`get_filtered`](Self::get_filtered) at every point in
+    /// `points`, like [`get_batch`](Self::get_batch).
+    ///
+    /// Functions that override `get_filtered` override this as well, while
+    /// the rest evaluate the batch with `get_batch`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `points` and `output` have different lengths.
+    fn get_batch_filtered(&self, points: &[[T; DIM]], width: f64, output: &mut [f64])
+    where
+        T: Copy,
+    {
+        let _ = width;
+        self.get_batch(points, output);
+    }
+}
+
+/// Noise functions that can compute their gradient analytically.
+///
+/// The gradient holds the partial derivative of the output with respect to
+/// each coordinate of the point. It is exact, rather than estimated from
+/// extra samples by finite differences, which makes it suitable for surface
+/// normals and slope dependent effects.
+///
+/// This is implemented by `Perlin`, `Simplex`, `OpenSimplex`, `SuperSimplex`,
+/// `OpenSimplex2S`, `OpenSimplex2F` and `Value`, by the fractals, and by `Add`, `Divide`, `Multiply`,
+/// `Negate`, `ScaleBias` and `Constant` when their sources implement it.
+///
+/// ```
+/// use noise::{Fbm, NoiseFn, NoiseFnGradient};
+///
+/// let fbm = Fbm::new();
+/// let (value, [dx, dy, dz]) = fbm.get_with_gradient([1.0, 2.0, 3.0]);
+/// assert_eq!(value, fbm.get([1.0, 2.0, 3.0]));
+/// ```
+pub trait NoiseFnGradient<const DIM: usize>: NoiseFn<f64, DIM> {
+    /// Returns the same value as [`NoiseFn::get`], along with the gradient at
+    /// `point`.
+    fn get_with_gradient(&self, point: [f64; DIM]) -> (f64, [f64; DIM]);
 }
 
-impl<'a, T, M: NoiseFn<T, DIM>, const DIM: usize> NoiseFn<T, DIM> for &'a M {
+/// Noise functions that output a vector of values instead of a single value.
+///
+/// `N` is the number of values in the output, which need not match the
+/// dimension of the point.
+///
+/// Tuples and arrays of scalar noise functions are vector noise functions,
+/// with one output value per function. [`Channel`] does the opposite, turning
+/// one value of the output back into a scalar noise function.
+///
+/// ```
+/// use noise::{Channel, Curl, NoiseFn, NoiseFnVector, Perlin, Simplex};
+///
+/// let curl = Curl::new(0);
+/// let [vx, vy] = curl.get_vector([1.0, 2.0]);
+/// assert_eq!(Channel::new(curl, 1).get([1.0, 2.0]), vy);
+///
+/// let rgb = (Perlin::new(0), Perlin::new(1), Simplex::new(2));
+/// let [r, g, b] = rgb.get_vector([1.0, 2.0, 3.0]);
+/// ```
+pub trait NoiseFnVector<T, const DIM: usize, const N: usize> {
+    fn get_vector(&self, point: [T; DIM]) -> [f64; N];
+}
+
+/// Deserializes a `(lower, upper)` pair of bounds, checking that they are in
+/// order.
+#[cfg(feature = "serde")]
+pub(crate) fn deserialize_bounds<'de, D>(deserializer: D) -> Result<(f64, f64), D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let (lower, upper) = <(f64, f64) as serde::Deserialize>::deserialize(deserializer)?;
+    if lower > upper {
+        return Err(serde::de::Error::custom(format!(
+            "lower bound {} is larger than upper bound {}",
+            lower, upper
+        )));
+    }
+
+    Ok((lower, upper))
+}
+
+/// Fills `output` with the result of calling `f` on each element of `points`.
+///
+/// Shared by the implementations of [`NoiseFn::get_batch`].
+#[inline(always)]
+pub(crate) fn evaluate_batch<P, F>(points: &[P], output: &mut [f64], mut f: F)
+where
+    P: Copy,
+    F: FnMut(P) -> f64,
+{
+    assert_eq!(
+        points.len(),
+        output.len(),
+        "points and output must have the same length"
+    );
+
+    for (point, value) in points.iter().zip(output) {
+        *value = f(*point);
+    }
+}
+
+/// Evaluates `noise` at an `f32` point by widening it to `f64`.
+///
+/// Shared by the `f32` implementations of the generators.
+#[inline]
+pub(crate) fn get_widened<N, const DIM: usize>(noise: &N, point: [f32; DIM]) -> f64
+where
+    N: NoiseFn<f64, DIM> + ?Sized,
+{
+    noise.get(widen(point))
+}
+
+/// Like [`get_widened`], but for a whole batch of points, which are widened a
+/// block at a time so that they still reach the `f64` batch implementation.
+pub(crate) fn evaluate_batch_widened<N, const DIM: usize>(
+    noise: &N,
+    points: &[[f32; DIM]],
+    output: &mut [f64],
+) where
+    N: NoiseFn<f64, DIM> + ?Sized,
+{
+    const BLOCK_SIZE: usize = 64;
+
+    assert_eq!(
+        points.len(),
+        output.len(),
+        "points and output must have the same length"
+    );
+
+    let mut widened = [[0.0; DIM]; BLOCK_SIZE];
+    for (points, output) in points.chunks(BLOCK_SIZE).zip(output.chunks_mut(BLOCK_SIZE)) {
+        for (widened, &point) in widened.iter_mut().zip(points) {
+            *widened = widen(point);
+        }
+        noise.get_batch(&widened[..points.len()], output);
+    }
+}
+
+#[inline(always)]
+pub(crate) fn widen<const DIM: usize>(point: [f32; DIM]) -> [f64; DIM] {
+    let mut widened = [0.0; DIM];
+    for (widened, &coordinate) in widened.iter_mut().zip(point.iter()) {
+        *widened = f64::from(coordinate);
+    }
+    widened
+}
+
+#[cfg(test)]
+thread_local! {
+    static FORCE_SCALAR: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+/// Runs `f` with [`evaluate_batch_lanes`] always taking the scalar fallback on
+/// this thread, so that tests cover it on CPUs with AVX2 and FMA as well.
+#[cfg(test)]
+pub(crate) fn with_scalar_batches<F: FnOnce()>(f: F) {
+    FORCE_SCALAR.with(|force| force.set(true));
+    f();
+    FORCE_SCALAR.with(|force| force.set(false));
+}
+
+/// Like [`evaluate_batch`], but evaluates `LANES` points at a time with
+/// `lanes` when the CPU supports AVX2 and FMA.
+///
+/// The few points left over at the end, and every point on other CPUs, are
+/// evaluated with `scalar`. Both closures are given `state`, which is
+/// typically the hasher shared by all points.
+#[inline(always)]
+pub(crate) fn evaluate_batch_lanes<S, P, F, G>(
+    points: &[P],
+    output: &mut [f64],
+    state: &mut S,
+    mut scalar: F,
+    lanes: G,
+) where
+    P: Copy,
+    F: FnMut(&mut S, P) -> f64,
+    G: FnMut(&mut S, &[P; LANES]) -> Lanes,
+{
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        #[cfg(test)]
+        let vectorize = !FORCE_SCALAR.with(|force| force.get());
+        #[cfg(not(test))]
+        let vectorize = true;
+
+        if vectorize && is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma") {
+            // Safety: the required CPU features were detected above.
+            return unsafe { evaluate_batch_lanes_avx2(points, output, state, scalar, lanes) };
+        }
+    }
+
+    let _ = lanes;
+    evaluate_batch(points, output, |point| scalar(state, point))
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+#[target_feature(enable = "avx2,fma")]
+unsafe fn evaluate_batch_lanes_avx2<S, P, F, G>(
+    points: &[P],
+    output: &mut [f64],
+    state: &mut S,
+    mut scalar: F,
+    mut lanes: G,
+) where
+    P: Copy,
+    F: FnMut(&mut S, P) -> f64,
+    G: FnMut(&mut S, &[P; LANES]) -> Lanes,
+{
+    assert_eq!(
+        points.len(),
+        output.len(),
+        "points and output must have the same length"
+    );
+
+    let mut point_chunks = points.chunks_exact(LANES);
+    let mut output_chunks = output.chunks_exact_mut(LANES);
+
+    for (points, output) in (&mut point_chunks).zip(&mut output_chunks) {
+        output.copy_from_slice(&lanes(state, points.try_into().unwrap()).0);
+    }
+
+    for (point, value) in point_chunks
+        .remainder()
+        .iter()
+        .zip(output_chunks.into_remainder())
+    {
+        *value = scalar(state, *point);
+    }
+}
+
+impl<T, M, const DIM: usize> NoiseFn<T, DIM> for &M
+where
+    M: NoiseFn<T, DIM> + ?Sized,
+{
     #[inline]
     fn get(&self, point: [T; DIM]) -> f64 {
         M::get(*self, point)
     }
+
+    #[inline]
+    fn get_batch(&self, points: &[[T; DIM]], output: &mut [f64])
+    where
+        T: Copy,
+    {
+        M::get_batch(*self, points, output)
+    }
+
+    #[inline]
+    fn get_filtered(&self, point: [T; DIM], width: f64) -> f64 {
+        M::get_filtered(*self, point, width)
+    }
+
+    #[inline]
+    fn get_batch_filtered(&self, points: &[[T; DIM]], width: f64, output: &mut [f64])
+    where
+        T: Copy,
+    {
+        M::get_batch_filtered(*self, points, width, output)
+    }
+}
+
+impl<T, M, const DIM: usize> NoiseFn<T, DIM> for Box<M>
+where
+    M: NoiseFn<T, DIM> + ?Sized,
+{
+    #[inline]
+    fn get(&self, point: [T; DIM]) -> f64 {
+        M::get(self, point)
+    }
+
+    #[inline]
+    fn get_batch(&self, points: &[[T; DIM]], output: &mut [f64])
+    where
+        T: Copy,
+    {
+        M::get_batch(self, points, output)
+    }
+
+    #[inline]
+    fn get_filtered(&self, point: [T; DIM], width: f64) -> f64 {
+        M::get_filtered(self, point, width)
+    }
+
+    #[inline]
+    fn get_batch_filtered(&self, points: &[[T; DIM]], width: f64, output: &mut [f64])
+    where
+        T: Copy,
+    {
+        M::get_batch_filtered(self, points, width, output)
+    }
 }
 
+impl<T, M, const DIM: usize> NoiseFn<T, DIM> for Arc<M>
+where
+    M: NoiseFn<T, DIM> + ?Sized,
+{
+    #[inline]
+    fn get(&self, point: [T; DIM]) -> f64 {
+        M::get(self, point)
+    }
+
+    #[inline]
+    fn get_batch(&self, points: &[[T; DIM]], output: &mut [f64])
+    where
+        T: Copy,
+    {
+        M::get_batch(self, points, output)
+    }
+
+    #[inline]
+    fn get_filtered(&self, point: [T; DIM], width: f64) -> f64 {
+        M::get_filtered(self, point, width)
+    }
+
+    #[inline]
+    fn get_batch_filtered(&self, points: &[[T; DIM]], width: f64, output: &mut [f64])
+    where
+        T: Copy,
+    {
+        M::get_batch_filtered(self, points, width, output)
+    }
+}
+
+impl<M, const DIM: usize> NoiseFnGradient<DIM> for &M
+where
+    M: NoiseFnGradient<DIM> + ?Sized,
+{
+    #[inline]
+    fn get_with_gradient(&self, point: [f64; DIM]) -> (f64, [f64; DIM]) {
+        M::get_with_gradient(*self, point)
+    }
+}
+
+impl<M, const DIM: usize> NoiseFnGradient<DIM> for Box<M>
+where
+    M: NoiseFnGradient<DIM> + ?Sized,
+{
+    #[inline]
+    fn get_with_gradient(&self, point: [f64; DIM]) -> (f64, [f64; DIM]) {
+        M::get_with_gradient(self, point)
+    }
+}
+
+impl<M, const DIM: usize> NoiseFnGradient<DIM> for Arc<M>
+where
+    M: NoiseFnGradient<DIM> + ?Sized,
+{
+    #[inline]
+    fn get_with_gradient(&self, point: [f64; DIM]) -> (f64, [f64; DIM]) {
+        M::get_with_gradient(self, point)
+    }
+}
+
+impl<T, M, const DIM: usize, const N: usize> NoiseFnVector<T, DIM, N> for &M
+where
+    M: NoiseFnVector<T, DIM, N> + ?Sized,
+{
+    #[inline]
+    fn get_vector(&self, point: [T; DIM]) -> [f64; N] {
+        M::get_vector(*self, point)
+    }
+}
+
+impl<T, M, const DIM: usize, const N: usize> NoiseFnVector<T, DIM, N> for Box<M>
+where
+    M: NoiseFnVector<T, DIM, N> + ?Sized,
+{
+    #[inline]
+    fn get_vector(&self, point: [T; DIM]) -> [f64; N] {
+        M::get_vector(self, point)
+    }
+}
+
+impl<T, M, const DIM: usize, const N: usize> NoiseFnVector<T, DIM, N> for Arc<M>
+where
+    M: NoiseFnVector<T, DIM, N> + ?Sized,
+{
+    #[inline]
+    fn get_vector(&self, point: [T; DIM]) -> [f64; N] {
+        M::get_vector(self, point)
+    }
+}
+
+impl<T, S, const DIM: usize, const N: usize> NoiseFnVector<T, DIM, N> for [S; N]
+where
+    S: NoiseFn<T, DIM>,
+    T: Copy,
+{
+    fn get_vector(&self, point: [T; DIM]) -> [f64; N] {
+        let mut result = [0.0; N];
+        for (value, source) in result.iter_mut().zip(self.iter()) {
+            *value = source.get(point);
+        }
+        result
+    }
+}
+
+/// Implements [`NoiseFnVector`] for a tuple of noise functions, with one
+/// output value per function.
+macro_rules! impl_noise_fn_vector_tuple {
+    ($($n:literal: ($($source:ident $index:tt),+)),+ $(,)?) => {$(
+        impl<T, $($source,)+ const DIM: usize> NoiseFnVector<T, DIM, $n> for ($($source,)+)
+        where
+            $($source: NoiseFn<T, DIM>,)+
+            T: Copy,
+        {
+            fn get_vector(&self, point: [T; DIM]) -> [f64; $n] {
+                [$(self.$index.get(point)),+]
+            }
+        }
+    )+};
+}
+
+impl_noise_fn_vector_tuple!(
+    2: (A 0, B 1),
+    3: (A 0, B 1, C 2),
+    4: (A 0, B 1, C 2, D 3),
+);
+
 /// Trait for functions that require a seed before generating their values
 pub trait Seedable {
     /// Set the seed for the function implementing the `Seedable` trait
-    fn set_seed(self, seed: u32) -> Self;
+    fn set_seed(self, seed: u64) -> Self;
 
     /// Getter to retrieve the seed from the function
-    fn seed(&self) -> u32;
+    fn seed(&self) -> u64;
+}
+
+/// Trait for functions whose output can be made to repeat along each axis
+pub trait Periodic {
+    /// Sets the period of each axis, in lattice cells, so that the output at
+    /// `point` is the same as at `point` moved by the period along that axis.
+    ///
+    /// A period of 0 leaves its axis aperiodic, as do the axes past the end of
+    /// `period`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` has more than 4 axes.
+    fn set_period<const N: usize>(self, period: [u32; N]) -> Self;
+
+    /// Getter to retrieve the period of every axis from the function
+    fn period(&self) -> [u32; 4];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks the gradient of `noise` at `points` against central differences.
+    pub(crate) fn assert_gradient<N, const DIM: usize>(noise: &N, points: &[[f64; DIM]])
+    where
+        N: NoiseFnGradient<DIM>,
+    {
+        const STEP: f64 = 1e-6;
+
+        for &point in points {
+            let (value, gradient) = noise.get_with_gradient(point);
+            assert_eq!(value, noise.get(point));
+
+            for axis in 0..DIM {
+                let (mut above, mut below) = (point, point);
+                above[axis] += STEP;
+                below[axis] -= STEP;
+                let expected = (noise.get(above) - noise.get(below)) / (2.0 * STEP);
+                assert!(
+                    (gradient[axis] - expected).abs() < 1e-4 * expected.abs().max(1.0),
+                    "axis {} at {:?}: {} != {}",
+                    axis,
+                    point,
+                    gradient[axis],
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_f32_matches_f64() {
+        fn check<N: NoiseFn<f32, 3> + NoiseFn<f64, 3>>(noise: N) {
+            let points: Vec<[f32; 3]> = (0..200)
+                .map(|i| [i as f32 * 0.07 - 7.0, i as f32 * -0.031, 1.5])
+                .collect();
+            let mut output = vec![0.0; points.len()];
+            noise.get_batch(&points, &mut output);
+
+            for (point, value) in points.iter().zip(output) {
+                let widened = [point[0] as f64, point[1] as f64, point[2] as f64];
+                assert_eq!(noise.get(widened), value);
+                assert_eq!(noise.get(*point), value);
+            }
+        }
+
+        check(Perlin::new(1));
+        check(Simplex::new(2));
+        check(Value::new().set_seed(3));
+        check(OpenSimplex::new().set_seed(4));
+        check(SuperSimplex::new().set_seed(5));
+        check(Worley::new(6));
+        check(Fbm::new());
+        check(ScalePoint::new(Perlin::new(7)).set_scale(0.5));
+    }
 }
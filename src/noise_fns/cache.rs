@@ -1,5 +1,11 @@
-use crate::noise_fns::NoiseFn;
-use std::cell::{Cell, RefCell};
+use crate::noise_fns::{evaluate_batch, NoiseFn};
+use num_traits::AsPrimitive;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::{Cell, RefCell},
+    sync::Mutex,
+};
 
 /// Noise function that caches the last output value generated by the source
 /// function.
@@ -14,14 +20,24 @@ use std::cell::{Cell, RefCell};
 /// multiple noise functions. If a source function is not cached, the source
 /// function will redundantly calculate the same output value once for each
 /// noise function in which it is included.
+///
+/// `Cache` can be sent to another thread, but not shared between threads. Use
+/// [`SyncCache`] for a graph that is shared.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
 pub struct Cache<Source> {
     /// Outputs the value to be cached.
     pub source: Source,
 
+    #[cfg_attr(feature = "serde", serde(skip))]
     value: Cell<Option<f64>>,
 
+    #[cfg_attr(feature = "serde", serde(skip))]
     point: RefCell<Vec<f64>>,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    width: Cell<Option<f64>>,
 }
 
 impl<Source> Cache<Source> {
@@ -30,24 +46,30 @@ impl<Source> Cache<Source> {
             source,
             value: Cell::new(None),
             point: RefCell::new(Vec::new()),
+            width: Cell::new(None),
         }
     }
-}
 
-impl<Source, const DIM: usize> NoiseFn<f64, DIM> for Cache<Source>
-where
-    Source: NoiseFn<f64, DIM>,
-{
-    fn get(&self, point: [f64; DIM]) -> f64 {
+    /// Returns the cached value if it was computed at `key` with the same
+    /// filter `width`, and otherwise caches the value from `evaluate`.
+    fn get_or_evaluate(
+        &self,
+        key: &[f64],
+        width: Option<f64>,
+        evaluate: impl FnOnce() -> f64,
+    ) -> f64 {
         match self.value.get() {
-            Some(value) if quick_eq(&*self.point.borrow(), &point) => value,
+            Some(value) if self.width.get() == width && quick_eq(&self.point.borrow(), key) => {
+                value
+            }
             Some(_) | None => {
-                let value = self.source.get(point);
+                let value = evaluate();
                 self.value.set(Some(value));
+                self.width.set(width);
 
                 let mut cached_point = self.point.borrow_mut();
                 cached_point.clear();
-                cached_point.extend_from_slice(&point);
+                cached_point.extend_from_slice(key);
 
                 value
             }
@@ -55,8 +77,264 @@ where
     }
 }
 
+impl<Source, T, const DIM: usize> NoiseFn<T, DIM> for Cache<Source>
+where
+    Source: NoiseFn<T, DIM>,
+    T: AsPrimitive<f64>,
+{
+    fn get(&self, point: [T; DIM]) -> f64 {
+        self.get_or_evaluate(&key(&point), None, || self.source.get(point))
+    }
+
+    fn get_filtered(&self, point: [T; DIM], width: f64) -> f64 {
+        self.get_or_evaluate(&key(&point), Some(width), || {
+            self.source.get_filtered(point, width)
+        })
+    }
+
+    fn get_batch_filtered(&self, points: &[[T; DIM]], width: f64, output: &mut [f64])
+    where
+        T: Copy,
+    {
+        evaluate_batch(points, output, |point| self.get_filtered(point, width));
+    }
+}
+
+/// Noise function that caches the last output value generated by the source
+/// function, and can be shared between threads.
+///
+/// This behaves like [`Cache`], but keeps the cached point behind a lock. When
+/// another thread holds the lock, the source function is called directly
+/// instead of waiting, so threads never block on each other.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct SyncCache<Source> {
+    /// Outputs the value to be cached.
+    pub source: Source,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    entry: Mutex<Option<CacheEntry>>,
+}
+
+/// The point, filter width and value last cached by a [`SyncCache`].
+#[derive(Debug)]
+struct CacheEntry {
+    point: Vec<f64>,
+    width: Option<f64>,
+    value: f64,
+}
+
+impl<Source> SyncCache<Source> {
+    pub fn new(source: Source) -> Self {
+        SyncCache {
+            source,
+            entry: Mutex::new(None),
+        }
+    }
+}
+
+impl<Source> SyncCache<Source> {
+    /// Returns the cached value if it was computed at `key` with the same
+    /// filter `width`, and otherwise caches the value from `evaluate`.
+    fn get_or_evaluate(
+        &self,
+        key: &[f64],
+        width: Option<f64>,
+        evaluate: impl FnOnce() -> f64,
+    ) -> f64 {
+        let mut entry = match self.entry.try_lock() {
+            Ok(entry) => entry,
+            Err(_) => return evaluate(),
+        };
+
+        match &mut *entry {
+            Some(entry) if entry.width == width && quick_eq(&entry.point, key) => entry.value,
+            Some(entry) => {
+                entry.value = evaluate();
+                entry.width = width;
+                entry.point.clear();
+                entry.point.extend_from_slice(key);
+
+                entry.value
+            }
+            None => {
+                let value = evaluate();
+                *entry = Some(CacheEntry {
+                    point: key.to_vec(),
+                    width,
+                    value,
+                });
+
+                value
+            }
+        }
+    }
+}
+
+impl<Source: Clone> Clone for SyncCache<Source> {
+    fn clone(&self) -> Self {
+        Self::new(self.source.clone())
+    }
+}
+
+impl<Source, T, const DIM: usize> NoiseFn<T, DIM> for SyncCache<Source>
+where
+    Source: NoiseFn<T, DIM>,
+    T: AsPrimitive<f64>,
+{
+    fn get(&self, point: [T; DIM]) -> f64 {
+        self.get_or_evaluate(&key(&point), None, || self.source.get(point))
+    }
+
+    fn get_filtered(&self, point: [T; DIM], width: f64) -> f64 {
+        self.get_or_evaluate(&key(&point), Some(width), || {
+            self.source.get_filtered(point, width)
+        })
+    }
+
+    fn get_batch_filtered(&self, points: &[[T; DIM]], width: f64, output: &mut [f64])
+    where
+        T: Copy,
+    {
+        evaluate_batch(points, output, |point| self.get_filtered(point, width));
+    }
+}
+
+/// Converts `point` to the key it is cached under.
+fn key<T, const DIM: usize>(point: &[T; DIM]) -> [f64; DIM]
+where
+    T: AsPrimitive<f64>,
+{
+    // Points are cached as `f64` whatever their type, which is lossless for `f32`.
+    let mut key = [0.0; DIM];
+    for (key, coordinate) in key.iter_mut().zip(point.iter()) {
+        *key = coordinate.as_();
+    }
+
+    key
+}
+
 fn quick_eq(a: &[f64], b: &[f64]) -> bool {
     assert_eq!(a.len(), b.len());
 
     a.iter().eq(b)
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::sync::Arc;
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    #[test]
+    fn test_send_sync() {
+        let hills: Box<dyn NoiseFn<f64, 3> + Send + Sync> = Box::new(Fbm::new());
+        let graph = Select::new(Abs::new(Perlin::new(0)), hills, Arc::new(Simplex::new(0)));
+        assert_send_sync(&graph);
+        assert_send(&Cache::new(Perlin::new(0)));
+        assert_send_sync(&SyncCache::new(graph));
+
+        let generators = (
+            (
+                BasicMulti::new(),
+                Billow::new(),
+                Checkerboard::new(1),
+                Constant::new(0.0),
+            ),
+            (
+                Cylinders::new(),
+                Fbm::new(),
+                HybridMulti::new(),
+                OpenSimplex::new(),
+            ),
+            (
+                Perlin::new(0),
+                RidgedMulti::new(),
+                Simplex::new(0),
+                SuperSimplex::new(),
+            ),
+            (Value::new(), Worley::new(0), ErodedFbm::new()),
+            (OpenSimplex2S::new(), OpenSimplex2F::new()),
+        );
+        assert_send_sync(&generators);
+
+        let source = || Arc::new(Perlin::new(0));
+        let modules = (
+            (
+                Abs::new(source()),
+                Clamp::new(source()),
+                Curve::new(source()),
+            ),
+            (
+                Exponent::new(source()),
+                Negate::new(source()),
+                ScaleBias::new(source()),
+            ),
+            (Terrace::new(source()), Add::new(source(), source())),
+            (
+                Divide::new(source(), source()),
+                Max::new(source(), source()),
+            ),
+            (
+                Min::new(source(), source()),
+                Multiply::new(source(), source()),
+            ),
+            (
+                Power::new(source(), source()),
+                Blend::new(source(), source(), source()),
+            ),
+            (
+                Select::new(source(), source(), source()),
+                RotatePoint::new(source()),
+            ),
+            (
+                ScalePoint::new(source()),
+                TranslatePoint::new(source()),
+                Turbulence::new(source()),
+            ),
+            Displace::new(source(), [source(), source(), source(), source()]),
+            Channel::<_, 3>::new(Curl::new(0), 0),
+        );
+        assert_send_sync(&modules);
+    }
+
+    #[test]
+    fn test_sync_cache_shared() {
+        let source = Fbm::new().set_seed(3);
+        let cache = SyncCache::new(source.clone());
+
+        std::thread::scope(|scope| {
+            for thread in 0..4 {
+                let (cache, source) = (&cache, &source);
+                scope.spawn(move || {
+                    for step in 0..100 {
+                        let point = [f64::from(step % 7), f64::from(thread), 0.5];
+                        assert_eq!(cache.get(point), source.get(point));
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn test_cache_filtered() {
+        let point = [0.4, 1.7, -2.3];
+        let source = BasicMulti::new();
+        let (filtered, unfiltered) = (source.get_filtered(point, 0.125), source.get(point));
+        assert_ne!(filtered, unfiltered);
+
+        // The width is part of the cached point, so neither value hides the other.
+        let cache = Cache::new(&source);
+        let sync_cache = SyncCache::new(&source);
+        for _ in 0..2 {
+            assert_eq!(cache.get(point), unfiltered);
+            assert_eq!(cache.get_filtered(point, 0.125), filtered);
+            assert_eq!(sync_cache.get(point), unfiltered);
+            assert_eq!(sync_cache.get_filtered(point, 0.125), filtered);
+        }
+    }
+}
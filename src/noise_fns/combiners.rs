@@ -1,7 +1,30 @@
-pub use self::{add::*, max::*, min::*, multiply::*, power::*};
+pub use self::{add::*, divide::*, max::*, min::*, multiply::*, power::*};
 
 mod add;
+mod divide;
 mod max;
 mod min;
 mod multiply;
 mod power;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        noise_fns::tests::assert_gradient, Constant, Negate, Perlin, ScaleBias, Simplex, Value,
+    };
+
+    #[test]
+    fn test_gradients() {
+        let points = [[0.4, 1.7, -2.3], [-5.1, 0.23, 3.3], [13.37, -2.9, 0.61]];
+
+        let expression = Add::new(
+            Multiply::new(Perlin::new(1), Simplex::new(2)),
+            ScaleBias::new(Negate::new(Value::new()))
+                .set_scale(0.5)
+                .set_bias(0.25),
+        );
+        assert_gradient(&expression, &points);
+        assert_gradient(&Divide::new(Perlin::new(3), Constant::new(2.0)), &points);
+    }
+}
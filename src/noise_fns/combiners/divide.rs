@@ -0,0 +1,57 @@
+use crate::{
+    math::dual::Dual,
+    noise_fns::{evaluate_batch, NoiseFn, NoiseFnGradient},
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Noise function that outputs the quotient of the output values from two source
+/// functions.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Divide<Source1, Source2> {
+    /// Outputs the dividend.
+    pub source1: Source1,
+
+    /// Outputs the divisor.
+    pub source2: Source2,
+}
+
+impl<Source1, Source2> Divide<Source1, Source2> {
+    pub fn new(source1: Source1, source2: Source2) -> Self {
+        Self { source1, source2 }
+    }
+}
+
+impl<Source1, Source2, T, const DIM: usize> NoiseFn<T, DIM> for Divide<Source1, Source2>
+where
+    Source1: NoiseFn<T, DIM>,
+    Source2: NoiseFn<T, DIM>,
+    T: Copy,
+{
+    fn get(&self, point: [T; DIM]) -> f64 {
+        self.source1.get(point) / self.source2.get(point)
+    }
+
+    fn get_filtered(&self, point: [T; DIM], width: f64) -> f64 {
+        self.source1.get_filtered(point, width) / self.source2.get_filtered(point, width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[T; DIM]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.get_filtered(point, width));
+    }
+}
+
+impl<Source1, Source2, const DIM: usize> NoiseFnGradient<DIM> for Divide<Source1, Source2>
+where
+    Source1: NoiseFnGradient<DIM>,
+    Source2: NoiseFnGradient<DIM>,
+{
+    fn get_with_gradient(&self, point: [f64; DIM]) -> (f64, [f64; DIM]) {
+        let a = Dual::from(self.source1.get_with_gradient(point));
+        let b = Dual::from(self.source2.get_with_gradient(point));
+
+        (a / b).into_tuple()
+    }
+}
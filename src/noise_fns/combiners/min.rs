@@ -1,26 +1,41 @@
-use crate::noise_fns::NoiseFn;
+use crate::noise_fns::{evaluate_batch, NoiseFn};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Noise function that outputs the smaller of the two output values from two source
 /// functions.
-pub struct Min<'a, T, const DIM: usize> {
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Min<Source1, Source2> {
     /// Outputs a value.
-    pub source1: &'a dyn NoiseFn<T, DIM>,
+    pub source1: Source1,
 
     /// Outputs a value.
-    pub source2: &'a dyn NoiseFn<T, DIM>,
+    pub source2: Source2,
 }
 
-impl<'a, T, const DIM: usize> Min<'a, T, DIM> {
-    pub fn new(source1: &'a dyn NoiseFn<T, DIM>, source2: &'a dyn NoiseFn<T, DIM>) -> Self {
+impl<Source1, Source2> Min<Source1, Source2> {
+    pub fn new(source1: Source1, source2: Source2) -> Self {
         Self { source1, source2 }
     }
 }
 
-impl<'a, T, const DIM: usize> NoiseFn<T, DIM> for Min<'a, T, DIM>
+impl<Source1, Source2, T, const DIM: usize> NoiseFn<T, DIM> for Min<Source1, Source2>
 where
+    Source1: NoiseFn<T, DIM>,
+    Source2: NoiseFn<T, DIM>,
     T: Copy,
 {
     fn get(&self, point: [T; DIM]) -> f64 {
         (self.source1.get(point)).min(self.source2.get(point))
     }
+
+    fn get_filtered(&self, point: [T; DIM], width: f64) -> f64 {
+        (self.source1.get_filtered(point, width)).min(self.source2.get_filtered(point, width))
+    }
+
+    fn get_batch_filtered(&self, points: &[[T; DIM]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.get_filtered(point, width));
+    }
 }
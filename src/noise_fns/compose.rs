@@ -0,0 +1,333 @@
+use crate::noise_fns::{
+    Abs, Add, BasicMulti, Billow, Blend, Cache, Channel, Checkerboard, Clamp, Constant, Curve,
+    Cylinders, Displace, Divide, ErodedFbm, Exponent, Fbm, HybridMulti, Max, Min, Multiply, Negate,
+    NoiseFn, OpenSimplex, OpenSimplex2F, OpenSimplex2S, Perlin, PerlinSurflet, Power, RidgedMulti,
+    RotatePoint, ScaleBias, ScalePoint, Select, Simplex, SuperSimplex, SyncCache, Terrace,
+    TranslatePoint, Turbulence, Value, Worley,
+};
+use std::{ops, sync::Arc};
+
+/// Methods for building noise graphs by chaining, instead of nesting the
+/// constructors of the modules.
+///
+/// Every noise function in this crate implements this trait, along with the
+/// `+`, `-`, `*`, `/` and unary `-` operators, which combine noise functions
+/// with [`Add`], [`Negate`], [`Multiply`] and [`Divide`]. All of them take
+/// their sources by value, so the resulting graph can be returned and stored
+/// like any other value.
+///
+/// ```
+/// use noise::{Fbm, NoiseFn, NoiseFnExt, Perlin, RidgedMulti, Seedable};
+///
+/// let hills = Fbm::new().scale_bias(0.5, -0.25);
+/// let ridges = RidgedMulti::new().set_seed(1).abs();
+/// let terrain = (hills + ridges * Perlin::new(2)).clamp(-1.0, 1.0);
+///
+/// let value: f64 = terrain.get([1.0, 2.0, 3.0]);
+/// ```
+///
+/// Noise functions defined outside this crate can opt in with an empty
+/// implementation.
+pub trait NoiseFnExt {
+    /// Outputs the absolute value of this function. See [`Abs`].
+    fn abs(self) -> Abs<Self>
+    where
+        Self: Sized,
+    {
+        Abs::new(self)
+    }
+
+    /// Clamps the output of this function to `lower_bound..=upper_bound`. See
+    /// [`Clamp`].
+    fn clamp(self, lower_bound: f64, upper_bound: f64) -> Clamp<Self>
+    where
+        Self: Sized,
+    {
+        Clamp::new(self).set_bounds(lower_bound, upper_bound)
+    }
+
+    /// Maps the output of this function onto a curve that has no control
+    /// points yet. See [`Curve`].
+    fn curve(self) -> Curve<Self>
+    where
+        Self: Sized,
+    {
+        Curve::new(self)
+    }
+
+    /// Maps the output of this function onto an exponential curve. See
+    /// [`Exponent`].
+    fn exponent(self, exponent: f64) -> Exponent<Self>
+    where
+        Self: Sized,
+    {
+        Exponent::new(self).set_exponent(exponent)
+    }
+
+    /// Multiplies the output of this function by `scale`, then adds `bias`.
+    /// See [`ScaleBias`].
+    fn scale_bias(self, scale: f64, bias: f64) -> ScaleBias<Self>
+    where
+        Self: Sized,
+    {
+        ScaleBias::new(self).set_scale(scale).set_bias(bias)
+    }
+
+    /// Maps the output of this function onto a terrace-forming curve that has
+    /// no control points yet. See [`Terrace`].
+    fn terrace(self) -> Terrace<Self>
+    where
+        Self: Sized,
+    {
+        Terrace::new(self)
+    }
+
+    /// Outputs the larger of the outputs of this function and `other`. See
+    /// [`Max`].
+    fn max<Other>(self, other: Other) -> Max<Self, Other>
+    where
+        Self: Sized,
+    {
+        Max::new(self, other)
+    }
+
+    /// Outputs the smaller of the outputs of this function and `other`. See
+    /// [`Min`].
+    fn min<Other>(self, other: Other) -> Min<Self, Other>
+    where
+        Self: Sized,
+    {
+        Min::new(self, other)
+    }
+
+    /// Raises the output of this function to the power of the output of
+    /// `exponent`. See [`Power`].
+    fn pow<Other>(self, exponent: Other) -> Power<Self, Other>
+    where
+        Self: Sized,
+    {
+        Power::new(self, exponent)
+    }
+
+    /// Blends between the outputs of this function and `other`, weighted by
+    /// the output of `control`. See [`Blend`].
+    fn blend<Other, Control>(self, other: Other, control: Control) -> Blend<Self, Other, Control>
+    where
+        Self: Sized,
+    {
+        Blend::new(self, other, control)
+    }
+
+    /// Outputs `other` where the output of `control` lies within
+    /// `lower_bound..=upper_bound`, and this function everywhere else. See
+    /// [`Select`].
+    fn select<Other, Control>(
+        self,
+        other: Other,
+        control: Control,
+        lower_bound: f64,
+        upper_bound: f64,
+    ) -> Select<Self, Other, Control>
+    where
+        Self: Sized,
+    {
+        Select::new(self, other, control).set_bounds(lower_bound, upper_bound)
+    }
+
+    /// Moves the input point by the given translations before evaluating this
+    /// function. See [`TranslatePoint`].
+    fn translate(self, x: f64, y: f64, z: f64, u: f64) -> TranslatePoint<Self>
+    where
+        Self: Sized,
+    {
+        TranslatePoint::new(self).set_all_translations(x, y, z, u)
+    }
+
+    /// Scales the input point by the given factors before evaluating this
+    /// function. See [`ScalePoint`].
+    fn scale(self, x: f64, y: f64, z: f64, u: f64) -> ScalePoint<Self>
+    where
+        Self: Sized,
+    {
+        ScalePoint::new(self).set_all_scales(x, y, z, u)
+    }
+
+    /// Rotates the input point by the given angles, in degrees, before
+    /// evaluating this function. See [`RotatePoint`].
+    fn rotate(self, x: f64, y: f64, z: f64, u: f64) -> RotatePoint<Self>
+    where
+        Self: Sized,
+    {
+        RotatePoint::new(self).set_angles(x, y, z, u)
+    }
+
+    /// Randomly displaces the input point before evaluating this function.
+    /// See [`Turbulence`].
+    fn turbulence(self, frequency: f64, power: f64, roughness: usize) -> Turbulence<Self>
+    where
+        Self: Sized,
+    {
+        Turbulence::new(self)
+            .set_frequency(frequency)
+            .set_power(power)
+            .set_roughness(roughness)
+    }
+
+    /// Displaces each coordinate of the input point by the matching output
+    /// value of a vector noise function before evaluating this function. See
+    /// [`Displace`].
+    fn displace<Displacement>(self, displacement: Displacement) -> Displace<Self, Displacement>
+    where
+        Self: Sized,
+    {
+        Displace::new(self, displacement)
+    }
+
+    /// Caches the last output of this function. See [`Cache`].
+    fn cache(self) -> Cache<Self>
+    where
+        Self: Sized,
+    {
+        Cache::new(self)
+    }
+
+    /// Caches the last output of this function in a way that can be shared
+    /// between threads. See [`SyncCache`].
+    fn sync_cache(self) -> SyncCache<Self>
+    where
+        Self: Sized,
+    {
+        SyncCache::new(self)
+    }
+}
+
+impl<M: NoiseFnExt + ?Sized> NoiseFnExt for &M {}
+
+impl<M: NoiseFnExt + ?Sized> NoiseFnExt for Box<M> {}
+
+impl<M: NoiseFnExt + ?Sized> NoiseFnExt for Arc<M> {}
+
+impl<'a, T, const DIM: usize> NoiseFnExt for dyn NoiseFn<T, DIM> + 'a {}
+
+/// Implements [`NoiseFnExt`] and the arithmetic operators for noise function
+/// types, which can't be done with a blanket implementation.
+macro_rules! impl_noise_fn_ext {
+    ($($name:ident $(<$($param:ident),+ $(; const $const_param:ident)*>)?),+ $(,)?) => {$(
+        impl$(<$($param,)+ $(const $const_param: usize,)*>)? NoiseFnExt for $name$(<$($param,)+ $($const_param,)*>)? {}
+
+        impl<$($($param,)+ $(const $const_param: usize,)*)? Rhs: NoiseFnExt> ops::Add<Rhs> for $name$(<$($param,)+ $($const_param,)*>)? {
+            type Output = Add<Self, Rhs>;
+
+            fn add(self, rhs: Rhs) -> Self::Output {
+                Add::new(self, rhs)
+            }
+        }
+
+        impl<$($($param,)+ $(const $const_param: usize,)*)? Rhs: NoiseFnExt> ops::Sub<Rhs> for $name$(<$($param,)+ $($const_param,)*>)? {
+            type Output = Add<Self, Negate<Rhs>>;
+
+            fn sub(self, rhs: Rhs) -> Self::Output {
+                Add::new(self, Negate::new(rhs))
+            }
+        }
+
+        impl<$($($param,)+ $(const $const_param: usize,)*)? Rhs: NoiseFnExt> ops::Mul<Rhs> for $name$(<$($param,)+ $($const_param,)*>)? {
+            type Output = Multiply<Self, Rhs>;
+
+            fn mul(self, rhs: Rhs) -> Self::Output {
+                Multiply::new(self, rhs)
+            }
+        }
+
+        impl<$($($param,)+ $(const $const_param: usize,)*)? Rhs: NoiseFnExt> ops::Div<Rhs> for $name$(<$($param,)+ $($const_param,)*>)? {
+            type Output = Divide<Self, Rhs>;
+
+            fn div(self, rhs: Rhs) -> Self::Output {
+                Divide::new(self, rhs)
+            }
+        }
+
+        impl$(<$($param,)+ $(const $const_param: usize,)*>)? ops::Neg for $name$(<$($param,)+ $($const_param,)*>)? {
+            type Output = Negate<Self>;
+
+            fn neg(self) -> Self::Output {
+                Negate::new(self)
+            }
+        }
+    )+};
+}
+
+impl_noise_fn_ext!(
+    // Generators
+    BasicMulti<T>,
+    Billow<T>,
+    Checkerboard,
+    Constant,
+    Cylinders,
+    ErodedFbm<T>,
+    Fbm<T>,
+    HybridMulti<T>,
+    OpenSimplex<H>,
+    OpenSimplex2F<H>,
+    OpenSimplex2S<H>,
+    Perlin<H>,
+    PerlinSurflet,
+    RidgedMulti<T>,
+    Simplex<H>,
+    SuperSimplex<H>,
+    Value<H>,
+    Worley<H>,
+    // Modifiers
+    Abs<Source>,
+    Channel<Source; const N>,
+    Clamp<Source>,
+    Curve<Source>,
+    Exponent<Source>,
+    Negate<Source>,
+    ScaleBias<Source>,
+    Terrace<Source>,
+    // Combiners
+    Add<Source1, Source2>,
+    Divide<Source1, Source2>,
+    Max<Source1, Source2>,
+    Min<Source1, Source2>,
+    Multiply<Source1, Source2>,
+    Power<Source1, Source2>,
+    // Selectors
+    Blend<Source1, Source2, Control>,
+    Select<Source1, Source2, Control>,
+    // Transformers
+    Displace<Source, Displacement>,
+    RotatePoint<Source>,
+    ScalePoint<Source>,
+    TranslatePoint<Source>,
+    Turbulence<Source>,
+    Cache<Source>,
+    SyncCache<Source>,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IntegerHasher;
+
+    #[test]
+    fn test_generic_sources() {
+        let point = [0.4, 1.7, -2.3];
+        let perlin = Perlin::<IntegerHasher>::from_seed(1);
+        let fbm = Fbm::<Simplex>::default();
+        let worley = Worley::<IntegerHasher>::from_seed(2);
+        let open_simplex = OpenSimplex2S::<IntegerHasher>::from_seed(3);
+        let surflet = PerlinSurflet::default();
+
+        let graph = (perlin.abs() + fbm.clone() - worley.clone()) * surflet
+            / open_simplex.scale_bias(0.5, 2.0);
+        let expected = (perlin.get(point).abs() + fbm.get(point) - worley.get(point))
+            * surflet.get(point)
+            / ScaleBias::new(&open_simplex)
+                .set_scale(0.5)
+                .set_bias(2.0)
+                .get(point);
+        assert_eq!(graph.get(point), expected);
+    }
+}
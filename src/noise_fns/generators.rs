@@ -1,16 +1,435 @@
 pub use self::{
-    checkerboard::*, constant::*, cylinders::*, fractals::*, open_simplex::*, perlin::*,
-    perlin_surflet::*, simplex::*, super_simplex::*, value::*, worley::*,
+    checkerboard::*, constant::*, curl::*, cylinders::*, fractals::*, open_simplex::*,
+    open_simplex2::*, perlin::*, perlin_surflet::*, simplex::*, super_simplex::*, value::*,
+    worley::*,
 };
 
 mod checkerboard;
 mod constant;
+mod curl;
 mod cylinders;
 mod fractals;
 mod open_simplex;
-mod perlin;
+mod open_simplex2;
+pub(crate) mod perlin;
 mod perlin_surflet;
 mod simplex;
 mod super_simplex;
 mod value;
 mod worley;
+
+/// Parameters of a generator that is described by its seed alone.
+///
+/// Generators deserialize from this, so that their hasher is rebuilt from the
+/// seed instead of being stored.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SeedParams {
+    #[serde(default)]
+    seed: u64,
+}
+
+#[cfg(feature = "serde")]
+macro_rules! impl_from_seed_params {
+    ($($generator:ty),+ $(,)?) => {$(
+        impl<H: crate::SeedableHasher> From<SeedParams> for $generator {
+            fn from(params: SeedParams) -> Self {
+                <$generator>::from_seed(params.seed)
+            }
+        }
+    )+};
+}
+
+#[cfg(feature = "serde")]
+impl_from_seed_params!(open_simplex::OpenSimplex<H>, super_simplex::SuperSimplex<H>);
+
+#[cfg(feature = "serde")]
+impl From<SeedParams> for perlin_surflet::PerlinSurflet {
+    fn from(params: SeedParams) -> Self {
+        crate::Seedable::set_seed(perlin_surflet::PerlinSurflet::default(), params.seed)
+    }
+}
+
+/// Parameters of a [`Periodic`](crate::Periodic) generator, which is
+/// described by its seed and period.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(super) struct PeriodicParams {
+    #[serde(default)]
+    seed: u64,
+    #[serde(default)]
+    period: [u32; 4],
+}
+
+#[cfg(feature = "serde")]
+macro_rules! impl_from_periodic_params {
+    ($($generator:ty),+ $(,)?) => {$(
+        impl<H: crate::SeedableHasher> From<PeriodicParams> for $generator {
+            fn from(params: PeriodicParams) -> Self {
+                crate::Periodic::set_period(<$generator>::from_seed(params.seed), params.period)
+            }
+        }
+    )+};
+}
+
+#[cfg(feature = "serde")]
+impl_from_periodic_params!(perlin::Perlin<H>, value::Value<H>);
+
+#[cfg(test)]
+mod tests {
+    use crate::{noise_fns::tests::assert_gradient, *};
+
+    #[test]
+    fn test_gradients() {
+        let points_1d = [[0.4], [-5.1], [13.37]];
+        let points_2d = [[0.4, 1.7], [-5.1, 0.23], [13.37, -2.9]];
+        let points_3d = [[0.4, 1.7, -2.3], [-5.1, 0.23, 3.3], [13.37, -2.9, 0.61]];
+        let points_4d = [[0.4, 1.7, -2.3, 0.8], [-5.1, 0.23, 3.3, -1.45]];
+
+        assert_gradient(&Perlin::new(1), &points_1d);
+        assert_gradient(&Perlin::new(1), &points_2d);
+        assert_gradient(&Perlin::new(1), &points_3d);
+        assert_gradient(&Perlin::new(1), &points_4d);
+        assert_gradient(&Value::new().set_seed(2), &points_1d);
+        assert_gradient(&Value::new().set_seed(2), &points_2d);
+        assert_gradient(&Value::new().set_seed(2), &points_3d);
+        assert_gradient(&Value::new().set_seed(2), &points_4d);
+        assert_gradient(&Simplex::new(3), &points_1d);
+        assert_gradient(&Simplex::new(3), &points_2d);
+        assert_gradient(&Simplex::new(3), &points_3d);
+        assert_gradient(&Simplex::new(3), &points_4d);
+        assert_gradient(&OpenSimplex::new().set_seed(4), &points_2d);
+        assert_gradient(&OpenSimplex::new().set_seed(4), &points_3d);
+        assert_gradient(&OpenSimplex::new().set_seed(4), &points_4d);
+        assert_gradient(&SuperSimplex::new().set_seed(5), &points_2d);
+        assert_gradient(&SuperSimplex::new().set_seed(5), &points_3d);
+        assert_gradient(&SuperSimplex::new().set_seed(5), &points_4d);
+        assert_gradient(&OpenSimplex2F::new().set_seed(6), &points_2d);
+        assert_gradient(&OpenSimplex2F::new().set_seed(6), &points_4d);
+
+        for orientation in [
+            LatticeOrientation::Classic,
+            LatticeOrientation::ImproveXY,
+            LatticeOrientation::ImproveXZ,
+        ] {
+            let open_simplex2s = OpenSimplex2S::new().set_orientation(orientation);
+            let open_simplex2f = OpenSimplex2F::new().set_orientation(orientation);
+            assert_gradient(&open_simplex2s.set_seed(7), &points_3d);
+            assert_gradient(&open_simplex2f.set_seed(6), &points_3d);
+        }
+    }
+
+    #[test]
+    fn test_1d() {
+        fn check<N: NoiseFn<f64, 1>>(noise: N) -> Vec<f64> {
+            let points: Vec<[f64; 1]> = (0..500).map(|i| [i as f64 * 0.037 - 9.0]).collect();
+            let mut output = vec![0.0; points.len()];
+            noise.get_batch(&points, &mut output);
+
+            for (point, value) in points.iter().zip(output.iter()) {
+                assert_eq!(noise.get(*point), *value);
+            }
+            output
+        }
+
+        let generators = [
+            check(Perlin::new(1)),
+            check(Simplex::new(2)),
+            check(Value::new().set_seed(3)),
+            check(Worley::new(4)),
+        ];
+        for values in generators.iter() {
+            assert!(values.iter().all(|value| (-1.0..=1.0).contains(value)));
+            assert!(values.iter().any(|&value| value != values[0]));
+        }
+
+        check(Worley::new(4).set_return_type(ReturnType::Distance2Sub));
+        check(Fbm::<Perlin>::default().set_rotation(OctaveRotation::Seeded));
+        check(Billow::<Simplex>::default());
+        check(BasicMulti::<Value>::default());
+        check(HybridMulti::<Perlin>::default());
+        check(RidgedMulti::<Perlin>::default());
+        check(ErodedFbm::<Simplex>::default());
+        check(Cache::new(ScaleBias::new(Perlin::new(5)).set_scale(0.5)));
+    }
+
+    #[test]
+    fn test_periodic() {
+        // Moving by a period must give exactly the same value, in batches too.
+        fn check<N: NoiseFn<f64, DIM>, const DIM: usize>(noise: N, period: [f64; DIM]) {
+            let points: Vec<[f64; DIM]> = (0..200)
+                .map(|i| {
+                    let mut point = [0.0; DIM];
+                    for (axis, coordinate) in point.iter_mut().enumerate() {
+                        *coordinate = ((i * (axis + 3) * 7) % 97) as f64 * 0.125 - 5.0;
+                    }
+                    point
+                })
+                .collect();
+            let moved: Vec<[f64; DIM]> = points
+                .iter()
+                .map(|point| {
+                    let mut moved = *point;
+                    for (coordinate, period) in moved.iter_mut().zip(period.iter()) {
+                        *coordinate += period;
+                    }
+                    moved
+                })
+                .collect();
+
+            let mut output = vec![0.0; points.len()];
+            noise.get_batch(&moved, &mut output);
+            for ((point, moved), value) in points.iter().zip(moved.iter()).zip(output.iter()) {
+                assert_eq!(noise.get(*point), noise.get(*moved));
+                assert_eq!(noise.get(*moved), *value);
+            }
+        }
+
+        check(Perlin::new(1).set_period([3]), [3.0]);
+        check(Perlin::new(1).set_period([4, 5]), [4.0, 5.0]);
+        check(Perlin::new(1).set_period([4, 5, 6]), [4.0, -5.0, 12.0]);
+        check(
+            Perlin::new(1).set_period([2, 3, 4, 5]),
+            [2.0, 3.0, 4.0, -5.0],
+        );
+        check(Value::new().set_period([3, 7]), [-3.0, 7.0]);
+        check(Value::new().set_period([3, 7, 2, 2]), [3.0, 7.0, 2.0, 2.0]);
+        check(Simplex::new(2).set_period([5]), [5.0]);
+        check(Simplex::new(2).set_period([5, 6]), [5.0, 6.0]);
+        check(Simplex::new(2).set_period([0, 4]), [0.0, -8.0]);
+
+        // Axes without a period stay aperiodic.
+        let perlin = Perlin::new(3).set_period([4]);
+        assert_ne!(perlin.get([0.3, 0.6]), perlin.get([0.3, 4.6]));
+        assert_eq!(perlin.period(), [4, 0, 0, 0]);
+
+        // With an integer lacunarity, fractals repeat every period / frequency.
+        let fbm = Fbm::new()
+            .set_octaves(4)
+            .set_frequency(2.0)
+            .set_lacunarity(2.0)
+            .set_period([4, 6]);
+        assert_eq!(fbm.period(), [4, 6, 0, 0]);
+        check(fbm, [2.0, 3.0]);
+        check(
+            RidgedMulti::<Value>::default()
+                .set_lacunarity(3.0)
+                .set_period([5, 5, 5]),
+            [5.0, 5.0, 5.0],
+        );
+        check(
+            Billow::<Simplex>::default()
+                .set_lacunarity(2.0)
+                .set_period([3, 4]),
+            [3.0, 4.0],
+        );
+
+        // The period is kept when the octaves are rebuilt.
+        let hybrid = HybridMulti::new()
+            .set_lacunarity(2.0)
+            .set_period([4, 4])
+            .set_seed(7)
+            .set_octaves(3);
+        assert_eq!(hybrid.period(), [4, 4, 0, 0]);
+        check(hybrid, [4.0, 4.0]);
+    }
+
+    #[test]
+    fn test_batch_matches_get() {
+        fn check<N: NoiseFn<f64, 3>>(noise: N) {
+            let points: Vec<[f64; 3]> = (0..200)
+                .map(|i| [i as f64 * 0.07 - 7.0, i as f64 * -0.031, 1.5])
+                .collect();
+            let mut output = vec![0.0; points.len()];
+            noise.get_batch(&points, &mut output);
+
+            for (point, value) in points.iter().zip(output) {
+                assert_eq!(noise.get(*point), value);
+            }
+        }
+
+        check(Perlin::new(1));
+        check(Simplex::new(2));
+        check(Value::new().set_seed(3));
+        check(OpenSimplex::new().set_seed(4));
+        check(SuperSimplex::new().set_seed(5));
+        check(Worley::new(6));
+        check(OpenSimplex2S::new().set_orientation(LatticeOrientation::ImproveXY));
+        check(OpenSimplex2F::new().set_orientation(LatticeOrientation::ImproveXZ));
+
+        fn check_2d<N: NoiseFn<f64, 2>>(noise: N) {
+            let points: Vec<[f64; 2]> = (0..200)
+                .map(|i| [i as f64 * 0.07 - 7.0, i as f64 * -0.031])
+                .collect();
+            let mut output = vec![0.0; points.len()];
+            noise.get_batch(&points, &mut output);
+
+            for (point, value) in points.iter().zip(output) {
+                assert_eq!(noise.get(*point), value);
+            }
+        }
+
+        check_2d(OpenSimplex2F::new().set_seed(7));
+
+        // In 4D, Super Simplex and OpenSimplex2F cache the hashes of a whole block of cells.
+        fn check_4d<N: NoiseFn<f64, 4>>(noise: N) {
+            let points: Vec<[f64; 4]> = (0..200)
+                .map(|i| {
+                    [
+                        i as f64 * 0.07 - 7.0,
+                        i as f64 * -0.031,
+                        1.5,
+                        i as f64 * 0.013,
+                    ]
+                })
+                .collect();
+            let mut output = vec![0.0; points.len()];
+            noise.get_batch(&points, &mut output);
+
+            for (point, value) in points.iter().zip(output) {
+                assert_eq!(noise.get(*point), value);
+            }
+        }
+
+        check_4d(SuperSimplex::new().set_seed(5));
+        check_4d(OpenSimplex2F::new().set_seed(7));
+    }
+
+    #[test]
+    fn test_scalar_batch_matches_get() {
+        // The vectorized kernels only run on CPUs with AVX2 and FMA, so force the
+        // fallback to check that both give exactly the values of `get`.
+        fn check<N: NoiseFn<f64, DIM>, const DIM: usize>(noise: N) {
+            let points: Vec<[f64; DIM]> = (0..203)
+                .map(|i| {
+                    let mut point = [0.0; DIM];
+                    for (axis, coordinate) in point.iter_mut().enumerate() {
+                        *coordinate = i as f64 * (0.07 - 0.023 * axis as f64) - 7.0;
+                    }
+                    point
+                })
+                .collect();
+            let mut vectorized = vec![0.0; points.len()];
+            noise.get_batch(&points, &mut vectorized);
+            let mut scalar = vec![0.0; points.len()];
+            crate::noise_fns::with_scalar_batches(|| noise.get_batch(&points, &mut scalar));
+
+            for ((point, vectorized), scalar) in points.iter().zip(vectorized).zip(scalar) {
+                assert_eq!(noise.get(*point), vectorized);
+                assert_eq!(noise.get(*point), scalar);
+            }
+        }
+
+        check::<_, 2>(Perlin::new(1));
+        check::<_, 3>(Perlin::new(1));
+        check::<_, 4>(Perlin::new(1));
+        check::<_, 2>(Simplex::new(2));
+        check::<_, 3>(Simplex::new(2));
+        check::<_, 4>(Simplex::new(2));
+        check::<_, 2>(Value::new().set_seed(3));
+        check::<_, 3>(Value::new().set_seed(3));
+        check::<_, 4>(Value::new().set_seed(3));
+    }
+
+    #[test]
+    fn test_seeds_unchanged() {
+        // Outputs for `u32` seeds must stay the same as when seeds were `u32`.
+        let point = [0.4, 1.7, -2.3];
+        let expected = [
+            (
+                0,
+                [
+                    0.24789616203234252,
+                    0.060794762807655806,
+                    -0.39929736723068876,
+                    -0.8431372549019608,
+                    -0.28701736327088456,
+                    -0.19966322709018672,
+                    -0.1416599959894589,
+                    -0.2744474248373447,
+                ],
+            ),
+            (
+                12345,
+                [
+                    0.017930989437448114,
+                    0.10406135028286767,
+                    -0.40665873861938806,
+                    -0.584313725490196,
+                    0.06412739369534652,
+                    0.40648895853874584,
+                    0.3389798533481564,
+                    -0.48974229714677125,
+                ],
+            ),
+            (
+                3_000_000_000,
+                [
+                    0.01729350289512385,
+                    -0.045281502304097936,
+                    -0.0314579933595448,
+                    0.21568627450980382,
+                    -0.13604778777114038,
+                    0.24180430660202362,
+                    0.12189274455062754,
+                    0.14112119160492975,
+                ],
+            ),
+        ];
+
+        for (seed, values) in expected.iter() {
+            let seed = *seed;
+            let fbm = Fbm::<Perlin>::new()
+                .set_seed(seed)
+                .set_rotation(OctaveRotation::Seeded);
+            let actual = [
+                Perlin::new(seed).get(point),
+                Simplex::new(seed).get(point),
+                Value::new().set_seed(seed).get(point),
+                Worley::new(seed).get(point),
+                OpenSimplex::new().set_seed(seed).get(point),
+                SuperSimplex::new().set_seed(seed).get(point),
+                OpenSimplex2F::new().set_seed(seed).get(point),
+                fbm.get([point[0], point[1]]),
+            ];
+            assert_eq!(&actual, values, "seed {}", seed);
+            assert_eq!(
+                OpenSimplex2S::new().set_seed(seed).get(point),
+                values[5],
+                "seed {}",
+                seed
+            );
+        }
+
+        // The high half of a `u64` seed still changes the table.
+        assert_ne!(
+            Perlin::new(1).get(point),
+            Perlin::new(1 | 1 << 40).get(point)
+        );
+    }
+
+    #[test]
+    fn test_integer_hasher() {
+        let perlin = Perlin::<IntegerHasher>::from_seed(1);
+        let simplex = Simplex::<IntegerHasher>::from_seed(1);
+        let worley = Worley::<IntegerHasher>::from_seed(1);
+        for i in 0..500 {
+            let point = [i as f64 * 0.37 + 0.1, i as f64 * -0.53 + 0.2, 0.3];
+            for value in [perlin.get(point), simplex.get(point), worley.get(point)].iter() {
+                assert!((-1.0..=1.0).contains(value));
+            }
+        }
+
+        // Unlike a permutation table, it doesn't repeat every 256 cells.
+        let point = [0.25, 0.5, 0.75];
+        let moved = [256.25, 0.5, 0.75];
+        let table = Perlin::new(1);
+        assert_eq!(table.get(point), table.get(moved));
+        assert_ne!(perlin.get(point), perlin.get(moved));
+
+        // All 64 bits of the seed matter.
+        assert_ne!(perlin.get(point), perlin.set_seed(1 | 1 << 40).get(point));
+    }
+}
@@ -1,4 +1,6 @@
-use crate::noise_fns::NoiseFn;
+use crate::noise_fns::{evaluate_batch_widened, get_widened, NoiseFn};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Noise function that outputs a checkerboard pattern.
 ///
@@ -9,6 +11,8 @@ use crate::noise_fns::NoiseFn;
 /// This noise function is not very useful by itself, but it can be used for
 /// debugging purposes.
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
 pub struct Checkerboard {
     // Controls the size of the block in 2^(size).
     size: usize,
@@ -53,3 +57,16 @@ impl<const N: usize> NoiseFn<f64, N> for Checkerboard {
         }
     }
 }
+
+impl<const DIM: usize> NoiseFn<f32, DIM> for Checkerboard
+where
+    Self: NoiseFn<f64, DIM>,
+{
+    fn get(&self, point: [f32; DIM]) -> f64 {
+        get_widened(self, point)
+    }
+
+    fn get_batch(&self, points: &[[f32; DIM]], output: &mut [f64]) {
+        evaluate_batch_widened(self, points, output);
+    }
+}
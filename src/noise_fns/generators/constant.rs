@@ -1,4 +1,6 @@
-use crate::noise_fns::NoiseFn;
+use crate::noise_fns::{NoiseFn, NoiseFnGradient};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Noise function that outputs a constant value.
 ///
@@ -8,6 +10,8 @@ use crate::noise_fns::NoiseFn;
 /// This function is not very useful by itself, but can be used as a source
 /// function for other noise functions.
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
 pub struct Constant {
     /// Constant value.
     pub value: f64,
@@ -24,3 +28,9 @@ impl<T: Copy, const N: usize> NoiseFn<T, N> for Constant {
         self.value
     }
 }
+
+impl<const N: usize> NoiseFnGradient<N> for Constant {
+    fn get_with_gradient(&self, _point: [f64; N]) -> (f64, [f64; N]) {
+        (self.value, [0.0; N])
+    }
+}
@@ -0,0 +1,366 @@
+use super::fractals::{build_transforms, transform_octave, OctaveTransform};
+use crate::{
+    math,
+    noise_fns::{simplex_2d, simplex_3d, MultiFractal, NoiseFnVector, OctaveRotation, Seedable},
+    permutationtable::PermutationTable,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Offsets at which the three potentials of 3-dimensional curl noise sample
+/// the same Simplex noise, so that they are uncorrelated.
+const POTENTIAL_OFFSETS: [[f64; 3]; 3] = [
+    [0.0, 0.0, 0.0],
+    [31.416, -47.853, 12.793],
+    [-103.929, 59.201, 88.415],
+];
+
+/// Noise function that outputs curl noise, a divergence-free vector field.
+///
+/// The field is the curl of Simplex noise used as a potential. In 2D, the
+/// output for a potential `ψ` is `(∂ψ/∂y, -∂ψ/∂x)`. In 3D, three uncorrelated
+/// Simplex potentials form a vector potential. Since the derivatives come
+/// from the analytic gradient of the Simplex noise, the divergence is zero
+/// everywhere, so particles moved along the field neither bunch up nor
+/// spread out.
+///
+/// The output is not confined to the [-1, 1] range, and grows in proportion
+/// to the frequency.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "CurlParams"))]
+pub struct Curl {
+    /// Frequency of the potential.
+    pub frequency: f64,
+
+    seed: u64,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    hasher: PermutationTable,
+}
+
+impl Curl {
+    pub const DEFAULT_SEED: u64 = 0;
+    pub const DEFAULT_FREQUENCY: f64 = 1.0;
+
+    pub fn new(seed: u64) -> Self {
+        Self {
+            frequency: Self::DEFAULT_FREQUENCY,
+            seed,
+            hasher: PermutationTable::new(seed),
+        }
+    }
+
+    pub fn set_frequency(self, frequency: f64) -> Self {
+        Self { frequency, ..self }
+    }
+
+    /// Returns the curl at a point that has already been multiplied by the
+    /// frequency, without the factor the frequency adds to the derivatives.
+    /// The potential is sampled after rotating and offsetting the point by
+    /// `transform`, if any.
+    fn curl_2d(&self, point: [f64; 2], transform: Option<&OctaveTransform>) -> [f64; 2] {
+        let [x, y] = transform_octave(transform, point);
+        let (_, mut gradient) = simplex_2d(x, y, &self.hasher);
+        if let Some(transform) = transform {
+            gradient = transform.apply_to_gradient(gradient);
+        }
+        let [dx, dy] = gradient;
+
+        [dy, -dx]
+    }
+
+    /// See [`curl_2d`](Self::curl_2d).
+    fn curl_3d(&self, point: [f64; 3], transform: Option<&OctaveTransform>) -> [f64; 3] {
+        let point = transform_octave(transform, point);
+        let [d1, d2, d3] = POTENTIAL_OFFSETS.map(|offset| {
+            let [x, y, z] = math::add3(point, offset);
+            let gradient = simplex_3d(x, y, z, &self.hasher).1;
+            match transform {
+                Some(transform) => transform.apply_to_gradient(gradient),
+                None => gradient,
+            }
+        });
+
+        [d3[1] - d2[2], d1[2] - d3[0], d2[0] - d1[1]]
+    }
+}
+
+impl Default for Curl {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_SEED)
+    }
+}
+
+impl Seedable for Curl {
+    fn set_seed(self, seed: u64) -> Self {
+        if self.seed == seed {
+            return self;
+        }
+
+        Self {
+            seed,
+            hasher: PermutationTable::new(seed),
+            ..self
+        }
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+/// 2-dimensional curl noise
+impl NoiseFnVector<f64, 2, 2> for Curl {
+    fn get_vector(&self, point: [f64; 2]) -> [f64; 2] {
+        let curl = self.curl_2d(math::mul2(point, self.frequency), None);
+
+        math::mul2(curl, self.frequency)
+    }
+}
+
+/// 3-dimensional curl noise
+impl NoiseFnVector<f64, 3, 3> for Curl {
+    fn get_vector(&self, point: [f64; 3]) -> [f64; 3] {
+        let curl = self.curl_3d(math::mul3(point, self.frequency), None);
+
+        math::mul3(curl, self.frequency)
+    }
+}
+
+/// Parameters of curl noise, from which its permutation table is rebuilt when
+/// deserializing.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CurlParams {
+    #[serde(default = "default_frequency")]
+    frequency: f64,
+    #[serde(default)]
+    seed: u64,
+}
+
+#[cfg(feature = "serde")]
+fn default_frequency() -> f64 {
+    Curl::DEFAULT_FREQUENCY
+}
+
+#[cfg(feature = "serde")]
+impl From<CurlParams> for Curl {
+    fn from(params: CurlParams) -> Self {
+        Curl::new(params.seed).set_frequency(params.frequency)
+    }
+}
+
+/// Noise function that outputs multi-octave curl noise.
+///
+/// The field is the curl of fractal Simplex noise, built up like `Fbm` from
+/// several octaves of potential. It is the sum of one [`Curl`] per octave, so
+/// it is divergence-free as well.
+///
+/// The persistence scales the potential of each octave. Because the curl of
+/// an octave also grows with its frequency, the velocity of each octave is
+/// scaled by the product of the persistence and the lacunarity. The default
+/// persistence makes this about one half, like the amplitudes of `Fbm`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "super::fractals::FractalParams"))]
+pub struct FractalCurl {
+    /// Total number of frequency octaves to generate the noise with.
+    pub octaves: usize,
+
+    /// The number of cycles per unit length of the first octave.
+    pub frequency: f64,
+
+    /// A multiplier that determines how quickly the frequency increases for
+    /// each successive octave.
+    pub lacunarity: f64,
+
+    /// A multiplier that determines how quickly the potential diminishes for
+    /// each successive octave.
+    pub persistence: f64,
+
+    seed: u64,
+    rotation: OctaveRotation,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    sources: Vec<Curl>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    transforms: Vec<OctaveTransform>,
+}
+
+fn build_sources(seed: u64, octaves: usize) -> Vec<Curl> {
+    (0..octaves)
+        .map(|x| Curl::new(seed.wrapping_add(x as u64)))
+        .collect()
+}
+
+impl FractalCurl {
+    pub const DEFAULT_SEED: u64 = 0;
+    pub const DEFAULT_OCTAVE_COUNT: usize = 4;
+    pub const DEFAULT_FREQUENCY: f64 = 1.0;
+    pub const DEFAULT_LACUNARITY: f64 = 2.0;
+    pub const DEFAULT_PERSISTENCE: f64 = 0.25;
+    pub const MAX_OCTAVES: usize = 32;
+
+    pub fn new() -> Self {
+        Self {
+            octaves: Self::DEFAULT_OCTAVE_COUNT,
+            frequency: Self::DEFAULT_FREQUENCY,
+            lacunarity: Self::DEFAULT_LACUNARITY,
+            persistence: Self::DEFAULT_PERSISTENCE,
+            seed: Self::DEFAULT_SEED,
+            rotation: OctaveRotation::None,
+            sources: build_sources(Self::DEFAULT_SEED, Self::DEFAULT_OCTAVE_COUNT),
+            transforms: Vec::new(),
+        }
+    }
+}
+
+impl Default for FractalCurl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MultiFractal for FractalCurl {
+    fn set_octaves(self, mut octaves: usize) -> Self {
+        if self.octaves == octaves {
+            return self;
+        }
+
+        octaves = octaves.clamp(1, Self::MAX_OCTAVES);
+        Self {
+            octaves,
+            sources: build_sources(self.seed, octaves),
+            transforms: build_transforms(self.rotation, self.seed, octaves),
+            ..self
+        }
+    }
+
+    fn set_frequency(self, frequency: f64) -> Self {
+        Self { frequency, ..self }
+    }
+
+    fn set_lacunarity(self, lacunarity: f64) -> Self {
+        Self { lacunarity, ..self }
+    }
+
+    fn set_persistence(self, persistence: f64) -> Self {
+        Self {
+            persistence,
+            ..self
+        }
+    }
+
+    fn set_rotation(self, rotation: OctaveRotation) -> Self {
+        Self {
+            rotation,
+            transforms: build_transforms(rotation, self.seed, self.octaves),
+            ..self
+        }
+    }
+}
+
+impl Seedable for FractalCurl {
+    fn set_seed(self, seed: u64) -> Self {
+        if self.seed == seed {
+            return self;
+        }
+
+        Self {
+            seed,
+            sources: build_sources(seed, self.octaves),
+            transforms: build_transforms(self.rotation, seed, self.octaves),
+            ..self
+        }
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+/// 2-dimensional multi-octave curl noise
+impl NoiseFnVector<f64, 2, 2> for FractalCurl {
+    fn get_vector(&self, point: [f64; 2]) -> [f64; 2] {
+        let mut result = [0.0; 2];
+        let mut frequency = self.frequency;
+
+        for x in 0..self.octaves {
+            let curl =
+                self.sources[x].curl_2d(math::mul2(point, frequency), self.transforms.get(x));
+            let amplitude = self.persistence.powi(x as i32) * frequency;
+            result = math::add2(result, math::mul2(curl, amplitude));
+
+            frequency *= self.lacunarity;
+        }
+
+        result
+    }
+}
+
+/// 3-dimensional multi-octave curl noise
+impl NoiseFnVector<f64, 3, 3> for FractalCurl {
+    fn get_vector(&self, point: [f64; 3]) -> [f64; 3] {
+        let mut result = [0.0; 3];
+        let mut frequency = self.frequency;
+
+        for x in 0..self.octaves {
+            let curl =
+                self.sources[x].curl_3d(math::mul3(point, frequency), self.transforms.get(x));
+            let amplitude = self.persistence.powi(x as i32) * frequency;
+            result = math::add3(result, math::mul3(curl, amplitude));
+
+            frequency *= self.lacunarity;
+        }
+
+        result
+    }
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<super::fractals::FractalParams> for FractalCurl {
+    type Error = String;
+
+    fn try_from(params: super::fractals::FractalParams) -> Result<Self, Self::Error> {
+        params.build("FractalCurl", Self::MAX_OCTAVES, Self::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Estimates the divergence of `field` at `point` by central differences.
+    fn divergence<F, const DIM: usize>(field: &F, point: [f64; DIM]) -> f64
+    where
+        F: NoiseFnVector<f64, DIM, DIM>,
+    {
+        const STEP: f64 = 1e-5;
+
+        (0..DIM)
+            .map(|axis| {
+                let (mut above, mut below) = (point, point);
+                above[axis] += STEP;
+                below[axis] -= STEP;
+                (field.get_vector(above)[axis] - field.get_vector(below)[axis]) / (2.0 * STEP)
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_divergence_free() {
+        let curl = Curl::new(3).set_frequency(1.7);
+        let fractal = FractalCurl::new()
+            .set_seed(5)
+            .set_rotation(OctaveRotation::Seeded);
+
+        for point in [[0.4, 1.7, -2.3], [-5.1, 0.23, 3.3], [13.37, -2.9, 0.61]] {
+            let flat = [point[0], point[1]];
+            assert!(divergence(&curl, flat).abs() < 1e-5);
+            assert!(divergence(&curl, point).abs() < 1e-5);
+            assert!(divergence(&fractal, flat).abs() < 1e-4);
+            assert!(divergence(&fractal, point).abs() < 1e-4);
+            assert_ne!(curl.get_vector(point), [0.0; 3]);
+        }
+    }
+}
@@ -1,4 +1,6 @@
-use crate::noise_fns::NoiseFn;
+use crate::noise_fns::{evaluate_batch_widened, get_widened, NoiseFn};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Noise function that outputs concentric cylinders.
 ///
@@ -6,6 +8,8 @@ use crate::noise_fns::NoiseFn;
 /// cylinders are oriented along the z axis similar to the concentric rings of
 /// a tree. Each cylinder extends infinitely along the z axis.
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
 pub struct Cylinders {
     /// Frequency of the concentric objects.
     pub frequency: f64,
@@ -48,3 +52,16 @@ impl<const N: usize> NoiseFn<f64, N> for Cylinders {
         1.0 - (nearest_dist * 4.0)
     }
 }
+
+impl<const DIM: usize> NoiseFn<f32, DIM> for Cylinders
+where
+    Self: NoiseFn<f64, DIM>,
+{
+    fn get(&self, point: [f32; DIM]) -> f64 {
+        get_widened(self, point)
+    }
+
+    fn get_batch(&self, points: &[[f32; DIM]], output: &mut [f64]) {
+        evaluate_batch_widened(self, points, output);
+    }
+}
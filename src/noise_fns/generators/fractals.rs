@@ -1,14 +1,31 @@
-pub use self::{basicmulti::*, billow::*, fbm::*, hybridmulti::*, ridgedmulti::*};
+pub(super) use self::rotation::{build_transforms, transform_octave, OctaveTransform};
+pub use self::{
+    basicmulti::*, billow::*, erodedfbm::*, fbm::*, hybridmulti::*, ridgedmulti::*,
+    rotation::OctaveRotation,
+};
 
 mod basicmulti;
 mod billow;
+mod erodedfbm;
 mod fbm;
 mod hybridmulti;
 mod ridgedmulti;
+mod rotation;
 
-use crate::noise_fns::Perlin;
+use crate::{
+    math::{dual::Dual, s_curve::cubic::Cubic},
+    noise_fns::{NoiseFn, NoiseFnGradient, Seedable},
+};
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
 
 /// Trait for `MultiFractal` functions
+///
+/// Fractals of [`Periodic`](crate::Periodic) sources are periodic too, with
+/// every octave given the same period. With an integer lacunarity, each finer
+/// octave then repeats a whole number of times within the period of the first,
+/// so the fractal repeats every `period / frequency` along each periodic axis,
+/// as long as the octaves aren't rotated.
 pub trait MultiFractal {
     fn set_octaves(self, octaves: usize) -> Self;
 
@@ -17,12 +34,295 @@ pub trait MultiFractal {
     fn set_lacunarity(self, lacunarity: f64) -> Self;
 
     fn set_persistence(self, persistence: f64) -> Self;
+
+    /// Sets how the input of each octave is rotated and offset, to hide the
+    /// alignment of the octaves with the axes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the angle of an [`OctaveRotation::Fixed`] rotation isn't
+    /// finite.
+    fn set_rotation(self, rotation: OctaveRotation) -> Self;
 }
 
-fn build_sources(seed: u32, octaves: usize) -> Vec<Perlin> {
+/// Builds the source of each octave from a copy of `prototype`, seeded with
+/// `seed` plus the index of the octave.
+fn build_sources<T>(prototype: &T, seed: u64, octaves: usize) -> Vec<T>
+where
+    T: Clone + Seedable,
+{
     let mut sources = Vec::with_capacity(octaves);
     for x in 0..octaves {
-        sources.push(Perlin::new(seed + x as u32));
+        sources.push(prototype.clone().set_seed(seed.wrapping_add(x as u64)));
     }
     sources
 }
+
+/// Multiplies every coordinate of `point` by `factor`.
+fn scale_point<const DIM: usize>(mut point: [f64; DIM], factor: f64) -> [f64; DIM] {
+    for coordinate in point.iter_mut() {
+        *coordinate *= factor;
+    }
+    point
+}
+
+/// The weight of an octave of `frequency` in a sample that covers `width`
+/// units, which fades the octave out as its period shrinks from four samples
+/// to two, below which it would alias.
+fn octave_weight(frequency: f64, width: f64) -> f64 {
+    (2.0 - 4.0 * (frequency * width).abs())
+        .clamp(0.0, 1.0)
+        .map_cubic()
+}
+
+/// Evaluates an octave at `point`, after the octave's `transform`, and
+/// multiplies it by `weight`. The octave isn't evaluated at all if it is faded
+/// out entirely.
+fn get_filtered_octave<S, const DIM: usize>(
+    source: &S,
+    transform: Option<&OctaveTransform>,
+    point: [f64; DIM],
+    weight: f64,
+) -> f64
+where
+    S: NoiseFn<f64, DIM>,
+{
+    if weight == 0.0 {
+        return 0.0;
+    }
+
+    source.get(transform_octave(transform, point)) * weight
+}
+
+/// Evaluates an octave with its gradient at `point`, which is the point the
+/// fractal is evaluated at multiplied by `frequency`, before the octave's
+/// `transform`. The gradient is taken with respect to the fractal's point.
+fn get_octave<S, const DIM: usize>(
+    source: &S,
+    transform: Option<&OctaveTransform>,
+    point: [f64; DIM],
+    frequency: f64,
+) -> Dual<DIM>
+where
+    S: NoiseFnGradient<DIM>,
+{
+    let (value, mut gradient) = source.get_with_gradient(transform_octave(transform, point));
+    if let Some(transform) = transform {
+        gradient = transform.apply_to_gradient(gradient);
+    }
+    Dual::new(value, gradient).scale_gradient(frequency)
+}
+
+/// Parameters of a fractal, from which its sources are rebuilt when
+/// deserializing. Missing parameters take the fractal's defaults.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(super) struct FractalParams {
+    octaves: Option<usize>,
+    frequency: Option<f64>,
+    lacunarity: Option<f64>,
+    persistence: Option<f64>,
+    rotation: Option<OctaveRotation>,
+    seed: Option<u64>,
+}
+
+#[cfg(feature = "serde")]
+impl FractalParams {
+    pub(super) fn build<F>(self, name: &str, max_octaves: usize, fractal: F) -> Result<F, String>
+    where
+        F: MultiFractal + Seedable,
+    {
+        if let Some(octaves) = self.octaves {
+            if octaves == 0 || octaves > max_octaves {
+                return Err(format!(
+                    "{} octaves must be between 1 and {}, got {}",
+                    name, max_octaves, octaves
+                ));
+            }
+        }
+
+        if let Some(rotation) = self.rotation {
+            rotation
+                .validate()
+                .map_err(|message| format!("{} {}", name, message))?;
+        }
+
+        let mut fractal = fractal;
+        if let Some(seed) = self.seed {
+            fractal = fractal.set_seed(seed);
+        }
+        if let Some(octaves) = self.octaves {
+            fractal = fractal.set_octaves(octaves);
+        }
+        if let Some(frequency) = self.frequency {
+            fractal = fractal.set_frequency(frequency);
+        }
+        if let Some(lacunarity) = self.lacunarity {
+            fractal = fractal.set_lacunarity(lacunarity);
+        }
+        if let Some(persistence) = self.persistence {
+            fractal = fractal.set_persistence(persistence);
+        }
+        if let Some(rotation) = self.rotation {
+            fractal = fractal.set_rotation(rotation);
+        }
+
+        Ok(fractal)
+    }
+}
+
+#[cfg(feature = "serde")]
+macro_rules! impl_try_from_fractal_params {
+    ($($fractal:ident),+ $(,)?) => {$(
+        impl<T> TryFrom<FractalParams> for $fractal<T>
+        where
+            T: Clone + Default + Seedable,
+        {
+            type Error = String;
+
+            fn try_from(params: FractalParams) -> Result<Self, Self::Error> {
+                params.build(stringify!($fractal), $fractal::MAX_OCTAVES, Self::default())
+            }
+        }
+    )+};
+}
+
+#[cfg(feature = "serde")]
+impl_try_from_fractal_params!(BasicMulti, Billow, Fbm, HybridMulti);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        noise_fns::{tests::assert_gradient, widen},
+        Abs, Add, Cache, OpenSimplex, Perlin, ReturnType, ScaleBias, ScalePoint, Select, Simplex,
+        TranslatePoint, Turbulence, Worley,
+    };
+
+    #[test]
+    fn test_gradients() {
+        let points = [[0.4, 1.7, -2.3], [-5.1, 0.23, 3.3], [13.37, -2.9, 0.61]];
+
+        assert_gradient(&Fbm::new().set_seed(1), &points);
+        assert_gradient(&Billow::new().set_seed(2), &points);
+        assert_gradient(&BasicMulti::new().set_seed(3), &points);
+        assert_gradient(&HybridMulti::new().set_seed(4), &points);
+        assert_gradient(&RidgedMulti::new().set_seed(5), &points);
+        assert_gradient(&Fbm::<Simplex>::default().set_seed(6), &points);
+        assert_gradient(&Billow::<OpenSimplex>::default().set_seed(7), &points);
+        assert_gradient(&Fbm::new().set_rotation(OctaveRotation::Seeded), &points);
+        assert_gradient(
+            &RidgedMulti::new().set_rotation(OctaveRotation::Fixed(0.6)),
+            &points,
+        );
+    }
+
+    #[test]
+    fn test_fractal_sources() {
+        let point = [0.4, 1.7, -2.3];
+
+        let fbm = Fbm::<Perlin>::default().set_seed(4);
+        assert_eq!(Fbm::new().set_seed(4).get(point), fbm.get(point));
+
+        let ridged = RidgedMulti::<Worley>::default().set_octaves(3).set_seed(4);
+        let perlin = RidgedMulti::new().set_octaves(3).set_seed(4);
+        assert_ne!(ridged.get(point), perlin.get(point));
+        assert_eq!(ridged.seed(), 4);
+
+        // Reseeding the octaves keeps the other settings of the prototype.
+        let distance = Worley::default().set_return_type(ReturnType::Distance);
+        let fractal = Fbm::with_source(distance.clone())
+            .set_octaves(1)
+            .set_seed(5);
+        assert_eq!(fractal.get(point), distance.set_seed(5).get(point) / 0.5);
+        let value = Fbm::<Worley>::default().set_octaves(1).set_seed(5);
+        assert_ne!(fractal.get(point), value.get(point));
+    }
+
+    #[test]
+    fn test_filtered_fractals() {
+        fn assert_unfiltered<F: NoiseFn<f64, 2> + NoiseFn<f64, 3> + NoiseFn<f64, 4>>(fractal: F) {
+            let (a, b) = ([0.4, 1.7, -2.3, 5.0], [-5.1, 0.23, 3.3, 0.8]);
+            assert_eq!(
+                fractal.get_filtered([a[0], a[1]], 0.0),
+                fractal.get([a[0], a[1]])
+            );
+            assert_eq!(
+                fractal.get_filtered([a[0], a[1], a[2]], 0.0),
+                fractal.get([a[0], a[1], a[2]])
+            );
+            assert_eq!(fractal.get_filtered(a, 0.0), fractal.get(a));
+
+            let mut output = [0.0; 2];
+            fractal.get_batch_filtered(&[a, b], 0.1, &mut output);
+            assert_eq!(
+                output,
+                [fractal.get_filtered(a, 0.1), fractal.get_filtered(b, 0.1)]
+            );
+        }
+
+        assert_unfiltered(Fbm::new().set_rotation(OctaveRotation::Seeded));
+        assert_unfiltered(Billow::new());
+        assert_unfiltered(BasicMulti::new());
+        assert_unfiltered(HybridMulti::new());
+        assert_unfiltered(RidgedMulti::new());
+
+        // Only the first octave is fine enough for a sample this wide.
+        let point = [0.4, 1.7, -2.3];
+        let fractal = BasicMulti::new();
+        let filtered = fractal.get_filtered(point, 0.125);
+        assert_eq!(filtered, BasicMulti::new().set_octaves(1).get(point));
+        assert_ne!(filtered, fractal.get(point));
+        assert_eq!(fractal.get_filtered(point, 1.0), 0.0);
+        let narrow = [0.4_f32, 1.7, -2.3];
+        assert_eq!(
+            fractal.get_filtered(narrow, 0.125),
+            fractal.get_filtered(widen(narrow), 0.125)
+        );
+    }
+
+    #[test]
+    fn test_filtered_wrappers() {
+        // A sample this wide filters out every octave of the fractal, which
+        // the wrappers must pass on.
+        let point = [0.4, 1.7, -2.3];
+        let fractal = BasicMulti::new();
+        assert_eq!(fractal.get_filtered(point, 1.0), 0.0);
+        assert_ne!(fractal.get(point), 0.0);
+
+        let wrapped = Add::new(
+            ScaleBias::new(Abs::new(&fractal)).set_bias(0.5),
+            Select::new(
+                TranslatePoint::new(&fractal).set_all_translations(1.0, 2.0, 3.0, 4.0),
+                Turbulence::new(&fractal),
+                Cache::new(&fractal),
+            ),
+        );
+        assert_eq!(wrapped.get_filtered(point, 1.0), 0.5);
+
+        let mut output = [0.0; 2];
+        wrapped.get_batch_filtered(&[point, point], 1.0, &mut output);
+        assert_eq!(output, [0.5; 2]);
+
+        // Scaling the point by 16 makes the first octave too fine as well.
+        let scaled = ScalePoint::new(&fractal).set_scale(16.0);
+        assert_ne!(scaled.get_filtered(point, 0.125), scaled.get(point));
+        assert_eq!(scaled.get_filtered(point, 0.125), 0.0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_errors() {
+        let error = |json| serde_json::from_str::<Fbm>(json).unwrap_err().to_string();
+
+        assert!(error(r#"{"octaves": 40}"#).contains("between 1 and 32"));
+        assert!(error(r#"{"octave": 4}"#).contains("unknown field `octave`"));
+        assert!(
+            serde_json::from_str::<ErodedFbm>(r#"{"gradient_influence": -1.0}"#)
+                .unwrap_err()
+                .to_string()
+                .contains("must be finite and not negative")
+        );
+    }
+}
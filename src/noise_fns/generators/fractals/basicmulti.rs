@@ -1,6 +1,15 @@
 use crate::math;
 
-use crate::noise_fns::{MultiFractal, NoiseFn, Perlin, Seedable};
+use super::{
+    get_filtered_octave, get_octave, octave_weight, scale_point, transform_octave, OctaveRotation,
+    OctaveTransform,
+};
+use crate::noise_fns::{
+    evaluate_batch, evaluate_batch_widened, get_widened, widen, MultiFractal, NoiseFn,
+    NoiseFnGradient, Periodic, Perlin, Seedable,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Noise function that outputs heterogenous Multifractal noise.
 ///
@@ -12,8 +21,18 @@ use crate::noise_fns::{MultiFractal, NoiseFn, Perlin, Seedable};
 /// smooth. As the value moves further away from zero, higher frequencies will
 /// not be as damped and thus will grow more jagged as iteration progresses.
 ///
+/// Like [`Fbm`](super::Fbm), the octaves can come from any [`Seedable`]
+/// generator `T`, and are [`Perlin`] noise by default.
 #[derive(Clone, Debug)]
-pub struct BasicMulti {
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        try_from = "super::FractalParams",
+        bound(deserialize = "T: Clone + Default + Seedable")
+    )
+)]
+pub struct BasicMulti<T = Perlin> {
     /// Total number of frequency octaves to generate the noise with.
     ///
     /// The number of octaves control the _amount of detail_ in the noise
@@ -42,12 +61,18 @@ pub struct BasicMulti {
     /// persistence produces "rougher" noise.
     pub persistence: f64,
 
-    seed: u32,
-    sources: Vec<Perlin>,
+    seed: u64,
+    rotation: OctaveRotation,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    prototype: T,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    sources: Vec<T>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    transforms: Vec<OctaveTransform>,
 }
 
 impl BasicMulti {
-    pub const DEFAULT_SEED: u32 = 0;
+    pub const DEFAULT_SEED: u64 = 0;
     pub const DEFAULT_OCTAVES: usize = 6;
     pub const DEFAULT_FREQUENCY: f64 = 2.0;
     pub const DEFAULT_LACUNARITY: f64 = std::f64::consts::PI * 2.0 / 3.0;
@@ -55,33 +80,61 @@ impl BasicMulti {
     pub const MAX_OCTAVES: usize = 32;
 
     pub fn new() -> Self {
-        Self {
-            seed: Self::DEFAULT_SEED,
-            octaves: Self::DEFAULT_OCTAVES,
-            frequency: Self::DEFAULT_FREQUENCY,
-            lacunarity: Self::DEFAULT_LACUNARITY,
-            persistence: Self::DEFAULT_PERSISTENCE,
-            sources: super::build_sources(Self::DEFAULT_SEED, Self::DEFAULT_OCTAVES),
-        }
+        Self::default()
     }
 }
 
-impl Default for BasicMulti {
+impl<T> Default for BasicMulti<T>
+where
+    T: Clone + Default + Seedable,
+{
     fn default() -> Self {
-        Self::new()
+        Self::with_source(T::default())
     }
 }
 
-impl MultiFractal for BasicMulti {
+impl<T> BasicMulti<T>
+where
+    T: Clone + Seedable,
+{
+    /// Creates the fractal with the default parameters, generating its
+    /// octaves with copies of `source` that are reseeded for each octave.
+    ///
+    /// The copies keep every setting of `source` other than its seed, so it
+    /// can be configured before it is passed in.
+    pub fn with_source(source: T) -> Self {
+        Self {
+            seed: BasicMulti::DEFAULT_SEED,
+            octaves: BasicMulti::DEFAULT_OCTAVES,
+            frequency: BasicMulti::DEFAULT_FREQUENCY,
+            lacunarity: BasicMulti::DEFAULT_LACUNARITY,
+            persistence: BasicMulti::DEFAULT_PERSISTENCE,
+            sources: super::build_sources(
+                &source,
+                BasicMulti::DEFAULT_SEED,
+                BasicMulti::DEFAULT_OCTAVES,
+            ),
+            rotation: OctaveRotation::None,
+            transforms: Vec::new(),
+            prototype: source,
+        }
+    }
+}
+
+impl<T> MultiFractal for BasicMulti<T>
+where
+    T: Clone + Seedable,
+{
     fn set_octaves(self, mut octaves: usize) -> Self {
         if self.octaves == octaves {
             return self;
         }
 
-        octaves = octaves.clamp(1, Self::MAX_OCTAVES);
+        octaves = octaves.clamp(1, BasicMulti::MAX_OCTAVES);
         Self {
             octaves,
-            sources: super::build_sources(self.seed, octaves),
+            sources: super::build_sources(&self.prototype, self.seed, octaves),
+            transforms: super::build_transforms(self.rotation, self.seed, octaves),
             ..self
         }
     }
@@ -100,32 +153,141 @@ impl MultiFractal for BasicMulti {
             ..self
         }
     }
+
+    fn set_rotation(self, rotation: OctaveRotation) -> Self {
+        Self {
+            rotation,
+            transforms: super::build_transforms(rotation, self.seed, self.octaves),
+            ..self
+        }
+    }
 }
 
-impl Seedable for BasicMulti {
-    fn set_seed(self, seed: u32) -> Self {
+impl<T> Seedable for BasicMulti<T>
+where
+    T: Clone + Seedable,
+{
+    fn set_seed(self, seed: u64) -> Self {
         if self.seed == seed {
             return self;
         }
 
         Self {
             seed,
-            sources: super::build_sources(seed, self.octaves),
+            sources: super::build_sources(&self.prototype, seed, self.octaves),
+            transforms: super::build_transforms(self.rotation, seed, self.octaves),
             ..self
         }
     }
 
-    fn seed(&self) -> u32 {
+    fn seed(&self) -> u64 {
         self.seed
     }
 }
 
+impl<T> Periodic for BasicMulti<T>
+where
+    T: Periodic,
+{
+    fn set_period<const N: usize>(self, period: [u32; N]) -> Self {
+        // The prototype keeps the period for the sources rebuilt by
+        // `set_seed` and `set_octaves`.
+        Self {
+            prototype: self.prototype.set_period(period),
+            sources: self
+                .sources
+                .into_iter()
+                .map(|source| source.set_period(period))
+                .collect(),
+            ..self
+        }
+    }
+
+    fn period(&self) -> [u32; 4] {
+        self.prototype.period()
+    }
+}
+
+impl<T> BasicMulti<T> {
+    /// Evaluates the fractal at `point` with the octaves that are too fine for
+    /// a sample covering `width` faded out.
+    fn filtered<const DIM: usize>(&self, point: [f64; DIM], width: f64) -> f64
+    where
+        T: NoiseFn<f64, DIM>,
+    {
+        let mut point = scale_point(point, self.frequency);
+        let mut frequency = self.frequency;
+        let mut result = get_filtered_octave(
+            &self.sources[0],
+            self.transforms.first(),
+            point,
+            octave_weight(frequency, width),
+        );
+
+        for x in 1..self.octaves {
+            point = scale_point(point, self.lacunarity);
+            frequency *= self.lacunarity;
+
+            let weight = octave_weight(frequency, width);
+            let signal =
+                get_filtered_octave(&self.sources[x], self.transforms.get(x), point, weight);
+            result += signal * self.persistence.powi(x as i32) * result;
+        }
+
+        result * 0.5
+    }
+}
+
+/// 1-dimensional `BasicMulti` noise
+impl<T> NoiseFn<f64, 1> for BasicMulti<T>
+where
+    T: NoiseFn<f64, 1>,
+{
+    fn get(&self, mut point: [f64; 1]) -> f64 {
+        // First unscaled octave of function; later octaves are scaled.
+        point = scale_point(point, self.frequency);
+        let mut result = self.sources[0].get(transform_octave(self.transforms.first(), point));
+
+        // Spectral construction inner loop, where the fractal is built.
+        for x in 1..self.octaves {
+            // Raise the spatial frequency.
+            point = scale_point(point, self.lacunarity);
+
+            // Get noise value.
+            let mut signal = self.sources[x].get(transform_octave(self.transforms.get(x), point));
+
+            // Scale the amplitude appropriately for this frequency.
+            signal *= self.persistence.powi(x as i32);
+
+            // Scale the signal by the current 'altitude' of the function.
+            signal *= result;
+
+            // Add signal to result.
+            result += signal;
+        }
+
+        // Scale the result to the [-1,1] range.
+        result * 0.5
+    }
+
+    fn get_filtered(&self, point: [f64; 1], width: f64) -> f64 {
+        self.filtered(point, width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f64; 1]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.filtered(point, width));
+    }
+}
+
 /// 2-dimensional `BasicMulti` noise
-impl NoiseFn<f64, 2> for BasicMulti {
+impl<T> NoiseFn<f64, 2> for BasicMulti<T>
+where
+    T: NoiseFn<f64, 2>,
+{
     fn get(&self, mut point: [f64; 2]) -> f64 {
         // First unscaled octave of function; later octaves are scaled.
         point = math::mul2(point, self.frequency);
-        let mut result = self.sources[0].get(point);
+        let mut result = self.sources[0].get(transform_octave(self.transforms.first(), point));
 
         // Spectral construction inner loop, where the fractal is built.
         for x in 1..self.octaves {
@@ -133,7 +295,7 @@ impl NoiseFn<f64, 2> for BasicMulti {
             point = math::mul2(point, self.lacunarity);
 
             // Get noise value.
-            let mut signal = self.sources[x].get(point);
+            let mut signal = self.sources[x].get(transform_octave(self.transforms.get(x), point));
 
             // Scale the amplitude appropriately for this frequency.
             signal *= self.persistence.powi(x as i32);
@@ -148,14 +310,25 @@ impl NoiseFn<f64, 2> for BasicMulti {
         // Scale the result to the [-1,1] range.
         result * 0.5
     }
+
+    fn get_filtered(&self, point: [f64; 2], width: f64) -> f64 {
+        self.filtered(point, width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f64; 2]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.filtered(point, width));
+    }
 }
 
 /// 3-dimensional `BasicMulti` noise
-impl NoiseFn<f64, 3> for BasicMulti {
+impl<T> NoiseFn<f64, 3> for BasicMulti<T>
+where
+    T: NoiseFn<f64, 3>,
+{
     fn get(&self, mut point: [f64; 3]) -> f64 {
         // First unscaled octave of function; later octaves are scaled.
         point = math::mul3(point, self.frequency);
-        let mut result = self.sources[0].get(point);
+        let mut result = self.sources[0].get(transform_octave(self.transforms.first(), point));
 
         // Spectral construction inner loop, where the fractal is built.
         for x in 1..self.octaves {
@@ -163,7 +336,7 @@ impl NoiseFn<f64, 3> for BasicMulti {
             point = math::mul3(point, self.lacunarity);
 
             // Get noise value.
-            let mut signal = self.sources[x].get(point);
+            let mut signal = self.sources[x].get(transform_octave(self.transforms.get(x), point));
 
             // Scale the amplitude appropriately for this frequency.
             signal *= self.persistence.powi(x as i32);
@@ -178,14 +351,25 @@ impl NoiseFn<f64, 3> for BasicMulti {
         // Scale the result to the [-1,1] range.
         result * 0.5
     }
+
+    fn get_filtered(&self, point: [f64; 3], width: f64) -> f64 {
+        self.filtered(point, width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f64; 3]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.filtered(point, width));
+    }
 }
 
 /// 4-dimensional `BasicMulti` noise
-impl NoiseFn<f64, 4> for BasicMulti {
+impl<T> NoiseFn<f64, 4> for BasicMulti<T>
+where
+    T: NoiseFn<f64, 4>,
+{
     fn get(&self, mut point: [f64; 4]) -> f64 {
         // First unscaled octave of function; later octaves are scaled.
         point = math::mul4(point, self.frequency);
-        let mut result = self.sources[0].get(point);
+        let mut result = self.sources[0].get(transform_octave(self.transforms.first(), point));
 
         // Spectral construction inner loop, where the fractal is built.
         for x in 1..self.octaves {
@@ -193,7 +377,7 @@ impl NoiseFn<f64, 4> for BasicMulti {
             point = math::mul4(point, self.lacunarity);
 
             // Get noise value.
-            let mut signal = self.sources[x].get(point);
+            let mut signal = self.sources[x].get(transform_octave(self.transforms.get(x), point));
 
             // Scale the amplitude appropriately for this frequency.
             signal *= self.persistence.powi(x as i32);
@@ -208,4 +392,59 @@ impl NoiseFn<f64, 4> for BasicMulti {
         // Scale the result to the [-1,1] range.
         result * 0.5
     }
+
+    fn get_filtered(&self, point: [f64; 4], width: f64) -> f64 {
+        self.filtered(point, width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f64; 4]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.filtered(point, width));
+    }
+}
+
+/// `BasicMulti` noise, with its gradient
+impl<T, const DIM: usize> NoiseFnGradient<DIM> for BasicMulti<T>
+where
+    Self: NoiseFn<f64, DIM>,
+    T: NoiseFnGradient<DIM>,
+{
+    fn get_with_gradient(&self, point: [f64; DIM]) -> (f64, [f64; DIM]) {
+        let mut point = scale_point(point, self.frequency);
+        let mut frequency = self.frequency;
+        let mut result = get_octave(&self.sources[0], self.transforms.first(), point, frequency);
+
+        for x in 1..self.octaves {
+            point = scale_point(point, self.lacunarity);
+            frequency *= self.lacunarity;
+
+            let signal = get_octave(&self.sources[x], self.transforms.get(x), point, frequency);
+            result += signal * self.persistence.powi(x as i32) * result;
+        }
+
+        (result * 0.5).into_tuple()
+    }
+}
+
+/// `BasicMulti` noise at `f32` coordinates
+impl<T, const DIM: usize> NoiseFn<f32, DIM> for BasicMulti<T>
+where
+    Self: NoiseFn<f64, DIM>,
+{
+    fn get(&self, point: [f32; DIM]) -> f64 {
+        get_widened(self, point)
+    }
+
+    fn get_batch(&self, points: &[[f32; DIM]], output: &mut [f64]) {
+        evaluate_batch_widened(self, points, output);
+    }
+
+    fn get_filtered(&self, point: [f32; DIM], width: f64) -> f64 {
+        NoiseFn::<f64, DIM>::get_filtered(self, widen(point), width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f32; DIM]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| {
+            NoiseFn::<f64, DIM>::get_filtered(self, widen(point), width)
+        });
+    }
 }
@@ -1,7 +1,15 @@
+use super::{
+    get_octave, octave_weight, scale_point, transform_octave, OctaveRotation, OctaveTransform,
+};
 use crate::{
-    math::{self, scale_shift},
-    noise_fns::{MultiFractal, NoiseFn, Perlin, Seedable},
+    math::{self, dual::Dual, scale_shift},
+    noise_fns::{
+        evaluate_batch, evaluate_batch_widened, get_widened, widen, MultiFractal, NoiseFn,
+        NoiseFnGradient, Periodic, Perlin, Seedable,
+    },
 };
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Noise function that outputs "billowy" noise.
 ///
@@ -10,8 +18,19 @@ use crate::{
 /// This noise function is nearly identical to fBm noise, except this noise
 /// function modifies each octave with an absolute-value function. See the
 /// documentation for fBm for more information.
+///
+/// Like [`Fbm`](super::Fbm), the octaves can come from any [`Seedable`]
+/// generator `T`, and are [`Perlin`] noise by default.
 #[derive(Clone, Debug)]
-pub struct Billow {
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        try_from = "super::FractalParams",
+        bound(deserialize = "T: Clone + Default + Seedable")
+    )
+)]
+pub struct Billow<T = Perlin> {
     /// Total number of frequency octaves to generate the noise with.
     ///
     /// The number of octaves control the _amount of detail_ in the noise
@@ -40,8 +59,15 @@ pub struct Billow {
     /// persistence produces "rougher" noise.
     pub persistence: f64,
 
-    seed: u32,
-    sources: Vec<Perlin>,
+    seed: u64,
+    rotation: OctaveRotation,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    prototype: T,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    sources: Vec<T>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    transforms: Vec<OctaveTransform>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
     scale_factor: f64,
 }
 
@@ -50,7 +76,7 @@ fn calc_scale_factor(persistence: f64, octaves: usize) -> f64 {
 }
 
 impl Billow {
-    pub const DEFAULT_SEED: u32 = 0;
+    pub const DEFAULT_SEED: u64 = 0;
     pub const DEFAULT_OCTAVE_COUNT: usize = 6;
     pub const DEFAULT_FREQUENCY: f64 = 1.0;
     pub const DEFAULT_LACUNARITY: f64 = std::f64::consts::PI * 2.0 / 3.0;
@@ -58,34 +84,65 @@ impl Billow {
     pub const MAX_OCTAVES: usize = 32;
 
     pub fn new() -> Self {
-        Self {
-            seed: Self::DEFAULT_SEED,
-            octaves: Self::DEFAULT_OCTAVE_COUNT,
-            frequency: Self::DEFAULT_FREQUENCY,
-            lacunarity: Self::DEFAULT_LACUNARITY,
-            persistence: Self::DEFAULT_PERSISTENCE,
-            sources: super::build_sources(Self::DEFAULT_SEED, Self::DEFAULT_OCTAVE_COUNT),
-            scale_factor: calc_scale_factor(Self::DEFAULT_PERSISTENCE, Self::DEFAULT_OCTAVE_COUNT),
-        }
+        Self::default()
     }
 }
 
-impl Default for Billow {
+impl<T> Default for Billow<T>
+where
+    T: Clone + Default + Seedable,
+{
     fn default() -> Self {
-        Self::new()
+        Self::with_source(T::default())
+    }
+}
+
+impl<T> Billow<T>
+where
+    T: Clone + Seedable,
+{
+    /// Creates the fractal with the default parameters, generating its
+    /// octaves with copies of `source` that are reseeded for each octave.
+    ///
+    /// The copies keep every setting of `source` other than its seed, so it
+    /// can be configured before it is passed in.
+    pub fn with_source(source: T) -> Self {
+        Self {
+            seed: Billow::DEFAULT_SEED,
+            octaves: Billow::DEFAULT_OCTAVE_COUNT,
+            frequency: Billow::DEFAULT_FREQUENCY,
+            lacunarity: Billow::DEFAULT_LACUNARITY,
+            persistence: Billow::DEFAULT_PERSISTENCE,
+            sources: super::build_sources(
+                &source,
+                Billow::DEFAULT_SEED,
+                Billow::DEFAULT_OCTAVE_COUNT,
+            ),
+            rotation: OctaveRotation::None,
+            transforms: Vec::new(),
+            scale_factor: calc_scale_factor(
+                Billow::DEFAULT_PERSISTENCE,
+                Billow::DEFAULT_OCTAVE_COUNT,
+            ),
+            prototype: source,
+        }
     }
 }
 
-impl MultiFractal for Billow {
+impl<T> MultiFractal for Billow<T>
+where
+    T: Clone + Seedable,
+{
     fn set_octaves(self, mut octaves: usize) -> Self {
         if self.octaves == octaves {
             return self;
         }
 
-        octaves = octaves.clamp(1, Self::MAX_OCTAVES);
+        octaves = octaves.clamp(1, Billow::MAX_OCTAVES);
         Self {
             octaves,
-            sources: super::build_sources(self.seed, octaves),
+            sources: super::build_sources(&self.prototype, self.seed, octaves),
+            transforms: super::build_transforms(self.rotation, self.seed, octaves),
             scale_factor: calc_scale_factor(self.persistence, octaves),
             ..self
         }
@@ -106,28 +163,133 @@ impl MultiFractal for Billow {
             ..self
         }
     }
+
+    fn set_rotation(self, rotation: OctaveRotation) -> Self {
+        Self {
+            rotation,
+            transforms: super::build_transforms(rotation, self.seed, self.octaves),
+            ..self
+        }
+    }
 }
 
-impl Seedable for Billow {
-    fn set_seed(self, seed: u32) -> Self {
+impl<T> Seedable for Billow<T>
+where
+    T: Clone + Seedable,
+{
+    fn set_seed(self, seed: u64) -> Self {
         if self.seed == seed {
             return self;
         }
 
         Self {
             seed,
-            sources: super::build_sources(seed, self.octaves),
+            sources: super::build_sources(&self.prototype, seed, self.octaves),
+            transforms: super::build_transforms(self.rotation, seed, self.octaves),
             ..self
         }
     }
 
-    fn seed(&self) -> u32 {
+    fn seed(&self) -> u64 {
         self.seed
     }
 }
 
+impl<T> Periodic for Billow<T>
+where
+    T: Periodic,
+{
+    fn set_period<const N: usize>(self, period: [u32; N]) -> Self {
+        // The prototype keeps the period for the sources rebuilt by
+        // `set_seed` and `set_octaves`.
+        Self {
+            prototype: self.prototype.set_period(period),
+            sources: self
+                .sources
+                .into_iter()
+                .map(|source| source.set_period(period))
+                .collect(),
+            ..self
+        }
+    }
+
+    fn period(&self) -> [u32; 4] {
+        self.prototype.period()
+    }
+}
+
+impl<T> Billow<T> {
+    /// Evaluates the fractal at `point` with the octaves that are too fine for
+    /// a sample covering `width` faded out.
+    fn filtered<const DIM: usize>(&self, point: [f64; DIM], width: f64) -> f64
+    where
+        T: NoiseFn<f64, DIM>,
+    {
+        let mut result = 0.0;
+        let mut point = scale_point(point, self.frequency);
+        let mut frequency = self.frequency;
+
+        for x in 0..self.octaves {
+            let weight = octave_weight(frequency, width);
+            if weight != 0.0 {
+                let signal = self.sources[x].get(transform_octave(self.transforms.get(x), point));
+                result += scale_shift(signal, 2.0) * weight * self.persistence.powi(x as i32);
+            }
+
+            point = scale_point(point, self.lacunarity);
+            frequency *= self.lacunarity;
+        }
+
+        result / self.scale_factor
+    }
+}
+
+/// 1-dimensional Billow noise
+impl<T> NoiseFn<f64, 1> for Billow<T>
+where
+    T: NoiseFn<f64, 1>,
+{
+    fn get(&self, mut point: [f64; 1]) -> f64 {
+        let mut result = 0.0;
+
+        point = scale_point(point, self.frequency);
+
+        for x in 0..self.octaves {
+            // Get the signal.
+            let mut signal = self.sources[x].get(transform_octave(self.transforms.get(x), point));
+
+            // Take the abs of the signal, then scale and shift back to
+            // the [-1,1] range.
+            signal = scale_shift(signal, 2.0);
+
+            // Scale the amplitude appropriately for this frequency.
+            signal *= self.persistence.powi(x as i32);
+
+            // Add the signal to the result.
+            result += signal;
+
+            // Increase the frequency for the next octave.
+            point = scale_point(point, self.lacunarity);
+        }
+
+        // Scale the result to the [-1,1] range.
+        result / self.scale_factor
+    }
+
+    fn get_filtered(&self, point: [f64; 1], width: f64) -> f64 {
+        self.filtered(point, width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f64; 1]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.filtered(point, width));
+    }
+}
+
 /// 2-dimensional Billow noise
-impl NoiseFn<f64, 2> for Billow {
+impl<T> NoiseFn<f64, 2> for Billow<T>
+where
+    T: NoiseFn<f64, 2>,
+{
     fn get(&self, mut point: [f64; 2]) -> f64 {
         let mut result = 0.0;
 
@@ -135,7 +297,7 @@ impl NoiseFn<f64, 2> for Billow {
 
         for x in 0..self.octaves {
             // Get the signal.
-            let mut signal = self.sources[x].get(point);
+            let mut signal = self.sources[x].get(transform_octave(self.transforms.get(x), point));
 
             // Take the abs of the signal, then scale and shift back to
             // the [-1,1] range.
@@ -154,10 +316,21 @@ impl NoiseFn<f64, 2> for Billow {
         // Scale the result to the [-1,1] range.
         result / self.scale_factor
     }
+
+    fn get_filtered(&self, point: [f64; 2], width: f64) -> f64 {
+        self.filtered(point, width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f64; 2]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.filtered(point, width));
+    }
 }
 
 /// 3-dimensional Billow noise
-impl NoiseFn<f64, 3> for Billow {
+impl<T> NoiseFn<f64, 3> for Billow<T>
+where
+    T: NoiseFn<f64, 3>,
+{
     fn get(&self, mut point: [f64; 3]) -> f64 {
         let mut result = 0.0;
 
@@ -165,7 +338,7 @@ impl NoiseFn<f64, 3> for Billow {
 
         for x in 0..self.octaves {
             // Get the signal.
-            let mut signal = self.sources[x].get(point);
+            let mut signal = self.sources[x].get(transform_octave(self.transforms.get(x), point));
 
             // Take the abs of the signal, then scale and shift back to
             // the [-1,1] range.
@@ -184,10 +357,21 @@ impl NoiseFn<f64, 3> for Billow {
         // Scale the result to the [-1,1] range.
         result / self.scale_factor
     }
+
+    fn get_filtered(&self, point: [f64; 3], width: f64) -> f64 {
+        self.filtered(point, width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f64; 3]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.filtered(point, width));
+    }
 }
 
 /// 4-dimensional Billow noise
-impl NoiseFn<f64, 4> for Billow {
+impl<T> NoiseFn<f64, 4> for Billow<T>
+where
+    T: NoiseFn<f64, 4>,
+{
     fn get(&self, mut point: [f64; 4]) -> f64 {
         let mut result = 0.0;
 
@@ -195,7 +379,7 @@ impl NoiseFn<f64, 4> for Billow {
 
         for x in 0..self.octaves {
             // Get the signal.
-            let mut signal = self.sources[x].get(point);
+            let mut signal = self.sources[x].get(transform_octave(self.transforms.get(x), point));
 
             // Take the abs of the signal, then scale and shift back to
             // the [-1,1] range.
@@ -214,4 +398,60 @@ impl NoiseFn<f64, 4> for Billow {
         // Scale the result to the [-1,1] range.
         result / self.scale_factor
     }
+
+    fn get_filtered(&self, point: [f64; 4], width: f64) -> f64 {
+        self.filtered(point, width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f64; 4]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.filtered(point, width));
+    }
+}
+
+/// Billow noise, with its gradient
+impl<T, const DIM: usize> NoiseFnGradient<DIM> for Billow<T>
+where
+    Self: NoiseFn<f64, DIM>,
+    T: NoiseFnGradient<DIM>,
+{
+    fn get_with_gradient(&self, point: [f64; DIM]) -> (f64, [f64; DIM]) {
+        let mut result = Dual::constant(0.0);
+        let mut point = scale_point(point, self.frequency);
+        let mut frequency = self.frequency;
+
+        for x in 0..self.octaves {
+            let signal = get_octave(&self.sources[x], self.transforms.get(x), point, frequency)
+                .scale_shift(2.0);
+            result += signal * self.persistence.powi(x as i32);
+
+            point = scale_point(point, self.lacunarity);
+            frequency *= self.lacunarity;
+        }
+
+        (result / self.scale_factor).into_tuple()
+    }
+}
+
+/// Billow noise at `f32` coordinates
+impl<T, const DIM: usize> NoiseFn<f32, DIM> for Billow<T>
+where
+    Self: NoiseFn<f64, DIM>,
+{
+    fn get(&self, point: [f32; DIM]) -> f64 {
+        get_widened(self, point)
+    }
+
+    fn get_batch(&self, points: &[[f32; DIM]], output: &mut [f64]) {
+        evaluate_batch_widened(self, points, output);
+    }
+
+    fn get_filtered(&self, point: [f32; DIM], width: f64) -> f64 {
+        NoiseFn::<f64, DIM>::get_filtered(self, widen(point), width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f32; DIM]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| {
+            NoiseFn::<f64, DIM>::get_filtered(self, widen(point), width)
+        });
+    }
 }
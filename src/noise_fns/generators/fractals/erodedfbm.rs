@@ -0,0 +1,390 @@
+use super::{get_octave, octave_weight, scale_point, OctaveRotation, OctaveTransform};
+use crate::noise_fns::{
+    evaluate_batch, evaluate_batch_widened, get_widened, widen, MultiFractal, NoiseFn,
+    NoiseFnGradient, Periodic, Seedable, Simplex,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+
+/// Noise function that outputs fBm noise in which each octave is damped by
+/// the slope of the octaves before it.
+///
+/// Each octave is weighted by the persistence, as in [`Fbm`](crate::Fbm),
+/// and is then divided by one plus the gradient influence times the squared
+/// length of the gradient of the previous octaves. Steep slopes therefore
+/// receive less detail than flat areas, which gives smooth eroded-looking
+/// valleys and ridges, while flat plains and peaks stay rough.
+///
+/// The slope comes from the analytic gradients of the octaves, so the
+/// generator `T` must implement [`NoiseFnGradient`]. It is [`Simplex`] noise
+/// by default.
+///
+/// The values output from this function will usually range from -1.0 to 1.0
+/// with default values for the parameters, and the damping only makes them
+/// smaller.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        try_from = "ErodedFbmParams",
+        bound(deserialize = "T: Clone + Default + Seedable")
+    )
+)]
+pub struct ErodedFbm<T = Simplex> {
+    /// Total number of frequency octaves to generate the noise with.
+    ///
+    /// The number of octaves control the _amount of detail_ in the noise
+    /// function. Adding more octaves increases the detail, with the drawback
+    /// of increasing the calculation time.
+    pub octaves: usize,
+
+    /// The number of cycles per unit length that the noise function outputs.
+    pub frequency: f64,
+
+    /// A multiplier that determines how quickly the frequency increases for
+    /// each successive octave in the noise function.
+    ///
+    /// The frequency of each successive octave is equal to the product of the
+    /// previous octave's frequency and the lacunarity value.
+    pub lacunarity: f64,
+
+    /// A multiplier that determines how quickly the weights of the octaves
+    /// diminish.
+    ///
+    /// The weight of each successive octave, before it is damped by the
+    /// slope, is equal to the product of the previous octave's weight and the
+    /// persistence value.
+    pub persistence: f64,
+
+    /// How strongly the slope of the previous octaves damps each octave. Zero
+    /// turns the damping off, which gives plain fBm.
+    pub gradient_influence: f64,
+
+    seed: u64,
+    rotation: OctaveRotation,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    prototype: T,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    sources: Vec<T>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    transforms: Vec<OctaveTransform>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    scale_factor: f64,
+}
+
+fn calc_scale_factor(persistence: f64, octaves: usize) -> f64 {
+    1.0 - persistence.powi(octaves as i32)
+}
+
+impl ErodedFbm {
+    pub const DEFAULT_SEED: u64 = 0;
+    pub const DEFAULT_OCTAVE_COUNT: usize = 6;
+    pub const DEFAULT_FREQUENCY: f64 = 1.0;
+    pub const DEFAULT_LACUNARITY: f64 = std::f64::consts::PI * 2.0 / 3.0;
+    pub const DEFAULT_PERSISTENCE: f64 = 0.5;
+    pub const DEFAULT_GRADIENT_INFLUENCE: f64 = 1.0;
+    pub const MAX_OCTAVES: usize = 32;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T> ErodedFbm<T> {
+    /// Sets how strongly the slope of the previous octaves damps each octave.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `gradient_influence` is negative or isn't finite.
+    pub fn set_gradient_influence(self, gradient_influence: f64) -> Self {
+        if let Err(message) = check_gradient_influence(gradient_influence) {
+            panic!("{}", message);
+        }
+
+        Self {
+            gradient_influence,
+            ..self
+        }
+    }
+
+    /// Evaluates the fractal at `point`, with the octaves that are too fine
+    /// for a sample covering `width` faded out.
+    fn evaluate<const DIM: usize>(&self, point: [f64; DIM], width: f64) -> f64
+    where
+        T: NoiseFnGradient<DIM>,
+    {
+        let mut result = 0.0;
+        let mut slope = [0.0; DIM];
+        let mut point = scale_point(point, self.frequency);
+        let mut frequency = self.frequency;
+
+        for x in 0..self.octaves {
+            let fade = octave_weight(frequency, width);
+            if fade != 0.0 {
+                let weight = self.persistence.powi(x as i32) * fade;
+                let (signal, gradient) =
+                    get_octave(&self.sources[x], self.transforms.get(x), point, frequency)
+                        .into_tuple();
+
+                // Damp the octave by the steepness of the octaves so far, then
+                // add its own slope for the octaves after it.
+                let steepness: f64 = slope.iter().map(|slope| slope * slope).sum();
+                result += signal * weight / (1.0 + self.gradient_influence * steepness);
+
+                for (slope, gradient) in slope.iter_mut().zip(gradient.iter()) {
+                    *slope += gradient * weight;
+                }
+            }
+
+            point = scale_point(point, self.lacunarity);
+            frequency *= self.lacunarity;
+        }
+
+        result / self.scale_factor
+    }
+}
+
+fn check_gradient_influence(gradient_influence: f64) -> Result<(), String> {
+    if gradient_influence >= 0.0 && gradient_influence.is_finite() {
+        Ok(())
+    } else {
+        Err(format!(
+            "the gradient influence must be finite and not negative, got {}",
+            gradient_influence
+        ))
+    }
+}
+
+/// Parameters of an [`ErodedFbm`], like those of the other fractals but with
+/// the gradient influence as well.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ErodedFbmParams {
+    #[serde(flatten)]
+    fractal: super::FractalParams,
+    gradient_influence: Option<f64>,
+}
+
+#[cfg(feature = "serde")]
+impl<T> TryFrom<ErodedFbmParams> for ErodedFbm<T>
+where
+    T: Clone + Default + Seedable,
+{
+    type Error = String;
+
+    fn try_from(params: ErodedFbmParams) -> Result<Self, Self::Error> {
+        let fractal = params
+            .fractal
+            .build("ErodedFbm", ErodedFbm::MAX_OCTAVES, Self::default())?;
+
+        Ok(match params.gradient_influence {
+            Some(gradient_influence) => {
+                check_gradient_influence(gradient_influence)
+                    .map_err(|message| format!("ErodedFbm {}", message))?;
+                fractal.set_gradient_influence(gradient_influence)
+            }
+            None => fractal,
+        })
+    }
+}
+
+impl<T> Default for ErodedFbm<T>
+where
+    T: Clone + Default + Seedable,
+{
+    fn default() -> Self {
+        Self::with_source(T::default())
+    }
+}
+
+impl<T> ErodedFbm<T>
+where
+    T: Clone + Seedable,
+{
+    /// Creates the fractal with the default parameters, generating its
+    /// octaves with copies of `source` that are reseeded for each octave.
+    ///
+    /// The copies keep every setting of `source` other than its seed, so it
+    /// can be configured before it is passed in.
+    pub fn with_source(source: T) -> Self {
+        Self {
+            seed: ErodedFbm::DEFAULT_SEED,
+            octaves: ErodedFbm::DEFAULT_OCTAVE_COUNT,
+            frequency: ErodedFbm::DEFAULT_FREQUENCY,
+            lacunarity: ErodedFbm::DEFAULT_LACUNARITY,
+            persistence: ErodedFbm::DEFAULT_PERSISTENCE,
+            gradient_influence: ErodedFbm::DEFAULT_GRADIENT_INFLUENCE,
+            sources: super::build_sources(
+                &source,
+                ErodedFbm::DEFAULT_SEED,
+                ErodedFbm::DEFAULT_OCTAVE_COUNT,
+            ),
+            rotation: OctaveRotation::None,
+            transforms: Vec::new(),
+            scale_factor: calc_scale_factor(
+                ErodedFbm::DEFAULT_PERSISTENCE,
+                ErodedFbm::DEFAULT_OCTAVE_COUNT,
+            ),
+            prototype: source,
+        }
+    }
+}
+
+impl<T> MultiFractal for ErodedFbm<T>
+where
+    T: Clone + Seedable,
+{
+    fn set_octaves(self, mut octaves: usize) -> Self {
+        if self.octaves == octaves {
+            return self;
+        }
+
+        octaves = octaves.clamp(1, ErodedFbm::MAX_OCTAVES);
+        Self {
+            octaves,
+            sources: super::build_sources(&self.prototype, self.seed, octaves),
+            transforms: super::build_transforms(self.rotation, self.seed, octaves),
+            scale_factor: calc_scale_factor(self.persistence, octaves),
+            ..self
+        }
+    }
+
+    fn set_frequency(self, frequency: f64) -> Self {
+        Self { frequency, ..self }
+    }
+
+    fn set_lacunarity(self, lacunarity: f64) -> Self {
+        Self { lacunarity, ..self }
+    }
+
+    fn set_persistence(self, persistence: f64) -> Self {
+        Self {
+            persistence,
+            scale_factor: calc_scale_factor(persistence, self.octaves),
+            ..self
+        }
+    }
+
+    fn set_rotation(self, rotation: OctaveRotation) -> Self {
+        Self {
+            rotation,
+            transforms: super::build_transforms(rotation, self.seed, self.octaves),
+            ..self
+        }
+    }
+}
+
+impl<T> Seedable for ErodedFbm<T>
+where
+    T: Clone + Seedable,
+{
+    fn set_seed(self, seed: u64) -> Self {
+        if self.seed == seed {
+            return self;
+        }
+
+        Self {
+            seed,
+            sources: super::build_sources(&self.prototype, seed, self.octaves),
+            transforms: super::build_transforms(self.rotation, seed, self.octaves),
+            ..self
+        }
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl<T> Periodic for ErodedFbm<T>
+where
+    T: Periodic,
+{
+    fn set_period<const N: usize>(self, period: [u32; N]) -> Self {
+        // The prototype keeps the period for the sources rebuilt by
+        // `set_seed` and `set_octaves`.
+        Self {
+            prototype: self.prototype.set_period(period),
+            sources: self
+                .sources
+                .into_iter()
+                .map(|source| source.set_period(period))
+                .collect(),
+            ..self
+        }
+    }
+
+    fn period(&self) -> [u32; 4] {
+        self.prototype.period()
+    }
+}
+
+/// ErodedFbm noise, in any dimension its generator has a gradient in
+impl<T, const DIM: usize> NoiseFn<f64, DIM> for ErodedFbm<T>
+where
+    T: NoiseFnGradient<DIM>,
+{
+    fn get(&self, point: [f64; DIM]) -> f64 {
+        self.evaluate(point, 0.0)
+    }
+
+    fn get_filtered(&self, point: [f64; DIM], width: f64) -> f64 {
+        self.evaluate(point, width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f64; DIM]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.evaluate(point, width));
+    }
+}
+
+/// ErodedFbm noise at `f32` coordinates
+impl<T, const DIM: usize> NoiseFn<f32, DIM> for ErodedFbm<T>
+where
+    T: NoiseFnGradient<DIM>,
+{
+    fn get(&self, point: [f32; DIM]) -> f64 {
+        get_widened(self, point)
+    }
+
+    fn get_batch(&self, points: &[[f32; DIM]], output: &mut [f64]) {
+        evaluate_batch_widened(self, points, output);
+    }
+
+    fn get_filtered(&self, point: [f32; DIM], width: f64) -> f64 {
+        self.evaluate(widen(point), width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f32; DIM]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.evaluate(widen(point), width));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Fbm;
+
+    #[test]
+    fn test_gradient_influence() {
+        let point = [0.4, 1.7, -2.3];
+
+        // Without damping, the octaves add up exactly like fBm.
+        let fbm = Fbm::<Simplex>::default().set_seed(2);
+        let eroded = ErodedFbm::new().set_seed(2);
+        assert_eq!(
+            eroded.clone().set_gradient_influence(0.0).get(point),
+            fbm.get(point)
+        );
+        assert_ne!(eroded.get(point), fbm.get(point));
+
+        // Strong damping leaves little but the first octave, which is scaled
+        // for one octave rather than six on its own.
+        let first = ErodedFbm::new().set_seed(2).set_octaves(1).get(point) / 2.0 * 64.0 / 63.0;
+        let damped = eroded.set_gradient_influence(1e9).get(point);
+        assert!((damped - first).abs() < 1e-3);
+    }
+}
@@ -1,6 +1,15 @@
-use crate::math;
-
-use crate::noise_fns::{MultiFractal, NoiseFn, Perlin, Seedable};
+use crate::math::{self, dual::Dual};
+
+use super::{
+    get_filtered_octave, get_octave, octave_weight, scale_point, transform_octave, OctaveRotation,
+    OctaveTransform,
+};
+use crate::noise_fns::{
+    evaluate_batch, evaluate_batch_widened, get_widened, widen, MultiFractal, NoiseFn,
+    NoiseFnGradient, Periodic, Perlin, Seedable,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Noise function that outputs fBm (fractal Brownian motion) noise.
 ///
@@ -18,8 +27,39 @@ use crate::noise_fns::{MultiFractal, NoiseFn, Perlin, Seedable};
 /// and ever-decreasing amplitude.
 ///
 /// fBm is commonly referred to as Perlin noise.
+///
+/// The octaves are generated by a noise function of type `T`, which is
+/// [`Perlin`] unless another is given. Any [`Seedable`] generator with a
+/// [`Default`] can be used instead, in which case the fractal is built with
+/// `default`:
+///
+/// ```
+/// use noise::{Fbm, MultiFractal, NoiseFn, Simplex};
+///
+/// let fbm = Fbm::<Simplex>::default().set_octaves(4);
+/// let value = fbm.get([1.0, 2.0]);
+/// ```
+///
+/// To configure the generator first, pass it to
+/// [`with_source`](Self::with_source), which builds the octaves from reseeded
+/// copies of it:
+///
+/// ```
+/// use noise::{Fbm, NoiseFn, ReturnType, Worley};
+///
+/// let fbm = Fbm::with_source(Worley::default().set_return_type(ReturnType::Distance));
+/// let value = fbm.get([1.0, 2.0]);
+/// ```
 #[derive(Clone, Debug)]
-pub struct Fbm {
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        try_from = "super::FractalParams",
+        bound(deserialize = "T: Clone + Default + Seedable")
+    )
+)]
+pub struct Fbm<T = Perlin> {
     /// Total number of frequency octaves to generate the noise with.
     ///
     /// The number of octaves control the _amount of detail_ in the noise
@@ -48,8 +88,15 @@ pub struct Fbm {
     /// persistence produces "rougher" noise.
     pub persistence: f64,
 
-    seed: u32,
-    sources: Vec<Perlin>,
+    seed: u64,
+    rotation: OctaveRotation,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    prototype: T,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    sources: Vec<T>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    transforms: Vec<OctaveTransform>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
     scale_factor: f64,
 }
 
@@ -58,7 +105,7 @@ fn calc_scale_factor(persistence: f64, octaves: usize) -> f64 {
 }
 
 impl Fbm {
-    pub const DEFAULT_SEED: u32 = 0;
+    pub const DEFAULT_SEED: u64 = 0;
     pub const DEFAULT_OCTAVE_COUNT: usize = 6;
     pub const DEFAULT_FREQUENCY: f64 = 1.0;
     pub const DEFAULT_LACUNARITY: f64 = std::f64::consts::PI * 2.0 / 3.0;
@@ -66,34 +113,58 @@ impl Fbm {
     pub const MAX_OCTAVES: usize = 32;
 
     pub fn new() -> Self {
-        Self {
-            seed: Self::DEFAULT_SEED,
-            octaves: Self::DEFAULT_OCTAVE_COUNT,
-            frequency: Self::DEFAULT_FREQUENCY,
-            lacunarity: Self::DEFAULT_LACUNARITY,
-            persistence: Self::DEFAULT_PERSISTENCE,
-            sources: super::build_sources(Self::DEFAULT_SEED, Self::DEFAULT_OCTAVE_COUNT),
-            scale_factor: calc_scale_factor(Self::DEFAULT_PERSISTENCE, Self::DEFAULT_OCTAVE_COUNT),
-        }
+        Self::default()
     }
 }
 
-impl Default for Fbm {
+impl<T> Default for Fbm<T>
+where
+    T: Clone + Default + Seedable,
+{
     fn default() -> Self {
-        Self::new()
+        Self::with_source(T::default())
     }
 }
 
-impl MultiFractal for Fbm {
+impl<T> Fbm<T>
+where
+    T: Clone + Seedable,
+{
+    /// Creates the fractal with the default parameters, generating its
+    /// octaves with copies of `source` that are reseeded for each octave.
+    ///
+    /// The copies keep every setting of `source` other than its seed, so it
+    /// can be configured before it is passed in.
+    pub fn with_source(source: T) -> Self {
+        Self {
+            seed: Fbm::DEFAULT_SEED,
+            octaves: Fbm::DEFAULT_OCTAVE_COUNT,
+            frequency: Fbm::DEFAULT_FREQUENCY,
+            lacunarity: Fbm::DEFAULT_LACUNARITY,
+            persistence: Fbm::DEFAULT_PERSISTENCE,
+            sources: super::build_sources(&source, Fbm::DEFAULT_SEED, Fbm::DEFAULT_OCTAVE_COUNT),
+            rotation: OctaveRotation::None,
+            transforms: Vec::new(),
+            scale_factor: calc_scale_factor(Fbm::DEFAULT_PERSISTENCE, Fbm::DEFAULT_OCTAVE_COUNT),
+            prototype: source,
+        }
+    }
+}
+
+impl<T> MultiFractal for Fbm<T>
+where
+    T: Clone + Seedable,
+{
     fn set_octaves(self, mut octaves: usize) -> Self {
         if self.octaves == octaves {
             return self;
         }
 
-        octaves = octaves.clamp(1, Self::MAX_OCTAVES);
+        octaves = octaves.clamp(1, Fbm::MAX_OCTAVES);
         Self {
             octaves,
-            sources: super::build_sources(self.seed, octaves),
+            sources: super::build_sources(&self.prototype, self.seed, octaves),
+            transforms: super::build_transforms(self.rotation, self.seed, octaves),
             scale_factor: calc_scale_factor(self.persistence, octaves),
             ..self
         }
@@ -114,28 +185,128 @@ impl MultiFractal for Fbm {
             ..self
         }
     }
+
+    fn set_rotation(self, rotation: OctaveRotation) -> Self {
+        Self {
+            rotation,
+            transforms: super::build_transforms(rotation, self.seed, self.octaves),
+            ..self
+        }
+    }
 }
 
-impl Seedable for Fbm {
-    fn set_seed(self, seed: u32) -> Self {
+impl<T> Seedable for Fbm<T>
+where
+    T: Clone + Seedable,
+{
+    fn set_seed(self, seed: u64) -> Self {
         if self.seed == seed {
             return self;
         }
 
         Self {
             seed,
-            sources: super::build_sources(seed, self.octaves),
+            sources: super::build_sources(&self.prototype, seed, self.octaves),
+            transforms: super::build_transforms(self.rotation, seed, self.octaves),
             ..self
         }
     }
 
-    fn seed(&self) -> u32 {
+    fn seed(&self) -> u64 {
         self.seed
     }
 }
 
+impl<T> Periodic for Fbm<T>
+where
+    T: Periodic,
+{
+    fn set_period<const N: usize>(self, period: [u32; N]) -> Self {
+        // The prototype keeps the period for the sources rebuilt by
+        // `set_seed` and `set_octaves`.
+        Self {
+            prototype: self.prototype.set_period(period),
+            sources: self
+                .sources
+                .into_iter()
+                .map(|source| source.set_period(period))
+                .collect(),
+            ..self
+        }
+    }
+
+    fn period(&self) -> [u32; 4] {
+        self.prototype.period()
+    }
+}
+
+impl<T> Fbm<T> {
+    /// Evaluates the fractal at `point` with the octaves that are too fine for
+    /// a sample covering `width` faded out.
+    fn filtered<const DIM: usize>(&self, point: [f64; DIM], width: f64) -> f64
+    where
+        T: NoiseFn<f64, DIM>,
+    {
+        let mut result = 0.0;
+        let mut point = scale_point(point, self.frequency);
+        let mut frequency = self.frequency;
+
+        for x in 0..self.octaves {
+            let weight = octave_weight(frequency, width);
+            let signal =
+                get_filtered_octave(&self.sources[x], self.transforms.get(x), point, weight);
+            result += signal * self.persistence.powi(x as i32);
+
+            point = scale_point(point, self.lacunarity);
+            frequency *= self.lacunarity;
+        }
+
+        result / self.scale_factor
+    }
+}
+
+/// 1-dimensional Fbm noise
+impl<T> NoiseFn<f64, 1> for Fbm<T>
+where
+    T: NoiseFn<f64, 1>,
+{
+    fn get(&self, mut point: [f64; 1]) -> f64 {
+        let mut result = 0.0;
+
+        point = scale_point(point, self.frequency);
+
+        for x in 0..self.octaves {
+            // Get the signal.
+            let mut signal = self.sources[x].get(transform_octave(self.transforms.get(x), point));
+
+            // Scale the amplitude appropriately for this frequency.
+            signal *= self.persistence.powi(x as i32);
+
+            // Add the signal to the result.
+            result += signal;
+
+            // Increase the frequency for the next octave.
+            point = scale_point(point, self.lacunarity);
+        }
+
+        // Scale the result into the [-1,1] range
+        result / self.scale_factor
+    }
+
+    fn get_filtered(&self, point: [f64; 1], width: f64) -> f64 {
+        self.filtered(point, width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f64; 1]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.filtered(point, width));
+    }
+}
+
 /// 2-dimensional Fbm noise
-impl NoiseFn<f64, 2> for Fbm {
+impl<T> NoiseFn<f64, 2> for Fbm<T>
+where
+    T: NoiseFn<f64, 2>,
+{
     fn get(&self, mut point: [f64; 2]) -> f64 {
         let mut result = 0.0;
 
@@ -143,7 +314,7 @@ impl NoiseFn<f64, 2> for Fbm {
 
         for x in 0..self.octaves {
             // Get the signal.
-            let mut signal = self.sources[x].get(point);
+            let mut signal = self.sources[x].get(transform_octave(self.transforms.get(x), point));
 
             // Scale the amplitude appropriately for this frequency.
             signal *= self.persistence.powi(x as i32);
@@ -158,10 +329,21 @@ impl NoiseFn<f64, 2> for Fbm {
         // Scale the result into the [-1,1] range
         result / self.scale_factor
     }
+
+    fn get_filtered(&self, point: [f64; 2], width: f64) -> f64 {
+        self.filtered(point, width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f64; 2]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.filtered(point, width));
+    }
 }
 
 /// 3-dimensional Fbm noise
-impl NoiseFn<f64, 3> for Fbm {
+impl<T> NoiseFn<f64, 3> for Fbm<T>
+where
+    T: NoiseFn<f64, 3>,
+{
     fn get(&self, mut point: [f64; 3]) -> f64 {
         let mut result = 0.0;
 
@@ -169,7 +351,7 @@ impl NoiseFn<f64, 3> for Fbm {
 
         for x in 0..self.octaves {
             // Get the signal.
-            let mut signal = self.sources[x].get(point);
+            let mut signal = self.sources[x].get(transform_octave(self.transforms.get(x), point));
 
             // Scale the amplitude appropriately for this frequency.
             signal *= self.persistence.powi(x as i32);
@@ -184,10 +366,21 @@ impl NoiseFn<f64, 3> for Fbm {
         // Scale the result into the [-1,1] range
         result / self.scale_factor
     }
+
+    fn get_filtered(&self, point: [f64; 3], width: f64) -> f64 {
+        self.filtered(point, width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f64; 3]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.filtered(point, width));
+    }
 }
 
 /// 4-dimensional Fbm noise
-impl NoiseFn<f64, 4> for Fbm {
+impl<T> NoiseFn<f64, 4> for Fbm<T>
+where
+    T: NoiseFn<f64, 4>,
+{
     fn get(&self, mut point: [f64; 4]) -> f64 {
         let mut result = 0.0;
 
@@ -195,7 +388,7 @@ impl NoiseFn<f64, 4> for Fbm {
 
         for x in 0..self.octaves {
             // Get the signal.
-            let mut signal = self.sources[x].get(point);
+            let mut signal = self.sources[x].get(transform_octave(self.transforms.get(x), point));
 
             // Scale the amplitude appropriately for this frequency.
             signal *= self.persistence.powi(x as i32);
@@ -210,4 +403,59 @@ impl NoiseFn<f64, 4> for Fbm {
         // Scale the result into the [-1,1] range
         result / self.scale_factor
     }
+
+    fn get_filtered(&self, point: [f64; 4], width: f64) -> f64 {
+        self.filtered(point, width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f64; 4]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.filtered(point, width));
+    }
+}
+
+/// Fbm noise, with its gradient
+impl<T, const DIM: usize> NoiseFnGradient<DIM> for Fbm<T>
+where
+    Self: NoiseFn<f64, DIM>,
+    T: NoiseFnGradient<DIM>,
+{
+    fn get_with_gradient(&self, point: [f64; DIM]) -> (f64, [f64; DIM]) {
+        let mut result = Dual::constant(0.0);
+        let mut point = scale_point(point, self.frequency);
+        let mut frequency = self.frequency;
+
+        for x in 0..self.octaves {
+            let signal = get_octave(&self.sources[x], self.transforms.get(x), point, frequency);
+            result += signal * self.persistence.powi(x as i32);
+
+            point = scale_point(point, self.lacunarity);
+            frequency *= self.lacunarity;
+        }
+
+        (result / self.scale_factor).into_tuple()
+    }
+}
+
+/// Fbm noise at `f32` coordinates
+impl<T, const DIM: usize> NoiseFn<f32, DIM> for Fbm<T>
+where
+    Self: NoiseFn<f64, DIM>,
+{
+    fn get(&self, point: [f32; DIM]) -> f64 {
+        get_widened(self, point)
+    }
+
+    fn get_batch(&self, points: &[[f32; DIM]], output: &mut [f64]) {
+        evaluate_batch_widened(self, points, output);
+    }
+
+    fn get_filtered(&self, point: [f32; DIM], width: f64) -> f64 {
+        NoiseFn::<f64, DIM>::get_filtered(self, widen(point), width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f32; DIM]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| {
+            NoiseFn::<f64, DIM>::get_filtered(self, widen(point), width)
+        });
+    }
 }
@@ -1,13 +1,33 @@
 use crate::math;
 
-use crate::noise_fns::{MultiFractal, NoiseFn, Perlin, Seedable};
+use super::{
+    get_filtered_octave, get_octave, octave_weight, scale_point, transform_octave, OctaveRotation,
+    OctaveTransform,
+};
+use crate::noise_fns::{
+    evaluate_batch, evaluate_batch_widened, get_widened, widen, MultiFractal, NoiseFn,
+    NoiseFnGradient, Periodic, Perlin, Seedable,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Noise function that outputs hybrid Multifractal noise.
 ///
 /// The result of this multifractal noise is that valleys in the noise should
 /// have smooth bottoms at all altitudes.
+///
+/// Like [`Fbm`](super::Fbm), the octaves can come from any [`Seedable`]
+/// generator `T`, and are [`Perlin`] noise by default.
 #[derive(Clone, Debug)]
-pub struct HybridMulti {
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        try_from = "super::FractalParams",
+        bound(deserialize = "T: Clone + Default + Seedable")
+    )
+)]
+pub struct HybridMulti<T = Perlin> {
     /// Total number of frequency octaves to generate the noise with.
     ///
     /// The number of octaves control the _amount of detail_ in the noise
@@ -36,12 +56,18 @@ pub struct HybridMulti {
     /// persistence produces "rougher" noise.
     pub persistence: f64,
 
-    seed: u32,
-    sources: Vec<Perlin>,
+    seed: u64,
+    rotation: OctaveRotation,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    prototype: T,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    sources: Vec<T>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    transforms: Vec<OctaveTransform>,
 }
 
 impl HybridMulti {
-    pub const DEFAULT_SEED: u32 = 0;
+    pub const DEFAULT_SEED: u64 = 0;
     pub const DEFAULT_OCTAVES: usize = 6;
     pub const DEFAULT_FREQUENCY: f64 = 2.0;
     pub const DEFAULT_LACUNARITY: f64 = std::f64::consts::PI * 2.0 / 3.0;
@@ -49,33 +75,61 @@ impl HybridMulti {
     pub const MAX_OCTAVES: usize = 32;
 
     pub fn new() -> Self {
-        Self {
-            seed: Self::DEFAULT_SEED,
-            octaves: Self::DEFAULT_OCTAVES,
-            frequency: Self::DEFAULT_FREQUENCY,
-            lacunarity: Self::DEFAULT_LACUNARITY,
-            persistence: Self::DEFAULT_PERSISTENCE,
-            sources: super::build_sources(Self::DEFAULT_SEED, Self::DEFAULT_OCTAVES),
-        }
+        Self::default()
     }
 }
 
-impl Default for HybridMulti {
+impl<T> Default for HybridMulti<T>
+where
+    T: Clone + Default + Seedable,
+{
     fn default() -> Self {
-        Self::new()
+        Self::with_source(T::default())
     }
 }
 
-impl MultiFractal for HybridMulti {
+impl<T> HybridMulti<T>
+where
+    T: Clone + Seedable,
+{
+    /// Creates the fractal with the default parameters, generating its
+    /// octaves with copies of `source` that are reseeded for each octave.
+    ///
+    /// The copies keep every setting of `source` other than its seed, so it
+    /// can be configured before it is passed in.
+    pub fn with_source(source: T) -> Self {
+        Self {
+            seed: HybridMulti::DEFAULT_SEED,
+            octaves: HybridMulti::DEFAULT_OCTAVES,
+            frequency: HybridMulti::DEFAULT_FREQUENCY,
+            lacunarity: HybridMulti::DEFAULT_LACUNARITY,
+            persistence: HybridMulti::DEFAULT_PERSISTENCE,
+            sources: super::build_sources(
+                &source,
+                HybridMulti::DEFAULT_SEED,
+                HybridMulti::DEFAULT_OCTAVES,
+            ),
+            rotation: OctaveRotation::None,
+            transforms: Vec::new(),
+            prototype: source,
+        }
+    }
+}
+
+impl<T> MultiFractal for HybridMulti<T>
+where
+    T: Clone + Seedable,
+{
     fn set_octaves(self, mut octaves: usize) -> Self {
         if self.octaves == octaves {
             return self;
         }
 
-        octaves = octaves.clamp(1, Self::MAX_OCTAVES);
+        octaves = octaves.clamp(1, HybridMulti::MAX_OCTAVES);
         Self {
             octaves,
-            sources: super::build_sources(self.seed, octaves),
+            sources: super::build_sources(&self.prototype, self.seed, octaves),
+            transforms: super::build_transforms(self.rotation, self.seed, octaves),
             ..self
         }
     }
@@ -94,32 +148,151 @@ impl MultiFractal for HybridMulti {
             ..self
         }
     }
+
+    fn set_rotation(self, rotation: OctaveRotation) -> Self {
+        Self {
+            rotation,
+            transforms: super::build_transforms(rotation, self.seed, self.octaves),
+            ..self
+        }
+    }
 }
 
-impl Seedable for HybridMulti {
-    fn set_seed(self, seed: u32) -> Self {
+impl<T> Seedable for HybridMulti<T>
+where
+    T: Clone + Seedable,
+{
+    fn set_seed(self, seed: u64) -> Self {
         if self.seed == seed {
             return self;
         }
 
         Self {
             seed,
-            sources: super::build_sources(seed, self.octaves),
+            sources: super::build_sources(&self.prototype, seed, self.octaves),
+            transforms: super::build_transforms(self.rotation, seed, self.octaves),
             ..self
         }
     }
 
-    fn seed(&self) -> u32 {
+    fn seed(&self) -> u64 {
         self.seed
     }
 }
 
+impl<T> Periodic for HybridMulti<T>
+where
+    T: Periodic,
+{
+    fn set_period<const N: usize>(self, period: [u32; N]) -> Self {
+        // The prototype keeps the period for the sources rebuilt by
+        // `set_seed` and `set_octaves`.
+        Self {
+            prototype: self.prototype.set_period(period),
+            sources: self
+                .sources
+                .into_iter()
+                .map(|source| source.set_period(period))
+                .collect(),
+            ..self
+        }
+    }
+
+    fn period(&self) -> [u32; 4] {
+        self.prototype.period()
+    }
+}
+
+impl<T> HybridMulti<T> {
+    /// Evaluates the fractal at `point` with the octaves that are too fine for
+    /// a sample covering `width` faded out.
+    fn filtered<const DIM: usize>(&self, point: [f64; DIM], width: f64) -> f64
+    where
+        T: NoiseFn<f64, DIM>,
+    {
+        let mut point = scale_point(point, self.frequency);
+        let mut frequency = self.frequency;
+        let mut result = get_filtered_octave(
+            &self.sources[0],
+            self.transforms.first(),
+            point,
+            octave_weight(frequency, width),
+        ) * self.persistence;
+        let mut weight = result;
+
+        for x in 1..self.octaves {
+            weight = weight.max(1.0);
+
+            point = scale_point(point, self.lacunarity);
+            frequency *= self.lacunarity;
+
+            let fade = octave_weight(frequency, width);
+            let signal = get_filtered_octave(&self.sources[x], self.transforms.get(x), point, fade)
+                * self.persistence.powi(x as i32);
+            result += weight * signal;
+            weight *= signal;
+        }
+
+        result * 3.0
+    }
+}
+
+/// 1-dimensional `HybridMulti` noise
+impl<T> NoiseFn<f64, 1> for HybridMulti<T>
+where
+    T: NoiseFn<f64, 1>,
+{
+    fn get(&self, mut point: [f64; 1]) -> f64 {
+        // First unscaled octave of function; later octaves are scaled.
+        point = scale_point(point, self.frequency);
+        let mut result = self.sources[0].get(transform_octave(self.transforms.first(), point))
+            * self.persistence;
+        let mut weight = result;
+
+        // Spectral construction inner loop, where the fractal is built.
+        for x in 1..self.octaves {
+            // Prevent divergence.
+            weight = weight.max(1.0);
+
+            // Raise the spatial frequency.
+            point = scale_point(point, self.lacunarity);
+
+            // Get noise value.
+            let mut signal = self.sources[x].get(transform_octave(self.transforms.get(x), point));
+
+            // Scale the amplitude appropriately for this frequency.
+            signal *= self.persistence.powi(x as i32);
+
+            // Add it in, weighted by previous octave's noise value.
+            result += weight * signal;
+
+            // Update the weighting value.
+            weight *= signal;
+        }
+
+        // Scale the result to the [-1,1] range
+        result * 3.0
+    }
+
+    fn get_filtered(&self, point: [f64; 1], width: f64) -> f64 {
+        self.filtered(point, width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f64; 1]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.filtered(point, width));
+    }
+}
+
 /// 2-dimensional `HybridMulti` noise
-impl NoiseFn<f64, 2> for HybridMulti {
+impl<T> NoiseFn<f64, 2> for HybridMulti<T>
+where
+    T: NoiseFn<f64, 2>,
+{
     fn get(&self, mut point: [f64; 2]) -> f64 {
         // First unscaled octave of function; later octaves are scaled.
         point = math::mul2(point, self.frequency);
-        let mut result = self.sources[0].get(point) * self.persistence;
+        let mut result = self.sources[0].get(transform_octave(self.transforms.first(), point))
+            * self.persistence;
         let mut weight = result;
 
         // Spectral construction inner loop, where the fractal is built.
@@ -131,7 +304,7 @@ impl NoiseFn<f64, 2> for HybridMulti {
             point = math::mul2(point, self.lacunarity);
 
             // Get noise value.
-            let mut signal = self.sources[x].get(point);
+            let mut signal = self.sources[x].get(transform_octave(self.transforms.get(x), point));
 
             // Scale the amplitude appropriately for this frequency.
             signal *= self.persistence.powi(x as i32);
@@ -146,14 +319,26 @@ impl NoiseFn<f64, 2> for HybridMulti {
         // Scale the result to the [-1,1] range
         result * 3.0
     }
+
+    fn get_filtered(&self, point: [f64; 2], width: f64) -> f64 {
+        self.filtered(point, width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f64; 2]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.filtered(point, width));
+    }
 }
 
 /// 3-dimensional `HybridMulti` noise
-impl NoiseFn<f64, 3> for HybridMulti {
+impl<T> NoiseFn<f64, 3> for HybridMulti<T>
+where
+    T: NoiseFn<f64, 3>,
+{
     fn get(&self, mut point: [f64; 3]) -> f64 {
         // First unscaled octave of function; later octaves are scaled.
         point = math::mul3(point, self.frequency);
-        let mut result = self.sources[0].get(point) * self.persistence;
+        let mut result = self.sources[0].get(transform_octave(self.transforms.first(), point))
+            * self.persistence;
         let mut weight = result;
 
         // Spectral construction inner loop, where the fractal is built.
@@ -165,7 +350,7 @@ impl NoiseFn<f64, 3> for HybridMulti {
             point = math::mul3(point, self.lacunarity);
 
             // Get noise value.
-            let mut signal = self.sources[x].get(point);
+            let mut signal = self.sources[x].get(transform_octave(self.transforms.get(x), point));
 
             // Scale the amplitude appropriately for this frequency.
             signal *= self.persistence.powi(x as i32);
@@ -180,14 +365,26 @@ impl NoiseFn<f64, 3> for HybridMulti {
         // Scale the result to the [-1,1] range
         result * 3.0
     }
+
+    fn get_filtered(&self, point: [f64; 3], width: f64) -> f64 {
+        self.filtered(point, width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f64; 3]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.filtered(point, width));
+    }
 }
 
 /// 4-dimensional `HybridMulti` noise
-impl NoiseFn<f64, 4> for HybridMulti {
+impl<T> NoiseFn<f64, 4> for HybridMulti<T>
+where
+    T: NoiseFn<f64, 4>,
+{
     fn get(&self, mut point: [f64; 4]) -> f64 {
         // First unscaled octave of function; later octaves are scaled.
         point = math::mul4(point, self.frequency);
-        let mut result = self.sources[0].get(point) * self.persistence;
+        let mut result = self.sources[0].get(transform_octave(self.transforms.first(), point))
+            * self.persistence;
         let mut weight = result;
 
         // Spectral construction inner loop, where the fractal is built.
@@ -199,7 +396,7 @@ impl NoiseFn<f64, 4> for HybridMulti {
             point = math::mul4(point, self.lacunarity);
 
             // Get noise value.
-            let mut signal = self.sources[x].get(point);
+            let mut signal = self.sources[x].get(transform_octave(self.transforms.get(x), point));
 
             // Scale the amplitude appropriately for this frequency.
             signal *= self.persistence.powi(x as i32);
@@ -214,4 +411,65 @@ impl NoiseFn<f64, 4> for HybridMulti {
         // Scale the result to the [-1,1] range
         result * 3.0
     }
+
+    fn get_filtered(&self, point: [f64; 4], width: f64) -> f64 {
+        self.filtered(point, width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f64; 4]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.filtered(point, width));
+    }
+}
+
+/// `HybridMulti` noise, with its gradient
+impl<T, const DIM: usize> NoiseFnGradient<DIM> for HybridMulti<T>
+where
+    Self: NoiseFn<f64, DIM>,
+    T: NoiseFnGradient<DIM>,
+{
+    fn get_with_gradient(&self, point: [f64; DIM]) -> (f64, [f64; DIM]) {
+        let mut point = scale_point(point, self.frequency);
+        let mut frequency = self.frequency;
+        let mut result = get_octave(&self.sources[0], self.transforms.first(), point, frequency)
+            * self.persistence;
+        let mut weight = result;
+
+        for x in 1..self.octaves {
+            weight = weight.max(1.0);
+
+            point = scale_point(point, self.lacunarity);
+            frequency *= self.lacunarity;
+
+            let signal = get_octave(&self.sources[x], self.transforms.get(x), point, frequency)
+                * self.persistence.powi(x as i32);
+            result += weight * signal;
+            weight = weight * signal;
+        }
+
+        (result * 3.0).into_tuple()
+    }
+}
+
+/// `HybridMulti` noise at `f32` coordinates
+impl<T, const DIM: usize> NoiseFn<f32, DIM> for HybridMulti<T>
+where
+    Self: NoiseFn<f64, DIM>,
+{
+    fn get(&self, point: [f32; DIM]) -> f64 {
+        get_widened(self, point)
+    }
+
+    fn get_batch(&self, points: &[[f32; DIM]], output: &mut [f64]) {
+        evaluate_batch_widened(self, points, output);
+    }
+
+    fn get_filtered(&self, point: [f32; DIM], width: f64) -> f64 {
+        NoiseFn::<f64, DIM>::get_filtered(self, widen(point), width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f32; DIM]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| {
+            NoiseFn::<f64, DIM>::get_filtered(self, widen(point), width)
+        });
+    }
 }
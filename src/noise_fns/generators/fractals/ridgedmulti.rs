@@ -1,7 +1,17 @@
+use super::{
+    get_octave, octave_weight, scale_point, transform_octave, OctaveRotation, OctaveTransform,
+};
 use crate::{
-    math::{self, scale_shift},
-    noise_fns::{MultiFractal, NoiseFn, Perlin, Seedable},
+    math::{self, dual::Dual, scale_shift},
+    noise_fns::{
+        evaluate_batch, evaluate_batch_widened, get_widened, widen, MultiFractal, NoiseFn,
+        NoiseFnGradient, Periodic, Perlin, Seedable,
+    },
 };
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
 
 /// Noise function that outputs ridged-multifractal noise.
 ///
@@ -19,8 +29,20 @@ use crate::{
 ///
 /// Ridged-multifractal noise is often used to generate craggy mountainous
 /// terrain or marble-like textures.
+///
+/// The octaves can come from any [`Seedable`] generator `T`, and are
+/// [`Perlin`] noise by default. Ridged [`Worley`](crate::Worley) noise, for
+/// instance, is `RidgedMulti::<Worley>::default()`.
 #[derive(Clone, Debug)]
-pub struct RidgedMulti {
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        try_from = "RidgedMultiParams",
+        bound(deserialize = "T: Clone + Default + Seedable")
+    )
+)]
+pub struct RidgedMulti<T = Perlin> {
     /// Total number of frequency octaves to generate the noise with.
     ///
     /// The number of octaves control the _amount of detail_ in the noise
@@ -55,12 +77,18 @@ pub struct RidgedMulti {
     /// half the height of the previous.
     pub attenuation: f64,
 
-    seed: u32,
-    sources: Vec<Perlin>,
+    seed: u64,
+    rotation: OctaveRotation,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    prototype: T,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    sources: Vec<T>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    transforms: Vec<OctaveTransform>,
 }
 
 impl RidgedMulti {
-    pub const DEFAULT_SEED: u32 = 0;
+    pub const DEFAULT_SEED: u64 = 0;
     pub const DEFAULT_OCTAVE_COUNT: usize = 6;
     pub const DEFAULT_FREQUENCY: f64 = 1.0;
     pub const DEFAULT_LACUNARITY: f64 = std::f64::consts::PI * 2.0 / 3.0;
@@ -69,17 +97,11 @@ impl RidgedMulti {
     pub const MAX_OCTAVES: usize = 32;
 
     pub fn new() -> Self {
-        Self {
-            seed: Self::DEFAULT_SEED,
-            octaves: Self::DEFAULT_OCTAVE_COUNT,
-            frequency: Self::DEFAULT_FREQUENCY,
-            lacunarity: Self::DEFAULT_LACUNARITY,
-            persistence: Self::DEFAULT_PERSISTENCE,
-            attenuation: Self::DEFAULT_ATTENUATION,
-            sources: super::build_sources(Self::DEFAULT_SEED, Self::DEFAULT_OCTAVE_COUNT),
-        }
+        Self::default()
     }
+}
 
+impl<T> RidgedMulti<T> {
     pub fn set_attenuation(self, attenuation: f64) -> Self {
         Self {
             attenuation,
@@ -88,22 +110,89 @@ impl RidgedMulti {
     }
 }
 
-impl Default for RidgedMulti {
+/// Parameters of a [`RidgedMulti`], like those of the other fractals but with
+/// the attenuation as well.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RidgedMultiParams {
+    #[serde(flatten)]
+    fractal: super::FractalParams,
+    attenuation: Option<f64>,
+}
+
+#[cfg(feature = "serde")]
+impl<T> TryFrom<RidgedMultiParams> for RidgedMulti<T>
+where
+    T: Clone + Default + Seedable,
+{
+    type Error = String;
+
+    fn try_from(params: RidgedMultiParams) -> Result<Self, Self::Error> {
+        let fractal =
+            params
+                .fractal
+                .build("RidgedMulti", RidgedMulti::MAX_OCTAVES, Self::default())?;
+
+        Ok(match params.attenuation {
+            Some(attenuation) => fractal.set_attenuation(attenuation),
+            None => fractal,
+        })
+    }
+}
+
+impl<T> Default for RidgedMulti<T>
+where
+    T: Clone + Default + Seedable,
+{
     fn default() -> Self {
-        Self::new()
+        Self::with_source(T::default())
+    }
+}
+
+impl<T> RidgedMulti<T>
+where
+    T: Clone + Seedable,
+{
+    /// Creates the fractal with the default parameters, generating its
+    /// octaves with copies of `source` that are reseeded for each octave.
+    ///
+    /// The copies keep every setting of `source` other than its seed, so it
+    /// can be configured before it is passed in.
+    pub fn with_source(source: T) -> Self {
+        Self {
+            seed: RidgedMulti::DEFAULT_SEED,
+            octaves: RidgedMulti::DEFAULT_OCTAVE_COUNT,
+            frequency: RidgedMulti::DEFAULT_FREQUENCY,
+            lacunarity: RidgedMulti::DEFAULT_LACUNARITY,
+            persistence: RidgedMulti::DEFAULT_PERSISTENCE,
+            attenuation: RidgedMulti::DEFAULT_ATTENUATION,
+            sources: super::build_sources(
+                &source,
+                RidgedMulti::DEFAULT_SEED,
+                RidgedMulti::DEFAULT_OCTAVE_COUNT,
+            ),
+            rotation: OctaveRotation::None,
+            transforms: Vec::new(),
+            prototype: source,
+        }
     }
 }
 
-impl MultiFractal for RidgedMulti {
+impl<T> MultiFractal for RidgedMulti<T>
+where
+    T: Clone + Seedable,
+{
     fn set_octaves(self, mut octaves: usize) -> Self {
         if self.octaves == octaves {
             return self;
         }
 
-        octaves = octaves.clamp(1, Self::MAX_OCTAVES);
+        octaves = octaves.clamp(1, RidgedMulti::MAX_OCTAVES);
         Self {
             octaves,
-            sources: super::build_sources(self.seed, octaves),
+            sources: super::build_sources(&self.prototype, self.seed, octaves),
+            transforms: super::build_transforms(self.rotation, self.seed, octaves),
             ..self
         }
     }
@@ -122,28 +211,164 @@ impl MultiFractal for RidgedMulti {
             ..self
         }
     }
+
+    fn set_rotation(self, rotation: OctaveRotation) -> Self {
+        Self {
+            rotation,
+            transforms: super::build_transforms(rotation, self.seed, self.octaves),
+            ..self
+        }
+    }
 }
 
-impl Seedable for RidgedMulti {
-    fn set_seed(self, seed: u32) -> Self {
+impl<T> Seedable for RidgedMulti<T>
+where
+    T: Clone + Seedable,
+{
+    fn set_seed(self, seed: u64) -> Self {
         if self.seed == seed {
             return self;
         }
 
         Self {
             seed,
-            sources: super::build_sources(seed, self.octaves),
+            sources: super::build_sources(&self.prototype, seed, self.octaves),
+            transforms: super::build_transforms(self.rotation, seed, self.octaves),
             ..self
         }
     }
 
-    fn seed(&self) -> u32 {
+    fn seed(&self) -> u64 {
         self.seed
     }
 }
 
+impl<T> Periodic for RidgedMulti<T>
+where
+    T: Periodic,
+{
+    fn set_period<const N: usize>(self, period: [u32; N]) -> Self {
+        // The prototype keeps the period for the sources rebuilt by
+        // `set_seed` and `set_octaves`.
+        Self {
+            prototype: self.prototype.set_period(period),
+            sources: self
+                .sources
+                .into_iter()
+                .map(|source| source.set_period(period))
+                .collect(),
+            ..self
+        }
+    }
+
+    fn period(&self) -> [u32; 4] {
+        self.prototype.period()
+    }
+}
+
+impl<T> RidgedMulti<T> {
+    /// Evaluates the fractal at `point` with the octaves that are too fine for
+    /// a sample covering `width` faded out.
+    fn filtered<const DIM: usize>(&self, point: [f64; DIM], width: f64) -> f64
+    where
+        T: NoiseFn<f64, DIM>,
+    {
+        let mut result = 0.0;
+        let mut weight = 1.0;
+        let mut point = scale_point(point, self.frequency);
+        let mut frequency = self.frequency;
+
+        for x in 0..self.octaves {
+            let fade = octave_weight(frequency, width);
+            let mut signal = 0.0;
+            if fade != 0.0 {
+                signal = self.sources[x].get(transform_octave(self.transforms.get(x), point));
+                signal = 1.0 - signal.abs();
+                signal *= signal;
+                signal *= weight * fade;
+            }
+
+            // The 4-dimensional noise multiplies by the attenuation instead.
+            weight = if DIM == 4 {
+                signal * self.attenuation
+            } else {
+                signal / self.attenuation
+            };
+            weight = weight.clamp(0.0, 1.0);
+
+            result += signal * self.persistence.powi(x as i32);
+
+            point = scale_point(point, self.lacunarity);
+            frequency *= self.lacunarity;
+        }
+
+        let scale = 2.0 - 0.5_f64.powi(self.octaves as i32 - 1);
+        scale_shift(result, 2.0 / scale)
+    }
+}
+
+/// 1-dimensional `RidgedMulti` noise
+impl<T> NoiseFn<f64, 1> for RidgedMulti<T>
+where
+    T: NoiseFn<f64, 1>,
+{
+    fn get(&self, mut point: [f64; 1]) -> f64 {
+        let mut result = 0.0;
+        let mut weight = 1.0;
+
+        point = scale_point(point, self.frequency);
+
+        for x in 0..self.octaves {
+            // Get the value.
+            let mut signal = self.sources[x].get(transform_octave(self.transforms.get(x), point));
+
+            // Make the ridges.
+            signal = signal.abs();
+            signal = 1.0 - signal;
+
+            // Square the signal to increase the sharpness of the ridges.
+            signal *= signal;
+
+            // Apply the weighting from the previous octave to the signal.
+            // Larger values have higher weights, producing sharp points along
+            // the ridges.
+            signal *= weight;
+
+            // Weight successive contributions by the previous signal.
+            weight = signal / self.attenuation;
+
+            // Clamp the weight to [0,1] to prevent the result from diverging.
+            weight = weight.clamp(0.0, 1.0);
+
+            // Scale the amplitude appropriately for this frequency.
+            signal *= self.persistence.powi(x as i32);
+
+            // Add the signal to the result.
+            result += signal;
+
+            // Increase the frequency.
+            point = scale_point(point, self.lacunarity);
+        }
+
+        // Scale and shift the result into the [-1,1] range
+        let scale = 2.0 - 0.5_f64.powi(self.octaves as i32 - 1);
+        scale_shift(result, 2.0 / scale)
+    }
+
+    fn get_filtered(&self, point: [f64; 1], width: f64) -> f64 {
+        self.filtered(point, width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f64; 1]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.filtered(point, width));
+    }
+}
+
 /// 2-dimensional `RidgedMulti` noise
-impl NoiseFn<f64, 2> for RidgedMulti {
+impl<T> NoiseFn<f64, 2> for RidgedMulti<T>
+where
+    T: NoiseFn<f64, 2>,
+{
     fn get(&self, mut point: [f64; 2]) -> f64 {
         let mut result = 0.0;
         let mut weight = 1.0;
@@ -152,7 +377,7 @@ impl NoiseFn<f64, 2> for RidgedMulti {
 
         for x in 0..self.octaves {
             // Get the value.
-            let mut signal = self.sources[x].get(point);
+            let mut signal = self.sources[x].get(transform_octave(self.transforms.get(x), point));
 
             // Make the ridges.
             signal = signal.abs();
@@ -186,10 +411,21 @@ impl NoiseFn<f64, 2> for RidgedMulti {
         let scale = 2.0 - 0.5_f64.powi(self.octaves as i32 - 1);
         scale_shift(result, 2.0 / scale)
     }
+
+    fn get_filtered(&self, point: [f64; 2], width: f64) -> f64 {
+        self.filtered(point, width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f64; 2]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.filtered(point, width));
+    }
 }
 
 /// 3-dimensional `RidgedMulti` noise
-impl NoiseFn<f64, 3> for RidgedMulti {
+impl<T> NoiseFn<f64, 3> for RidgedMulti<T>
+where
+    T: NoiseFn<f64, 3>,
+{
     fn get(&self, mut point: [f64; 3]) -> f64 {
         let mut result = 0.0;
         let mut weight = 1.0;
@@ -198,7 +434,7 @@ impl NoiseFn<f64, 3> for RidgedMulti {
 
         for x in 0..self.octaves {
             // Get the value.
-            let mut signal = self.sources[x].get(point);
+            let mut signal = self.sources[x].get(transform_octave(self.transforms.get(x), point));
 
             // Make the ridges.
             signal = signal.abs();
@@ -232,10 +468,21 @@ impl NoiseFn<f64, 3> for RidgedMulti {
         let scale = 2.0 - 0.5_f64.powi(self.octaves as i32 - 1);
         scale_shift(result, 2.0 / scale)
     }
+
+    fn get_filtered(&self, point: [f64; 3], width: f64) -> f64 {
+        self.filtered(point, width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f64; 3]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.filtered(point, width));
+    }
 }
 
 /// 4-dimensional `RidgedMulti` noise
-impl NoiseFn<f64, 4> for RidgedMulti {
+impl<T> NoiseFn<f64, 4> for RidgedMulti<T>
+where
+    T: NoiseFn<f64, 4>,
+{
     fn get(&self, mut point: [f64; 4]) -> f64 {
         let mut result = 0.0;
         let mut weight = 1.0;
@@ -244,7 +491,7 @@ impl NoiseFn<f64, 4> for RidgedMulti {
 
         for x in 0..self.octaves {
             // Get the value.
-            let mut signal = self.sources[x].get(point);
+            let mut signal = self.sources[x].get(transform_octave(self.transforms.get(x), point));
 
             // Make the ridges.
             signal = signal.abs();
@@ -278,4 +525,72 @@ impl NoiseFn<f64, 4> for RidgedMulti {
         let scale = 2.0 - 0.5_f64.powi(self.octaves as i32 - 1);
         scale_shift(result, 2.0 / scale)
     }
+
+    fn get_filtered(&self, point: [f64; 4], width: f64) -> f64 {
+        self.filtered(point, width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f64; 4]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.filtered(point, width));
+    }
+}
+
+/// `RidgedMulti` noise, with its gradient
+impl<T, const DIM: usize> NoiseFnGradient<DIM> for RidgedMulti<T>
+where
+    Self: NoiseFn<f64, DIM>,
+    T: NoiseFnGradient<DIM>,
+{
+    fn get_with_gradient(&self, point: [f64; DIM]) -> (f64, [f64; DIM]) {
+        let mut result = Dual::constant(0.0);
+        let mut weight = Dual::constant(1.0);
+        let mut point = scale_point(point, self.frequency);
+        let mut frequency = self.frequency;
+
+        for x in 0..self.octaves {
+            let mut signal = Dual::constant(1.0)
+                - get_octave(&self.sources[x], self.transforms.get(x), point, frequency).abs();
+            signal = signal * signal * weight;
+
+            // The 4-dimensional noise multiplies by the attenuation instead.
+            weight = if DIM == 4 {
+                signal * self.attenuation
+            } else {
+                signal / self.attenuation
+            };
+            weight = weight.clamp(0.0, 1.0);
+
+            result += signal * self.persistence.powi(x as i32);
+
+            point = scale_point(point, self.lacunarity);
+            frequency *= self.lacunarity;
+        }
+
+        let scale = 2.0 - 0.5_f64.powi(self.octaves as i32 - 1);
+        result.scale_shift(2.0 / scale).into_tuple()
+    }
+}
+
+/// `RidgedMulti` noise at `f32` coordinates
+impl<T, const DIM: usize> NoiseFn<f32, DIM> for RidgedMulti<T>
+where
+    Self: NoiseFn<f64, DIM>,
+{
+    fn get(&self, point: [f32; DIM]) -> f64 {
+        get_widened(self, point)
+    }
+
+    fn get_batch(&self, points: &[[f32; DIM]], output: &mut [f64]) {
+        evaluate_batch_widened(self, points, output);
+    }
+
+    fn get_filtered(&self, point: [f32; DIM], width: f64) -> f64 {
+        NoiseFn::<f64, DIM>::get_filtered(self, widen(point), width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[f32; DIM]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| {
+            NoiseFn::<f64, DIM>::get_filtered(self, widen(point), width)
+        });
+    }
 }
@@ -0,0 +1,239 @@
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The offset between the inputs of successive octaves for
+/// [`OctaveRotation::Fixed`], the fractional parts of the golden ratio and of
+/// the square roots of 2, 3 and 5.
+const FIXED_OFFSET: [f64; 4] = [0.618_034, 0.414_214, 0.732_051, 0.236_068];
+
+/// How the input of each octave of a fractal is rotated and offset.
+///
+/// The lattice of every octave of a fractal is aligned with the axes, and the
+/// alignment adds up across the octaves into visible horizontal and vertical
+/// features, especially at a low lacunarity. Rotating and offsetting the input
+/// of each octave differently hides them.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum OctaveRotation {
+    /// The octaves are neither rotated nor offset.
+    #[default]
+    None,
+
+    /// Each octave is rotated relative to the previous one by the given angle,
+    /// in radians, in every plane spanned by two axes, and offset by a fixed
+    /// amount.
+    Fixed(f64),
+
+    /// Each octave is rotated and offset randomly, depending on the seed of
+    /// the fractal.
+    Seeded,
+}
+
+impl OctaveRotation {
+    /// Checks that the angle of a fixed rotation is finite.
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        match *self {
+            OctaveRotation::Fixed(angle) if !angle.is_finite() => Err(format!(
+                "the octave rotation angle must be finite, got {}",
+                angle
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A square matrix that is large enough for any dimension, holding a smaller
+/// one in its top left corner.
+type Matrix = [[f64; 4]; 4];
+
+const IDENTITY: Matrix = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    let mut result = [[0.0; 4]; 4];
+    for (row, a_row) in result.iter_mut().zip(a) {
+        for (column, entry) in row.iter_mut().enumerate() {
+            *entry = a_row
+                .iter()
+                .zip(b)
+                .map(|(a, b_row)| a * b_row[column])
+                .sum();
+        }
+    }
+    result
+}
+
+/// Builds a rotation in `dimensions` dimensions out of one rotation in every
+/// plane spanned by two axes, by the angle `angle` returns for the plane.
+fn plane_rotations<F>(dimensions: usize, mut angle: F) -> Matrix
+where
+    F: FnMut() -> f64,
+{
+    let mut result = IDENTITY;
+    for i in 0..dimensions {
+        for j in i + 1..dimensions {
+            let (sin, cos) = angle().sin_cos();
+            let mut plane = IDENTITY;
+            plane[i][i] = cos;
+            plane[i][j] = -sin;
+            plane[j][i] = sin;
+            plane[j][j] = cos;
+            result = multiply(&plane, &result);
+        }
+    }
+    result
+}
+
+/// The rotation and offset applied to the input of one octave.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct OctaveTransform {
+    /// The rotations for inputs of 1 to 4 dimensions.
+    rotations: [Matrix; 4],
+    offset: [f64; 4],
+}
+
+impl OctaveTransform {
+    /// Rotates and offsets `point`.
+    #[inline]
+    pub(crate) fn apply<const DIM: usize>(&self, point: [f64; DIM]) -> [f64; DIM] {
+        let rotation = &self.rotations[DIM - 1];
+        let mut result = [0.0; DIM];
+        for (axis, coordinate) in result.iter_mut().enumerate() {
+            *coordinate = self.offset[axis];
+            for (&entry, &value) in rotation[axis].iter().zip(point.iter()) {
+                *coordinate += entry * value;
+            }
+        }
+        result
+    }
+
+    /// Turns the gradient of an octave at a transformed point into the
+    /// gradient with respect to the point before the transform.
+    #[inline]
+    pub(crate) fn apply_to_gradient<const DIM: usize>(&self, gradient: [f64; DIM]) -> [f64; DIM] {
+        let rotation = &self.rotations[DIM - 1];
+        let mut result = [0.0; DIM];
+        for (row, &value) in rotation.iter().zip(gradient.iter()) {
+            for (coordinate, &entry) in result.iter_mut().zip(row.iter()) {
+                *coordinate += entry * value;
+            }
+        }
+        result
+    }
+}
+
+/// Rotates and offsets `point` for an octave, if the fractal transforms the
+/// input of its octaves.
+#[inline]
+pub(crate) fn transform_octave<const DIM: usize>(
+    transform: Option<&OctaveTransform>,
+    point: [f64; DIM],
+) -> [f64; DIM] {
+    match transform {
+        Some(transform) => transform.apply(point),
+        None => point,
+    }
+}
+
+/// Builds the transforms of the octaves of a fractal, which are empty if its
+/// octaves aren't transformed.
+///
+/// # Panics
+///
+/// Panics if the angle of a [`OctaveRotation::Fixed`] rotation isn't finite.
+pub(crate) fn build_transforms(
+    rotation: OctaveRotation,
+    seed: u64,
+    octaves: usize,
+) -> Vec<OctaveTransform> {
+    if let Err(message) = rotation.validate() {
+        panic!("{}", message);
+    }
+
+    match rotation {
+        OctaveRotation::None => Vec::new(),
+        OctaveRotation::Fixed(angle) => {
+            let steps = [1, 2, 3, 4].map(|dimensions| plane_rotations(dimensions, || angle));
+            let mut transform = OctaveTransform {
+                rotations: [IDENTITY; 4],
+                offset: [0.0; 4],
+            };
+
+            (0..octaves)
+                .map(|_| {
+                    let current = transform;
+                    for (rotation, step) in transform.rotations.iter_mut().zip(&steps) {
+                        *rotation = multiply(step, rotation);
+                    }
+                    for (offset, step) in transform.offset.iter_mut().zip(&FIXED_OFFSET) {
+                        *offset += step;
+                    }
+                    current
+                })
+                .collect()
+        }
+        OctaveRotation::Seeded => {
+            let mut rng = XorShiftRng::seed_from_u64(seed);
+
+            (0..octaves)
+                .map(|_| {
+                    let rotations = [1, 2, 3, 4].map(|dimensions| {
+                        plane_rotations(dimensions, || {
+                            rng.gen_range(0.0, std::f64::consts::PI * 2.0)
+                        })
+                    });
+                    let offset = [(); 4].map(|_| rng.gen());
+                    OctaveTransform { rotations, offset }
+                })
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transforms_are_rigid() {
+        let (p1, p2) = ([0.4, 1.7, -2.3, 5.0], [-5.1, 0.23, 3.3, 0.8]);
+        let distance = |a: [f64; 4], b: [f64; 4]| {
+            a.iter()
+                .zip(&b)
+                .map(|(a, b)| (a - b) * (a - b))
+                .sum::<f64>()
+                .sqrt()
+        };
+
+        for rotation in [OctaveRotation::Fixed(0.6), OctaveRotation::Seeded] {
+            let transforms = build_transforms(rotation, 3, 6);
+            assert_eq!(transforms.len(), 6);
+            assert_eq!(transforms[1].apply([0.0]), [transforms[1].offset[0]]);
+
+            for transform in &transforms[1..] {
+                let moved = distance(transform.apply(p1), transform.apply(p2));
+                assert!((moved - distance(p1, p2)).abs() < 1e-12);
+                assert_ne!(transform.apply(p1), p1);
+
+                let [x, y, z, _] = p1;
+                let back = transform.apply_to_gradient(transform.apply([x, y, z]));
+                let offset = transform.apply_to_gradient(transform.apply([0.0; 3]));
+                for axis in 0..3 {
+                    assert!((back[axis] - offset[axis] - p1[axis]).abs() < 1e-12);
+                }
+            }
+        }
+
+        assert!(build_transforms(OctaveRotation::None, 3, 6).is_empty());
+        assert_ne!(
+            build_transforms(OctaveRotation::Seeded, 3, 2)[1].apply(p1),
+            build_transforms(OctaveRotation::Seeded, 4, 2)[1].apply(p1)
+        );
+    }
+}
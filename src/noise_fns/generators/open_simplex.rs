@@ -3,10 +3,20 @@
 //! <http://uniblock.tumblr.com/post/97868843242/noise>
 
 use crate::{
-    gradient, math,
-    noise_fns::{NoiseFn, Seedable},
-    permutationtable::{NoiseHasher, PermutationTable},
+    gradient,
+    math::{
+        self,
+        dual::{self, Dual},
+    },
+    noise_fns::{
+        evaluate_batch, evaluate_batch_widened, get_widened, NoiseFn, NoiseFnGradient, Seedable,
+    },
+    permutationtable::{
+        CachedHasher, CellHasher, DirectHasher, NoiseHasher, PermutationTable, SeedableHasher,
+    },
 };
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::ops::Add;
 
 const STRETCH_CONSTANT_2D: f64 = -0.211_324_865_405_187; //(1/sqrt(2+1)-1)/2;
@@ -22,44 +32,58 @@ const NORM_CONSTANT_4D: f64 = 1.0 / 6.869_909_007_095_662_5;
 
 /// Noise function that outputs 2/3/4-dimensional Open Simplex noise.
 #[derive(Clone, Copy, Debug)]
-pub struct OpenSimplex {
-    seed: u32,
-    perm_table: PermutationTable,
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(from = "super::SeedParams", bound(deserialize = "H: SeedableHasher"))
+)]
+pub struct OpenSimplex<H = PermutationTable> {
+    seed: u64,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    hasher: H,
 }
 
 impl OpenSimplex {
-    const DEFAULT_SEED: u32 = 0;
+    const DEFAULT_SEED: u64 = 0;
 
     pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<H: SeedableHasher> OpenSimplex<H> {
+    /// Creates the noise function with `seed`, hashing the lattice with any
+    /// [`SeedableHasher`] rather than the default [`PermutationTable`].
+    pub fn from_seed(seed: u64) -> Self {
         Self {
-            seed: Self::DEFAULT_SEED,
-            perm_table: PermutationTable::new(Self::DEFAULT_SEED),
+            seed,
+            hasher: H::from_seed(seed),
         }
     }
 }
 
 impl Default for OpenSimplex {
     fn default() -> Self {
-        Self::new()
+        Self::from_seed(Self::DEFAULT_SEED)
     }
 }
 
-impl Seedable for OpenSimplex {
+impl<H: SeedableHasher> Seedable for OpenSimplex<H> {
     /// Sets the seed value for Open Simplex noise
-    fn set_seed(self, seed: u32) -> Self {
+    fn set_seed(self, seed: u64) -> Self {
         // If the new seed is the same as the current seed, just return self.
         if self.seed == seed {
             return self;
         }
 
-        // Otherwise, regenerate the permutation table based on the new seed.
+        // Otherwise, regenerate the hasher based on the new seed.
         Self {
             seed,
-            perm_table: PermutationTable::new(seed),
+            hasher: H::from_seed(seed),
         }
     }
 
-    fn seed(&self) -> u32 {
+    fn seed(&self) -> u64 {
         self.seed
     }
 }
@@ -67,551 +91,636 @@ impl Seedable for OpenSimplex {
 /// 2-dimensional [`OpenSimplex` Noise](http://uniblock.tumblr.com/post/97868843242/noise)
 ///
 /// This is a slower but higher quality form of gradient noise than `Perlin` 2D.
-impl NoiseFn<f64, 2> for OpenSimplex {
+impl<H: NoiseHasher> NoiseFn<f64, 2> for OpenSimplex<H> {
     fn get(&self, point: [f64; 2]) -> f64 {
-        fn gradient(perm_table: &PermutationTable, vertex: [f64; 2], pos: [f64; 2]) -> f64 {
-            let attn = 2.0 - math::dot2(pos, pos);
-            if attn > 0.0 {
-                let index = perm_table.hash(&math::to_isize2(vertex));
-                let vec = gradient::grad2(index);
-                attn.powi(4) * math::dot2(pos, vec)
-            } else {
-                0.0
-            }
+        open_simplex_2d(&mut DirectHasher::new(&self.hasher), point).value
+    }
+
+    fn get_batch(&self, points: &[[f64; 2]], output: &mut [f64]) {
+        let mut hasher = CachedHasher::<_, 2, 4>::new(&self.hasher, 0);
+        evaluate_batch(points, output, |point| {
+            open_simplex_2d(&mut hasher, point).value
+        });
+    }
+}
+
+#[inline(always)]
+fn open_simplex_2d<H>(hasher: &mut H, point: [f64; 2]) -> Dual<2>
+where
+    H: CellHasher<2>,
+{
+    #[inline(always)]
+    fn gradient<H: CellHasher<2>>(hasher: &H, vertex: [isize; 2], pos: [f64; 2]) -> Dual<2> {
+        let attn = 2.0 - math::dot2(pos, pos);
+        if attn > 0.0 {
+            let index = hasher.hash(vertex);
+            let vec = gradient::grad2(index);
+            dual::surflet(attn, math::dot2(pos, vec), vec, pos)
+        } else {
+            Dual::constant(0.0)
         }
+    }
 
-        // Place input coordinates onto grid.
-        let stretch_offset = math::fold2(point, Add::add) * STRETCH_CONSTANT_2D;
-        let stretched = math::map2(point, |v| v + stretch_offset);
+    // Place input coordinates onto grid.
+    let stretch_offset = math::fold2(point, Add::add) * STRETCH_CONSTANT_2D;
+    let stretched = math::map2(point, |v| v + stretch_offset);
+
+    // Floor to get grid coordinates of rhombus (stretched square) cell origin.
+    let stretched_floor = math::map2(stretched, f64::floor);
+    hasher.set_cell(math::to_isize2(stretched_floor));
+
+    // Skew out to get actual coordinates of rhombus origin. We'll need these later.
+    let squish_offset = math::fold2(stretched_floor, Add::add) * SQUISH_CONSTANT_2D;
+    let skewed_floor = math::map2(stretched_floor, |v| v + squish_offset);
+
+    // Compute grid coordinates relative to rhombus origin.
+    let rel_coords = math::sub2(stretched, stretched_floor);
+
+    // Sum those together to get a value that determines which region we're in.
+    let region_sum = math::fold2(rel_coords, Add::add);
+
+    // Positions relative to origin point (0, 0).
+    let pos0 = math::sub2(point, skewed_floor);
+
+    let mut value = Dual::constant(0.0);
+
+    let mut vertex;
+    let mut dpos;
+
+    // (0, 0) --- (1, 0)
+    // |   A     /     |
+    // |       /       |
+    // |     /     B   |
+    // (0, 1) --- (1, 1)
+
+    let t0 = SQUISH_CONSTANT_2D;
+    let t1 = SQUISH_CONSTANT_2D + 1.0;
+    let t2 = SQUISH_CONSTANT_2D + t1;
+
+    // Contribution (1, 0)
+    vertex = [1, 0];
+    dpos = math::sub2(pos0, [t1, t0]);
+    value += gradient(hasher, vertex, dpos);
+
+    // Contribution (0, 1)
+    vertex = [0, 1];
+    dpos = math::sub2(pos0, [t0, t1]);
+    value += gradient(hasher, vertex, dpos);
+
+    // See the graph for an intuitive explanation; the sum of `x` and `y` is
+    // only greater than `1` if we're on Region B.
+    if region_sum > 1.0 {
+        // Contribution (1, 1)
+        vertex = [1, 1];
+        // We are moving across the diagonal `/`, so we'll need to add by the
+        // squish constant
+        dpos = math::sub2(pos0, [t2, t2]);
+    } else {
+        vertex = [0, 0];
+        dpos = math::sub2(pos0, [0.0, 0.0]);
+    }
 
-        // Floor to get grid coordinates of rhombus (stretched square) cell origin.
-        let stretched_floor = math::map2(stretched, f64::floor);
+    // Point (0, 0) or (1, 1)
+    value += gradient(hasher, vertex, dpos);
 
-        // Skew out to get actual coordinates of rhombus origin. We'll need these later.
-        let squish_offset = math::fold2(stretched_floor, Add::add) * SQUISH_CONSTANT_2D;
-        let skewed_floor = math::map2(stretched_floor, |v| v + squish_offset);
+    value * NORM_CONSTANT_2D
+}
 
-        // Compute grid coordinates relative to rhombus origin.
-        let rel_coords = math::sub2(stretched, stretched_floor);
+/// 3-dimensional [`OpenSimplex` Noise](http://uniblock.tumblr.com/post/97868843242/noise)
+///
+/// This is a slower but higher quality form of gradient noise than `Perlin` 3D.
+impl<H: NoiseHasher> NoiseFn<f64, 3> for OpenSimplex<H> {
+    fn get(&self, point: [f64; 3]) -> f64 {
+        open_simplex_3d(&mut DirectHasher::new(&self.hasher), point).value
+    }
 
-        // Sum those together to get a value that determines which region we're in.
-        let region_sum = math::fold2(rel_coords, Add::add);
+    fn get_batch(&self, points: &[[f64; 3]], output: &mut [f64]) {
+        let mut hasher = CachedHasher::<_, 3, 8>::new(&self.hasher, 0);
+        evaluate_batch(points, output, |point| {
+            open_simplex_3d(&mut hasher, point).value
+        });
+    }
+}
 
-        // Positions relative to origin point (0, 0).
-        let pos0 = math::sub2(point, skewed_floor);
+#[inline(always)]
+fn open_simplex_3d<H>(hasher: &mut H, point: [f64; 3]) -> Dual<3>
+where
+    H: CellHasher<3>,
+{
+    #[inline(always)]
+    fn gradient<H: CellHasher<3>>(hasher: &H, vertex: [isize; 3], pos: [f64; 3]) -> Dual<3> {
+        let attn = 2.0 - math::dot3(pos, pos);
+        if attn > 0.0 {
+            let index = hasher.hash(vertex);
+            let vec = gradient::grad3(index);
+            dual::surflet(attn, math::dot3(pos, vec), vec, pos)
+        } else {
+            Dual::constant(0.0)
+        }
+    }
 
-        let mut value = 0.0;
+    // Place input coordinates on simplectic h1.0ycomb.
+    let stretch_offset = math::fold3(point, Add::add) * STRETCH_CONSTANT_3D;
+    let stretched = math::map3(point, |v| v + stretch_offset);
 
-        let mut vertex;
-        let mut dpos;
+    // Floor to get simplectic h1.0ycomb coordinates of rhombohedron
+    // (stretched cube) super-cell origin.
+    let stretched_floor = math::map3(stretched, f64::floor);
+    hasher.set_cell(math::to_isize3(stretched_floor));
 
-        // (0, 0) --- (1, 0)
-        // |   A     /     |
-        // |       /       |
-        // |     /     B   |
-        // (0, 1) --- (1, 1)
+    // Skew out to get actual coordinates of rhombohedron origin. We'll need
+    // these later.
+    let squish_offset = math::fold3(stretched_floor, Add::add) * SQUISH_CONSTANT_3D;
+    let skewed_floor = math::map3(stretched_floor, |v| v + squish_offset);
 
-        let t0 = SQUISH_CONSTANT_2D;
-        let t1 = SQUISH_CONSTANT_2D + 1.0;
-        let t2 = SQUISH_CONSTANT_2D + t1;
-
-        // Contribution (1, 0)
-        vertex = math::add2(stretched_floor, [1.0, 0.0]);
-        dpos = math::sub2(pos0, [t1, t0]);
-        value += gradient(&self.perm_table, vertex, dpos);
-
-        // Contribution (0, 1)
-        vertex = math::add2(stretched_floor, [0.0, 1.0]);
-        dpos = math::sub2(pos0, [t0, t1]);
-        value += gradient(&self.perm_table, vertex, dpos);
-
-        // See the graph for an intuitive explanation; the sum of `x` and `y` is
-        // only greater than `1` if we're on Region B.
-        if region_sum > 1.0 {
-            // Contribution (1, 1)
-            vertex = math::add2(stretched_floor, [1.0, 1.0]);
-            // We are moving across the diagonal `/`, so we'll need to add by the
-            // squish constant
-            dpos = math::sub2(pos0, [t2, t2]);
-        } else {
-            vertex = math::add2(stretched_floor, [0.0, 0.0]);
-            dpos = math::sub2(pos0, [0.0, 0.0]);
-        }
+    // Compute simplectic h1.0ycomb coordinates relative to rhombohedral origin.
+    let rel_coords = math::sub3(stretched, stretched_floor);
 
-        // Point (0, 0) or (1, 1)
-        value += gradient(&self.perm_table, vertex, dpos);
+    // Sum those together to get a value that determines which region we're in.
+    let region_sum = math::fold3(rel_coords, Add::add);
 
-        value * NORM_CONSTANT_2D
-    }
-}
+    // Positions relative to origin point.
+    let pos0 = math::sub3(point, skewed_floor);
 
-/// 3-dimensional [`OpenSimplex` Noise](http://uniblock.tumblr.com/post/97868843242/noise)
-///
-/// This is a slower but higher quality form of gradient noise than `Perlin` 3D.
-impl NoiseFn<f64, 3> for OpenSimplex {
-    fn get(&self, point: [f64; 3]) -> f64 {
-        fn gradient(perm_table: &PermutationTable, vertex: [f64; 3], pos: [f64; 3]) -> f64 {
-            let attn = 2.0 - math::dot3(pos, pos);
-            if attn > 0.0 {
-                let index = perm_table.hash(&math::to_isize3(vertex));
-                let vec = gradient::grad3(index);
-                attn.powi(4) * math::dot3(pos, vec)
-            } else {
-                0.0
-            }
-        }
-
-        // Place input coordinates on simplectic h1.0ycomb.
-        let stretch_offset = math::fold3(point, Add::add) * STRETCH_CONSTANT_3D;
-        let stretched = math::map3(point, |v| v + stretch_offset);
-
-        // Floor to get simplectic h1.0ycomb coordinates of rhombohedron
-        // (stretched cube) super-cell origin.
-        let stretched_floor = math::map3(stretched, f64::floor);
-
-        // Skew out to get actual coordinates of rhombohedron origin. We'll need
-        // these later.
-        let squish_offset = math::fold3(stretched_floor, Add::add) * SQUISH_CONSTANT_3D;
-        let skewed_floor = math::map3(stretched_floor, |v| v + squish_offset);
-
-        // Compute simplectic h1.0ycomb coordinates relative to rhombohedral origin.
-        let rel_coords = math::sub3(stretched, stretched_floor);
-
-        // Sum those together to get a value that determines which region we're in.
-        let region_sum = math::fold3(rel_coords, Add::add);
-
-        // Positions relative to origin point.
-        let pos0 = math::sub3(point, skewed_floor);
-
-        let mut value = 0.0;
-
-        let mut vertex;
-        let mut dpos;
-
-        if region_sum <= 1.0 {
-            // We're inside the tetrahedron (3-Simplex) at (0, 0, 0)
-            let t0 = SQUISH_CONSTANT_3D;
-            let t1 = SQUISH_CONSTANT_2D + 1.0;
-
-            // Contribution at (0, 0, 0)
-            vertex = math::add3(stretched_floor, [0.0, 0.0, 0.0]);
-            dpos = math::sub3(pos0, [0.0, 0.0, 0.0]);
-            value += gradient(&self.perm_table, vertex, dpos);
-
-            // Contribution at (1, 0, 0)
-            vertex = math::add3(stretched_floor, [1.0, 0.0, 0.0]);
-            dpos = math::sub3(pos0, [t1, t0, t0]);
-            value += gradient(&self.perm_table, vertex, dpos);
-
-            // Contribution at (0, 1, 0)
-            vertex = math::add3(stretched_floor, [0.0, 1.0, 0.0]);
-            dpos = math::sub3(pos0, [t0, t1, t0]);
-            value += gradient(&self.perm_table, vertex, dpos);
-
-            // Contribution at (0, 0, 1)
-            vertex = math::add3(stretched_floor, [0.0, 0.0, 1.0]);
-            dpos = math::sub3(pos0, [t0, t0, t1]);
-            value += gradient(&self.perm_table, vertex, dpos);
-        } else if region_sum >= 2.0 {
-            // We're inside the tetrahedron (3-Simplex) at (1, 1, 1)
-            let t0 = 2.0 * SQUISH_CONSTANT_3D;
-            let t1 = 1.0 + 2.0 * SQUISH_CONSTANT_3D;
-            let t2 = t1 + SQUISH_CONSTANT_3D;
-
-            // Contribution at (1, 1, 0)
-            vertex = math::add3(stretched_floor, [1.0, 1.0, 0.0]);
-            dpos = math::sub3(pos0, [t1, t1, t0]);
-            value += gradient(&self.perm_table, vertex, dpos);
-
-            // Contribution at (1, 0, 1)
-            vertex = math::add3(stretched_floor, [1.0, 0.0, 1.0]);
-            dpos = math::sub3(pos0, [t1, t0, t1]);
-            value += gradient(&self.perm_table, vertex, dpos);
-
-            // Contribution at (0, 1, 1)
-            vertex = math::add3(stretched_floor, [0.0, 1.0, 1.0]);
-            dpos = math::sub3(pos0, [t0, t1, t1]);
-            value += gradient(&self.perm_table, vertex, dpos);
-
-            // Contribution at (1, 1, 1)
-            vertex = math::add3(stretched_floor, [1.0, 1.0, 1.0]);
-            dpos = math::sub3(pos0, [t2, t2, t2]);
-            value += gradient(&self.perm_table, vertex, dpos);
-        } else {
-            // We're inside the octahedron (Rectified 3-Simplex) inbetween.
-            let t0 = SQUISH_CONSTANT_3D;
-            let t1 = 1.0 + SQUISH_CONSTANT_3D;
-            let t2 = 2.0 * SQUISH_CONSTANT_3D;
-            let t3 = 1.0 + 2.0 * SQUISH_CONSTANT_3D;
-
-            // Contribution at (1, 0, 0)
-            vertex = math::add3(stretched_floor, [1.0, 0.0, 0.0]);
-            dpos = math::sub3(pos0, [t1, t0, t0]);
-            value += gradient(&self.perm_table, vertex, dpos);
-
-            // Contribution at (0, 1, 0)
-            vertex = math::add3(stretched_floor, [0.0, 1.0, 0.0]);
-            dpos = math::sub3(pos0, [t0, t1, t0]);
-            value += gradient(&self.perm_table, vertex, dpos);
-
-            // Contribution at (0, 0, 1)
-            vertex = math::add3(stretched_floor, [0.0, 0.0, 1.0]);
-            dpos = math::sub3(pos0, [t0, t0, t1]);
-            value += gradient(&self.perm_table, vertex, dpos);
-
-            // Contribution at (1, 1, 0)
-            vertex = math::add3(stretched_floor, [1.0, 1.0, 0.0]);
-            dpos = math::sub3(pos0, [t3, t3, t2]);
-            value += gradient(&self.perm_table, vertex, dpos);
-
-            // Contribution at (1, 0, 1)
-            vertex = math::add3(stretched_floor, [1.0, 0.0, 1.0]);
-            dpos = math::sub3(pos0, [t3, t2, t3]);
-            value += gradient(&self.perm_table, vertex, dpos);
-
-            // Contribution at (0, 1, 1)
-            vertex = math::add3(stretched_floor, [0.0, 1.0, 1.0]);
-            dpos = math::sub3(pos0, [t2, t3, t3]);
-            value += gradient(&self.perm_table, vertex, dpos);
-        }
-
-        value * NORM_CONSTANT_3D
+    let mut value = Dual::constant(0.0);
+
+    let mut vertex;
+    let mut dpos;
+
+    if region_sum <= 1.0 {
+        // We're inside the tetrahedron (3-Simplex) at (0, 0, 0)
+        let t0 = SQUISH_CONSTANT_3D;
+        let t1 = SQUISH_CONSTANT_2D + 1.0;
+
+        // Contribution at (0, 0, 0)
+        vertex = [0, 0, 0];
+        dpos = math::sub3(pos0, [0.0, 0.0, 0.0]);
+        value += gradient(hasher, vertex, dpos);
+
+        // Contribution at (1, 0, 0)
+        vertex = [1, 0, 0];
+        dpos = math::sub3(pos0, [t1, t0, t0]);
+        value += gradient(hasher, vertex, dpos);
+
+        // Contribution at (0, 1, 0)
+        vertex = [0, 1, 0];
+        dpos = math::sub3(pos0, [t0, t1, t0]);
+        value += gradient(hasher, vertex, dpos);
+
+        // Contribution at (0, 0, 1)
+        vertex = [0, 0, 1];
+        dpos = math::sub3(pos0, [t0, t0, t1]);
+        value += gradient(hasher, vertex, dpos);
+    } else if region_sum >= 2.0 {
+        // We're inside the tetrahedron (3-Simplex) at (1, 1, 1)
+        let t0 = 2.0 * SQUISH_CONSTANT_3D;
+        let t1 = 1.0 + 2.0 * SQUISH_CONSTANT_3D;
+        let t2 = t1 + SQUISH_CONSTANT_3D;
+
+        // Contribution at (1, 1, 0)
+        vertex = [1, 1, 0];
+        dpos = math::sub3(pos0, [t1, t1, t0]);
+        value += gradient(hasher, vertex, dpos);
+
+        // Contribution at (1, 0, 1)
+        vertex = [1, 0, 1];
+        dpos = math::sub3(pos0, [t1, t0, t1]);
+        value += gradient(hasher, vertex, dpos);
+
+        // Contribution at (0, 1, 1)
+        vertex = [0, 1, 1];
+        dpos = math::sub3(pos0, [t0, t1, t1]);
+        value += gradient(hasher, vertex, dpos);
+
+        // Contribution at (1, 1, 1)
+        vertex = [1, 1, 1];
+        dpos = math::sub3(pos0, [t2, t2, t2]);
+        value += gradient(hasher, vertex, dpos);
+    } else {
+        // We're inside the octahedron (Rectified 3-Simplex) inbetween.
+        let t0 = SQUISH_CONSTANT_3D;
+        let t1 = 1.0 + SQUISH_CONSTANT_3D;
+        let t2 = 2.0 * SQUISH_CONSTANT_3D;
+        let t3 = 1.0 + 2.0 * SQUISH_CONSTANT_3D;
+
+        // Contribution at (1, 0, 0)
+        vertex = [1, 0, 0];
+        dpos = math::sub3(pos0, [t1, t0, t0]);
+        value += gradient(hasher, vertex, dpos);
+
+        // Contribution at (0, 1, 0)
+        vertex = [0, 1, 0];
+        dpos = math::sub3(pos0, [t0, t1, t0]);
+        value += gradient(hasher, vertex, dpos);
+
+        // Contribution at (0, 0, 1)
+        vertex = [0, 0, 1];
+        dpos = math::sub3(pos0, [t0, t0, t1]);
+        value += gradient(hasher, vertex, dpos);
+
+        // Contribution at (1, 1, 0)
+        vertex = [1, 1, 0];
+        dpos = math::sub3(pos0, [t3, t3, t2]);
+        value += gradient(hasher, vertex, dpos);
+
+        // Contribution at (1, 0, 1)
+        vertex = [1, 0, 1];
+        dpos = math::sub3(pos0, [t3, t2, t3]);
+        value += gradient(hasher, vertex, dpos);
+
+        // Contribution at (0, 1, 1)
+        vertex = [0, 1, 1];
+        dpos = math::sub3(pos0, [t2, t3, t3]);
+        value += gradient(hasher, vertex, dpos);
     }
+
+    value * NORM_CONSTANT_3D
 }
 
 /// 4-dimensional [`OpenSimplex` Noise](http://uniblock.tumblr.com/post/97868843242/noise)
 ///
 /// This is a slower but higher quality form of gradient noise than `Perlin` 4D.
-impl NoiseFn<f64, 4> for OpenSimplex {
+impl<H: NoiseHasher> NoiseFn<f64, 4> for OpenSimplex<H> {
     fn get(&self, point: [f64; 4]) -> f64 {
-        #[inline(always)]
-        fn gradient(perm_table: &PermutationTable, vertex: [f64; 4], pos: [f64; 4]) -> f64 {
-            let attn = 2.0 - math::dot4(pos, pos);
-            if attn > 0.0 {
-                let index = perm_table.hash(&math::to_isize4(vertex));
-                let vec = gradient::grad4(index);
-                attn.powi(4) * math::dot4(pos, vec)
-            } else {
-                0.0
-            }
-        }
-
-        // Place input coordinates on simplectic h1.0ycomb.
-        let stretch_offset = math::fold4(point, Add::add) * STRETCH_CONSTANT_4D;
-        let stretched = math::map4(point, |v| v + stretch_offset);
-
-        // Floor to get simplectic h1.0ycomb coordinates of rhombo-hypercube
-        // super-cell origin.
-        let stretched_floor = math::map4(stretched, f64::floor);
-
-        // Skew out to get actual coordinates of stretched rhombo-hypercube origin.
-        // We'll need these later.
-        let squish_offset = math::fold4(stretched_floor, Add::add) * SQUISH_CONSTANT_4D;
-        let skewed_floor = math::map4(stretched_floor, |v| v + squish_offset);
-
-        // Compute simplectic h1.0ycomb coordinates relative to rhombo-hypercube
-        // origin.
-        let rel_coords = math::sub4(stretched, stretched_floor);
-
-        // Sum those together to get a value that determines which region
-        // we're in.
-        let region_sum = math::fold4(rel_coords, Add::add);
-
-        // Position relative to origin point.
-        let mut pos0 = math::sub4(point, skewed_floor);
-
-        let mut value = 0.0;
-        if region_sum <= 1.0 {
-            // We're inside the pentachoron (4-Simplex) at (0, 0, 0, 0)
-
-            // Contribution at (0, 0, 0, 0)
-            value += gradient(&self.perm_table, stretched_floor, pos0);
-
-            // Contribution at (1, 0, 0, 0)
-            let pos1;
-            {
-                let vertex = math::add4(stretched_floor, [1.0, 0.0, 0.0, 0.0]);
-                pos1 = math::sub4(
-                    pos0,
-                    [
-                        1.0 + SQUISH_CONSTANT_4D,
-                        SQUISH_CONSTANT_4D,
-                        SQUISH_CONSTANT_4D,
-                        SQUISH_CONSTANT_4D,
-                    ],
-                );
-                value += gradient(&self.perm_table, vertex, pos1);
-            }
-
-            // Contribution at (0, 1, 0, 0)
-            let pos2;
-            {
-                let vertex = math::add4(stretched_floor, [0.0, 1.0, 0.0, 0.0]);
-                pos2 = [pos1[0] + 1.0, pos1[1] - 1.0, pos1[2], pos1[3]];
-                value += gradient(&self.perm_table, vertex, pos2);
-            }
-
-            // Contribution at (0, 0, 1, 0)
-            let pos3;
-            {
-                let vertex = math::add4(stretched_floor, [0.0, 0.0, 1.0, 0.0]);
-                pos3 = [pos2[0], pos1[1], pos1[2] - 1.0, pos1[3]];
-                value += gradient(&self.perm_table, vertex, pos3);
-            }
-
-            // Contribution at (0, 0, 0, 1)
-            let pos4;
-            {
-                let vertex = math::add4(stretched_floor, [0.0, 0.0, 0.0, 1.0]);
-                pos4 = [pos2[0], pos1[1], pos1[2], pos1[3] - 1.0];
-                value += gradient(&self.perm_table, vertex, pos4);
-            }
-        } else if region_sum >= 3.0 {
-            // We're inside the pentachoron (4-Simplex) at (1, 1, 1, 1)
-            let squish_constant_3 = 3.0 * SQUISH_CONSTANT_4D;
-
-            // Contribution at (1, 1, 1, 0)
-            let pos4;
-            {
-                let vertex = math::add4(stretched_floor, [1.0, 1.0, 1.0, 0.0]);
-                pos4 = math::sub4(
-                    pos0,
-                    [
-                        1.0 + squish_constant_3,
-                        1.0 + squish_constant_3,
-                        1.0 + squish_constant_3,
-                        squish_constant_3,
-                    ],
-                );
-                value += gradient(&self.perm_table, vertex, pos4);
-            }
-
-            // Contribution at (1, 1, 0, 1)
-            let pos3;
-            {
-                let vertex = math::add4(stretched_floor, [1.0, 1.0, 0.0, 1.0]);
-                pos3 = [pos4[0], pos4[1], pos4[2] + 1.0, pos4[3] - 1.0];
-                value += gradient(&self.perm_table, vertex, pos3);
-            }
-
-            // Contribution at (1, 0, 1, 1)
-            let pos2;
-            {
-                let vertex = math::add4(stretched_floor, [1.0, 0.0, 1.0, 1.0]);
-                pos2 = [pos4[0], pos4[1] + 1.0, pos4[2], pos3[3]];
-                value += gradient(&self.perm_table, vertex, pos2);
-            }
-
-            // Contribution at (0, 1, 1, 1)
-            let pos1;
-            {
-                let vertex = math::add4(stretched_floor, [0.0, 1.0, 1.0, 1.0]);
-                pos1 = [pos0[0] - squish_constant_3, pos4[1], pos4[2], pos3[3]];
-                value += gradient(&self.perm_table, vertex, pos1);
-            }
-
-            // Contribution at (1, 1, 1, 1)
-            {
-                let vertex = math::add4(stretched_floor, [1.0, 1.0, 1.0, 1.0]);
-                pos0[0] = pos4[0] - SQUISH_CONSTANT_4D;
-                pos0[1] = pos4[1] - SQUISH_CONSTANT_4D;
-                pos0[2] = pos4[2] - SQUISH_CONSTANT_4D;
-                pos0[3] = pos3[3] - SQUISH_CONSTANT_4D;
-                value += gradient(&self.perm_table, vertex, pos0);
-            }
-        } else if region_sum <= 2.0 {
-            // We're inside the first dispentachoron (Rectified 4-Simplex)
-
-            // Contribution at (1, 0, 0, 0)
-            let pos1;
-            {
-                let vertex = math::add4(stretched_floor, [1.0, 0.0, 0.0, 0.0]);
-                pos1 = math::sub4(
-                    pos0,
-                    [
-                        1.0 + SQUISH_CONSTANT_4D,
-                        SQUISH_CONSTANT_4D,
-                        SQUISH_CONSTANT_4D,
-                        SQUISH_CONSTANT_4D,
-                    ],
-                );
-                value += gradient(&self.perm_table, vertex, pos1);
-            }
-
-            // Contribution at (0, 1, 0, 0)
-            let pos2;
-            {
-                let vertex = math::add4(stretched_floor, [0.0, 1.0, 0.0, 0.0]);
-                pos2 = [pos1[0] + 1.0, pos1[1] - 1.0, pos1[2], pos1[3]];
-                value += gradient(&self.perm_table, vertex, pos2);
-            }
-
-            // Contribution at (0, 0, 1, 0)
-            let pos3;
-            {
-                let vertex = math::add4(stretched_floor, [0.0, 0.0, 1.0, 0.0]);
-                pos3 = [pos2[0], pos1[1], pos1[2] - 1.0, pos1[3]];
-                value += gradient(&self.perm_table, vertex, pos3);
-            }
-
-            // Contribution at (0, 0, 0, 1)
-            let pos4;
-            {
-                let vertex = math::add4(stretched_floor, [0.0, 0.0, 0.0, 1.0]);
-                pos4 = [pos2[0], pos1[1], pos1[2], pos1[3] - 1.0];
-                value += gradient(&self.perm_table, vertex, pos4);
-            }
-
-            // Contribution at (1, 1, 0, 0)
-            let pos5;
-            {
-                let vertex = math::add4(stretched_floor, [1.0, 1.0, 0.0, 0.0]);
-                pos5 = [
-                    pos1[0] - SQUISH_CONSTANT_4D,
-                    pos2[1] - SQUISH_CONSTANT_4D,
-                    pos1[2] - SQUISH_CONSTANT_4D,
-                    pos1[3] - SQUISH_CONSTANT_4D,
-                ];
-                value += gradient(&self.perm_table, vertex, pos5);
-            }
-
-            // Contribution at (1, 0, 1, 0)
-            let pos6;
-            {
-                let vertex = math::add4(stretched_floor, [1.0, 0.0, 1.0, 0.0]);
-                pos6 = [pos5[0], pos5[1] + 1.0, pos5[2] - 1.0, pos5[3]];
-                value += gradient(&self.perm_table, vertex, pos6);
-            }
-
-            // Contribution at (1, 0, 0, 1)
-            let pos7;
-            {
-                let vertex = math::add4(stretched_floor, [1.0, 0.0, 0.0, 1.0]);
-                pos7 = [pos5[0], pos6[1], pos5[2], pos5[3] - 1.0];
-                value += gradient(&self.perm_table, vertex, pos7);
-            }
-
-            // Contribution at (0, 1, 1, 0)
-            let pos8;
-            {
-                let vertex = math::add4(stretched_floor, [0.0, 1.0, 1.0, 0.0]);
-                pos8 = [pos5[0] + 1.0, pos5[1], pos6[2], pos5[3]];
-                value += gradient(&self.perm_table, vertex, pos8);
-            }
-
-            // Contribution at (0, 1, 0, 1)
-            let pos9;
-            {
-                let vertex = math::add4(stretched_floor, [0.0, 1.0, 0.0, 1.0]);
-                pos9 = [pos8[0], pos5[1], pos5[2], pos7[3]];
-                value += gradient(&self.perm_table, vertex, pos9);
-            }
-
-            // Contribution at (0, 0, 1, 1)
-            let pos10;
-            {
-                let vertex = math::add4(stretched_floor, [0.0, 0.0, 1.0, 1.0]);
-                pos10 = [pos8[0], pos6[1], pos6[2], pos7[3]];
-                value += gradient(&self.perm_table, vertex, pos10);
-            }
+        open_simplex_4d(&mut DirectHasher::new(&self.hasher), point).value
+    }
+
+    fn get_batch(&self, points: &[[f64; 4]], output: &mut [f64]) {
+        let mut hasher = CachedHasher::<_, 4, 16>::new(&self.hasher, 0);
+        evaluate_batch(points, output, |point| {
+            open_simplex_4d(&mut hasher, point).value
+        });
+    }
+}
+
+/// [`OpenSimplex` Noise](http://uniblock.tumblr.com/post/97868843242/noise), with its gradient
+impl<H: NoiseHasher> NoiseFnGradient<2> for OpenSimplex<H> {
+    fn get_with_gradient(&self, point: [f64; 2]) -> (f64, [f64; 2]) {
+        open_simplex_2d(&mut DirectHasher::new(&self.hasher), point).into_tuple()
+    }
+}
+
+/// [`OpenSimplex` Noise](http://uniblock.tumblr.com/post/97868843242/noise), with its gradient
+impl<H: NoiseHasher> NoiseFnGradient<3> for OpenSimplex<H> {
+    fn get_with_gradient(&self, point: [f64; 3]) -> (f64, [f64; 3]) {
+        open_simplex_3d(&mut DirectHasher::new(&self.hasher), point).into_tuple()
+    }
+}
+
+/// [`OpenSimplex` Noise](http://uniblock.tumblr.com/post/97868843242/noise), with its gradient
+impl<H: NoiseHasher> NoiseFnGradient<4> for OpenSimplex<H> {
+    fn get_with_gradient(&self, point: [f64; 4]) -> (f64, [f64; 4]) {
+        open_simplex_4d(&mut DirectHasher::new(&self.hasher), point).into_tuple()
+    }
+}
+
+/// [`OpenSimplex` Noise](http://uniblock.tumblr.com/post/97868843242/noise) at `f32` coordinates
+impl<H, const DIM: usize> NoiseFn<f32, DIM> for OpenSimplex<H>
+where
+    Self: NoiseFn<f64, DIM>,
+{
+    fn get(&self, point: [f32; DIM]) -> f64 {
+        get_widened(self, point)
+    }
+
+    fn get_batch(&self, points: &[[f32; DIM]], output: &mut [f64]) {
+        evaluate_batch_widened(self, points, output);
+    }
+}
+
+#[inline(always)]
+fn open_simplex_4d<H>(hasher: &mut H, point: [f64; 4]) -> Dual<4>
+where
+    H: CellHasher<4>,
+{
+    #[inline(always)]
+    fn gradient<H: CellHasher<4>>(hasher: &H, vertex: [isize; 4], pos: [f64; 4]) -> Dual<4> {
+        let attn = 2.0 - math::dot4(pos, pos);
+        if attn > 0.0 {
+            let index = hasher.hash(vertex);
+            let vec = gradient::grad4(index);
+            dual::surflet(attn, math::dot4(pos, vec), vec, pos)
         } else {
-            // We're inside the second dispentachoron (Rectified 4-Simplex)
-            let squish_constant_3 = 3.0 * SQUISH_CONSTANT_4D;
-
-            // Contribution at (1, 1, 1, 0)
-            let pos4;
-            {
-                let vertex = math::add4(stretched_floor, [1.0, 1.0, 1.0, 0.0]);
-                pos4 = math::sub4(
-                    pos0,
-                    [
-                        1.0 + squish_constant_3,
-                        1.0 + squish_constant_3,
-                        1.0 + squish_constant_3,
-                        squish_constant_3,
-                    ],
-                );
-                value += gradient(&self.perm_table, vertex, pos4);
-            }
-
-            // Contribution at (1, 1, 0, 1)
-            let pos3;
-            {
-                let vertex = math::add4(stretched_floor, [1.0, 1.0, 0.0, 1.0]);
-                pos3 = [pos4[0], pos4[1], pos4[2] + 1.0, pos4[3] - 1.0];
-                value += gradient(&self.perm_table, vertex, pos3);
-            }
-
-            // Contribution at (1, 0, 1, 1)
-            let pos2;
-            {
-                let vertex = math::add4(stretched_floor, [1.0, 0.0, 1.0, 1.0]);
-                pos2 = [pos4[0], pos4[1] + 1.0, pos4[2], pos3[3]];
-                value += gradient(&self.perm_table, vertex, pos2);
-            }
-
-            // Contribution at (0, 1, 1, 1)
-            let pos1;
-            {
-                let vertex = math::add4(stretched_floor, [0.0, 1.0, 1.0, 1.0]);
-                pos1 = [pos4[0] + 1.0, pos4[1], pos4[2], pos3[3]];
-                value += gradient(&self.perm_table, vertex, pos1);
-            }
-
-            // Contribution at (1, 1, 0, 0)
-            let pos5;
-            {
-                let vertex = math::add4(stretched_floor, [1.0, 1.0, 0.0, 0.0]);
-                pos5 = [
-                    pos4[0] + SQUISH_CONSTANT_4D,
-                    pos4[1] + SQUISH_CONSTANT_4D,
-                    pos3[2] + SQUISH_CONSTANT_4D,
-                    pos4[3] + SQUISH_CONSTANT_4D,
-                ];
-                value += gradient(&self.perm_table, vertex, pos5);
-            }
-
-            // Contribution at (1, 0, 1, 0)
-            let pos6;
-            {
-                let vertex = math::add4(stretched_floor, [1.0, 0.0, 1.0, 0.0]);
-                pos6 = [pos5[0], pos5[1] + 1.0, pos5[2] - 1.0, pos5[3]];
-                value += gradient(&self.perm_table, vertex, pos6);
-            }
-
-            // Contribution at (1, 0, 0, 1)
-            let pos7;
-            {
-                let vertex = math::add4(stretched_floor, [1.0, 0.0, 0.0, 1.0]);
-                pos7 = [pos5[0], pos6[1], pos5[2], pos5[3] - 1.0];
-                value += gradient(&self.perm_table, vertex, pos7);
-            }
-
-            // Contribution at (0, 1, 1, 0)
-            let pos8;
-            {
-                let vertex = math::add4(stretched_floor, [0.0, 1.0, 1.0, 0.0]);
-                pos8 = [pos5[0] + 1.0, pos5[1], pos6[2], pos5[3]];
-                value += gradient(&self.perm_table, vertex, pos8);
-            }
-
-            // Contribution at (0, 1, 0, 1)
-            let pos9;
-            {
-                let vertex = math::add4(stretched_floor, [0.0, 1.0, 0.0, 1.0]);
-                pos9 = [pos8[0], pos5[1], pos5[2], pos7[3]];
-                value += gradient(&self.perm_table, vertex, pos9);
-            }
-
-            // Contribution at (0, 0, 1, 1)
-            let pos10;
-            {
-                let vertex = math::add4(stretched_floor, [0.0, 0.0, 1.0, 1.0]);
-                pos10 = [pos8[0], pos6[1], pos6[2], pos7[3]];
-                value += gradient(&self.perm_table, vertex, pos10);
-            }
-        }
-
-        value * NORM_CONSTANT_4D
+            Dual::constant(0.0)
+        }
     }
+
+    // Place input coordinates on simplectic h1.0ycomb.
+    let stretch_offset = math::fold4(point, Add::add) * STRETCH_CONSTANT_4D;
+    let stretched = math::map4(point, |v| v + stretch_offset);
+
+    // Floor to get simplectic h1.0ycomb coordinates of rhombo-hypercube
+    // super-cell origin.
+    let stretched_floor = math::map4(stretched, f64::floor);
+    hasher.set_cell(math::to_isize4(stretched_floor));
+
+    // Skew out to get actual coordinates of stretched rhombo-hypercube origin.
+    // We'll need these later.
+    let squish_offset = math::fold4(stretched_floor, Add::add) * SQUISH_CONSTANT_4D;
+    let skewed_floor = math::map4(stretched_floor, |v| v + squish_offset);
+
+    // Compute simplectic h1.0ycomb coordinates relative to rhombo-hypercube
+    // origin.
+    let rel_coords = math::sub4(stretched, stretched_floor);
+
+    // Sum those together to get a value that determines which region
+    // we're in.
+    let region_sum = math::fold4(rel_coords, Add::add);
+
+    // Position relative to origin point.
+    let mut pos0 = math::sub4(point, skewed_floor);
+
+    let mut value = Dual::constant(0.0);
+    if region_sum <= 1.0 {
+        // We're inside the pentachoron (4-Simplex) at (0, 0, 0, 0)
+
+        // Contribution at (0, 0, 0, 0)
+        value += gradient(hasher, [0, 0, 0, 0], pos0);
+
+        // Contribution at (1, 0, 0, 0)
+        let pos1;
+        {
+            let vertex = [1, 0, 0, 0];
+            pos1 = math::sub4(
+                pos0,
+                [
+                    1.0 + SQUISH_CONSTANT_4D,
+                    SQUISH_CONSTANT_4D,
+                    SQUISH_CONSTANT_4D,
+                    SQUISH_CONSTANT_4D,
+                ],
+            );
+            value += gradient(hasher, vertex, pos1);
+        }
+
+        // Contribution at (0, 1, 0, 0)
+        let pos2;
+        {
+            let vertex = [0, 1, 0, 0];
+            pos2 = [pos1[0] + 1.0, pos1[1] - 1.0, pos1[2], pos1[3]];
+            value += gradient(hasher, vertex, pos2);
+        }
+
+        // Contribution at (0, 0, 1, 0)
+        let pos3;
+        {
+            let vertex = [0, 0, 1, 0];
+            pos3 = [pos2[0], pos1[1], pos1[2] - 1.0, pos1[3]];
+            value += gradient(hasher, vertex, pos3);
+        }
+
+        // Contribution at (0, 0, 0, 1)
+        let pos4;
+        {
+            let vertex = [0, 0, 0, 1];
+            pos4 = [pos2[0], pos1[1], pos1[2], pos1[3] - 1.0];
+            value += gradient(hasher, vertex, pos4);
+        }
+    } else if region_sum >= 3.0 {
+        // We're inside the pentachoron (4-Simplex) at (1, 1, 1, 1)
+        let squish_constant_3 = 3.0 * SQUISH_CONSTANT_4D;
+
+        // Contribution at (1, 1, 1, 0)
+        let pos4;
+        {
+            let vertex = [1, 1, 1, 0];
+            pos4 = math::sub4(
+                pos0,
+                [
+                    1.0 + squish_constant_3,
+                    1.0 + squish_constant_3,
+                    1.0 + squish_constant_3,
+                    squish_constant_3,
+                ],
+            );
+            value += gradient(hasher, vertex, pos4);
+        }
+
+        // Contribution at (1, 1, 0, 1)
+        let pos3;
+        {
+            let vertex = [1, 1, 0, 1];
+            pos3 = [pos4[0], pos4[1], pos4[2] + 1.0, pos4[3] - 1.0];
+            value += gradient(hasher, vertex, pos3);
+        }
+
+        // Contribution at (1, 0, 1, 1)
+        let pos2;
+        {
+            let vertex = [1, 0, 1, 1];
+            pos2 = [pos4[0], pos4[1] + 1.0, pos4[2], pos3[3]];
+            value += gradient(hasher, vertex, pos2);
+        }
+
+        // Contribution at (0, 1, 1, 1)
+        let pos1;
+        {
+            let vertex = [0, 1, 1, 1];
+            pos1 = [pos0[0] - squish_constant_3, pos4[1], pos4[2], pos3[3]];
+            value += gradient(hasher, vertex, pos1);
+        }
+
+        // Contribution at (1, 1, 1, 1)
+        {
+            let vertex = [1, 1, 1, 1];
+            pos0[0] = pos4[0] - SQUISH_CONSTANT_4D;
+            pos0[1] = pos4[1] - SQUISH_CONSTANT_4D;
+            pos0[2] = pos4[2] - SQUISH_CONSTANT_4D;
+            pos0[3] = pos3[3] - SQUISH_CONSTANT_4D;
+            value += gradient(hasher, vertex, pos0);
+        }
+    } else if region_sum <= 2.0 {
+        // We're inside the first dispentachoron (Rectified 4-Simplex)
+
+        // Contribution at (1, 0, 0, 0)
+        let pos1;
+        {
+            let vertex = [1, 0, 0, 0];
+            pos1 = math::sub4(
+                pos0,
+                [
+                    1.0 + SQUISH_CONSTANT_4D,
+                    SQUISH_CONSTANT_4D,
+                    SQUISH_CONSTANT_4D,
+                    SQUISH_CONSTANT_4D,
+                ],
+            );
+            value += gradient(hasher, vertex, pos1);
+        }
+
+        // Contribution at (0, 1, 0, 0)
+        let pos2;
+        {
+            let vertex = [0, 1, 0, 0];
+            pos2 = [pos1[0] + 1.0, pos1[1] - 1.0, pos1[2], pos1[3]];
+            value += gradient(hasher, vertex, pos2);
+        }
+
+        // Contribution at (0, 0, 1, 0)
+        let pos3;
+        {
+            let vertex = [0, 0, 1, 0];
+            pos3 = [pos2[0], pos1[1], pos1[2] - 1.0, pos1[3]];
+            value += gradient(hasher, vertex, pos3);
+        }
+
+        // Contribution at (0, 0, 0, 1)
+        let pos4;
+        {
+            let vertex = [0, 0, 0, 1];
+            pos4 = [pos2[0], pos1[1], pos1[2], pos1[3] - 1.0];
+            value += gradient(hasher, vertex, pos4);
+        }
+
+        // Contribution at (1, 1, 0, 0)
+        let pos5;
+        {
+            let vertex = [1, 1, 0, 0];
+            pos5 = [
+                pos1[0] - SQUISH_CONSTANT_4D,
+                pos2[1] - SQUISH_CONSTANT_4D,
+                pos1[2] - SQUISH_CONSTANT_4D,
+                pos1[3] - SQUISH_CONSTANT_4D,
+            ];
+            value += gradient(hasher, vertex, pos5);
+        }
+
+        // Contribution at (1, 0, 1, 0)
+        let pos6;
+        {
+            let vertex = [1, 0, 1, 0];
+            pos6 = [pos5[0], pos5[1] + 1.0, pos5[2] - 1.0, pos5[3]];
+            value += gradient(hasher, vertex, pos6);
+        }
+
+        // Contribution at (1, 0, 0, 1)
+        let pos7;
+        {
+            let vertex = [1, 0, 0, 1];
+            pos7 = [pos5[0], pos6[1], pos5[2], pos5[3] - 1.0];
+            value += gradient(hasher, vertex, pos7);
+        }
+
+        // Contribution at (0, 1, 1, 0)
+        let pos8;
+        {
+            let vertex = [0, 1, 1, 0];
+            pos8 = [pos5[0] + 1.0, pos5[1], pos6[2], pos5[3]];
+            value += gradient(hasher, vertex, pos8);
+        }
+
+        // Contribution at (0, 1, 0, 1)
+        let pos9;
+        {
+            let vertex = [0, 1, 0, 1];
+            pos9 = [pos8[0], pos5[1], pos5[2], pos7[3]];
+            value += gradient(hasher, vertex, pos9);
+        }
+
+        // Contribution at (0, 0, 1, 1)
+        let pos10;
+        {
+            let vertex = [0, 0, 1, 1];
+            pos10 = [pos8[0], pos6[1], pos6[2], pos7[3]];
+            value += gradient(hasher, vertex, pos10);
+        }
+    } else {
+        // We're inside the second dispentachoron (Rectified 4-Simplex)
+        let squish_constant_3 = 3.0 * SQUISH_CONSTANT_4D;
+
+        // Contribution at (1, 1, 1, 0)
+        let pos4;
+        {
+            let vertex = [1, 1, 1, 0];
+            pos4 = math::sub4(
+                pos0,
+                [
+                    1.0 + squish_constant_3,
+                    1.0 + squish_constant_3,
+                    1.0 + squish_constant_3,
+                    squish_constant_3,
+                ],
+            );
+            value += gradient(hasher, vertex, pos4);
+        }
+
+        // Contribution at (1, 1, 0, 1)
+        let pos3;
+        {
+            let vertex = [1, 1, 0, 1];
+            pos3 = [pos4[0], pos4[1], pos4[2] + 1.0, pos4[3] - 1.0];
+            value += gradient(hasher, vertex, pos3);
+        }
+
+        // Contribution at (1, 0, 1, 1)
+        let pos2;
+        {
+            let vertex = [1, 0, 1, 1];
+            pos2 = [pos4[0], pos4[1] + 1.0, pos4[2], pos3[3]];
+            value += gradient(hasher, vertex, pos2);
+        }
+
+        // Contribution at (0, 1, 1, 1)
+        let pos1;
+        {
+            let vertex = [0, 1, 1, 1];
+            pos1 = [pos4[0] + 1.0, pos4[1], pos4[2], pos3[3]];
+            value += gradient(hasher, vertex, pos1);
+        }
+
+        // Contribution at (1, 1, 0, 0)
+        let pos5;
+        {
+            let vertex = [1, 1, 0, 0];
+            pos5 = [
+                pos4[0] + SQUISH_CONSTANT_4D,
+                pos4[1] + SQUISH_CONSTANT_4D,
+                pos3[2] + SQUISH_CONSTANT_4D,
+                pos4[3] + SQUISH_CONSTANT_4D,
+            ];
+            value += gradient(hasher, vertex, pos5);
+        }
+
+        // Contribution at (1, 0, 1, 0)
+        let pos6;
+        {
+            let vertex = [1, 0, 1, 0];
+            pos6 = [pos5[0], pos5[1] + 1.0, pos5[2] - 1.0, pos5[3]];
+            value += gradient(hasher, vertex, pos6);
+        }
+
+        // Contribution at (1, 0, 0, 1)
+        let pos7;
+        {
+            let vertex = [1, 0, 0, 1];
+            pos7 = [pos5[0], pos6[1], pos5[2], pos5[3] - 1.0];
+            value += gradient(hasher, vertex, pos7);
+        }
+
+        // Contribution at (0, 1, 1, 0)
+        let pos8;
+        {
+            let vertex = [0, 1, 1, 0];
+            pos8 = [pos5[0] + 1.0, pos5[1], pos6[2], pos5[3]];
+            value += gradient(hasher, vertex, pos8);
+        }
+
+        // Contribution at (0, 1, 0, 1)
+        let pos9;
+        {
+            let vertex = [0, 1, 0, 1];
+            pos9 = [pos8[0], pos5[1], pos5[2], pos7[3]];
+            value += gradient(hasher, vertex, pos9);
+        }
+
+        // Contribution at (0, 0, 1, 1)
+        let pos10;
+        {
+            let vertex = [0, 0, 1, 1];
+            pos10 = [pos8[0], pos6[1], pos6[2], pos7[3]];
+            value += gradient(hasher, vertex, pos10);
+        }
+    }
+
+    value * NORM_CONSTANT_4D
 }
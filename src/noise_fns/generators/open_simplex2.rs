@@ -0,0 +1,667 @@
+//! OpenSimplex2 noise, the successor of [`OpenSimplex`](crate::OpenSimplex) noise, in its smooth
+//! and fast variants, as detailed here: <https://github.com/KdotJPG/OpenSimplex2>
+
+use super::super_simplex::{
+    simplex_lattice_4d, super_simplex_2d, super_simplex_3d_rotated, super_simplex_4d,
+};
+use crate::{
+    gradient,
+    math::{
+        self,
+        dual::{self, Dual},
+    },
+    noise_fns::{
+        evaluate_batch, evaluate_batch_widened, get_widened, NoiseFn, NoiseFnGradient, Seedable,
+    },
+    permutationtable::{
+        CachedHasher, CellHasher, DirectHasher, NoiseHasher, PermutationTable, SeedableHasher,
+    },
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::ops::Add;
+
+const TO_REAL_CONSTANT_2D: f64 = -0.211_324_865_405_187; // (1 / sqrt(2 + 1) - 1) / 2
+const TO_SIMPLEX_CONSTANT_2D: f64 = 0.366_025_403_784_439; // (sqrt(2 + 1) - 1) / 2
+
+// Used to rotate the 3D lattices so that a plane is orthogonal to their main diagonal
+const ORTHOGONALIZER_3D: f64 = -0.211_324_865_405_187; // (1 / sqrt(2 + 1) - 1) / 2
+const FRAC_1_SQRT_3: f64 = 0.577_350_269_189_626; // 1 / sqrt(3)
+
+// Determined the same way as the 4D Super Simplex constant, see the open_simplex2 example
+const NORM_CONSTANT_FAST_2D: f64 = 1.0 / 0.010_080_204_702_811_43;
+const NORM_CONSTANT_FAST_3D: f64 = 1.0 / 0.009_289_062_925_455_9;
+const NORM_CONSTANT_FAST_4D: f64 = 1.0 / 0.022_897_336_089_597_86;
+
+// Points taken into account for 4D OpenSimplex2F, for each half of the simplex cube the point can
+// be in along each axis, as listed by the open_simplex2 example. The points for the region with
+// index `i` are LATTICE_LOOKUP_FAST_4D[LATTICE_REGIONS_FAST_4D[i]..LATTICE_REGIONS_FAST_4D[i + 1]].
+#[rustfmt::skip]
+const LATTICE_LOOKUP_FAST_4D: [[i8; 4]; 258] =
+    [[0, 0, 0, -1],[0, 0, -1, 0],[0, -1, 0, 0],[-1, 0, 0, 0],[0, 0, 0, 0],[1, 0, 0, 0],[0, 1, 0, 0],[1, 1, 0, 0],
+     [0, 0, 1, 0],[1, 0, 1, 0],[0, 1, 1, 0],[1, 1, 1, 0],[0, 0, 0, 1],[1, 0, 0, 1],[0, 1, 0, 1],[1, 1, 0, 1],
+     [0, 0, 1, 1],[1, 0, 1, 1],[0, 1, 1, 1],[1, 1, 1, 1],
+
+     [0, 0, 0, -1],[0, 0, -1, 0],[0, -1, 0, 0],[0, 0, 0, 0],[1, 0, 0, 0],[0, 1, 0, 0],[1, 1, 0, 0],[0, 0, 1, 0],
+     [1, 0, 1, 0],[1, 1, 1, 0],[0, 0, 0, 1],[1, 0, 0, 1],[1, 1, 0, 1],[1, 0, 1, 1],[1, 1, 1, 1],[2, 1, 1, 1],
+
+     [0, 0, 0, -1],[0, 0, -1, 0],[-1, 0, 0, 0],[0, 0, 0, 0],[1, 0, 0, 0],[0, 1, 0, 0],[1, 1, 0, 0],[0, 0, 1, 0],
+     [0, 1, 1, 0],[1, 1, 1, 0],[0, 0, 0, 1],[0, 1, 0, 1],[1, 1, 0, 1],[0, 1, 1, 1],[1, 1, 1, 1],[1, 2, 1, 1],
+
+     [0, 0, 0, -1],[0, 0, -1, 0],[0, 0, 0, 0],[1, 0, 0, 0],[0, 1, 0, 0],[1, 1, 0, 0],[1, 0, 1, 0],[0, 1, 1, 0],
+     [1, 1, 1, 0],[1, 0, 0, 1],[0, 1, 0, 1],[1, 1, 0, 1],[1, 1, 1, 1],[2, 1, 1, 1],[1, 2, 1, 1],
+
+     [0, 0, 0, -1],[0, -1, 0, 0],[-1, 0, 0, 0],[0, 0, 0, 0],[1, 0, 0, 0],[0, 1, 0, 0],[0, 0, 1, 0],[1, 0, 1, 0],
+     [0, 1, 1, 0],[1, 1, 1, 0],[0, 0, 0, 1],[0, 0, 1, 1],[1, 0, 1, 1],[0, 1, 1, 1],[1, 1, 1, 1],[1, 1, 2, 1],
+
+     [0, 0, 0, -1],[0, -1, 0, 0],[0, 0, 0, 0],[1, 0, 0, 0],[1, 1, 0, 0],[0, 0, 1, 0],[1, 0, 1, 0],[0, 1, 1, 0],
+     [1, 1, 1, 0],[1, 0, 0, 1],[0, 0, 1, 1],[1, 0, 1, 1],[1, 1, 1, 1],[2, 1, 1, 1],[1, 1, 2, 1],
+
+     [0, 0, 0, -1],[-1, 0, 0, 0],[0, 0, 0, 0],[0, 1, 0, 0],[1, 1, 0, 0],[0, 0, 1, 0],[1, 0, 1, 0],[0, 1, 1, 0],
+     [1, 1, 1, 0],[0, 1, 0, 1],[0, 0, 1, 1],[0, 1, 1, 1],[1, 1, 1, 1],[1, 2, 1, 1],[1, 1, 2, 1],
+
+     [0, 0, 0, -1],[0, 0, 0, 0],[1, 0, 0, 0],[0, 1, 0, 0],[1, 1, 0, 0],[0, 0, 1, 0],[1, 0, 1, 0],[0, 1, 1, 0],
+     [1, 1, 1, 0],[1, 1, 0, 1],[1, 0, 1, 1],[0, 1, 1, 1],[1, 1, 1, 1],[2, 1, 1, 1],[1, 2, 1, 1],[1, 1, 2, 1],
+
+     [0, 0, -1, 0],[0, -1, 0, 0],[-1, 0, 0, 0],[0, 0, 0, 0],[1, 0, 0, 0],[0, 1, 0, 0],[0, 0, 1, 0],[0, 0, 0, 1],
+     [1, 0, 0, 1],[0, 1, 0, 1],[1, 1, 0, 1],[0, 0, 1, 1],[1, 0, 1, 1],[0, 1, 1, 1],[1, 1, 1, 1],[1, 1, 1, 2],
+
+     [0, 0, -1, 0],[0, -1, 0, 0],[0, 0, 0, 0],[1, 0, 0, 0],[1, 1, 0, 0],[1, 0, 1, 0],[0, 0, 0, 1],[1, 0, 0, 1],
+     [0, 1, 0, 1],[1, 1, 0, 1],[0, 0, 1, 1],[1, 0, 1, 1],[1, 1, 1, 1],[2, 1, 1, 1],[1, 1, 1, 2],
+
+     [0, 0, -1, 0],[-1, 0, 0, 0],[0, 0, 0, 0],[0, 1, 0, 0],[1, 1, 0, 0],[0, 1, 1, 0],[0, 0, 0, 1],[1, 0, 0, 1],
+     [0, 1, 0, 1],[1, 1, 0, 1],[0, 0, 1, 1],[0, 1, 1, 1],[1, 1, 1, 1],[1, 2, 1, 1],[1, 1, 1, 2],
+
+     [0, 0, -1, 0],[0, 0, 0, 0],[1, 0, 0, 0],[0, 1, 0, 0],[1, 1, 0, 0],[1, 1, 1, 0],[0, 0, 0, 1],[1, 0, 0, 1],
+     [0, 1, 0, 1],[1, 1, 0, 1],[1, 0, 1, 1],[0, 1, 1, 1],[1, 1, 1, 1],[2, 1, 1, 1],[1, 2, 1, 1],[1, 1, 1, 2],
+
+     [0, -1, 0, 0],[-1, 0, 0, 0],[0, 0, 0, 0],[0, 0, 1, 0],[1, 0, 1, 0],[0, 1, 1, 0],[0, 0, 0, 1],[1, 0, 0, 1],
+     [0, 1, 0, 1],[0, 0, 1, 1],[1, 0, 1, 1],[0, 1, 1, 1],[1, 1, 1, 1],[1, 1, 2, 1],[1, 1, 1, 2],
+
+     [0, -1, 0, 0],[0, 0, 0, 0],[1, 0, 0, 0],[0, 0, 1, 0],[1, 0, 1, 0],[1, 1, 1, 0],[0, 0, 0, 1],[1, 0, 0, 1],
+     [1, 1, 0, 1],[0, 0, 1, 1],[1, 0, 1, 1],[0, 1, 1, 1],[1, 1, 1, 1],[2, 1, 1, 1],[1, 1, 2, 1],[1, 1, 1, 2],
+
+     [-1, 0, 0, 0],[0, 0, 0, 0],[0, 1, 0, 0],[0, 0, 1, 0],[0, 1, 1, 0],[1, 1, 1, 0],[0, 0, 0, 1],[0, 1, 0, 1],
+     [1, 1, 0, 1],[0, 0, 1, 1],[1, 0, 1, 1],[0, 1, 1, 1],[1, 1, 1, 1],[1, 2, 1, 1],[1, 1, 2, 1],[1, 1, 1, 2],
+
+     [0, 0, 0, 0],[1, 0, 0, 0],[0, 1, 0, 0],[1, 1, 0, 0],[0, 0, 1, 0],[1, 0, 1, 0],[0, 1, 1, 0],[1, 1, 1, 0],
+     [0, 0, 0, 1],[1, 0, 0, 1],[0, 1, 0, 1],[1, 1, 0, 1],[0, 0, 1, 1],[1, 0, 1, 1],[0, 1, 1, 1],[1, 1, 1, 1],
+     [2, 1, 1, 1],[1, 2, 1, 1],[1, 1, 2, 1],[1, 1, 1, 2]];
+
+const LATTICE_REGIONS_FAST_4D: [usize; 17] = [
+    0, 20, 36, 52, 67, 83, 98, 113, 129, 145, 160, 175, 191, 206, 222, 238, 258,
+];
+
+/// How the 3D lattices of the OpenSimplex2 noise functions are oriented.
+///
+/// The lattices are rotated so that none of their features line up with the
+/// axes. With [`ImproveXY`](LatticeOrientation::ImproveXY) or
+/// [`ImproveXZ`](LatticeOrientation::ImproveXZ), the rotation is chosen so
+/// that slices of the noise in that plane look like good 2D noise, which
+/// suits noise in which the remaining axis is vertical or time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum LatticeOrientation {
+    /// The main diagonal of the lattices points along the main diagonal of
+    /// the axes, as in Super Simplex noise. No plane is favoured.
+    #[default]
+    Classic,
+
+    /// The main diagonal of the lattices points along the Z axis.
+    ImproveXY,
+
+    /// The main diagonal of the lattices points along the Y axis.
+    ImproveXZ,
+}
+
+impl LatticeOrientation {
+    /// Rotates a point from real space onto the lattices.
+    #[inline(always)]
+    fn rotate(self, [x, y, z]: [f64; 3]) -> [f64; 3] {
+        match self {
+            LatticeOrientation::Classic => {
+                let r = (x + y + z) * (2.0 / 3.0);
+                [r - x, r - y, r - z]
+            }
+            LatticeOrientation::ImproveXY => {
+                let xy = x + y;
+                let s2 = xy * ORTHOGONALIZER_3D;
+                let zz = z * FRAC_1_SQRT_3;
+                [x + s2 + zz, y + s2 + zz, xy * -FRAC_1_SQRT_3 + zz]
+            }
+            LatticeOrientation::ImproveXZ => {
+                let xz = x + z;
+                let s2 = xz * ORTHOGONALIZER_3D;
+                let yy = y * FRAC_1_SQRT_3;
+                [x + s2 + yy, xz * -FRAC_1_SQRT_3 + yy, z + s2 + yy]
+            }
+        }
+    }
+
+    /// Takes a gradient with respect to a point on the lattices back to real
+    /// space. The rotations are orthogonal, so this applies their transpose.
+    #[inline(always)]
+    fn unrotate_gradient(self, [x, y, z]: [f64; 3]) -> [f64; 3] {
+        match self {
+            LatticeOrientation::Classic => self.rotate([x, y, z]),
+            LatticeOrientation::ImproveXY => {
+                let xy = x + y;
+                let s2 = xy * ORTHOGONALIZER_3D;
+                let zz = z * -FRAC_1_SQRT_3;
+                [x + s2 + zz, y + s2 + zz, (xy + z) * FRAC_1_SQRT_3]
+            }
+            LatticeOrientation::ImproveXZ => {
+                let xz = x + z;
+                let s2 = xz * ORTHOGONALIZER_3D;
+                let yy = y * -FRAC_1_SQRT_3;
+                [x + s2 + yy, (xz + y) * FRAC_1_SQRT_3, z + s2 + yy]
+            }
+        }
+    }
+}
+
+/// Noise function that outputs 2/3/4-dimensional OpenSimplex2S noise.
+///
+/// This is the smooth variant of OpenSimplex2. Every point is influenced by
+/// many lattice points with wide surflets, which gives smooth, even noise at
+/// a higher cost than [`OpenSimplex2F`]. It is the same noise as
+/// [`SuperSimplex`](crate::SuperSimplex) with the classic lattice
+/// orientation, and the 3D lattices can be reoriented with
+/// [`set_orientation`](OpenSimplex2S::set_orientation).
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(from = "OpenSimplex2Params", bound(deserialize = "H: SeedableHasher"))
+)]
+pub struct OpenSimplex2S<H = PermutationTable> {
+    seed: u64,
+    orientation: LatticeOrientation,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    hasher: H,
+}
+
+impl OpenSimplex2S {
+    pub const DEFAULT_SEED: u64 = 0;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<H> OpenSimplex2S<H> {
+    /// Sets how the 3D lattices are oriented. This has no effect in 2D and 4D.
+    pub fn set_orientation(self, orientation: LatticeOrientation) -> Self {
+        Self {
+            orientation,
+            ..self
+        }
+    }
+
+    pub fn orientation(&self) -> LatticeOrientation {
+        self.orientation
+    }
+}
+
+impl<H: SeedableHasher> OpenSimplex2S<H> {
+    /// Creates the noise function with `seed`, hashing the lattice with any
+    /// [`SeedableHasher`] rather than the default [`PermutationTable`].
+    pub fn from_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            orientation: LatticeOrientation::default(),
+            hasher: H::from_seed(seed),
+        }
+    }
+}
+
+impl Default for OpenSimplex2S {
+    fn default() -> Self {
+        Self::from_seed(Self::DEFAULT_SEED)
+    }
+}
+
+impl<H: SeedableHasher> Seedable for OpenSimplex2S<H> {
+    /// Sets the seed value for OpenSimplex2S noise
+    fn set_seed(self, seed: u64) -> Self {
+        // If the new seed is the same as the current seed, just return self.
+        if self.seed == seed {
+            return self;
+        }
+
+        // Otherwise, regenerate the hasher based on the new seed.
+        Self {
+            seed,
+            hasher: H::from_seed(seed),
+            ..self
+        }
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+/// 2-dimensional OpenSimplex2S noise
+impl<H: NoiseHasher> NoiseFn<f64, 2> for OpenSimplex2S<H> {
+    fn get(&self, point: [f64; 2]) -> f64 {
+        super_simplex_2d(&mut DirectHasher::new(&self.hasher), point).value
+    }
+
+    fn get_batch(&self, points: &[[f64; 2]], output: &mut [f64]) {
+        // The lattice lookup reaches one point outside the cell in every direction.
+        let mut hasher = CachedHasher::<_, 2, 16>::new(&self.hasher, -1);
+        evaluate_batch(points, output, |point| {
+            super_simplex_2d(&mut hasher, point).value
+        });
+    }
+}
+
+/// 3-dimensional OpenSimplex2S noise
+impl<H: NoiseHasher> NoiseFn<f64, 3> for OpenSimplex2S<H> {
+    fn get(&self, point: [f64; 3]) -> f64 {
+        open_simplex2s_3d(
+            &mut DirectHasher::new(&self.hasher),
+            &mut DirectHasher::new(&self.hasher),
+            self.orientation,
+            point,
+        )
+        .value
+    }
+
+    fn get_batch(&self, points: &[[f64; 3]], output: &mut [f64]) {
+        // Each of the two lattices moves between cells independently, so give each its own cache.
+        let mut hasher = CachedHasher::<_, 3, 8>::new(&self.hasher, 0);
+        let mut second_hasher = CachedHasher::<_, 3, 8>::new(&self.hasher, 0);
+        evaluate_batch(points, output, |point| {
+            open_simplex2s_3d(&mut hasher, &mut second_hasher, self.orientation, point).value
+        });
+    }
+}
+
+/// 4-dimensional OpenSimplex2S noise
+impl<H: NoiseHasher> NoiseFn<f64, 4> for OpenSimplex2S<H> {
+    fn get(&self, point: [f64; 4]) -> f64 {
+        super_simplex_4d(&mut DirectHasher::new(&self.hasher), point).value
+    }
+
+    fn get_batch(&self, points: &[[f64; 4]], output: &mut [f64]) {
+        // The lattice lookup reaches one point outside the cell in every direction.
+        let mut hasher = CachedHasher::<_, 4, 256>::new(&self.hasher, -1);
+        evaluate_batch(points, output, |point| {
+            super_simplex_4d(&mut hasher, point).value
+        });
+    }
+}
+
+/// 2-dimensional OpenSimplex2S noise, with its gradient
+impl<H: NoiseHasher> NoiseFnGradient<2> for OpenSimplex2S<H> {
+    fn get_with_gradient(&self, point: [f64; 2]) -> (f64, [f64; 2]) {
+        super_simplex_2d(&mut DirectHasher::new(&self.hasher), point).into_tuple()
+    }
+}
+
+/// 3-dimensional OpenSimplex2S noise, with its gradient
+impl<H: NoiseHasher> NoiseFnGradient<3> for OpenSimplex2S<H> {
+    fn get_with_gradient(&self, point: [f64; 3]) -> (f64, [f64; 3]) {
+        open_simplex2s_3d(
+            &mut DirectHasher::new(&self.hasher),
+            &mut DirectHasher::new(&self.hasher),
+            self.orientation,
+            point,
+        )
+        .into_tuple()
+    }
+}
+
+/// 4-dimensional OpenSimplex2S noise, with its gradient
+impl<H: NoiseHasher> NoiseFnGradient<4> for OpenSimplex2S<H> {
+    fn get_with_gradient(&self, point: [f64; 4]) -> (f64, [f64; 4]) {
+        super_simplex_4d(&mut DirectHasher::new(&self.hasher), point).into_tuple()
+    }
+}
+
+/// OpenSimplex2S noise at `f32` coordinates
+impl<H, const DIM: usize> NoiseFn<f32, DIM> for OpenSimplex2S<H>
+where
+    Self: NoiseFn<f64, DIM>,
+{
+    fn get(&self, point: [f32; DIM]) -> f64 {
+        get_widened(self, point)
+    }
+
+    fn get_batch(&self, points: &[[f32; DIM]], output: &mut [f64]) {
+        evaluate_batch_widened(self, points, output);
+    }
+}
+
+#[inline(always)]
+fn open_simplex2s_3d<H>(
+    hasher: &mut H,
+    second_hasher: &mut H,
+    orientation: LatticeOrientation,
+    point: [f64; 3],
+) -> Dual<3>
+where
+    H: CellHasher<3>,
+{
+    let mut value = super_simplex_3d_rotated(hasher, second_hasher, orientation.rotate(point));
+    value.gradient = orientation.unrotate_gradient(value.gradient);
+    value
+}
+
+/// Noise function that outputs 2/3/4-dimensional OpenSimplex2F noise.
+///
+/// This is the fast variant of OpenSimplex2. Every point is influenced by
+/// only the few lattice points around it, with narrower surflets than in
+/// [`OpenSimplex2S`], which makes it quicker to evaluate at the cost of a
+/// slightly less even look. The 3D lattices can be reoriented with
+/// [`set_orientation`](OpenSimplex2F::set_orientation).
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(from = "OpenSimplex2Params", bound(deserialize = "H: SeedableHasher"))
+)]
+pub struct OpenSimplex2F<H = PermutationTable> {
+    seed: u64,
+    orientation: LatticeOrientation,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    hasher: H,
+}
+
+impl OpenSimplex2F {
+    pub const DEFAULT_SEED: u64 = 0;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<H> OpenSimplex2F<H> {
+    /// Sets how the 3D lattices are oriented. This has no effect in 2D and 4D.
+    pub fn set_orientation(self, orientation: LatticeOrientation) -> Self {
+        Self {
+            orientation,
+            ..self
+        }
+    }
+
+    pub fn orientation(&self) -> LatticeOrientation {
+        self.orientation
+    }
+}
+
+impl<H: SeedableHasher> OpenSimplex2F<H> {
+    /// Creates the noise function with `seed`, hashing the lattice with any
+    /// [`SeedableHasher`] rather than the default [`PermutationTable`].
+    pub fn from_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            orientation: LatticeOrientation::default(),
+            hasher: H::from_seed(seed),
+        }
+    }
+}
+
+impl Default for OpenSimplex2F {
+    fn default() -> Self {
+        Self::from_seed(Self::DEFAULT_SEED)
+    }
+}
+
+impl<H: SeedableHasher> Seedable for OpenSimplex2F<H> {
+    /// Sets the seed value for OpenSimplex2F noise
+    fn set_seed(self, seed: u64) -> Self {
+        // If the new seed is the same as the current seed, just return self.
+        if self.seed == seed {
+            return self;
+        }
+
+        // Otherwise, regenerate the hasher based on the new seed.
+        Self {
+            seed,
+            hasher: H::from_seed(seed),
+            ..self
+        }
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+/// 2-dimensional OpenSimplex2F noise
+impl<H: NoiseHasher> NoiseFn<f64, 2> for OpenSimplex2F<H> {
+    fn get(&self, point: [f64; 2]) -> f64 {
+        open_simplex2f_2d(&mut DirectHasher::new(&self.hasher), point).value
+    }
+
+    fn get_batch(&self, points: &[[f64; 2]], output: &mut [f64]) {
+        // The triangle the point is in only reaches the corners of its cell.
+        let mut hasher = CachedHasher::<_, 2, 4>::new(&self.hasher, 0);
+        evaluate_batch(points, output, |point| {
+            open_simplex2f_2d(&mut hasher, point).value
+        });
+    }
+}
+
+/// 3-dimensional OpenSimplex2F noise
+impl<H: NoiseHasher> NoiseFn<f64, 3> for OpenSimplex2F<H> {
+    fn get(&self, point: [f64; 3]) -> f64 {
+        open_simplex2f_3d(
+            &mut DirectHasher::new(&self.hasher),
+            &mut DirectHasher::new(&self.hasher),
+            self.orientation,
+            point,
+        )
+        .value
+    }
+
+    fn get_batch(&self, points: &[[f64; 3]], output: &mut [f64]) {
+        // Each of the two lattices moves between cells independently, so give each its own cache.
+        // The neighbour of the closest point can lie on either side of it.
+        let mut hasher = CachedHasher::<_, 3, 27>::new(&self.hasher, -1);
+        let mut second_hasher = CachedHasher::<_, 3, 27>::new(&self.hasher, -1);
+        evaluate_batch(points, output, |point| {
+            open_simplex2f_3d(&mut hasher, &mut second_hasher, self.orientation, point).value
+        });
+    }
+}
+
+/// 4-dimensional OpenSimplex2F noise
+impl<H: NoiseHasher> NoiseFn<f64, 4> for OpenSimplex2F<H> {
+    fn get(&self, point: [f64; 4]) -> f64 {
+        open_simplex2f_4d(&mut DirectHasher::new(&self.hasher), point).value
+    }
+
+    fn get_batch(&self, points: &[[f64; 4]], output: &mut [f64]) {
+        // The lattice lookup reaches one point outside the cell in every direction.
+        let mut hasher = CachedHasher::<_, 4, 256>::new(&self.hasher, -1);
+        evaluate_batch(points, output, |point| {
+            open_simplex2f_4d(&mut hasher, point).value
+        });
+    }
+}
+
+/// 2-dimensional OpenSimplex2F noise, with its gradient
+impl<H: NoiseHasher> NoiseFnGradient<2> for OpenSimplex2F<H> {
+    fn get_with_gradient(&self, point: [f64; 2]) -> (f64, [f64; 2]) {
+        open_simplex2f_2d(&mut DirectHasher::new(&self.hasher), point).into_tuple()
+    }
+}
+
+/// 3-dimensional OpenSimplex2F noise, with its gradient
+impl<H: NoiseHasher> NoiseFnGradient<3> for OpenSimplex2F<H> {
+    fn get_with_gradient(&self, point: [f64; 3]) -> (f64, [f64; 3]) {
+        open_simplex2f_3d(
+            &mut DirectHasher::new(&self.hasher),
+            &mut DirectHasher::new(&self.hasher),
+            self.orientation,
+            point,
+        )
+        .into_tuple()
+    }
+}
+
+/// 4-dimensional OpenSimplex2F noise, with its gradient
+impl<H: NoiseHasher> NoiseFnGradient<4> for OpenSimplex2F<H> {
+    fn get_with_gradient(&self, point: [f64; 4]) -> (f64, [f64; 4]) {
+        open_simplex2f_4d(&mut DirectHasher::new(&self.hasher), point).into_tuple()
+    }
+}
+
+/// OpenSimplex2F noise at `f32` coordinates
+impl<H, const DIM: usize> NoiseFn<f32, DIM> for OpenSimplex2F<H>
+where
+    Self: NoiseFn<f64, DIM>,
+{
+    fn get(&self, point: [f32; DIM]) -> f64 {
+        get_widened(self, point)
+    }
+
+    fn get_batch(&self, points: &[[f32; DIM]], output: &mut [f64]) {
+        evaluate_batch_widened(self, points, output);
+    }
+}
+
+#[inline(always)]
+fn open_simplex2f_2d<H>(hasher: &mut H, point: [f64; 2]) -> Dual<2>
+where
+    H: CellHasher<2>,
+{
+    let mut value = Dual::constant(0.0);
+
+    // Transform point from real space to simplex space
+    let to_simplex_offset = math::fold2(point, Add::add) * TO_SIMPLEX_CONSTANT_2D;
+    let simplex_point = math::map2(point, |v| v + to_simplex_offset);
+
+    // Get base point of simplex and barycentric coordinates in simplex space
+    let simplex_base_point = math::map2(simplex_point, f64::floor);
+    hasher.set_cell(math::to_isize2(simplex_base_point));
+    let simplex_rel_coords = math::sub2(simplex_point, simplex_base_point);
+
+    // The point is in the triangle between the base point, the opposite corner of the cell and
+    // whichever of the two other corners is on its side of the diagonal.
+    let middle = if simplex_rel_coords[0] >= simplex_rel_coords[1] {
+        [1, 0]
+    } else {
+        [0, 1]
+    };
+
+    for lattice_point in [[0, 0], middle, [1, 1]] {
+        let dpos = math::sub2(simplex_rel_coords, math::to_f64_2(lattice_point));
+
+        // Transform the offset from simplex space to real space
+        let to_real_offset = math::fold2(dpos, Add::add) * TO_REAL_CONSTANT_2D;
+        let dpos = math::map2(dpos, |v| v + to_real_offset);
+
+        let attn = 0.5 - math::dot2(dpos, dpos);
+        if attn > 0.0 {
+            let gradient = gradient::grad2(hasher.hash(lattice_point));
+            value += dual::surflet(attn, math::dot2(gradient, dpos), gradient, dpos);
+        }
+    }
+
+    value * NORM_CONSTANT_FAST_2D
+}
+
+#[inline(always)]
+fn open_simplex2f_3d<H>(
+    hasher: &mut H,
+    second_hasher: &mut H,
+    orientation: LatticeOrientation,
+    point: [f64; 3],
+) -> Dual<3>
+where
+    H: CellHasher<3>,
+{
+    // The two cubic lattices, offset by half a cell along each axis, together form a
+    // body-centered cubic lattice.
+    let rotated_point = orientation.rotate(point);
+    let second_rotated_point = math::map3(rotated_point, |v| v + 512.5);
+
+    let mut value = open_simplex2f_3d_lattice(hasher, rotated_point)
+        + open_simplex2f_3d_lattice(second_hasher, second_rotated_point);
+    value.gradient = orientation.unrotate_gradient(value.gradient);
+
+    value * NORM_CONSTANT_FAST_3D
+}
+
+/// Sums the surflets of the two points of a cubic lattice that can reach `point`: the closest
+/// one, and its neighbour along the axis the point is furthest from it on.
+#[inline(always)]
+fn open_simplex2f_3d_lattice<H>(hasher: &mut H, point: [f64; 3]) -> Dual<3>
+where
+    H: CellHasher<3>,
+{
+    let mut value = Dual::constant(0.0);
+
+    let base_point = math::map3(point, f64::round);
+    hasher.set_cell(math::to_isize3(base_point));
+    let rel_coords = math::sub3(point, base_point);
+
+    let [x, y, z] = math::map3(rel_coords, f64::abs);
+    let axis = if x >= y && x >= z {
+        0
+    } else if y >= z {
+        1
+    } else {
+        2
+    };
+    let mut neighbour = [0; 3];
+    neighbour[axis] = if rel_coords[axis] >= 0.0 { 1 } else { -1 };
+
+    for lattice_point in [[0, 0, 0], neighbour] {
+        let dpos = math::sub3(rel_coords, math::to_f64_3(lattice_point));
+        let attn = 0.5 - math::dot3(dpos, dpos);
+        if attn > 0.0 {
+            let gradient = gradient::grad3(hasher.hash(lattice_point));
+            value += dual::surflet(attn, math::dot3(gradient, dpos), gradient, dpos);
+        }
+    }
+
+    value
+}
+
+#[inline(always)]
+fn open_simplex2f_4d<H>(hasher: &mut H, point: [f64; 4]) -> Dual<4>
+where
+    H: CellHasher<4>,
+{
+    simplex_lattice_4d(
+        hasher,
+        point,
+        &LATTICE_LOOKUP_FAST_4D,
+        &LATTICE_REGIONS_FAST_4D,
+        0.6,
+    ) * NORM_CONSTANT_FAST_4D
+}
+
+/// Parameters of an OpenSimplex2 noise function.
+///
+/// The noise functions deserialize from this, so that their hasher
+/// is rebuilt from the seed instead of being stored.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OpenSimplex2Params {
+    #[serde(default)]
+    seed: u64,
+    #[serde(default)]
+    orientation: LatticeOrientation,
+}
+
+#[cfg(feature = "serde")]
+impl<H: SeedableHasher> From<OpenSimplex2Params> for OpenSimplex2S<H> {
+    fn from(params: OpenSimplex2Params) -> Self {
+        Self::from_seed(params.seed).set_orientation(params.orientation)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<H: SeedableHasher> From<OpenSimplex2Params> for OpenSimplex2F<H> {
+    fn from(params: OpenSimplex2Params) -> Self {
+        Self::from_seed(params.seed).set_orientation(params.orientation)
+    }
+}
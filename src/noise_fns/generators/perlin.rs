@@ -1,105 +1,210 @@
+use std::ops::{Add, Mul, Sub};
+
 use crate::{
-    math::{self, s_curve::quintic::Quintic},
-    noise_fns::{NoiseFn, Seedable},
-    permutationtable::{NoiseHasher, PermutationTable},
+    math::{
+        self,
+        dual::{self, Dual},
+        lanes::{self, Lanes, LANES},
+        s_curve::quintic::Quintic,
+    },
+    noise_fns::{
+        evaluate_batch_lanes, evaluate_batch_widened, get_widened, NoiseFn, NoiseFnGradient,
+        Periodic, Seedable,
+    },
+    permutationtable::{
+        expand_period, CachedHasher, CellHasher, DirectHasher, NoiseHasher, PeriodicHasher,
+        PermutationTable, SeedableHasher,
+    },
 };
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
-/// Noise function that outputs 2/3/4-dimensional Perlin noise.
+/// Noise function that outputs 1/2/3/4-dimensional Perlin noise.
+///
+/// With a [period](Periodic::set_period) set, the output repeats every that
+/// many lattice cells along each periodic axis, so it tiles exactly.
 #[derive(Clone, Copy, Debug)]
-pub struct Perlin {
-    seed: u32,
-    perm_table: PermutationTable,
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        from = "super::PeriodicParams",
+        bound(deserialize = "H: SeedableHasher")
+    )
+)]
+pub struct Perlin<H = PermutationTable> {
+    seed: u64,
+    period: [u32; 4],
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    hasher: H,
 }
 
 impl Perlin {
-    pub const DEFAULT_SEED: u32 = 0;
+    pub const DEFAULT_SEED: u64 = 0;
 
-    pub fn new(seed: u32) -> Self {
+    pub fn new(seed: u64) -> Self {
+        Self::from_seed(seed)
+    }
+}
+
+impl<H: SeedableHasher> Perlin<H> {
+    /// Creates the noise function with `seed`, hashing the lattice with any
+    /// [`SeedableHasher`] rather than the default [`PermutationTable`].
+    pub fn from_seed(seed: u64) -> Self {
         Self {
             seed,
-            perm_table: PermutationTable::new(seed),
+            period: [0; 4],
+            hasher: H::from_seed(seed),
         }
     }
 }
 
+impl<H> Perlin<H> {
+    fn periodic_hasher(&self) -> PeriodicHasher<'_, H> {
+        PeriodicHasher::new(&self.hasher, self.period)
+    }
+}
+
 impl Default for Perlin {
     fn default() -> Self {
-        Self::new(Self::DEFAULT_SEED)
+        Self::from_seed(Self::DEFAULT_SEED)
     }
 }
 
-impl Seedable for Perlin {
+impl<H: SeedableHasher> Seedable for Perlin<H> {
     /// Sets the seed value for Perlin noise
-    fn set_seed(self, seed: u32) -> Self {
+    fn set_seed(self, seed: u64) -> Self {
         // If the new seed is the same as the current seed, just return self.
         if self.seed == seed {
             return self;
         }
 
-        // Otherwise, regenerate the permutation table based on the new seed.
+        // Otherwise, regenerate the hasher based on the new seed.
         Self {
             seed,
-            perm_table: PermutationTable::new(seed),
+            hasher: H::from_seed(seed),
+            ..self
         }
     }
 
-    fn seed(&self) -> u32 {
+    fn seed(&self) -> u64 {
         self.seed
     }
 }
 
+impl<H> Periodic for Perlin<H> {
+    fn set_period<const N: usize>(self, period: [u32; N]) -> Self {
+        Self {
+            period: expand_period(period),
+            ..self
+        }
+    }
+
+    fn period(&self) -> [u32; 4] {
+        self.period
+    }
+}
+
+/// 1-dimensional perlin noise
+impl<H: NoiseHasher> NoiseFn<f64, 1> for Perlin<H> {
+    fn get(&self, point: [f64; 1]) -> f64 {
+        perlin_1d(&mut DirectHasher::new(&self.periodic_hasher()), point)
+    }
+
+    fn get_batch(&self, points: &[[f64; 1]], output: &mut [f64]) {
+        let periodic = self.periodic_hasher();
+        let mut hasher = CachedHasher::<_, 1, 2>::new(&periodic, 0);
+        evaluate_batch_lanes(points, output, &mut hasher, perlin_1d, perlin_1d_lanes);
+    }
+}
+
+// 1/(sqrt(N)/2), N=1 -> 2
+const SCALE_FACTOR_1D: f64 = 2.0;
+
+#[inline(always)]
+pub(crate) fn perlin_1d<H>(hasher: &mut H, point: [f64; 1]) -> f64
+where
+    H: CellHasher<1>,
+{
+    #[inline(always)]
+    fn gradient_dot_v(perm: usize, point: f64) -> f64 {
+        GRADIENTS_1D[perm & 0b1][0] * point
+    }
+
+    let floored = point[0].floor();
+    hasher.set_cell([floored as isize]);
+    let distance = point[0] - floored;
+
+    let g0 = gradient_dot_v(hasher.hash([0]), distance);
+    let g1 = gradient_dot_v(hasher.hash([1]), distance - 1.0);
+
+    let u = distance.map_quintic();
+
+    let unscaled_result = linear_interpolation(u, g0, g1);
+
+    let scaled_result = unscaled_result * SCALE_FACTOR_1D;
+
+    // Clamp away any accumulated float errors, as in 2D.
+    scaled_result.clamp(-1.0, 1.0)
+}
+
+#[inline(always)]
+fn linear_interpolation<T>(u: T, g0: T, g1: T) -> T
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + Mul<Output = T>,
+{
+    let k0 = g0;
+    let k1 = g1 - g0;
+
+    k0 + k1 * u
+}
+
 /// 2-dimensional perlin noise
-impl NoiseFn<f64, 2> for Perlin {
+impl<H: NoiseHasher> NoiseFn<f64, 2> for Perlin<H> {
     fn get(&self, point: [f64; 2]) -> f64 {
-        perlin_2d(&self.perm_table, point)
+        perlin_2d(&mut DirectHasher::new(&self.periodic_hasher()), point)
+    }
+
+    fn get_batch(&self, points: &[[f64; 2]], output: &mut [f64]) {
+        let periodic = self.periodic_hasher();
+        let mut hasher = CachedHasher::<_, 2, 4>::new(&periodic, 0);
+        evaluate_batch_lanes(points, output, &mut hasher, perlin_2d, perlin_2d_lanes);
     }
 }
 
-#[inline(always)]
-pub(crate) fn perlin_2d(hasher: &dyn NoiseHasher, point: [f64; 2]) -> f64 {
-    // Unscaled range of linearly interpolated perlin noise should be (-sqrt(N)/2, sqrt(N)/2).
-    // Need to invert this value and multiply the unscaled result by the value to get a scaled
-    // range of (-1, 1).
-    //
-    // 1/(sqrt(N)/2), N=2 -> sqrt(2)
-    const SCALE_FACTOR: f64 = std::f64::consts::SQRT_2;
+// Unscaled range of linearly interpolated perlin noise should be (-sqrt(N)/2, sqrt(N)/2).
+// Need to invert this value and multiply the unscaled result by the value to get a scaled
+// range of (-1, 1).
+//
+// 1/(sqrt(N)/2), N=2 -> sqrt(2)
+const SCALE_FACTOR_2D: f64 = std::f64::consts::SQRT_2;
 
+#[inline(always)]
+pub(crate) fn perlin_2d<H>(hasher: &mut H, point: [f64; 2]) -> f64
+where
+    H: CellHasher<2>,
+{
     #[inline(always)]
-    #[rustfmt::skip]
     fn gradient_dot_v(perm: usize, point: [f64; 2]) -> f64 {
-        let [x, y] = point;
-
-        match perm & 0b11 {
-            0 =>  x + y, // ( 1,  1)
-            1 => -x + y, // (-1,  1)
-            2 =>  x - y, // ( 1, -1)
-            3 => -x - y, // (-1, -1)
-            _ => unreachable!(),
-        }
+        math::dot2(GRADIENTS_2D[perm & 0b11], point)
     }
 
     let floored = math::map2(point, f64::floor);
     let corner = math::to_isize2(floored);
-    let far_corner = math::add2(corner, [1; 2]);
+    hasher.set_cell(corner);
     let distance = math::sub2(point, floored);
     let far_distance = math::sub2(distance, [1.0; 2]);
 
-    let g00 = gradient_dot_v(hasher.hash(&corner), distance);
-    let g10 = gradient_dot_v(
-        hasher.hash(&[far_corner[0], corner[1]]),
-        [far_distance[0], distance[1]],
-    );
-    let g01 = gradient_dot_v(
-        hasher.hash(&[corner[0], far_corner[1]]),
-        [distance[0], far_distance[1]],
-    );
-    let g11 = gradient_dot_v(hasher.hash(&far_corner), far_distance);
+    let g00 = gradient_dot_v(hasher.hash([0, 0]), distance);
+    let g10 = gradient_dot_v(hasher.hash([1, 0]), [far_distance[0], distance[1]]);
+    let g01 = gradient_dot_v(hasher.hash([0, 1]), [distance[0], far_distance[1]]);
+    let g11 = gradient_dot_v(hasher.hash([1, 1]), far_distance);
 
     let [u, v] = distance.map_quintic();
 
     let unscaled_result = bilinear_interpolation(u, v, g00, g01, g10, g11);
 
-    let scaled_result = unscaled_result * SCALE_FACTOR;
+    let scaled_result = unscaled_result * SCALE_FACTOR_2D;
 
     // At this point, we should be really damn close to the (-1, 1) range, but some float errors
     // could have accumulated, so let's just clamp the results to (-1, 1) to cut off any
@@ -108,7 +213,10 @@ pub(crate) fn perlin_2d(hasher: &dyn NoiseHasher, point: [f64; 2]) -> f64 {
 }
 
 #[inline(always)]
-fn bilinear_interpolation(u: f64, v: f64, g00: f64, g01: f64, g10: f64, g11: f64) -> f64 {
+fn bilinear_interpolation<T>(u: T, v: T, g00: T, g01: T, g10: T, g11: T) -> T
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + Mul<Output = T>,
+{
     let k0 = g00;
     let k1 = g10 - g00;
     let k2 = g01 - g00;
@@ -118,81 +226,93 @@ fn bilinear_interpolation(u: f64, v: f64, g00: f64, g01: f64, g10: f64, g11: f64
 }
 
 /// 3-dimensional perlin noise
-impl NoiseFn<f64, 3> for Perlin {
+impl<H: NoiseHasher> NoiseFn<f64, 3> for Perlin<H> {
     fn get(&self, point: [f64; 3]) -> f64 {
-        perlin_3d(&self.perm_table, point)
+        perlin_3d(&mut DirectHasher::new(&self.periodic_hasher()), point)
+    }
+
+    fn get_batch(&self, points: &[[f64; 3]], output: &mut [f64]) {
+        let periodic = self.periodic_hasher();
+        let mut hasher = CachedHasher::<_, 3, 8>::new(&periodic, 0);
+        evaluate_batch_lanes(points, output, &mut hasher, perlin_3d, perlin_3d_lanes);
     }
 }
 
+// Unscaled range of linearly interpolated perlin noise should be (-sqrt(N)/2, sqrt(N)/2).
+// Need to invert this value and multiply the unscaled result by the value to get a scaled
+// range of (-1, 1).
+//
+// 1/(sqrt(N)/2), N=3 -> 2/sqrt(3)
+// sqrt() is not a const function, so use a high-precision value instead.
+// TODO: Replace fixed const values with const fn if sqrt() ever becomes a const function.
+// 2/sqrt(3) = 1.1547005383792515290182975610039149112952035025402537520372046529
+const SCALE_FACTOR_3D: f64 = 1.154_700_538_379_251_5;
+
 #[inline(always)]
 #[allow(clippy::many_single_char_names)]
-pub(crate) fn perlin_3d(hasher: &dyn NoiseHasher, point: [f64; 3]) -> f64 {
-    // Unscaled range of linearly interpolated perlin noise should be (-sqrt(N)/2, sqrt(N)/2).
-    // Need to invert this value and multiply the unscaled result by the value to get a scaled
-    // range of (-1, 1).
-    //
-    // 1/(sqrt(N)/2), N=3 -> 2/sqrt(3)
-    // sqrt() is not a const function, so use a high-precision value instead.
-    // TODO: Replace fixed const values with const fn if sqrt() ever becomes a const function.
-    // 2/sqrt(3) = 1.1547005383792515290182975610039149112952035025402537520372046529
-    const SCALE_FACTOR: f64 = 1.154_700_538_379_251_5;
-
+pub(crate) fn perlin_3d<H>(hasher: &mut H, point: [f64; 3]) -> f64
+where
+    H: CellHasher<3>,
+{
     #[inline(always)]
-    #[rustfmt::skip]
     fn gradient_dot_v(perm: usize, point: [f64; 3]) -> f64 {
-        let [x, y, z] = point;
-
-        match perm & 0b1111 {
-            0  | 12 =>  x + y    , // ( 1,  1,  0)
-            1  | 13 => -x + y    , // (-1,  1,  0)
-            2       =>  x - y    , // ( 1, -1,  0)
-            3       => -x - y    , // (-1, -1,  0)
-            4       =>  x     + z, // ( 1,  0,  1)
-            5       => -x     + z, // (-1,  0,  1)
-            6       =>  x     - z, // ( 1,  0, -1)
-            7       => -x     - z, // (-1,  0, -1)
-            8       =>      y + z, // ( 0,  1,  1)
-            9  | 14 =>     -y + z, // ( 0, -1,  1)
-            10      =>      y - z, // ( 0,  1, -1)
-            11 | 15 =>     -y - z, // ( 0, -1, -1)
-            _ => unreachable!(),
-        }
+        math::dot3(GRADIENTS_3D[perm & 0b1111], point)
     }
 
     let floored = math::map3(point, f64::floor);
     let corner = math::to_isize3(floored);
-    let far_corner = math::add3(corner, [1; 3]);
+    hasher.set_cell(corner);
     let distance = math::sub3(point, floored);
     let far_distance = math::sub3(distance, [1.0; 3]);
 
-    let g000 = gradient_dot_v(hasher.hash(&corner), distance);
+    let g000 = gradient_dot_v(hasher.hash([0, 0, 0]), distance);
     let g100 = gradient_dot_v(
-        hasher.hash(&[far_corner[0], corner[1], corner[2]]),
+        hasher.hash([1, 0, 0]),
         [far_distance[0], distance[1], distance[2]],
     );
     let g010 = gradient_dot_v(
-        hasher.hash(&[corner[0], far_corner[1], corner[2]]),
+        hasher.hash([0, 1, 0]),
         [distance[0], far_distance[1], distance[2]],
     );
     let g110 = gradient_dot_v(
-        hasher.hash(&[far_corner[0], far_corner[1], corner[2]]),
+        hasher.hash([1, 1, 0]),
         [far_distance[0], far_distance[1], distance[2]],
     );
     let g001 = gradient_dot_v(
-        hasher.hash(&[corner[0], corner[1], far_corner[2]]),
+        hasher.hash([0, 0, 1]),
         [distance[0], distance[1], far_distance[2]],
     );
     let g101 = gradient_dot_v(
-        hasher.hash(&[far_corner[0], corner[1], far_corner[2]]),
+        hasher.hash([1, 0, 1]),
         [far_distance[0], distance[1], far_distance[2]],
     );
     let g011 = gradient_dot_v(
-        hasher.hash(&[corner[0], far_corner[1], far_corner[2]]),
+        hasher.hash([0, 1, 1]),
         [distance[0], far_distance[1], far_distance[2]],
     );
-    let g111 = gradient_dot_v(hasher.hash(&far_corner), far_distance);
+    let g111 = gradient_dot_v(hasher.hash([1, 1, 1]), far_distance);
 
-    let [a, b, c] = distance.map_quintic();
+    let unscaled_result = trilinear_interpolation(
+        distance.map_quintic(),
+        [g000, g100, g010, g110, g001, g101, g011, g111],
+    );
+
+    let scaled_result = unscaled_result * SCALE_FACTOR_3D;
+
+    // At this point, we should be really damn close to the (-1, 1) range, but some float errors
+    // could have accumulated, so let's just clamp the results to (-1, 1) to cut off any
+    // outliers and return it.
+    scaled_result.clamp(-1.0, 1.0)
+}
+
+#[inline(always)]
+#[allow(clippy::many_single_char_names)]
+fn trilinear_interpolation<T>(weights: [T; 3], corners: [T; 8]) -> T
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + Mul<Output = T>,
+{
+    let [a, b, c] = weights;
+    let [g000, g100, g010, g110, g001, g101, g011, g111] = corners;
 
     let k0 = g000;
     let k1 = g100 - g000;
@@ -203,257 +323,197 @@ pub(crate) fn perlin_3d(hasher: &dyn NoiseHasher, point: [f64; 3]) -> f64 {
     let k6 = g000 + g011 - g010 - g001;
     let k7 = g100 + g010 + g001 + g111 - g000 - g110 - g101 - g011;
 
-    let unscaled_result =
-        k0 + k1 * a + k2 * b + k3 * c + k4 * a * b + k5 * a * c + k6 * b * c + k7 * a * b * c;
-
-    let scaled_result = unscaled_result * SCALE_FACTOR;
-
-    // At this point, we should be really damn close to the (-1, 1) range, but some float errors
-    // could have accumulated, so let's just clamp the results to (-1, 1) to cut off any
-    // outliers and return it.
-    scaled_result.clamp(-1.0, 1.0)
+    k0 + k1 * a + k2 * b + k3 * c + k4 * a * b + k5 * a * c + k6 * b * c + k7 * a * b * c
 }
 
 /// 4-dimensional perlin noise
-impl NoiseFn<f64, 4> for Perlin {
+impl<H: NoiseHasher> NoiseFn<f64, 4> for Perlin<H> {
     fn get(&self, point: [f64; 4]) -> f64 {
-        perlin_4d(&self.perm_table, point)
+        perlin_4d(&mut DirectHasher::new(&self.periodic_hasher()), point)
+    }
+
+    fn get_batch(&self, points: &[[f64; 4]], output: &mut [f64]) {
+        let periodic = self.periodic_hasher();
+        let mut hasher = CachedHasher::<_, 4, 16>::new(&periodic, 0);
+        evaluate_batch_lanes(points, output, &mut hasher, perlin_4d, perlin_4d_lanes);
+    }
+}
+
+/// perlin noise at `f32` coordinates
+impl<H, const DIM: usize> NoiseFn<f32, DIM> for Perlin<H>
+where
+    Self: NoiseFn<f64, DIM>,
+{
+    fn get(&self, point: [f32; DIM]) -> f64 {
+        get_widened(self, point)
+    }
+
+    fn get_batch(&self, points: &[[f32; DIM]], output: &mut [f64]) {
+        evaluate_batch_widened(self, points, output);
     }
 }
 
+// Unscaled range of linearly interpolated perlin noise should be (-sqrt(N)/2, sqrt(N)/2).
+// Need to invert this value and multiply the unscaled result by the value to get a scaled
+// range of (-1, 1).
+const SCALE_FACTOR_4D: f64 = 1.0; // 1/(sqrt(N)/2), N=4 -> 2/sqrt(4) -> 2/2 -> 1
+
 #[inline(always)]
 #[rustfmt::skip]
 #[allow(clippy::many_single_char_names)]
-pub(crate) fn perlin_4d(hasher: &dyn NoiseHasher, point: [f64; 4]) -> f64 {
-    // Unscaled range of linearly interpolated perlin noise should be (-sqrt(N)/2, sqrt(N)/2).
-    // Need to invert this value and multiply the unscaled result by the value to get a scaled
-    // range of (-1, 1).
-    const SCALE_FACTOR: f64 = 1.0; // 1/(sqrt(N)/2), N=4 -> 2/sqrt(4) -> 2/2 -> 1
-
+pub(crate) fn perlin_4d<H>(hasher: &mut H, point: [f64; 4]) -> f64
+where
+    H: CellHasher<4>,
+{
     #[inline(always)]
     fn gradient_dot_v(perm: usize, point: [f64; 4]) -> f64 {
-        let [x, y, z, w] = point;
-
-        match perm & 0b11111 {
-            0  | 28 =>  x + y + z    , // ( 1,  1,  1,  0)
-            1       => -x + y + z    , // (-1,  1,  1,  0)
-            2       =>  x - y + z    , // ( 1, -1,  1,  0)
-            3       =>  x + y - z    , // ( 1,  1, -1,  0)
-            4       => -x + y - z    , // (-1,  1, -1,  0)
-            5       =>  x - y - z    , // ( 1, -1, -1,  0)
-            6       =>  x - y - z    , // (-1, -1, -1,  0)
-            7  | 29 =>  x + y     + w, // ( 1,  1,  0,  1)
-            8       => -x + y     + w, // (-1,  1,  0,  1)
-            9       =>  x - y     + w, // ( 1, -1,  0,  1)
-            10      =>  x + y     - w, // ( 1,  1,  0, -1)
-            11      =>  x + y     - w, // (-1,  1,  0, -1)
-            12      =>  x + y     - w, // ( 1, -1,  0, -1)
-            13      => -x - y     - w, // (-1, -1,  0, -1)
-            14 | 30 =>  x     + z + w, // ( 1,  0,  1,  1)
-            15      => -x     + z + w, // (-1,  0,  1,  1)
-            16      =>  x     - z + w, // ( 1,  0, -1,  1)
-            17      =>  x     + z - w, // ( 1,  0,  1, -1)
-            18      =>  x     + z - w, // (-1,  0,  1, -1)
-            19      =>  x     + z - w, // ( 1,  0, -1, -1)
-            20      => -x     - z - w, // (-1,  0, -1, -1)
-            21 | 31 =>      y + z + w, // ( 0,  1,  1,  1)
-            22      =>     -y + z + w, // ( 0, -1,  1,  1)
-            23      =>      y - z + w, // ( 0,  1, -1,  1)
-            24      =>      y - z - w, // ( 0,  1,  1, -1)
-            25      =>     -y - z - w, // ( 0, -1,  1, -1)
-            26      =>  x + y + z - w, // ( 0,  1, -1, -1)
-            27      => -x + y + z - w, // ( 0, -1, -1, -1)
-            _ => unreachable!(),
-        }
+        math::dot4(GRADIENTS_4D[perm & 0b11111], point)
     }
 
     let floored = math::map4(point, f64::floor);
     let corner = math::to_isize4(floored);
-    let far_corner = math::add4(corner, [1; 4]);
+    hasher.set_cell(corner);
     let distance = math::sub4(point, floored);
     let far_distance = math::sub4(distance, [1.0; 4]);
 
     let g0000 = gradient_dot_v(
-        hasher.hash(&corner),
+        hasher.hash([0, 0, 0, 0]),
         distance,
     );
     let g1000 = gradient_dot_v(
-        hasher.hash(&[
-            far_corner[0],
-            corner[1],
-            corner[2],
-            corner[3],
-        ]),
+        hasher.hash([1, 0, 0, 0]),
         [far_distance[0],
         distance[1],
         distance[2],
         distance[3]],
     );
     let g0100 = gradient_dot_v(
-        hasher.hash(&[
-            corner[0],
-            far_corner[1],
-            corner[2],
-            corner[3],
-        ]),
+        hasher.hash([0, 1, 0, 0]),
         [distance[0],
         far_distance[1],
         distance[2],
         distance[3]],
     );
     let g1100 = gradient_dot_v(
-        hasher.hash(&[
-            far_corner[0],
-            far_corner[1],
-            corner[2],
-            corner[3]
-        ]),
+        hasher.hash([1, 1, 0, 0]),
         [far_distance[0],
         far_distance[1],
         distance[2],
         distance[3]],
     );
     let g0010 = gradient_dot_v(
-        hasher.hash(&[
-            corner[0],
-            corner[1],
-            far_corner[2],
-            corner[3],
-        ]),
+        hasher.hash([0, 0, 1, 0]),
         [distance[0],
         distance[1],
         far_distance[2],
         distance[3]],
     );
     let g1010 = gradient_dot_v(
-        hasher.hash(&[
-            far_corner[0],
-            corner[1],
-            far_corner[2],
-            corner[3]
-        ]),
+        hasher.hash([1, 0, 1, 0]),
         [far_distance[0],
         distance[1],
         far_distance[2],
         distance[3]],
     );
     let g0110 = gradient_dot_v(
-        hasher.hash(&[
-            corner[0],
-            far_corner[1],
-            far_corner[2],
-            corner[3]
-        ]),
+        hasher.hash([0, 1, 1, 0]),
         [distance[0],
         far_distance[1],
         far_distance[2],
         distance[3]],
     );
     let g1110 = gradient_dot_v(
-        hasher.hash(&[
-            far_corner[0],
-            far_corner[1],
-            far_corner[2],
-            corner[3]
-        ]),
+        hasher.hash([1, 1, 1, 0]),
         [far_distance[0],
         far_distance[1],
         far_distance[2],
         distance[3]],
     );
     let g0001 = gradient_dot_v(
-        hasher.hash(&[
-            corner[0],
-            corner[1],
-            corner[2],
-            far_corner[3],
-        ]),
+        hasher.hash([0, 0, 0, 1]),
         [distance[0],
         distance[1],
         distance[2],
         far_distance[3]],
     );
     let g1001 = gradient_dot_v(
-        hasher.hash(&[
-            far_corner[0],
-            corner[1],
-            corner[2],
-            far_corner[3]
-        ]),
+        hasher.hash([1, 0, 0, 1]),
         [far_distance[0],
         distance[1],
         distance[2],
         far_distance[3]],
     );
     let g0101 = gradient_dot_v(
-        hasher.hash(&[
-            corner[0],
-            far_corner[1],
-            corner[2],
-            far_corner[3]
-        ]),
+        hasher.hash([0, 1, 0, 1]),
         [distance[0],
         far_distance[1],
         distance[2],
         far_distance[3]],
     );
     let g1101 = gradient_dot_v(
-        hasher.hash(&[
-            far_corner[0],
-            far_corner[1],
-            corner[2],
-            far_corner[3]
-        ]),
+        hasher.hash([1, 1, 0, 1]),
         [far_distance[0],
         far_distance[1],
         distance[2],
         far_distance[3]],
     );
     let g0011 = gradient_dot_v(
-        hasher.hash(&[
-            corner[0],
-            corner[1],
-            far_corner[2],
-            far_corner[3]
-        ]),
+        hasher.hash([0, 0, 1, 1]),
         [distance[0],
         distance[1],
         far_distance[2],
         far_distance[3]],
     );
     let g1011 = gradient_dot_v(
-        hasher.hash(&[
-            far_corner[0],
-            corner[1],
-            far_corner[2],
-            far_corner[3]
-        ]),
+        hasher.hash([1, 0, 1, 1]),
         [far_distance[0],
         distance[1],
         far_distance[2],
         far_distance[3]],
     );
     let g0111 = gradient_dot_v(
-        hasher.hash(&[
-            corner[0],
-            far_corner[1],
-            far_corner[2],
-            far_corner[3]
-        ]),
+        hasher.hash([0, 1, 1, 1]),
         [distance[0],
         far_distance[1],
         far_distance[2],
         far_distance[3]],
     );
     let g1111 = gradient_dot_v(
-        hasher.hash(&far_corner),
+        hasher.hash([1, 1, 1, 1]),
         [far_distance[0],
         far_distance[1],
         far_distance[2],
         far_distance[3]],
     );
 
-    let [a, b, c, d] = distance.map_quintic();
+    let unscaled_result = quadrilinear_interpolation(
+        distance.map_quintic(),
+        [
+            g0000, g1000, g0100, g1100, g0010, g1010, g0110, g1110,
+            g0001, g1001, g0101, g1101, g0011, g1011, g0111, g1111,
+        ],
+    );
+
+    let scaled_result = unscaled_result * SCALE_FACTOR_4D;
+
+    // At this point, we should be really damn close to the (-1, 1) range, but some float errors
+    // could have accumulated, so let's just clamp the results to (-1, 1) to cut off any
+    // outliers and return it.
+    scaled_result.clamp(-1.0, 1.0)
+}
+
+#[inline(always)]
+#[rustfmt::skip]
+#[allow(clippy::many_single_char_names)]
+fn quadrilinear_interpolation<T>(weights: [T; 4], corners: [T; 16]) -> T
+where
+    T: Copy + Add<Output = T> + Sub<Output = T> + Mul<Output = T>,
+{
+    let [a, b, c, d] = weights;
+    let [
+        g0000, g1000, g0100, g1100, g0010, g1010, g0110, g1110,
+        g0001, g1001, g0101, g1101, g0011, g1011, g0111, g1111,
+    ] = corners;
 
     let k0 = g0000;
     let k1 = g1000 - g0000;
@@ -472,7 +532,7 @@ pub(crate) fn perlin_4d(hasher: &dyn NoiseHasher, point: [f64; 4]) -> f64 {
     let k14 = g0111 + g0100 + g0010 + g0001 - g0000 - g1011 - g1101 - g1110;
     let k15 = g1111 + g1000 + g0100 + g0010 + g0001 - g0000 - g0111 - g1011 - g1101 - g1110;
 
-    let unscaled_result = k0
+    k0
         + k1 * a
         + k2 * b
         + k3 * c
@@ -487,12 +547,200 @@ pub(crate) fn perlin_4d(hasher: &dyn NoiseHasher, point: [f64; 4]) -> f64 {
         + k12 * a * b * d
         + k13 * a * c * d
         + k14 * b * c * d
-        + k15 * a * b * c * d;
+        + k15 * a * b * c * d
+}
 
-    let scaled_result = unscaled_result * SCALE_FACTOR;
+#[inline(always)]
+fn perlin_1d_lanes<H: CellHasher<1>>(hasher: &mut H, points: &[[f64; 1]; LANES]) -> Lanes {
+    let ([g0, g1], [u]) = perlin_lanes(hasher, points, &GRADIENTS_1D);
+    let unscaled_result = linear_interpolation(u, g0, g1);
 
-    // At this point, we should be really damn close to the (-1, 1) range, but some float errors
-    // could have accumulated, so let's just clamp the results to (-1, 1) to cut off any
-    // outliers and return it.
-    scaled_result.clamp(-1.0, 1.0)
+    (unscaled_result * SCALE_FACTOR_1D).map(|value| value.clamp(-1.0, 1.0))
+}
+
+#[inline(always)]
+fn perlin_2d_lanes<H: CellHasher<2>>(hasher: &mut H, points: &[[f64; 2]; LANES]) -> Lanes {
+    let ([g00, g10, g01, g11], [u, v]) = perlin_lanes(hasher, points, &GRADIENTS_2D);
+    let unscaled_result = bilinear_interpolation(u, v, g00, g01, g10, g11);
+
+    (unscaled_result * SCALE_FACTOR_2D).map(|value| value.clamp(-1.0, 1.0))
+}
+
+#[inline(always)]
+fn perlin_3d_lanes<H: CellHasher<3>>(hasher: &mut H, points: &[[f64; 3]; LANES]) -> Lanes {
+    let (corners, weights) = perlin_lanes(hasher, points, &GRADIENTS_3D);
+    let unscaled_result = trilinear_interpolation(weights, corners);
+
+    (unscaled_result * SCALE_FACTOR_3D).map(|value| value.clamp(-1.0, 1.0))
 }
+
+#[inline(always)]
+fn perlin_4d_lanes<H: CellHasher<4>>(hasher: &mut H, points: &[[f64; 4]; LANES]) -> Lanes {
+    let (corners, weights) = perlin_lanes(hasher, points, &GRADIENTS_4D);
+    let unscaled_result = quadrilinear_interpolation(weights, corners);
+
+    (unscaled_result * SCALE_FACTOR_4D).map(|value| value.clamp(-1.0, 1.0))
+}
+
+/// Computes the gradient contribution of every corner of the cell containing each of `LANES`
+/// points, ordered as by [`lanes::corner_offset`], along with the interpolation weights.
+///
+/// `CORNERS` must equal `2^DIM`.
+#[inline(always)]
+fn perlin_lanes<H, const DIM: usize, const CORNERS: usize>(
+    hasher: &mut H,
+    points: &[[f64; DIM]; LANES],
+    gradients: &[[f64; DIM]],
+) -> ([Lanes; CORNERS], [Lanes; DIM])
+where
+    H: CellHasher<DIM>,
+{
+    let (cells, distance) = lanes::split_cells(points);
+
+    // Hashing is inherently scalar, so the gradients are looked up one lane at a time.
+    let mut hashes = [[0; LANES]; CORNERS];
+    for (lane, &cell) in cells.iter().enumerate() {
+        hasher.set_cell(cell);
+        for (index, hashes) in hashes.iter_mut().enumerate() {
+            hashes[lane] = hasher.hash(lanes::corner_offset(index)) & (gradients.len() - 1);
+        }
+    }
+
+    let mut corners = [Lanes::default(); CORNERS];
+    for (index, (corner, hashes)) in corners.iter_mut().zip(hashes.iter()).enumerate() {
+        let offset = lanes::corner_offset::<DIM>(index);
+        for axis in 0..DIM {
+            let gradient = Lanes(hashes.map(|hash| gradients[hash][axis]));
+            let product = gradient * distance[axis].map(|d| d - offset[axis] as f64);
+            *corner = if axis == 0 {
+                product
+            } else {
+                *corner + product
+            };
+        }
+    }
+
+    let mut weights = distance;
+    for weight in weights.iter_mut() {
+        *weight = lanes::quintic(*weight);
+    }
+
+    (corners, weights)
+}
+
+/// 1-dimensional perlin noise, with its gradient
+impl<H: NoiseHasher> NoiseFnGradient<1> for Perlin<H> {
+    fn get_with_gradient(&self, point: [f64; 1]) -> (f64, [f64; 1]) {
+        let periodic = self.periodic_hasher();
+        let mut hasher = DirectHasher::new(&periodic);
+        let ([g0, g1], [u]) = perlin_gradient(&mut hasher, point, &GRADIENTS_1D);
+        let unscaled_result = linear_interpolation(u, g0, g1);
+
+        (unscaled_result * SCALE_FACTOR_1D)
+            .clamp(-1.0, 1.0)
+            .into_tuple()
+    }
+}
+
+/// 2-dimensional perlin noise, with its gradient
+impl<H: NoiseHasher> NoiseFnGradient<2> for Perlin<H> {
+    fn get_with_gradient(&self, point: [f64; 2]) -> (f64, [f64; 2]) {
+        let periodic = self.periodic_hasher();
+        let mut hasher = DirectHasher::new(&periodic);
+        let ([g00, g10, g01, g11], [u, v]) = perlin_gradient(&mut hasher, point, &GRADIENTS_2D);
+        let unscaled_result = bilinear_interpolation(u, v, g00, g01, g10, g11);
+
+        (unscaled_result * SCALE_FACTOR_2D)
+            .clamp(-1.0, 1.0)
+            .into_tuple()
+    }
+}
+
+/// 3-dimensional perlin noise, with its gradient
+impl<H: NoiseHasher> NoiseFnGradient<3> for Perlin<H> {
+    fn get_with_gradient(&self, point: [f64; 3]) -> (f64, [f64; 3]) {
+        let periodic = self.periodic_hasher();
+        let mut hasher = DirectHasher::new(&periodic);
+        let (corners, weights) = perlin_gradient(&mut hasher, point, &GRADIENTS_3D);
+        let unscaled_result = trilinear_interpolation(weights, corners);
+
+        (unscaled_result * SCALE_FACTOR_3D)
+            .clamp(-1.0, 1.0)
+            .into_tuple()
+    }
+}
+
+/// 4-dimensional perlin noise, with its gradient
+impl<H: NoiseHasher> NoiseFnGradient<4> for Perlin<H> {
+    fn get_with_gradient(&self, point: [f64; 4]) -> (f64, [f64; 4]) {
+        let periodic = self.periodic_hasher();
+        let mut hasher = DirectHasher::new(&periodic);
+        let (corners, weights) = perlin_gradient(&mut hasher, point, &GRADIENTS_4D);
+        let unscaled_result = quadrilinear_interpolation(weights, corners);
+
+        (unscaled_result * SCALE_FACTOR_4D)
+            .clamp(-1.0, 1.0)
+            .into_tuple()
+    }
+}
+
+/// Like [`perlin_lanes`], but for a single point, with the gradient of every corner contribution
+/// and weight.
+#[inline(always)]
+fn perlin_gradient<H, const DIM: usize, const CORNERS: usize>(
+    hasher: &mut H,
+    point: [f64; DIM],
+    gradients: &[[f64; DIM]],
+) -> ([Dual<DIM>; CORNERS], [Dual<DIM>; DIM])
+where
+    H: CellHasher<DIM>,
+{
+    let mut cell = [0; DIM];
+    let mut distance = point;
+    for axis in 0..DIM {
+        let floored = point[axis].floor();
+        cell[axis] = floored as isize;
+        distance[axis] = point[axis] - floored;
+    }
+    hasher.set_cell(cell);
+
+    let mut corners = [Dual::constant(0.0); CORNERS];
+    for (index, corner) in corners.iter_mut().enumerate() {
+        let offset = lanes::corner_offset::<DIM>(index);
+        let gradient = gradients[hasher.hash(offset) & (gradients.len() - 1)];
+        let mut value = 0.0;
+        for axis in 0..DIM {
+            value += gradient[axis] * (distance[axis] - offset[axis] as f64);
+        }
+        *corner = Dual::new(value, gradient);
+    }
+
+    (corners, dual::quintic(distance))
+}
+
+const GRADIENTS_1D: [[f64; 1]; 2] = [[1.0], [-1.0]];
+
+#[rustfmt::skip]
+const GRADIENTS_2D: [[f64; 2]; 4] = [
+    [ 1.0,  1.0], [-1.0,  1.0], [ 1.0, -1.0], [-1.0, -1.0],
+];
+
+#[rustfmt::skip]
+const GRADIENTS_3D: [[f64; 3]; 16] = [
+    [ 1.0,  1.0,  0.0], [-1.0,  1.0,  0.0], [ 1.0, -1.0,  0.0], [-1.0, -1.0,  0.0],
+    [ 1.0,  0.0,  1.0], [-1.0,  0.0,  1.0], [ 1.0,  0.0, -1.0], [-1.0,  0.0, -1.0],
+    [ 0.0,  1.0,  1.0], [ 0.0, -1.0,  1.0], [ 0.0,  1.0, -1.0], [ 0.0, -1.0, -1.0],
+    [ 1.0,  1.0,  0.0], [-1.0,  1.0,  0.0], [ 0.0, -1.0,  1.0], [ 0.0, -1.0, -1.0],
+];
+
+#[rustfmt::skip]
+const GRADIENTS_4D: [[f64; 4]; 32] = [
+    [ 1.0,  1.0,  1.0,  0.0], [-1.0,  1.0,  1.0,  0.0], [ 1.0, -1.0,  1.0,  0.0], [ 1.0,  1.0, -1.0,  0.0],
+    [-1.0,  1.0, -1.0,  0.0], [ 1.0, -1.0, -1.0,  0.0], [ 1.0, -1.0, -1.0,  0.0], [ 1.0,  1.0,  0.0,  1.0],
+    [-1.0,  1.0,  0.0,  1.0], [ 1.0, -1.0,  0.0,  1.0], [ 1.0,  1.0,  0.0, -1.0], [ 1.0,  1.0,  0.0, -1.0],
+    [ 1.0,  1.0,  0.0, -1.0], [-1.0, -1.0,  0.0, -1.0], [ 1.0,  0.0,  1.0,  1.0], [-1.0,  0.0,  1.0,  1.0],
+    [ 1.0,  0.0, -1.0,  1.0], [ 1.0,  0.0,  1.0, -1.0], [ 1.0,  0.0,  1.0, -1.0], [ 1.0,  0.0,  1.0, -1.0],
+    [-1.0,  0.0, -1.0, -1.0], [ 0.0,  1.0,  1.0,  1.0], [ 0.0, -1.0,  1.0,  1.0], [ 0.0,  1.0, -1.0,  1.0],
+    [ 0.0,  1.0, -1.0, -1.0], [ 0.0, -1.0, -1.0, -1.0], [ 1.0,  1.0,  1.0, -1.0], [-1.0,  1.0,  1.0, -1.0],
+    [ 1.0,  1.0,  1.0,  0.0], [ 1.0,  1.0,  0.0,  1.0], [ 1.0,  0.0,  1.0,  1.0], [ 0.0,  1.0,  1.0,  1.0],
+];
@@ -3,19 +3,24 @@ use crate::{
     noise_fns::{NoiseFn, Seedable},
     permutationtable::{NoiseHasher, PermutationTable},
 };
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
-/// Noise function that outputs 2/3/4-dimensional Perlin noise.
+/// Noise function that outputs 2/3/4-dimensional Perlin surflet noise.
 ///
-/// THis is a variant of original perlin noise, based on the principles of simplex noise to
+/// This is a variant of original perlin noise, based on the principles of simplex noise to
 /// calculate the values at a point using wavelets instead of interpolated gradients.
 #[derive(Clone, Copy, Debug)]
-pub struct Perlin {
-    seed: u32,
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "super::SeedParams"))]
+pub struct PerlinSurflet {
+    seed: u64,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
     perm_table: PermutationTable,
 }
 
-impl Perlin {
-    pub const DEFAULT_SEED: u32 = 0;
+impl PerlinSurflet {
+    pub const DEFAULT_SEED: u64 = 0;
 
     pub fn new() -> Self {
         Self {
@@ -25,15 +30,15 @@ impl Perlin {
     }
 }
 
-impl Default for Perlin {
+impl Default for PerlinSurflet {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl Seedable for Perlin {
+impl Seedable for PerlinSurflet {
     /// Sets the seed value for Perlin noise
-    fn set_seed(self, seed: u32) -> Self {
+    fn set_seed(self, seed: u64) -> Self {
         // If the new seed is the same as the current seed, just return self.
         if self.seed == seed {
             return self;
@@ -46,13 +51,13 @@ impl Seedable for Perlin {
         }
     }
 
-    fn seed(&self) -> u32 {
+    fn seed(&self) -> u64 {
         self.seed
     }
 }
 
 /// 2-dimensional perlin noise
-impl NoiseFn<f64, 2> for Perlin {
+impl NoiseFn<f64, 2> for PerlinSurflet {
     fn get(&self, point: [f64; 2]) -> f64 {
         const SCALE_FACTOR: f64 = 3.160_493_827_160_493_7;
 
@@ -68,9 +73,9 @@ impl NoiseFn<f64, 2> for Perlin {
 
         let floored = math::map2(point, f64::floor);
         let near_corner = math::to_isize2(floored);
-        let far_corner = math::add2(near_corner, math::one2());
+        let far_corner = math::add2(near_corner, math::const2(1));
         let near_distance = math::sub2(point, floored);
-        let far_distance = math::sub2(near_distance, math::one2());
+        let far_distance = math::sub2(near_distance, math::const2(1.0));
 
         let f00 = surflet(
             &self.perm_table,
@@ -99,7 +104,7 @@ impl NoiseFn<f64, 2> for Perlin {
 }
 
 /// 3-dimensional perlin noise
-impl NoiseFn<f64, 3> for Perlin {
+impl NoiseFn<f64, 3> for PerlinSurflet {
     fn get(&self, point: [f64; 3]) -> f64 {
         const SCALE_FACTOR: f64 = 3.889_855_325_553_107_4;
 
@@ -115,9 +120,9 @@ impl NoiseFn<f64, 3> for Perlin {
 
         let floored = math::map3(point, f64::floor);
         let near_corner = math::to_isize3(floored);
-        let far_corner = math::add3(near_corner, math::one3());
+        let far_corner = math::add3(near_corner, math::const3(1));
         let near_distance = math::sub3(point, floored);
-        let far_distance = math::sub3(near_distance, math::one3());
+        let far_distance = math::sub3(near_distance, math::const3(1.0));
 
         let f000 = surflet(
             &self.perm_table,
@@ -166,7 +171,7 @@ impl NoiseFn<f64, 3> for Perlin {
 }
 
 /// 4-dimensional perlin noise
-impl NoiseFn<f64, 4> for Perlin {
+impl NoiseFn<f64, 4> for PerlinSurflet {
     fn get(&self, point: [f64; 4]) -> f64 {
         const SCALE_FACTOR: f64 = 4.424_369_240_215_691;
 
@@ -182,9 +187,9 @@ impl NoiseFn<f64, 4> for Perlin {
 
         let floored = math::map4(point, f64::floor);
         let near_corner = math::to_isize4(floored);
-        let far_corner = math::add4(near_corner, math::one4());
+        let far_corner = math::add4(near_corner, math::const4(1));
         let near_distance = math::sub4(point, floored);
-        let far_distance = math::sub4(near_distance, math::one4());
+        let far_distance = math::sub4(near_distance, math::const4(1.0));
 
         let f0000 = surflet(
             &self.perm_table,
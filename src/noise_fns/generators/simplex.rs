@@ -1,8 +1,17 @@
 use crate::{
     gradient,
-    noise_fns::{NoiseFn, Seedable},
-    permutationtable::{NoiseHasher, PermutationTable},
+    math::lanes::{Lanes, LANES},
+    noise_fns::{
+        evaluate_batch, evaluate_batch_lanes, evaluate_batch_widened, get_widened, NoiseFn,
+        NoiseFnGradient, Periodic, Seedable,
+    },
+    permutationtable::{
+        expand_period, CachedHasher, CellHasher, DirectHasher, NoiseHasher, PeriodicHasher,
+        PermutationTable, SeedableHasher,
+    },
 };
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Noise function that outputs N-dimensional Simplex noise.
 ///
@@ -11,6 +20,7 @@ use crate::{
 ///
 /// This is Stefan Gustavson's original copyright notice:
 ///
+/// ```text
 /// /* sdnoise1234, Simplex noise with true analytic
 ///  * derivative in 1D to 4D.
 ///  *
@@ -28,56 +38,135 @@ use crate::{
 ///  * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the GNU
 ///  * General Public License for more details.
 ///  */
+/// ```
+///
+/// A [period](Periodic::set_period) only has an effect in 1D and 2D. The
+/// skewed lattice of 2D simplex noise doesn't repeat along the axes, so while a
+/// period is set, 2D noise is instead generated on a lattice that is only
+/// sheared along x, as in psrdnoise by Stefan Gustavson and Ian McEwan. Its
+/// features are a little larger, and it repeats along y every two cells, so
+/// the period of the y axis must be even.
 #[derive(Clone, Copy, Debug)]
-pub struct Simplex {
-    seed: u32,
-    hasher: PermutationTable,
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        try_from = "super::PeriodicParams",
+        bound(deserialize = "H: SeedableHasher")
+    )
+)]
+pub struct Simplex<H = PermutationTable> {
+    seed: u64,
+    period: [u32; 4],
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    hasher: H,
 }
 
 impl Simplex {
-    pub const DEFAULT_SEED: u32 = 0;
+    pub const DEFAULT_SEED: u64 = 0;
 
-    pub fn new(seed: u32) -> Self {
+    pub fn new(seed: u64) -> Self {
+        Self::from_seed(seed)
+    }
+}
+
+impl<H: SeedableHasher> Simplex<H> {
+    /// Creates the noise function with `seed`, hashing the lattice with any
+    /// [`SeedableHasher`] rather than the default [`PermutationTable`].
+    pub fn from_seed(seed: u64) -> Self {
         Simplex {
             seed,
-            hasher: PermutationTable::new(seed),
+            period: [0; 4],
+            hasher: H::from_seed(seed),
         }
     }
 }
 
+impl<H> Simplex<H> {
+    fn is_periodic_2d(&self) -> bool {
+        self.period[..2] != [0; 2]
+    }
+}
+
 impl Default for Simplex {
     fn default() -> Self {
-        Self::new(Self::DEFAULT_SEED)
+        Self::from_seed(Self::DEFAULT_SEED)
     }
 }
 
-impl Seedable for Simplex {
+impl<H: SeedableHasher> Seedable for Simplex<H> {
     /// Sets the seed value for Simplex noise
-    fn set_seed(self, seed: u32) -> Self {
+    fn set_seed(self, seed: u64) -> Self {
         // If the new seed is the same as the current seed, just return self.
         if self.seed == seed {
             return self;
         }
 
-        // Otherwise, regenerate the permutation table based on the new seed.
+        // Otherwise, regenerate the hasher based on the new seed.
         Simplex {
             seed,
-            hasher: PermutationTable::new(seed),
+            hasher: H::from_seed(seed),
+            ..self
         }
     }
 
-    fn seed(&self) -> u32 {
+    fn seed(&self) -> u64 {
         self.seed
     }
 }
 
+impl<H> Periodic for Simplex<H> {
+    /// Sets the period of each axis, in lattice cells. Only the 1D and 2D
+    /// noise repeat.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` has more than 4 axes, or if the period of the y axis
+    /// is odd.
+    fn set_period<const N: usize>(self, period: [u32; N]) -> Self {
+        let period = expand_period(period);
+        if let Err(message) = validate_period(period) {
+            panic!("{}", message);
+        }
+
+        Simplex { period, ..self }
+    }
+
+    fn period(&self) -> [u32; 4] {
+        self.period
+    }
+}
+
+/// Checks that the period of the y axis is even, which the periodic 2D lattice needs.
+fn validate_period(period: [u32; 4]) -> Result<(), String> {
+    if period[1] & 1 == 0 {
+        Ok(())
+    } else {
+        Err(format!(
+            "the period of the y axis of Simplex noise must be even, got {}",
+            period[1]
+        ))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<H: SeedableHasher> std::convert::TryFrom<super::PeriodicParams> for Simplex<H> {
+    type Error = String;
+
+    fn try_from(params: super::PeriodicParams) -> Result<Self, Self::Error> {
+        validate_period(params.period)?;
+
+        Ok(Self::from_seed(params.seed).set_period(params.period))
+    }
+}
+
 fn grad1(hash: u8) -> f64 {
     let h = hash & 15;
     let gx = (1 + (h & 7)) as f64; // Gradient value is one of 1.0, 2.0, ..., 8.0
-    match h & 8 {
-        0 => -gx,
-        1 => gx, // Make half of the gradients negative
-        _ => unreachable!(),
+    if h & 8 == 0 {
+        -gx
+    } else {
+        gx // Make half of the gradients negative
     }
 }
 
@@ -105,15 +194,27 @@ pub fn unskew_factor(n: usize) -> f64 {
 
 /// 1D Simplex Noise with Derivative
 #[inline(always)]
-pub fn simplex_1d(x: f64, hasher: &dyn NoiseHasher) -> (f64, f64) {
+pub fn simplex_1d<H>(x: f64, hasher: &H) -> (f64, f64)
+where
+    H: NoiseHasher + ?Sized,
+{
+    simplex_1d_impl(x, &mut DirectHasher::new(hasher))
+}
+
+#[inline(always)]
+fn simplex_1d_impl<H>(x: f64, hasher: &mut H) -> (f64, f64)
+where
+    H: CellHasher<1>,
+{
     let cell = x.floor() as isize;
+    hasher.set_cell([cell]);
 
     let near_distance = x - cell as f64;
     let far_distance = near_distance - 1.0;
 
     // Calculate gradient indexes for each corner
-    let gi0 = hasher.hash(&[cell]);
-    let gi1 = hasher.hash(&[cell + 1]);
+    let gi0 = hasher.hash([0]);
+    let gi1 = hasher.hash([1]);
 
     struct SurfletComponents {
         value: f64,
@@ -175,7 +276,18 @@ pub fn simplex_1d(x: f64, hasher: &dyn NoiseHasher) -> (f64, f64) {
 }
 
 #[inline(always)]
-pub fn simplex_2d(x: f64, y: f64, hasher: &dyn NoiseHasher) -> (f64, [f64; 2]) {
+pub fn simplex_2d<H>(x: f64, y: f64, hasher: &H) -> (f64, [f64; 2])
+where
+    H: NoiseHasher + ?Sized,
+{
+    simplex_2d_impl(x, y, &mut DirectHasher::new(hasher))
+}
+
+#[inline(always)]
+fn simplex_2d_impl<H>(x: f64, y: f64, hasher: &mut H) -> (f64, [f64; 2])
+where
+    H: CellHasher<2>,
+{
     let f2: f64 = skew_factor(2);
     let g2: f64 = unskew_factor(2);
 
@@ -185,6 +297,7 @@ pub fn simplex_2d(x: f64, y: f64, hasher: &dyn NoiseHasher) -> (f64, [f64; 2]) {
     let skewed_y = y + skew;
     let cell_x = skewed_x.floor() as isize;
     let cell_y = skewed_y.floor() as isize;
+    hasher.set_cell([cell_x, cell_y]);
 
     let unskew = (cell_x + cell_y) as f64 * g2;
     let unskewed_x = cell_x as f64 - unskew; /* Unskew the cell origin back to (x,y) space */
@@ -212,9 +325,9 @@ pub fn simplex_2d(x: f64, y: f64, hasher: &dyn NoiseHasher) -> (f64, [f64; 2]) {
     let y2 = distance_y - 1.0 + 2.0 * g2;
 
     // Calculate gradient indexes for each corner
-    let gi0 = hasher.hash(&[cell_x, cell_y]);
-    let gi1 = hasher.hash(&[cell_x + i1, cell_y + j1]);
-    let gi2 = hasher.hash(&[cell_x + 1, cell_y + 1]);
+    let gi0 = hasher.hash([0, 0]);
+    let gi1 = hasher.hash([i1, j1]);
+    let gi2 = hasher.hash([1, 1]);
 
     struct SurfletComponents {
         value: f64,
@@ -303,8 +416,85 @@ pub fn simplex_2d(x: f64, y: f64, hasher: &dyn NoiseHasher) -> (f64, [f64; 2]) {
     (noise, [dnoise_dx, dnoise_dy])
 }
 
+/// 2D Simplex noise with derivative, on a lattice that repeats along the axes.
+///
+/// The vertices of the lattice lie at `(i - j / 2, j)`, so that it maps onto itself when moved by
+/// a whole cell along x, or by two cells along y. Each vertex is wrapped into the period before
+/// it is hashed, which is why the period of the y axis must be even.
+#[inline(always)]
+fn periodic_simplex_2d<H>(point: [f64; 2], hasher: &H, period: [u32; 4]) -> (f64, [f64; 2])
+where
+    H: NoiseHasher + ?Sized,
+{
+    const RADIUS: f64 = 0.8;
+    const SCALE: f64 = 10.9;
+
+    let [x, y] = point;
+    let sheared_x = x + y * 0.5;
+    let cell_x = sheared_x.floor();
+    let cell_y = y.floor();
+
+    // Lower triangle: (0,0)->(1,0)->(1,1), upper triangle: (0,0)->(0,1)->(1,1)
+    let middle = if sheared_x - cell_x >= y - cell_y {
+        [1, 0]
+    } else {
+        [0, 1]
+    };
+
+    let mut noise = 0.0;
+    let mut derivative = [0.0; 2];
+    for offset in [[0, 0], middle, [1, 1]] {
+        let i = cell_x as isize + offset[0];
+        let j = cell_y as isize + offset[1];
+        let distance_x = x - (i as f64 - j as f64 * 0.5);
+        let distance_y = y - j as f64;
+
+        let t = RADIUS - (distance_x * distance_x + distance_y * distance_y);
+        if t <= 0.0 {
+            // No influence
+            continue;
+        }
+
+        // Twice the x coordinate of the vertex is an integer, so it can be wrapped exactly.
+        let wrap = |coordinate: isize, period: u32| {
+            if period == 0 {
+                coordinate
+            } else {
+                coordinate.rem_euclid(period as isize)
+            }
+        };
+        let wrapped_j = wrap(j, period[1]);
+        let wrapped_i = (wrap(2 * i - j, 2 * period[0]) + wrapped_j) / 2;
+
+        let [gradient_x, gradient_y] = gradient::grad2(hasher.hash(&[wrapped_i, wrapped_j]));
+        let gradient_dot = gradient_x * distance_x + gradient_y * distance_y;
+        let t2 = t * t;
+        let t4 = t2 * t2;
+
+        noise += t4 * gradient_dot;
+        derivative[0] += t4 * gradient_x - 8.0 * t2 * t * gradient_dot * distance_x;
+        derivative[1] += t4 * gradient_y - 8.0 * t2 * t * gradient_dot * distance_y;
+    }
+
+    (
+        SCALE * noise,
+        [SCALE * derivative[0], SCALE * derivative[1]],
+    )
+}
+
+#[inline(always)]
+pub fn simplex_3d<H>(x: f64, y: f64, z: f64, hasher: &H) -> (f64, [f64; 3])
+where
+    H: NoiseHasher + ?Sized,
+{
+    simplex_3d_impl(x, y, z, &mut DirectHasher::new(hasher))
+}
+
 #[inline(always)]
-pub fn simplex_3d(x: f64, y: f64, z: f64, hasher: &dyn NoiseHasher) -> (f64, [f64; 3]) {
+fn simplex_3d_impl<H>(x: f64, y: f64, z: f64, hasher: &mut H) -> (f64, [f64; 3])
+where
+    H: CellHasher<3>,
+{
     let f3 = skew_factor(3);
     let g3 = unskew_factor(3);
 
@@ -316,6 +506,7 @@ pub fn simplex_3d(x: f64, y: f64, z: f64, hasher: &dyn NoiseHasher) -> (f64, [f6
     let cell_x = skewed_x.floor() as isize;
     let cell_y = skewed_y.floor() as isize;
     let cell_z = skewed_z.floor() as isize;
+    hasher.set_cell([cell_x, cell_y, cell_z]);
 
     let unskew = (cell_x + cell_y + cell_z) as f64 * g3;
     let unskewed_x = cell_x as f64 - unskew; /* Unskew the cell origin back to (x,y,z) space */
@@ -369,10 +560,10 @@ pub fn simplex_3d(x: f64, y: f64, z: f64, hasher: &dyn NoiseHasher) -> (f64, [f6
     let z3 = distance_z - 1.0 + 3.0 * g3;
 
     // Calculate gradient indexes for each corner
-    let gi0 = hasher.hash(&[cell_x, cell_y, cell_z]);
-    let gi1 = hasher.hash(&[cell_x + i1, cell_y + j1, cell_z + k1]);
-    let gi2 = hasher.hash(&[cell_x + i2, cell_y + j2, cell_z + k2]);
-    let gi3 = hasher.hash(&[cell_x + 1, cell_y + 1, cell_z + 1]);
+    let gi0 = hasher.hash([0, 0, 0]);
+    let gi1 = hasher.hash([i1, j1, k1]);
+    let gi2 = hasher.hash([i2, j2, k2]);
+    let gi3 = hasher.hash([1, 1, 1]);
 
     struct SurfletComponents {
         value: f64,
@@ -496,9 +687,20 @@ pub fn simplex_3d(x: f64, y: f64, z: f64, hasher: &dyn NoiseHasher) -> (f64, [f6
     (noise, [dnoise_dx, dnoise_dy, dnoise_dz])
 }
 
+#[inline(always)]
+pub fn simplex_4d<H>(x: f64, y: f64, z: f64, w: f64, hasher: &H) -> (f64, [f64; 4])
+where
+    H: NoiseHasher + ?Sized,
+{
+    simplex_4d_impl(x, y, z, w, &mut DirectHasher::new(hasher))
+}
+
 #[allow(clippy::many_single_char_names)]
 #[inline(always)]
-pub fn simplex_4d(x: f64, y: f64, z: f64, w: f64, hasher: &dyn NoiseHasher) -> (f64, [f64; 4]) {
+fn simplex_4d_impl<H>(x: f64, y: f64, z: f64, w: f64, hasher: &mut H) -> (f64, [f64; 4])
+where
+    H: CellHasher<4>,
+{
     let f4 = skew_factor(4);
     let g4 = unskew_factor(4);
 
@@ -512,6 +714,7 @@ pub fn simplex_4d(x: f64, y: f64, z: f64, w: f64, hasher: &dyn NoiseHasher) -> (
     let cell_y = skewed_y.floor() as isize;
     let cell_z = skewed_z.floor() as isize;
     let cell_w = skewed_w.floor() as isize;
+    hasher.set_cell([cell_x, cell_y, cell_z, cell_w]);
 
     let unskew = (cell_x + cell_y + cell_z + cell_w) as f64 * g4; // Factor for 4D unskewing
     let unskewed_x = cell_x as f64 - unskew; // Unskew the cell origin back to (x,y,z,w) space
@@ -583,11 +786,11 @@ pub fn simplex_4d(x: f64, y: f64, z: f64, w: f64, hasher: &dyn NoiseHasher) -> (
     let w4 = distance_w - 1.0 + 4.0 * g4;
 
     // Calculate gradient indexes for each corner
-    let gi0 = hasher.hash(&[cell_x, cell_y, cell_z, cell_w]);
-    let gi1 = hasher.hash(&[cell_x + i1, cell_y + j1, cell_z + k1, cell_w + l1]);
-    let gi2 = hasher.hash(&[cell_x + i2, cell_y + j2, cell_z + k2, cell_w + l2]);
-    let gi3 = hasher.hash(&[cell_x + i3, cell_y + j3, cell_z + k3, cell_w + l3]);
-    let gi4 = hasher.hash(&[cell_x + 1, cell_y + 1, cell_z + 1, cell_w + 1]);
+    let gi0 = hasher.hash([0, 0, 0, 0]);
+    let gi1 = hasher.hash([i1, j1, k1, l1]);
+    let gi2 = hasher.hash([i2, j2, k2, l2]);
+    let gi3 = hasher.hash([i3, j3, k3, l3]);
+    let gi4 = hasher.hash([1, 1, 1, 1]);
 
     struct SurfletComponents {
         value: f64,
@@ -751,10 +954,10 @@ pub fn simplex_4d(x: f64, y: f64, z: f64, w: f64, hasher: &dyn NoiseHasher) -> (
         + corner3.t4 * corner3.gradient_w
         + corner4.t4 * corner4.gradient_w;
 
-    dnoise_dx *= 28.0; /* Scale derivative to match the noise scaling */
-    dnoise_dy *= 28.0;
-    dnoise_dz *= 28.0;
-    dnoise_dw *= 28.0;
+    dnoise_dx *= 27.0; /* Scale derivative to match the noise scaling */
+    dnoise_dy *= 27.0;
+    dnoise_dz *= 27.0;
+    dnoise_dw *= 27.0;
 
     (noise, [dnoise_dx, dnoise_dy, dnoise_dz, dnoise_dw])
 }
@@ -774,29 +977,314 @@ const SIMPLEX: [[u8; 4]; 64] = [
     [2, 1, 0, 3], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [3, 1, 0, 2], [0, 0, 0, 0], [3, 2, 0, 1], [3, 2, 1, 0],
 ];
 
+/// 1-dimensional Simplex noise
+impl<H: NoiseHasher> NoiseFn<f64, 1> for Simplex<H> {
+    fn get(&self, point: [f64; 1]) -> f64 {
+        let (result, _) = simplex_1d(point[0], &PeriodicHasher::new(&self.hasher, self.period));
+
+        result
+    }
+
+    fn get_batch(&self, points: &[[f64; 1]], output: &mut [f64]) {
+        let periodic = PeriodicHasher::new(&self.hasher, self.period);
+        let mut hasher = CachedHasher::<_, 1, 2>::new(&periodic, 0);
+        evaluate_batch(points, output, |point| {
+            let (result, _) = simplex_1d_impl(point[0], &mut hasher);
+
+            result
+        });
+    }
+}
+
 /// 2-dimensional Simplex noise
-impl NoiseFn<f64, 2> for Simplex {
+impl<H: NoiseHasher> NoiseFn<f64, 2> for Simplex<H> {
     fn get(&self, point: [f64; 2]) -> f64 {
-        let (result, _) = simplex_2d(point[0], point[1], &self.hasher);
+        let (result, _) = self.get_with_gradient(point);
 
         result
     }
+
+    fn get_batch(&self, points: &[[f64; 2]], output: &mut [f64]) {
+        if self.is_periodic_2d() {
+            evaluate_batch(points, output, |point| {
+                let (result, _) = periodic_simplex_2d(point, &self.hasher, self.period);
+
+                result
+            });
+            return;
+        }
+
+        let mut hasher = CachedHasher::<_, 2, 4>::new(&self.hasher, 0);
+        evaluate_batch_lanes(
+            points,
+            output,
+            &mut hasher,
+            |hasher, point| {
+                let (result, _) = simplex_2d_impl(point[0], point[1], hasher);
+
+                result
+            },
+            simplex_2d_lanes,
+        );
+    }
 }
 
 /// 3-dimensional Simplex noise
-impl NoiseFn<f64, 3> for Simplex {
+impl<H: NoiseHasher> NoiseFn<f64, 3> for Simplex<H> {
     fn get(&self, point: [f64; 3]) -> f64 {
         let (result, _) = simplex_3d(point[0], point[1], point[2], &self.hasher);
 
         result
     }
+
+    fn get_batch(&self, points: &[[f64; 3]], output: &mut [f64]) {
+        let mut hasher = CachedHasher::<_, 3, 8>::new(&self.hasher, 0);
+        evaluate_batch_lanes(
+            points,
+            output,
+            &mut hasher,
+            |hasher, point| {
+                let (result, _) = simplex_3d_impl(point[0], point[1], point[2], hasher);
+
+                result
+            },
+            simplex_3d_lanes,
+        );
+    }
 }
 
 /// 4-dimensional Simplex noise
-impl NoiseFn<f64, 4> for Simplex {
+impl<H: NoiseHasher> NoiseFn<f64, 4> for Simplex<H> {
     fn get(&self, point: [f64; 4]) -> f64 {
         let (result, _) = simplex_4d(point[0], point[1], point[2], point[3], &self.hasher);
 
         result
     }
+
+    fn get_batch(&self, points: &[[f64; 4]], output: &mut [f64]) {
+        let mut hasher = CachedHasher::<_, 4, 16>::new(&self.hasher, 0);
+        evaluate_batch_lanes(
+            points,
+            output,
+            &mut hasher,
+            |hasher, point| {
+                let (result, _) = simplex_4d_impl(point[0], point[1], point[2], point[3], hasher);
+
+                result
+            },
+            simplex_4d_lanes,
+        );
+    }
+}
+
+/// 1-dimensional Simplex noise, with its gradient
+impl<H: NoiseHasher> NoiseFnGradient<1> for Simplex<H> {
+    fn get_with_gradient(&self, point: [f64; 1]) -> (f64, [f64; 1]) {
+        let (result, gradient) =
+            simplex_1d(point[0], &PeriodicHasher::new(&self.hasher, self.period));
+
+        (result, [gradient])
+    }
+}
+
+/// 2-dimensional Simplex noise, with its gradient
+impl<H: NoiseHasher> NoiseFnGradient<2> for Simplex<H> {
+    fn get_with_gradient(&self, point: [f64; 2]) -> (f64, [f64; 2]) {
+        if self.is_periodic_2d() {
+            periodic_simplex_2d(point, &self.hasher, self.period)
+        } else {
+            simplex_2d(point[0], point[1], &self.hasher)
+        }
+    }
+}
+
+/// 3-dimensional Simplex noise, with its gradient
+impl<H: NoiseHasher> NoiseFnGradient<3> for Simplex<H> {
+    fn get_with_gradient(&self, point: [f64; 3]) -> (f64, [f64; 3]) {
+        simplex_3d(point[0], point[1], point[2], &self.hasher)
+    }
+}
+
+/// 4-dimensional Simplex noise, with its gradient
+impl<H: NoiseHasher> NoiseFnGradient<4> for Simplex<H> {
+    fn get_with_gradient(&self, point: [f64; 4]) -> (f64, [f64; 4]) {
+        simplex_4d(point[0], point[1], point[2], point[3], &self.hasher)
+    }
+}
+
+/// Simplex noise at `f32` coordinates
+impl<H, const DIM: usize> NoiseFn<f32, DIM> for Simplex<H>
+where
+    Self: NoiseFn<f64, DIM>,
+{
+    fn get(&self, point: [f32; DIM]) -> f64 {
+        get_widened(self, point)
+    }
+
+    fn get_batch(&self, points: &[[f32; DIM]], output: &mut [f64]) {
+        evaluate_batch_widened(self, points, output);
+    }
+}
+
+#[inline(always)]
+fn simplex_2d_lanes<H: CellHasher<2>>(hasher: &mut H, points: &[[f64; 2]; LANES]) -> Lanes {
+    simplex_lanes::<_, _, 2, 3>(hasher, points, gradient::grad2, 0.5, 40.0, false)
+}
+
+#[inline(always)]
+fn simplex_3d_lanes<H: CellHasher<3>>(hasher: &mut H, points: &[[f64; 3]; LANES]) -> Lanes {
+    simplex_lanes::<_, _, 3, 4>(hasher, points, gradient::grad3, 0.5, 28.0, true)
+}
+
+#[inline(always)]
+fn simplex_4d_lanes<H: CellHasher<4>>(hasher: &mut H, points: &[[f64; 4]; LANES]) -> Lanes {
+    simplex_lanes::<_, _, 4, 5>(hasher, points, gradient::grad4, 0.6, 27.0, false)
+}
+
+/// Evaluates `DIM`-dimensional simplex noise at `LANES` points at once, without derivatives.
+///
+/// `CORNERS` must equal `DIM + 1`. Each corner contributes `(radius - d²)⁴ (g · d)` while `d²` is
+/// less than `radius`, and the sum is multiplied by `scale`.
+///
+/// Instead of branching on the order of the coordinates, the simplex is found by ranking them:
+/// corner `n` is offset along the `n` axes with the highest rank. When two coordinates are equal,
+/// the first one ranks higher if `prefer_first` is set, matching the comparisons of the scalar
+/// kernel for each dimension.
+#[inline(always)]
+fn simplex_lanes<H, G, const DIM: usize, const CORNERS: usize>(
+    hasher: &mut H,
+    points: &[[f64; DIM]; LANES],
+    gradient: G,
+    radius: f64,
+    scale: f64,
+    prefer_first: bool,
+) -> Lanes
+where
+    H: CellHasher<DIM>,
+    G: Fn(usize) -> [f64; DIM],
+{
+    let skew_factor = skew_factor(DIM);
+    let unskew_factor = unskew_factor(DIM);
+
+    let mut point = [Lanes::default(); DIM];
+    for (lane, coordinates) in points.iter().enumerate() {
+        for (axis, coordinate) in point.iter_mut().enumerate() {
+            coordinate[lane] = coordinates[axis];
+        }
+    }
+
+    let mut skew = Lanes::default();
+    for &coordinate in point.iter() {
+        skew = skew + coordinate;
+    }
+    let skew = skew * skew_factor;
+
+    // Cell coordinates stay as floats so they can be processed as vectors.
+    let mut cell = [Lanes::default(); DIM];
+    let mut unskew = Lanes::default();
+    for (cell, &coordinate) in cell.iter_mut().zip(point.iter()) {
+        *cell = (coordinate + skew).map(f64::floor);
+        unskew = unskew + *cell;
+    }
+    let unskew = unskew * unskew_factor;
+
+    let mut distance = [Lanes::default(); DIM];
+    for axis in 0..DIM {
+        distance[axis] = point[axis] - (cell[axis] - unskew);
+    }
+
+    let mut rank = [Lanes::default(); DIM];
+    for first in 0..DIM {
+        for second in first + 1..DIM {
+            let first_wins = distance[first].zip_with(distance[second], |a, b| {
+                let first_wins = if prefer_first { a >= b } else { a > b };
+                if first_wins {
+                    1.0
+                } else {
+                    0.0
+                }
+            });
+            rank[first] = rank[first] + first_wins;
+            rank[second] = rank[second] + first_wins.map(|wins| 1.0 - wins);
+        }
+    }
+
+    let mut offset = [[Lanes::default(); DIM]; CORNERS];
+    let mut corner_distance = [[Lanes::default(); DIM]; CORNERS];
+    let mut attenuation = [Lanes::default(); CORNERS];
+    for index in 0..CORNERS {
+        let threshold = (DIM - index) as f64;
+        let mut length_squared = Lanes::default();
+        for axis in 0..DIM {
+            offset[index][axis] = rank[axis].map(|rank| if rank >= threshold { 1.0 } else { 0.0 });
+
+            let d =
+                (distance[axis] - offset[index][axis]).map(|d| d + index as f64 * unskew_factor);
+            corner_distance[index][axis] = d;
+            length_squared = length_squared + d * d;
+        }
+        attenuation[index] = length_squared.map(|length_squared| radius - length_squared);
+    }
+
+    // Hashing is inherently scalar, so the gradient at each corner is looked up one lane at a
+    // time.
+    let mut gradient_dot = [Lanes::default(); CORNERS];
+    for lane in 0..LANES {
+        let mut lane_cell = [0; DIM];
+        for axis in 0..DIM {
+            lane_cell[axis] = cell[axis][lane] as isize;
+        }
+        hasher.set_cell(lane_cell);
+
+        for index in 0..CORNERS {
+            let mut lane_offset = [0; DIM];
+            for axis in 0..DIM {
+                lane_offset[axis] = offset[index][axis][lane] as isize;
+            }
+
+            let gradient = gradient(hasher.hash(lane_offset));
+            let mut dot = 0.0;
+            for axis in 0..DIM {
+                dot += gradient[axis] * corner_distance[index][axis][lane];
+            }
+            gradient_dot[index][lane] = dot;
+        }
+    }
+
+    let mut result = Lanes::default();
+    for (&attenuation, &gradient_dot) in attenuation.iter().zip(gradient_dot.iter()) {
+        let contribution = attenuation.zip_with(gradient_dot, |t, gradient_dot| {
+            let t2 = t * t;
+            let t4 = t2 * t2;
+            if t > 0.0 {
+                t4 * gradient_dot
+            } else {
+                0.0
+            }
+        });
+        result = result + contribution;
+    }
+    result * scale
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "must be even")]
+    fn test_period_odd_y() {
+        Simplex::new(0).set_period([4, 5]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_period() {
+        assert!(
+            serde_json::from_str::<Simplex>(r#"{"period": [4, 5, 0, 0]}"#)
+                .unwrap_err()
+                .to_string()
+                .contains("must be even")
+        );
+    }
 }
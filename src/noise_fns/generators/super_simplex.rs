@@ -1,17 +1,30 @@
 use crate::{
-    gradient, math,
-    noise_fns::{NoiseFn, Seedable},
-    permutationtable::{NoiseHasher, PermutationTable},
+    gradient,
+    math::{
+        self,
+        dual::{self, Dual},
+    },
+    noise_fns::{
+        evaluate_batch, evaluate_batch_widened, get_widened, NoiseFn, NoiseFnGradient, Seedable,
+    },
+    permutationtable::{
+        CachedHasher, CellHasher, DirectHasher, NoiseHasher, PermutationTable, SeedableHasher,
+    },
 };
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::ops::Add;
 
 const TO_REAL_CONSTANT_2D: f64 = -0.211_324_865_405_187; // (1 / sqrt(2 + 1) - 1) / 2
 const TO_SIMPLEX_CONSTANT_2D: f64 = 0.366_025_403_784_439; // (sqrt(2 + 1) - 1) / 2
 const TO_SIMPLEX_CONSTANT_3D: f64 = -2.0 / 3.0;
+const TO_REAL_CONSTANT_4D: f64 = -0.138_196_601_125_010_5; // (1 / sqrt(4 + 1) - 1) / 4
+const TO_SIMPLEX_CONSTANT_4D: f64 = 0.309_016_994_374_947_45; // (sqrt(4 + 1) - 1) / 4
 
 // Determined using the Mathematica code listed in the super_simplex example and find_maximum_super_simplex.nb
 const NORM_CONSTANT_2D: f64 = 1.0 / 0.054_282_952_886_616_23;
 const NORM_CONSTANT_3D: f64 = 1.0 / 0.086_766_400_165_536_9;
+const NORM_CONSTANT_4D: f64 = 1.0 / 0.115_917_763_953_589_2;
 
 // Points taken into account for 2D:
 //              (-1,  0)
@@ -88,165 +101,444 @@ const LATTICE_LOOKUP_3D: [[i8; 3]; 4 * 16] =
      [0, 0, 0],[0, 1, 1],[1, 0, 1],[1, 1, 0],
      [1, 1, 1],[0, 1, 1],[1, 0, 1],[1, 1, 0]];
 
-/// Noise function that outputs 2/3-dimensional Super Simplex noise.
+// Points taken into account for 4D, for each half of the simplex cube the point can be in along
+// each axis, as listed by the super_simplex example. The points for the region with index `i`
+// are LATTICE_LOOKUP_4D[LATTICE_REGIONS_4D[i]..LATTICE_REGIONS_4D[i + 1]].
+#[rustfmt::skip]
+const LATTICE_LOOKUP_4D: [[i8; 4]; 482] =
+    [[0, 0, -1, -1],[0, -1, 0, -1],[-1, 0, 0, -1],[0, 0, 0, -1],[0, -1, -1, 0],[-1, 0, -1, 0],[0, 0, -1, 0],[-1, -1, 0, 0],
+     [0, -1, 0, 0],[-1, 0, 0, 0],[0, 0, 0, 0],[1, 0, 0, 0],[0, 1, 0, 0],[1, 1, 0, 0],[0, 0, 1, 0],[1, 0, 1, 0],
+     [0, 1, 1, 0],[1, 1, 1, 0],[0, 0, 0, 1],[1, 0, 0, 1],[0, 1, 0, 1],[1, 1, 0, 1],[0, 0, 1, 1],[1, 0, 1, 1],
+     [0, 1, 1, 1],[1, 1, 1, 1],
+
+     [0, -1, -1, -1],[0, 0, -1, -1],[0, -1, 0, -1],[0, 0, 0, -1],[1, 0, 0, -1],[0, -1, -1, 0],[0, 0, -1, 0],[1, 0, -1, 0],
+     [0, -1, 0, 0],[1, -1, 0, 0],[0, 0, 0, 0],[1, 0, 0, 0],[0, 1, 0, 0],[1, 1, 0, 0],[0, 0, 1, 0],[1, 0, 1, 0],
+     [0, 1, 1, 0],[1, 1, 1, 0],[2, 1, 1, 0],[0, 0, 0, 1],[1, 0, 0, 1],[0, 1, 0, 1],[1, 1, 0, 1],[2, 1, 0, 1],
+     [0, 0, 1, 1],[1, 0, 1, 1],[2, 0, 1, 1],[1, 1, 1, 1],[2, 1, 1, 1],
+
+     [-1, 0, -1, -1],[0, 0, -1, -1],[-1, 0, 0, -1],[0, 0, 0, -1],[0, 1, 0, -1],[-1, 0, -1, 0],[0, 0, -1, 0],[0, 1, -1, 0],
+     [-1, 0, 0, 0],[0, 0, 0, 0],[1, 0, 0, 0],[-1, 1, 0, 0],[0, 1, 0, 0],[1, 1, 0, 0],[0, 0, 1, 0],[1, 0, 1, 0],
+     [0, 1, 1, 0],[1, 1, 1, 0],[1, 2, 1, 0],[0, 0, 0, 1],[1, 0, 0, 1],[0, 1, 0, 1],[1, 1, 0, 1],[1, 2, 0, 1],
+     [0, 0, 1, 1],[0, 1, 1, 1],[1, 1, 1, 1],[0, 2, 1, 1],[1, 2, 1, 1],
+
+     [0, 0, -1, -1],[0, 0, 0, -1],[1, 0, 0, -1],[0, 1, 0, -1],[1, 1, 0, -1],[0, 0, -1, 0],[1, 0, -1, 0],[0, 1, -1, 0],
+     [1, 1, -1, 0],[0, 0, 0, 0],[1, 0, 0, 0],[0, 1, 0, 0],[1, 1, 0, 0],[2, 1, 0, 0],[1, 2, 0, 0],[0, 0, 1, 0],
+     [1, 0, 1, 0],[0, 1, 1, 0],[1, 1, 1, 0],[2, 1, 1, 0],[1, 2, 1, 0],[0, 0, 0, 1],[1, 0, 0, 1],[0, 1, 0, 1],
+     [1, 1, 0, 1],[2, 1, 0, 1],[1, 2, 0, 1],[1, 0, 1, 1],[0, 1, 1, 1],[1, 1, 1, 1],[2, 1, 1, 1],[1, 2, 1, 1],
+     [2, 2, 1, 1],
+
+     [-1, -1, 0, -1],[0, -1, 0, -1],[-1, 0, 0, -1],[0, 0, 0, -1],[0, 0, 1, -1],[-1, -1, 0, 0],[0, -1, 0, 0],[-1, 0, 0, 0],
+     [0, 0, 0, 0],[1, 0, 0, 0],[0, 1, 0, 0],[1, 1, 0, 0],[0, -1, 1, 0],[-1, 0, 1, 0],[0, 0, 1, 0],[1, 0, 1, 0],
+     [0, 1, 1, 0],[1, 1, 1, 0],[1, 1, 2, 0],[0, 0, 0, 1],[1, 0, 0, 1],[0, 1, 0, 1],[0, 0, 1, 1],[1, 0, 1, 1],
+     [0, 1, 1, 1],[1, 1, 1, 1],[1, 0, 2, 1],[0, 1, 2, 1],[1, 1, 2, 1],
+
+     [0, -1, 0, -1],[0, 0, 0, -1],[1, 0, 0, -1],[0, 0, 1, -1],[1, 0, 1, -1],[0, -1, 0, 0],[1, -1, 0, 0],[0, 0, 0, 0],
+     [1, 0, 0, 0],[0, 1, 0, 0],[1, 1, 0, 0],[0, -1, 1, 0],[1, -1, 1, 0],[0, 0, 1, 0],[1, 0, 1, 0],[2, 0, 1, 0],
+     [0, 1, 1, 0],[1, 1, 1, 0],[2, 1, 1, 0],[1, 0, 2, 0],[1, 1, 2, 0],[0, 0, 0, 1],[1, 0, 0, 1],[1, 1, 0, 1],
+     [0, 0, 1, 1],[1, 0, 1, 1],[2, 0, 1, 1],[0, 1, 1, 1],[1, 1, 1, 1],[2, 1, 1, 1],[1, 0, 2, 1],[1, 1, 2, 1],
+     [2, 1, 2, 1],
+
+     [-1, 0, 0, -1],[0, 0, 0, -1],[0, 1, 0, -1],[0, 0, 1, -1],[0, 1, 1, -1],[-1, 0, 0, 0],[0, 0, 0, 0],[1, 0, 0, 0],
+     [-1, 1, 0, 0],[0, 1, 0, 0],[1, 1, 0, 0],[-1, 0, 1, 0],[0, 0, 1, 0],[1, 0, 1, 0],[-1, 1, 1, 0],[0, 1, 1, 0],
+     [1, 1, 1, 0],[0, 2, 1, 0],[1, 2, 1, 0],[0, 1, 2, 0],[1, 1, 2, 0],[0, 0, 0, 1],[0, 1, 0, 1],[1, 1, 0, 1],
+     [0, 0, 1, 1],[1, 0, 1, 1],[0, 1, 1, 1],[1, 1, 1, 1],[0, 2, 1, 1],[1, 2, 1, 1],[0, 1, 2, 1],[1, 1, 2, 1],
+     [1, 2, 2, 1],
+
+     [0, 0, 0, -1],[1, 0, 0, -1],[0, 1, 0, -1],[0, 0, 1, -1],[0, 0, 0, 0],[1, 0, 0, 0],[0, 1, 0, 0],[1, 1, 0, 0],
+     [0, 0, 1, 0],[1, 0, 1, 0],[0, 1, 1, 0],[1, 1, 1, 0],[2, 1, 1, 0],[1, 2, 1, 0],[1, 1, 2, 0],[1, 0, 0, 1],
+     [0, 1, 0, 1],[1, 1, 0, 1],[0, 0, 1, 1],[1, 0, 1, 1],[0, 1, 1, 1],[1, 1, 1, 1],[2, 1, 1, 1],[1, 2, 1, 1],
+     [2, 2, 1, 1],[1, 1, 2, 1],[2, 1, 2, 1],[1, 2, 2, 1],[2, 2, 2, 1],
+
+     [-1, -1, -1, 0],[0, -1, -1, 0],[-1, 0, -1, 0],[0, 0, -1, 0],[-1, -1, 0, 0],[0, -1, 0, 0],[-1, 0, 0, 0],[0, 0, 0, 0],
+     [1, 0, 0, 0],[0, 1, 0, 0],[1, 1, 0, 0],[0, 0, 1, 0],[1, 0, 1, 0],[0, 1, 1, 0],[0, 0, -1, 1],[0, -1, 0, 1],
+     [-1, 0, 0, 1],[0, 0, 0, 1],[1, 0, 0, 1],[0, 1, 0, 1],[1, 1, 0, 1],[0, 0, 1, 1],[1, 0, 1, 1],[0, 1, 1, 1],
+     [1, 1, 1, 1],[1, 1, 0, 2],[1, 0, 1, 2],[0, 1, 1, 2],[1, 1, 1, 2],
+
+     [0, -1, -1, 0],[0, 0, -1, 0],[1, 0, -1, 0],[0, -1, 0, 0],[1, -1, 0, 0],[0, 0, 0, 0],[1, 0, 0, 0],[0, 1, 0, 0],
+     [1, 1, 0, 0],[0, 0, 1, 0],[1, 0, 1, 0],[1, 1, 1, 0],[0, 0, -1, 1],[1, 0, -1, 1],[0, -1, 0, 1],[1, -1, 0, 1],
+     [0, 0, 0, 1],[1, 0, 0, 1],[2, 0, 0, 1],[0, 1, 0, 1],[1, 1, 0, 1],[2, 1, 0, 1],[0, 0, 1, 1],[1, 0, 1, 1],
+     [2, 0, 1, 1],[0, 1, 1, 1],[1, 1, 1, 1],[2, 1, 1, 1],[1, 0, 0, 2],[1, 1, 0, 2],[1, 0, 1, 2],[1, 1, 1, 2],
+     [2, 1, 1, 2],
+
+     [-1, 0, -1, 0],[0, 0, -1, 0],[0, 1, -1, 0],[-1, 0, 0, 0],[0, 0, 0, 0],[1, 0, 0, 0],[-1, 1, 0, 0],[0, 1, 0, 0],
+     [1, 1, 0, 0],[0, 0, 1, 0],[0, 1, 1, 0],[1, 1, 1, 0],[0, 0, -1, 1],[0, 1, -1, 1],[-1, 0, 0, 1],[0, 0, 0, 1],
+     [1, 0, 0, 1],[-1, 1, 0, 1],[0, 1, 0, 1],[1, 1, 0, 1],[0, 2, 0, 1],[1, 2, 0, 1],[0, 0, 1, 1],[1, 0, 1, 1],
+     [0, 1, 1, 1],[1, 1, 1, 1],[0, 2, 1, 1],[1, 2, 1, 1],[0, 1, 0, 2],[1, 1, 0, 2],[0, 1, 1, 2],[1, 1, 1, 2],
+     [1, 2, 1, 2],
+
+     [0, 0, -1, 0],[1, 0, -1, 0],[0, 1, -1, 0],[0, 0, 0, 0],[1, 0, 0, 0],[0, 1, 0, 0],[1, 1, 0, 0],[1, 0, 1, 0],
+     [0, 1, 1, 0],[1, 1, 1, 0],[0, 0, -1, 1],[0, 0, 0, 1],[1, 0, 0, 1],[0, 1, 0, 1],[1, 1, 0, 1],[2, 1, 0, 1],
+     [1, 2, 0, 1],[0, 0, 1, 1],[1, 0, 1, 1],[0, 1, 1, 1],[1, 1, 1, 1],[2, 1, 1, 1],[1, 2, 1, 1],[2, 2, 1, 1],
+     [1, 1, 0, 2],[1, 1, 1, 2],[2, 1, 1, 2],[1, 2, 1, 2],[2, 2, 1, 2],
+
+     [-1, -1, 0, 0],[0, -1, 0, 0],[-1, 0, 0, 0],[0, 0, 0, 0],[1, 0, 0, 0],[0, 1, 0, 0],[0, -1, 1, 0],[-1, 0, 1, 0],
+     [0, 0, 1, 0],[1, 0, 1, 0],[0, 1, 1, 0],[1, 1, 1, 0],[0, -1, 0, 1],[-1, 0, 0, 1],[0, 0, 0, 1],[1, 0, 0, 1],
+     [0, 1, 0, 1],[1, 1, 0, 1],[0, -1, 1, 1],[-1, 0, 1, 1],[0, 0, 1, 1],[1, 0, 1, 1],[0, 1, 1, 1],[1, 1, 1, 1],
+     [0, 0, 2, 1],[1, 0, 2, 1],[0, 1, 2, 1],[1, 1, 2, 1],[0, 0, 1, 2],[1, 0, 1, 2],[0, 1, 1, 2],[1, 1, 1, 2],
+     [1, 1, 2, 2],
+
+     [0, -1, 0, 0],[1, -1, 0, 0],[0, 0, 0, 0],[1, 0, 0, 0],[1, 1, 0, 0],[0, -1, 1, 0],[0, 0, 1, 0],[1, 0, 1, 0],
+     [0, 1, 1, 0],[1, 1, 1, 0],[0, -1, 0, 1],[0, 0, 0, 1],[1, 0, 0, 1],[0, 1, 0, 1],[1, 1, 0, 1],[0, 0, 1, 1],
+     [1, 0, 1, 1],[2, 0, 1, 1],[0, 1, 1, 1],[1, 1, 1, 1],[2, 1, 1, 1],[1, 0, 2, 1],[1, 1, 2, 1],[2, 1, 2, 1],
+     [1, 0, 1, 2],[1, 1, 1, 2],[2, 1, 1, 2],[1, 1, 2, 2],[2, 1, 2, 2],
+
+     [-1, 0, 0, 0],[0, 0, 0, 0],[-1, 1, 0, 0],[0, 1, 0, 0],[1, 1, 0, 0],[-1, 0, 1, 0],[0, 0, 1, 0],[1, 0, 1, 0],
+     [0, 1, 1, 0],[1, 1, 1, 0],[-1, 0, 0, 1],[0, 0, 0, 1],[1, 0, 0, 1],[0, 1, 0, 1],[1, 1, 0, 1],[0, 0, 1, 1],
+     [1, 0, 1, 1],[0, 1, 1, 1],[1, 1, 1, 1],[0, 2, 1, 1],[1, 2, 1, 1],[0, 1, 2, 1],[1, 1, 2, 1],[1, 2, 2, 1],
+     [0, 1, 1, 2],[1, 1, 1, 2],[1, 2, 1, 2],[1, 1, 2, 2],[1, 2, 2, 2],
+
+     [0, 0, 0, 0],[1, 0, 0, 0],[0, 1, 0, 0],[1, 1, 0, 0],[0, 0, 1, 0],[1, 0, 1, 0],[0, 1, 1, 0],[1, 1, 1, 0],
+     [0, 0, 0, 1],[1, 0, 0, 1],[0, 1, 0, 1],[1, 1, 0, 1],[0, 0, 1, 1],[1, 0, 1, 1],[0, 1, 1, 1],[1, 1, 1, 1],
+     [2, 1, 1, 1],[1, 2, 1, 1],[2, 2, 1, 1],[1, 1, 2, 1],[2, 1, 2, 1],[1, 2, 2, 1],[1, 1, 1, 2],[2, 1, 1, 2],
+     [1, 2, 1, 2],[1, 1, 2, 2]];
+
+const LATTICE_REGIONS_4D: [usize; 17] = [
+    0, 26, 55, 84, 117, 146, 179, 212, 241, 270, 303, 336, 365, 398, 427, 456, 482,
+];
+
+/// Noise function that outputs 2/3/4-dimensional Super Simplex noise.
 #[derive(Clone, Copy, Debug)]
-pub struct SuperSimplex {
-    seed: u32,
-    perm_table: PermutationTable,
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(from = "super::SeedParams", bound(deserialize = "H: SeedableHasher"))
+)]
+pub struct SuperSimplex<H = PermutationTable> {
+    seed: u64,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    hasher: H,
 }
 
 impl SuperSimplex {
-    pub const DEFAULT_SEED: u32 = 0;
+    pub const DEFAULT_SEED: u64 = 0;
 
     pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<H: SeedableHasher> SuperSimplex<H> {
+    /// Creates the noise function with `seed`, hashing the lattice with any
+    /// [`SeedableHasher`] rather than the default [`PermutationTable`].
+    pub fn from_seed(seed: u64) -> Self {
         Self {
-            seed: Self::DEFAULT_SEED,
-            perm_table: PermutationTable::new(Self::DEFAULT_SEED),
+            seed,
+            hasher: H::from_seed(seed),
         }
     }
 }
 
 impl Default for SuperSimplex {
     fn default() -> Self {
-        Self::new()
+        Self::from_seed(Self::DEFAULT_SEED)
     }
 }
 
-impl Seedable for SuperSimplex {
+impl<H: SeedableHasher> Seedable for SuperSimplex<H> {
     /// Sets the seed value for Super Simplex noise
-    fn set_seed(self, seed: u32) -> Self {
+    fn set_seed(self, seed: u64) -> Self {
         // If the new seed is the same as the current seed, just return self.
         if self.seed == seed {
             return self;
         }
 
-        // Otherwise, regenerate the permutation table based on the new seed.
+        // Otherwise, regenerate the hasher based on the new seed.
         Self {
             seed,
-            perm_table: PermutationTable::new(seed),
+            hasher: H::from_seed(seed),
         }
     }
 
-    fn seed(&self) -> u32 {
+    fn seed(&self) -> u64 {
         self.seed
     }
 }
 
 /// 2-dimensional Super Simplex noise
-impl NoiseFn<f64, 2> for SuperSimplex {
+impl<H: NoiseHasher> NoiseFn<f64, 2> for SuperSimplex<H> {
     fn get(&self, point: [f64; 2]) -> f64 {
-        let mut value = 0.0;
-
-        // Transform point from real space to simplex space
-        let to_simplex_offset = math::fold2(point, Add::add) * TO_SIMPLEX_CONSTANT_2D;
-        let simplex_point = math::map2(point, |v| v + to_simplex_offset);
-
-        // Get base point of simplex and barycentric coordinates in simplex space
-        let simplex_base_point = math::map2(simplex_point, f64::floor);
-        let simplex_base_point_i = math::to_isize2(simplex_base_point);
-        let simplex_rel_coords = math::sub2(simplex_point, simplex_base_point);
-
-        // Create index to lookup table from barycentric coordinates
-        let region_sum = math::fold2(simplex_rel_coords, Add::add).floor();
-        let index = ((region_sum >= 1.0) as usize) << 2
-            | ((simplex_rel_coords[0] - simplex_rel_coords[1] * 0.5 + 1.0 - region_sum * 0.5 >= 1.0)
-                as usize)
-                << 3
-            | ((simplex_rel_coords[1] - simplex_rel_coords[0] * 0.5 + 1.0 - region_sum * 0.5 >= 1.0)
-                as usize)
-                << 4;
-
-        // Transform barycentric coordinates to real space
-        let to_real_offset = math::fold2(simplex_rel_coords, Add::add) * TO_REAL_CONSTANT_2D;
-        let real_rel_coords = math::map2(simplex_rel_coords, |v| v + to_real_offset);
-
-        for lattice_lookup in &LATTICE_LOOKUP_2D[index..index + 4] {
-            let dpos = math::add2(real_rel_coords, math::cast2(lattice_lookup.1));
-            let attn = (2.0 / 3.0) - math::dot2(dpos, dpos);
-            if attn > 0.0 {
-                let lattice_point = math::add2(simplex_base_point_i, math::cast2(lattice_lookup.0));
-                let gradient = gradient::grad2(self.perm_table.hash(&lattice_point));
-                value += attn.powi(4) * math::dot2(gradient, dpos);
-            }
-        }
+        super_simplex_2d(&mut DirectHasher::new(&self.hasher), point).value
+    }
 
-        value * NORM_CONSTANT_2D
+    fn get_batch(&self, points: &[[f64; 2]], output: &mut [f64]) {
+        // The lattice lookup reaches one point outside the cell in every direction.
+        let mut hasher = CachedHasher::<_, 2, 16>::new(&self.hasher, -1);
+        evaluate_batch(points, output, |point| {
+            super_simplex_2d(&mut hasher, point).value
+        });
+    }
+}
+
+#[inline(always)]
+pub(super) fn super_simplex_2d<H>(hasher: &mut H, point: [f64; 2]) -> Dual<2>
+where
+    H: CellHasher<2>,
+{
+    let mut value = Dual::constant(0.0);
+
+    // Transform point from real space to simplex space
+    let to_simplex_offset = math::fold2(point, Add::add) * TO_SIMPLEX_CONSTANT_2D;
+    let simplex_point = math::map2(point, |v| v + to_simplex_offset);
+
+    // Get base point of simplex and barycentric coordinates in simplex space
+    let simplex_base_point = math::map2(simplex_point, f64::floor);
+    hasher.set_cell(math::to_isize2(simplex_base_point));
+    let simplex_rel_coords = math::sub2(simplex_point, simplex_base_point);
+
+    // Create index to lookup table from barycentric coordinates
+    let region_sum = math::fold2(simplex_rel_coords, Add::add).floor();
+    let index = ((region_sum >= 1.0) as usize) << 2
+        | ((simplex_rel_coords[0] - simplex_rel_coords[1] * 0.5 + 1.0 - region_sum * 0.5 >= 1.0)
+            as usize)
+            << 3
+        | ((simplex_rel_coords[1] - simplex_rel_coords[0] * 0.5 + 1.0 - region_sum * 0.5 >= 1.0)
+            as usize)
+            << 4;
+
+    // Transform barycentric coordinates to real space
+    let to_real_offset = math::fold2(simplex_rel_coords, Add::add) * TO_REAL_CONSTANT_2D;
+    let real_rel_coords = math::map2(simplex_rel_coords, |v| v + to_real_offset);
+
+    for lattice_lookup in &LATTICE_LOOKUP_2D[index..index + 4] {
+        let dpos = math::add2(real_rel_coords, math::cast2(lattice_lookup.1));
+        let attn = (2.0 / 3.0) - math::dot2(dpos, dpos);
+        if attn > 0.0 {
+            let gradient = gradient::grad2(hasher.hash(math::cast2(lattice_lookup.0)));
+            value += dual::surflet(attn, math::dot2(gradient, dpos), gradient, dpos);
+        }
     }
+
+    value * NORM_CONSTANT_2D
 }
 
 /// 3-dimensional Super Simplex noise
-impl NoiseFn<f64, 3> for SuperSimplex {
+impl<H: NoiseHasher> NoiseFn<f64, 3> for SuperSimplex<H> {
     fn get(&self, point: [f64; 3]) -> f64 {
-        let mut value = 0.0;
-
-        // Transform point from real space to simplex space
-        let to_simplex_offset = math::fold3(point, Add::add) * TO_SIMPLEX_CONSTANT_3D;
-        let simplex_point = math::map3(point, |v| -(v + to_simplex_offset));
-        let second_simplex_point = math::map3(simplex_point, |v| v + 512.5);
-
-        // Get base point of simplex and barycentric coordinates in simplex space
-        let simplex_base_point = math::map3(simplex_point, f64::floor);
-        let simplex_base_point_i = math::to_isize3(simplex_base_point);
-        let simplex_rel_coords = math::sub3(simplex_point, simplex_base_point);
-        let second_simplex_base_point = math::map3(second_simplex_point, f64::floor);
-        let second_simplex_base_point_i = math::to_isize3(second_simplex_base_point);
-        let second_simplex_rel_coords = math::sub3(second_simplex_point, second_simplex_base_point);
-
-        // Create indices to lookup table from barycentric coordinates
-        let index = ((simplex_rel_coords[0] + simplex_rel_coords[1] + simplex_rel_coords[2] >= 1.5)
+        super_simplex_3d(
+            &mut DirectHasher::new(&self.hasher),
+            &mut DirectHasher::new(&self.hasher),
+            point,
+        )
+        .value
+    }
+
+    fn get_batch(&self, points: &[[f64; 3]], output: &mut [f64]) {
+        // Each of the two lattices moves between cells independently, so give each its own cache.
+        let mut hasher = CachedHasher::<_, 3, 8>::new(&self.hasher, 0);
+        let mut second_hasher = CachedHasher::<_, 3, 8>::new(&self.hasher, 0);
+        evaluate_batch(points, output, |point| {
+            super_simplex_3d(&mut hasher, &mut second_hasher, point).value
+        });
+    }
+}
+
+/// 2-dimensional Super Simplex noise, with its gradient
+impl<H: NoiseHasher> NoiseFnGradient<2> for SuperSimplex<H> {
+    fn get_with_gradient(&self, point: [f64; 2]) -> (f64, [f64; 2]) {
+        super_simplex_2d(&mut DirectHasher::new(&self.hasher), point).into_tuple()
+    }
+}
+
+/// 3-dimensional Super Simplex noise, with its gradient
+impl<H: NoiseHasher> NoiseFnGradient<3> for SuperSimplex<H> {
+    fn get_with_gradient(&self, point: [f64; 3]) -> (f64, [f64; 3]) {
+        super_simplex_3d(
+            &mut DirectHasher::new(&self.hasher),
+            &mut DirectHasher::new(&self.hasher),
+            point,
+        )
+        .into_tuple()
+    }
+}
+
+/// 4-dimensional Super Simplex noise
+impl<H: NoiseHasher> NoiseFn<f64, 4> for SuperSimplex<H> {
+    fn get(&self, point: [f64; 4]) -> f64 {
+        super_simplex_4d(&mut DirectHasher::new(&self.hasher), point).value
+    }
+
+    fn get_batch(&self, points: &[[f64; 4]], output: &mut [f64]) {
+        // The lattice lookup reaches one point outside the cell in every direction.
+        let mut hasher = CachedHasher::<_, 4, 256>::new(&self.hasher, -1);
+        evaluate_batch(points, output, |point| {
+            super_simplex_4d(&mut hasher, point).value
+        });
+    }
+}
+
+/// 4-dimensional Super Simplex noise, with its gradient
+impl<H: NoiseHasher> NoiseFnGradient<4> for SuperSimplex<H> {
+    fn get_with_gradient(&self, point: [f64; 4]) -> (f64, [f64; 4]) {
+        super_simplex_4d(&mut DirectHasher::new(&self.hasher), point).into_tuple()
+    }
+}
+
+/// Super Simplex noise at `f32` coordinates
+impl<H, const DIM: usize> NoiseFn<f32, DIM> for SuperSimplex<H>
+where
+    Self: NoiseFn<f64, DIM>,
+{
+    fn get(&self, point: [f32; DIM]) -> f64 {
+        get_widened(self, point)
+    }
+
+    fn get_batch(&self, points: &[[f32; DIM]], output: &mut [f64]) {
+        evaluate_batch_widened(self, points, output);
+    }
+}
+
+#[inline(always)]
+fn super_simplex_3d<H>(hasher: &mut H, second_hasher: &mut H, point: [f64; 3]) -> Dual<3>
+where
+    H: CellHasher<3>,
+{
+    // Transform point from real space to simplex space
+    let to_simplex_offset = math::fold3(point, Add::add) * TO_SIMPLEX_CONSTANT_3D;
+    let simplex_point = math::map3(point, |v| -(v + to_simplex_offset));
+
+    let mut value = super_simplex_3d_rotated(hasher, second_hasher, simplex_point);
+
+    // The lattices lie in simplex space, which is a linear transform of real space, so take the
+    // gradient back through that transform.
+    let sum = math::fold3(value.gradient, Add::add) * -TO_SIMPLEX_CONSTANT_3D;
+    value.gradient = math::map3(value.gradient, |g| sum - g);
+
+    value
+}
+
+/// Evaluates 3-dimensional Super Simplex noise at a point that has already been transformed to
+/// simplex space, with the gradient taken with respect to that point.
+#[inline(always)]
+pub(super) fn super_simplex_3d_rotated<H>(
+    hasher: &mut H,
+    second_hasher: &mut H,
+    simplex_point: [f64; 3],
+) -> Dual<3>
+where
+    H: CellHasher<3>,
+{
+    let mut value = Dual::constant(0.0);
+
+    let second_simplex_point = math::map3(simplex_point, |v| v + 512.5);
+
+    // Get base point of simplex and barycentric coordinates in simplex space
+    let simplex_base_point = math::map3(simplex_point, f64::floor);
+    hasher.set_cell(math::to_isize3(simplex_base_point));
+    let simplex_rel_coords = math::sub3(simplex_point, simplex_base_point);
+    let second_simplex_base_point = math::map3(second_simplex_point, f64::floor);
+    second_hasher.set_cell(math::to_isize3(second_simplex_base_point));
+    let second_simplex_rel_coords = math::sub3(second_simplex_point, second_simplex_base_point);
+
+    // Create indices to lookup table from barycentric coordinates
+    let index = ((simplex_rel_coords[0] + simplex_rel_coords[1] + simplex_rel_coords[2] >= 1.5)
+        as usize)
+        << 2
+        | ((-simplex_rel_coords[0] + simplex_rel_coords[1] + simplex_rel_coords[2] >= 0.5)
             as usize)
-            << 2
-            | ((-simplex_rel_coords[0] + simplex_rel_coords[1] + simplex_rel_coords[2] >= 0.5)
-                as usize)
-                << 3
-            | ((simplex_rel_coords[0] - simplex_rel_coords[1] + simplex_rel_coords[2] >= 0.5)
-                as usize)
-                << 4
-            | ((simplex_rel_coords[0] + simplex_rel_coords[1] - simplex_rel_coords[2] >= 0.5)
-                as usize)
-                << 5;
-        let second_index = ((second_simplex_rel_coords[0]
+            << 3
+        | ((simplex_rel_coords[0] - simplex_rel_coords[1] + simplex_rel_coords[2] >= 0.5) as usize)
+            << 4
+        | ((simplex_rel_coords[0] + simplex_rel_coords[1] - simplex_rel_coords[2] >= 0.5) as usize)
+            << 5;
+    let second_index = ((second_simplex_rel_coords[0]
+        + second_simplex_rel_coords[1]
+        + second_simplex_rel_coords[2]
+        >= 1.5) as usize)
+        << 2
+        | ((-second_simplex_rel_coords[0]
             + second_simplex_rel_coords[1]
             + second_simplex_rel_coords[2]
-            >= 1.5) as usize)
-            << 2
-            | ((-second_simplex_rel_coords[0]
-                + second_simplex_rel_coords[1]
-                + second_simplex_rel_coords[2]
-                >= 0.5) as usize)
-                << 3
-            | ((second_simplex_rel_coords[0] - second_simplex_rel_coords[1]
-                + second_simplex_rel_coords[2]
-                >= 0.5) as usize)
-                << 4
-            | ((second_simplex_rel_coords[0] + second_simplex_rel_coords[1]
-                - second_simplex_rel_coords[2]
-                >= 0.5) as usize)
-                << 5;
-
-        // Sum contributions from first lattice
-        for &lattice_lookup in &LATTICE_LOOKUP_3D[index..index + 4] {
-            let dpos = math::sub3(simplex_rel_coords, math::cast3(lattice_lookup));
-            let attn = 0.75 - math::dot3(dpos, dpos);
-            if attn > 0.0 {
-                let lattice_point = math::add3(simplex_base_point_i, math::cast3(lattice_lookup));
-                let gradient = gradient::grad3(self.perm_table.hash(&lattice_point));
-                value += attn.powi(4) * math::dot3(gradient, dpos);
-            }
+            >= 0.5) as usize)
+            << 3
+        | ((second_simplex_rel_coords[0] - second_simplex_rel_coords[1]
+            + second_simplex_rel_coords[2]
+            >= 0.5) as usize)
+            << 4
+        | ((second_simplex_rel_coords[0] + second_simplex_rel_coords[1]
+            - second_simplex_rel_coords[2]
+            >= 0.5) as usize)
+            << 5;
+
+    // Sum contributions from first lattice
+    for &lattice_lookup in &LATTICE_LOOKUP_3D[index..index + 4] {
+        let dpos = math::sub3(simplex_rel_coords, math::cast3(lattice_lookup));
+        let attn = 0.75 - math::dot3(dpos, dpos);
+        if attn > 0.0 {
+            let gradient = gradient::grad3(hasher.hash(math::cast3(lattice_lookup)));
+            value += dual::surflet(attn, math::dot3(gradient, dpos), gradient, dpos);
         }
+    }
 
-        // Sum contributions from second lattice
-        for &lattice_lookup in &LATTICE_LOOKUP_3D[second_index..second_index + 4] {
-            let dpos = math::sub3(second_simplex_rel_coords, math::cast3(lattice_lookup));
-            let attn = 0.75 - math::dot3(dpos, dpos);
-            if attn > 0.0 {
-                let lattice_point =
-                    math::add3(second_simplex_base_point_i, math::cast3(lattice_lookup));
-                let gradient = gradient::grad3(self.perm_table.hash(&lattice_point));
-                value += attn.powi(4) * math::dot3(gradient, dpos);
-            }
+    // Sum contributions from second lattice
+    for &lattice_lookup in &LATTICE_LOOKUP_3D[second_index..second_index + 4] {
+        let dpos = math::sub3(second_simplex_rel_coords, math::cast3(lattice_lookup));
+        let attn = 0.75 - math::dot3(dpos, dpos);
+        if attn > 0.0 {
+            let gradient = gradient::grad3(second_hasher.hash(math::cast3(lattice_lookup)));
+            value += dual::surflet(attn, math::dot3(gradient, dpos), gradient, dpos);
         }
+    }
 
-        value * NORM_CONSTANT_3D
+    value * NORM_CONSTANT_3D
+}
+
+#[inline(always)]
+pub(super) fn super_simplex_4d<H>(hasher: &mut H, point: [f64; 4]) -> Dual<4>
+where
+    H: CellHasher<4>,
+{
+    simplex_lattice_4d(hasher, point, &LATTICE_LOOKUP_4D, &LATTICE_REGIONS_4D, 0.8)
+        * NORM_CONSTANT_4D
+}
+
+/// Sums the surflets of radius `sqrt(radius_squared)` around the points of the 4-dimensional
+/// simplex lattice, taking the points to visit for each half-cell region from `lattice_lookup`,
+/// which `lattice_regions` indexes into. The sum isn't normalized.
+#[inline(always)]
+pub(super) fn simplex_lattice_4d<H>(
+    hasher: &mut H,
+    point: [f64; 4],
+    lattice_lookup: &[[i8; 4]],
+    lattice_regions: &[usize; 17],
+    radius_squared: f64,
+) -> Dual<4>
+where
+    H: CellHasher<4>,
+{
+    let mut value = Dual::constant(0.0);
+
+    // Transform point from real space to simplex space
+    let to_simplex_offset = math::fold4(point, Add::add) * TO_SIMPLEX_CONSTANT_4D;
+    let simplex_point = math::map4(point, |v| v + to_simplex_offset);
+
+    // Get base point of simplex and barycentric coordinates in simplex space
+    let simplex_base_point = math::map4(simplex_point, f64::floor);
+    hasher.set_cell(math::to_isize4(simplex_base_point));
+    let simplex_rel_coords = math::sub4(simplex_point, simplex_base_point);
+
+    // Create index to lookup table from the half of the cell the point is in along each axis
+    let region = (simplex_rel_coords[0] >= 0.5) as usize
+        | ((simplex_rel_coords[1] >= 0.5) as usize) << 1
+        | ((simplex_rel_coords[2] >= 0.5) as usize) << 2
+        | ((simplex_rel_coords[3] >= 0.5) as usize) << 3;
+
+    // Transform barycentric coordinates to real space
+    let to_real_offset = math::fold4(simplex_rel_coords, Add::add) * TO_REAL_CONSTANT_4D;
+    let real_rel_coords = math::map4(simplex_rel_coords, |v| v + to_real_offset);
+
+    for &lattice_lookup in &lattice_lookup[lattice_regions[region]..lattice_regions[region + 1]] {
+        let lattice_point: [f64; 4] = math::cast4(lattice_lookup);
+        let to_real_offset = math::fold4(lattice_point, Add::add) * TO_REAL_CONSTANT_4D;
+        let dpos = math::sub4(
+            real_rel_coords,
+            math::map4(lattice_point, |v| v + to_real_offset),
+        );
+        let attn = radius_squared - math::dot4(dpos, dpos);
+        if attn > 0.0 {
+            let gradient = gradient::grad4(hasher.hash(math::cast4(lattice_lookup)));
+            value += dual::surflet(attn, math::dot4(gradient, dpos), gradient, dpos);
+        }
     }
+
+    value
 }
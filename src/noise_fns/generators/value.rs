@@ -1,254 +1,418 @@
 use crate::math::s_curve::quintic::Quintic;
 use crate::{
-    math::{self, interpolate},
-    noise_fns::{NoiseFn, Seedable},
-    permutationtable::{NoiseHasher, PermutationTable},
+    math::{
+        self,
+        dual::{self, Dual},
+        interpolate,
+        lanes::{self, Lanes, LANES},
+    },
+    noise_fns::{
+        evaluate_batch_lanes, evaluate_batch_widened, get_widened, NoiseFn, NoiseFnGradient,
+        Periodic, Seedable,
+    },
+    permutationtable::{
+        expand_period, CachedHasher, CellHasher, DirectHasher, NoiseHasher, PeriodicHasher,
+        PermutationTable, SeedableHasher,
+    },
 };
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
-/// Noise function that outputs 2/3/4-dimensional Value noise.
+/// Noise function that outputs 1/2/3/4-dimensional Value noise.
+///
+/// With a [period](Periodic::set_period) set, the output repeats every that
+/// many lattice cells along each periodic axis, so it tiles exactly.
 #[derive(Clone, Copy, Debug)]
-pub struct Value {
-    seed: u32,
-    perm_table: PermutationTable,
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        from = "super::PeriodicParams",
+        bound(deserialize = "H: SeedableHasher")
+    )
+)]
+pub struct Value<H = PermutationTable> {
+    seed: u64,
+    period: [u32; 4],
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    hasher: H,
 }
 
 impl Value {
-    pub const DEFAULT_SEED: u32 = 0;
+    pub const DEFAULT_SEED: u64 = 0;
 
     pub fn new() -> Self {
+        Self::from_seed(Self::DEFAULT_SEED)
+    }
+}
+
+impl<H: SeedableHasher> Value<H> {
+    /// Creates the noise function with `seed`, hashing the lattice with any
+    /// [`SeedableHasher`] rather than the default [`PermutationTable`].
+    pub fn from_seed(seed: u64) -> Self {
         Self {
-            seed: Self::DEFAULT_SEED,
-            perm_table: PermutationTable::new(Self::DEFAULT_SEED),
+            seed,
+            period: [0; 4],
+            hasher: H::from_seed(seed),
         }
     }
 }
 
+impl<H> Value<H> {
+    fn periodic_hasher(&self) -> PeriodicHasher<'_, H> {
+        PeriodicHasher::new(&self.hasher, self.period)
+    }
+}
+
 impl Default for Value {
     fn default() -> Self {
-        Self::new()
+        Self::from_seed(Self::DEFAULT_SEED)
     }
 }
 
-impl Seedable for Value {
+impl<H: SeedableHasher> Seedable for Value<H> {
     /// Sets the seed value for Value noise
-    fn set_seed(self, seed: u32) -> Self {
+    fn set_seed(self, seed: u64) -> Self {
         // If the new seed is the same as the current seed, just return self.
         if self.seed == seed {
             return self;
         }
 
-        // Otherwise, regenerate the permutation table based on the new seed.
+        // Otherwise, regenerate the hasher based on the new seed.
         Self {
             seed,
-            perm_table: PermutationTable::new(seed),
+            hasher: H::from_seed(seed),
+            ..self
         }
     }
 
-    fn seed(&self) -> u32 {
+    fn seed(&self) -> u64 {
         self.seed
     }
 }
 
-/// 2-dimensional value noise
-impl NoiseFn<f64, 2> for Value {
-    fn get(&self, point: [f64; 2]) -> f64 {
-        fn get(perm_table: &PermutationTable, corner: [isize; 2]) -> f64 {
-            perm_table.hash(&corner) as f64 / 255.0
+impl<H> Periodic for Value<H> {
+    fn set_period<const N: usize>(self, period: [u32; N]) -> Self {
+        Self {
+            period: expand_period(period),
+            ..self
         }
+    }
 
-        let floored = math::map2(point, f64::floor);
-        let near_corner = math::to_isize2(floored);
-        let far_corner = math::add2(near_corner, math::one2());
-        let weight = math::sub2(point, floored).map_quintic();
+    fn period(&self) -> [u32; 4] {
+        self.period
+    }
+}
 
-        let f00 = get(&self.perm_table, [near_corner[0], near_corner[1]]);
-        let f10 = get(&self.perm_table, [far_corner[0], near_corner[1]]);
-        let f01 = get(&self.perm_table, [near_corner[0], far_corner[1]]);
-        let f11 = get(&self.perm_table, [far_corner[0], far_corner[1]]);
+/// 1-dimensional value noise
+impl<H: NoiseHasher> NoiseFn<f64, 1> for Value<H> {
+    fn get(&self, point: [f64; 1]) -> f64 {
+        value_1d(&mut DirectHasher::new(&self.periodic_hasher()), point)
+    }
+
+    fn get_batch(&self, points: &[[f64; 1]], output: &mut [f64]) {
+        let periodic = self.periodic_hasher();
+        let mut hasher = CachedHasher::<_, 1, 2>::new(&periodic, 0);
+        evaluate_batch_lanes(
+            points,
+            output,
+            &mut hasher,
+            value_1d,
+            value_lanes::<_, 1, 2>,
+        );
+    }
+}
+
+#[inline(always)]
+fn value_1d<H>(hasher: &mut H, point: [f64; 1]) -> f64
+where
+    H: CellHasher<1>,
+{
+    #[inline(always)]
+    fn get<H: CellHasher<1>>(hasher: &H, offset: [isize; 1]) -> f64 {
+        hasher.hash(offset) as f64 / 255.0
+    }
+
+    let floored = point[0].floor();
+    hasher.set_cell([floored as isize]);
+    let weight = (point[0] - floored).map_quintic();
+
+    let f0 = get(hasher, [0]);
+    let f1 = get(hasher, [1]);
+
+    let d = interpolate::linear(f0, f1, weight);
+
+    d * 2.0 - 1.0
+}
+
+/// 2-dimensional value noise
+impl<H: NoiseHasher> NoiseFn<f64, 2> for Value<H> {
+    fn get(&self, point: [f64; 2]) -> f64 {
+        value_2d(&mut DirectHasher::new(&self.periodic_hasher()), point)
+    }
 
-        let d0 = interpolate::linear(f00, f10, weight[0]);
-        let d1 = interpolate::linear(f01, f11, weight[0]);
-        let d = interpolate::linear(d0, d1, weight[1]);
+    fn get_batch(&self, points: &[[f64; 2]], output: &mut [f64]) {
+        let periodic = self.periodic_hasher();
+        let mut hasher = CachedHasher::<_, 2, 4>::new(&periodic, 0);
+        evaluate_batch_lanes(
+            points,
+            output,
+            &mut hasher,
+            value_2d,
+            value_lanes::<_, 2, 4>,
+        );
+    }
+}
 
-        d * 2.0 - 1.0
+#[inline(always)]
+fn value_2d<H>(hasher: &mut H, point: [f64; 2]) -> f64
+where
+    H: CellHasher<2>,
+{
+    #[inline(always)]
+    fn get<H: CellHasher<2>>(hasher: &H, offset: [isize; 2]) -> f64 {
+        hasher.hash(offset) as f64 / 255.0
     }
+
+    let floored = math::map2(point, f64::floor);
+    hasher.set_cell(math::to_isize2(floored));
+    let weight = math::sub2(point, floored).map_quintic();
+
+    let f00 = get(hasher, [0, 0]);
+    let f10 = get(hasher, [1, 0]);
+    let f01 = get(hasher, [0, 1]);
+    let f11 = get(hasher, [1, 1]);
+
+    let d0 = interpolate::linear(f00, f10, weight[0]);
+    let d1 = interpolate::linear(f01, f11, weight[0]);
+    let d = interpolate::linear(d0, d1, weight[1]);
+
+    d * 2.0 - 1.0
 }
 
 /// 3-dimensional value noise
-impl NoiseFn<f64, 3> for Value {
+impl<H: NoiseHasher> NoiseFn<f64, 3> for Value<H> {
     fn get(&self, point: [f64; 3]) -> f64 {
-        fn get(perm_table: &PermutationTable, corner: [isize; 3]) -> f64 {
-            perm_table.hash(&corner) as f64 / 255.0
-        }
-
-        let floored = math::map3(point, f64::floor);
-        let near_corner = math::to_isize3(floored);
-        let far_corner = math::add3(near_corner, math::one3());
-        let weight = math::sub3(point, floored).map_quintic();
+        value_3d(&mut DirectHasher::new(&self.periodic_hasher()), point)
+    }
 
-        let f000 = get(
-            &self.perm_table,
-            [near_corner[0], near_corner[1], near_corner[2]],
-        );
-        let f100 = get(
-            &self.perm_table,
-            [far_corner[0], near_corner[1], near_corner[2]],
-        );
-        let f010 = get(
-            &self.perm_table,
-            [near_corner[0], far_corner[1], near_corner[2]],
+    fn get_batch(&self, points: &[[f64; 3]], output: &mut [f64]) {
+        let periodic = self.periodic_hasher();
+        let mut hasher = CachedHasher::<_, 3, 8>::new(&periodic, 0);
+        evaluate_batch_lanes(
+            points,
+            output,
+            &mut hasher,
+            value_3d,
+            value_lanes::<_, 3, 8>,
         );
-        let f110 = get(
-            &self.perm_table,
-            [far_corner[0], far_corner[1], near_corner[2]],
-        );
-        let f001 = get(
-            &self.perm_table,
-            [near_corner[0], near_corner[1], far_corner[2]],
-        );
-        let f101 = get(
-            &self.perm_table,
-            [far_corner[0], near_corner[1], far_corner[2]],
-        );
-        let f011 = get(
-            &self.perm_table,
-            [near_corner[0], far_corner[1], far_corner[2]],
-        );
-        let f111 = get(
-            &self.perm_table,
-            [far_corner[0], far_corner[1], far_corner[2]],
-        );
-
-        let d00 = interpolate::linear(f000, f100, weight[0]);
-        let d01 = interpolate::linear(f001, f101, weight[0]);
-        let d10 = interpolate::linear(f010, f110, weight[0]);
-        let d11 = interpolate::linear(f011, f111, weight[0]);
-        let d0 = interpolate::linear(d00, d10, weight[1]);
-        let d1 = interpolate::linear(d01, d11, weight[1]);
-        let d = interpolate::linear(d0, d1, weight[2]);
+    }
+}
 
-        d * 2.0 - 1.0
+#[inline(always)]
+fn value_3d<H>(hasher: &mut H, point: [f64; 3]) -> f64
+where
+    H: CellHasher<3>,
+{
+    #[inline(always)]
+    fn get<H: CellHasher<3>>(hasher: &H, offset: [isize; 3]) -> f64 {
+        hasher.hash(offset) as f64 / 255.0
     }
+
+    let floored = math::map3(point, f64::floor);
+    hasher.set_cell(math::to_isize3(floored));
+    let weight = math::sub3(point, floored).map_quintic();
+
+    let f000 = get(hasher, [0, 0, 0]);
+    let f100 = get(hasher, [1, 0, 0]);
+    let f010 = get(hasher, [0, 1, 0]);
+    let f110 = get(hasher, [1, 1, 0]);
+    let f001 = get(hasher, [0, 0, 1]);
+    let f101 = get(hasher, [1, 0, 1]);
+    let f011 = get(hasher, [0, 1, 1]);
+    let f111 = get(hasher, [1, 1, 1]);
+
+    let d00 = interpolate::linear(f000, f100, weight[0]);
+    let d01 = interpolate::linear(f001, f101, weight[0]);
+    let d10 = interpolate::linear(f010, f110, weight[0]);
+    let d11 = interpolate::linear(f011, f111, weight[0]);
+    let d0 = interpolate::linear(d00, d10, weight[1]);
+    let d1 = interpolate::linear(d01, d11, weight[1]);
+    let d = interpolate::linear(d0, d1, weight[2]);
+
+    d * 2.0 - 1.0
 }
 
 /// 4-dimensional value noise
-impl NoiseFn<f64, 4> for Value {
+impl<H: NoiseHasher> NoiseFn<f64, 4> for Value<H> {
     fn get(&self, point: [f64; 4]) -> f64 {
-        fn get(perm_table: &PermutationTable, corner: [isize; 4]) -> f64 {
-            perm_table.hash(&corner) as f64 / 255.0
-        }
+        value_4d(&mut DirectHasher::new(&self.periodic_hasher()), point)
+    }
 
-        let floored = math::map4(point, f64::floor);
-        let near_corner = math::to_isize4(floored);
-        let far_corner = math::add4(near_corner, math::one4());
-        let weight = math::sub4(point, floored).map_quintic();
-
-        let f0000 = get(
-            &self.perm_table,
-            [
-                near_corner[0],
-                near_corner[1],
-                near_corner[2],
-                near_corner[3],
-            ],
-        );
-        let f1000 = get(
-            &self.perm_table,
-            [
-                far_corner[0],
-                near_corner[1],
-                near_corner[2],
-                near_corner[3],
-            ],
-        );
-        let f0100 = get(
-            &self.perm_table,
-            [
-                near_corner[0],
-                far_corner[1],
-                near_corner[2],
-                near_corner[3],
-            ],
-        );
-        let f1100 = get(
-            &self.perm_table,
-            [far_corner[0], far_corner[1], near_corner[2], near_corner[3]],
-        );
-        let f0010 = get(
-            &self.perm_table,
-            [
-                near_corner[0],
-                near_corner[1],
-                far_corner[2],
-                near_corner[3],
-            ],
-        );
-        let f1010 = get(
-            &self.perm_table,
-            [far_corner[0], near_corner[1], far_corner[2], near_corner[3]],
-        );
-        let f0110 = get(
-            &self.perm_table,
-            [near_corner[0], far_corner[1], far_corner[2], near_corner[3]],
-        );
-        let f1110 = get(
-            &self.perm_table,
-            [far_corner[0], far_corner[1], far_corner[2], near_corner[3]],
-        );
-        let f0001 = get(
-            &self.perm_table,
-            [
-                near_corner[0],
-                near_corner[1],
-                near_corner[2],
-                far_corner[3],
-            ],
-        );
-        let f1001 = get(
-            &self.perm_table,
-            [far_corner[0], near_corner[1], near_corner[2], far_corner[3]],
-        );
-        let f0101 = get(
-            &self.perm_table,
-            [near_corner[0], far_corner[1], near_corner[2], far_corner[3]],
-        );
-        let f1101 = get(
-            &self.perm_table,
-            [far_corner[0], far_corner[1], near_corner[2], far_corner[3]],
-        );
-        let f0011 = get(
-            &self.perm_table,
-            [near_corner[0], near_corner[1], far_corner[2], far_corner[3]],
-        );
-        let f1011 = get(
-            &self.perm_table,
-            [far_corner[0], near_corner[1], far_corner[2], far_corner[3]],
-        );
-        let f0111 = get(
-            &self.perm_table,
-            [near_corner[0], far_corner[1], far_corner[2], far_corner[3]],
-        );
-        let f1111 = get(
-            &self.perm_table,
-            [far_corner[0], far_corner[1], far_corner[2], far_corner[3]],
+    fn get_batch(&self, points: &[[f64; 4]], output: &mut [f64]) {
+        let periodic = self.periodic_hasher();
+        let mut hasher = CachedHasher::<_, 4, 16>::new(&periodic, 0);
+        evaluate_batch_lanes(
+            points,
+            output,
+            &mut hasher,
+            value_4d,
+            value_lanes::<_, 4, 16>,
         );
+    }
+}
 
-        let d000 = interpolate::linear(f0000, f1000, weight[0]);
-        let d010 = interpolate::linear(f0010, f1010, weight[0]);
-        let d100 = interpolate::linear(f0100, f1100, weight[0]);
-        let d110 = interpolate::linear(f0110, f1110, weight[0]);
-        let d001 = interpolate::linear(f0001, f1001, weight[0]);
-        let d011 = interpolate::linear(f0011, f1011, weight[0]);
-        let d101 = interpolate::linear(f0101, f1101, weight[0]);
-        let d111 = interpolate::linear(f0111, f1111, weight[0]);
-        let d00 = interpolate::linear(d000, d100, weight[1]);
-        let d10 = interpolate::linear(d010, d110, weight[1]);
-        let d01 = interpolate::linear(d001, d101, weight[1]);
-        let d11 = interpolate::linear(d011, d111, weight[1]);
-        let d0 = interpolate::linear(d00, d10, weight[2]);
-        let d1 = interpolate::linear(d01, d11, weight[2]);
-        let d = interpolate::linear(d0, d1, weight[3]);
-
-        d * 2.0 - 1.0
+/// value noise at `f32` coordinates
+impl<H, const DIM: usize> NoiseFn<f32, DIM> for Value<H>
+where
+    Self: NoiseFn<f64, DIM>,
+{
+    fn get(&self, point: [f32; DIM]) -> f64 {
+        get_widened(self, point)
     }
+
+    fn get_batch(&self, points: &[[f32; DIM]], output: &mut [f64]) {
+        evaluate_batch_widened(self, points, output);
+    }
+}
+
+#[inline(always)]
+fn value_4d<H>(hasher: &mut H, point: [f64; 4]) -> f64
+where
+    H: CellHasher<4>,
+{
+    #[inline(always)]
+    fn get<H: CellHasher<4>>(hasher: &H, offset: [isize; 4]) -> f64 {
+        hasher.hash(offset) as f64 / 255.0
+    }
+
+    let floored = math::map4(point, f64::floor);
+    hasher.set_cell(math::to_isize4(floored));
+    let weight = math::sub4(point, floored).map_quintic();
+
+    let f0000 = get(hasher, [0, 0, 0, 0]);
+    let f1000 = get(hasher, [1, 0, 0, 0]);
+    let f0100 = get(hasher, [0, 1, 0, 0]);
+    let f1100 = get(hasher, [1, 1, 0, 0]);
+    let f0010 = get(hasher, [0, 0, 1, 0]);
+    let f1010 = get(hasher, [1, 0, 1, 0]);
+    let f0110 = get(hasher, [0, 1, 1, 0]);
+    let f1110 = get(hasher, [1, 1, 1, 0]);
+    let f0001 = get(hasher, [0, 0, 0, 1]);
+    let f1001 = get(hasher, [1, 0, 0, 1]);
+    let f0101 = get(hasher, [0, 1, 0, 1]);
+    let f1101 = get(hasher, [1, 1, 0, 1]);
+    let f0011 = get(hasher, [0, 0, 1, 1]);
+    let f1011 = get(hasher, [1, 0, 1, 1]);
+    let f0111 = get(hasher, [0, 1, 1, 1]);
+    let f1111 = get(hasher, [1, 1, 1, 1]);
+
+    let d000 = interpolate::linear(f0000, f1000, weight[0]);
+    let d010 = interpolate::linear(f0010, f1010, weight[0]);
+    let d100 = interpolate::linear(f0100, f1100, weight[0]);
+    let d110 = interpolate::linear(f0110, f1110, weight[0]);
+    let d001 = interpolate::linear(f0001, f1001, weight[0]);
+    let d011 = interpolate::linear(f0011, f1011, weight[0]);
+    let d101 = interpolate::linear(f0101, f1101, weight[0]);
+    let d111 = interpolate::linear(f0111, f1111, weight[0]);
+    let d00 = interpolate::linear(d000, d100, weight[1]);
+    let d10 = interpolate::linear(d010, d110, weight[1]);
+    let d01 = interpolate::linear(d001, d101, weight[1]);
+    let d11 = interpolate::linear(d011, d111, weight[1]);
+    let d0 = interpolate::linear(d00, d10, weight[2]);
+    let d1 = interpolate::linear(d01, d11, weight[2]);
+    let d = interpolate::linear(d0, d1, weight[3]);
+
+    d * 2.0 - 1.0
+}
+
+/// Evaluates `DIM`-dimensional value noise at `LANES` points at once.
+///
+/// `CORNERS` must equal `2^DIM`.
+#[inline(always)]
+fn value_lanes<H, const DIM: usize, const CORNERS: usize>(
+    hasher: &mut H,
+    points: &[[f64; DIM]; LANES],
+) -> Lanes
+where
+    H: CellHasher<DIM>,
+{
+    let (cells, distance) = lanes::split_cells(points);
+
+    let mut values = [Lanes::default(); CORNERS];
+    for (lane, &cell) in cells.iter().enumerate() {
+        hasher.set_cell(cell);
+        for (index, value) in values.iter_mut().enumerate() {
+            value[lane] = hasher.hash(lanes::corner_offset(index)) as f64 / 255.0;
+        }
+    }
+
+    let mut weights = distance;
+    for weight in weights.iter_mut() {
+        *weight = lanes::quintic(*weight);
+    }
+
+    lanes::interpolate_corners(values, weights).map(|d| d * 2.0 - 1.0)
+}
+
+/// 1-dimensional value noise, with its gradient
+impl<H: NoiseHasher> NoiseFnGradient<1> for Value<H> {
+    fn get_with_gradient(&self, point: [f64; 1]) -> (f64, [f64; 1]) {
+        let periodic = self.periodic_hasher();
+        let mut hasher = DirectHasher::new(&periodic);
+        value_gradient::<_, 1, 2>(&mut hasher, point).into_tuple()
+    }
+}
+
+/// 2-dimensional value noise, with its gradient
+impl<H: NoiseHasher> NoiseFnGradient<2> for Value<H> {
+    fn get_with_gradient(&self, point: [f64; 2]) -> (f64, [f64; 2]) {
+        let periodic = self.periodic_hasher();
+        let mut hasher = DirectHasher::new(&periodic);
+        value_gradient::<_, 2, 4>(&mut hasher, point).into_tuple()
+    }
+}
+
+/// 3-dimensional value noise, with its gradient
+impl<H: NoiseHasher> NoiseFnGradient<3> for Value<H> {
+    fn get_with_gradient(&self, point: [f64; 3]) -> (f64, [f64; 3]) {
+        let periodic = self.periodic_hasher();
+        let mut hasher = DirectHasher::new(&periodic);
+        value_gradient::<_, 3, 8>(&mut hasher, point).into_tuple()
+    }
+}
+
+/// 4-dimensional value noise, with its gradient
+impl<H: NoiseHasher> NoiseFnGradient<4> for Value<H> {
+    fn get_with_gradient(&self, point: [f64; 4]) -> (f64, [f64; 4]) {
+        let periodic = self.periodic_hasher();
+        let mut hasher = DirectHasher::new(&periodic);
+        value_gradient::<_, 4, 16>(&mut hasher, point).into_tuple()
+    }
+}
+
+/// Like [`value_lanes`], but for a single point, with its gradient.
+#[inline(always)]
+fn value_gradient<H, const DIM: usize, const CORNERS: usize>(
+    hasher: &mut H,
+    point: [f64; DIM],
+) -> Dual<DIM>
+where
+    H: CellHasher<DIM>,
+{
+    let mut cell = [0; DIM];
+    let mut distance = point;
+    for axis in 0..DIM {
+        let floored = point[axis].floor();
+        cell[axis] = floored as isize;
+        distance[axis] = point[axis] - floored;
+    }
+    hasher.set_cell(cell);
+
+    let mut values = [Dual::constant(0.0); CORNERS];
+    for (index, value) in values.iter_mut().enumerate() {
+        *value = Dual::constant(hasher.hash(lanes::corner_offset(index)) as f64 / 255.0);
+    }
+
+    lanes::interpolate_corners(values, dual::quintic(distance)) * 2.0 - Dual::constant(1.0)
 }
@@ -1,53 +1,140 @@
 use crate::{
     math,
-    noise_fns::{NoiseFn, Seedable},
-    permutationtable::{NoiseHasher, PermutationTable},
+    noise_fns::{evaluate_batch, evaluate_batch_widened, get_widened, NoiseFn, Seedable},
+    permutationtable::{
+        CachedHasher, CellHasher, DirectHasher, NoiseHasher, PermutationTable, SeedableHasher,
+    },
 };
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+use std::{any::TypeId, fmt, sync::Arc};
+
+/// Evaluates `$body` with `$metric` bound to the [`Metric`] matching a
+/// [`DistanceFunction`], so that the search for the nearest seed points is
+/// compiled separately for each built-in distance function.
+macro_rules! with_metric {
+    ($distance_function:expr, |$metric:ident| $body:expr) => {
+        match $distance_function {
+            DistanceFunction::Euclidean => {
+                let $metric = metrics::Euclidean;
+                $body
+            }
+            DistanceFunction::EuclideanSquared => {
+                let $metric = metrics::EuclideanSquared;
+                $body
+            }
+            DistanceFunction::Manhattan => {
+                let $metric = metrics::Manhattan;
+                $body
+            }
+            DistanceFunction::Chebyshev => {
+                let $metric = metrics::Chebyshev;
+                $body
+            }
+            DistanceFunction::Quadratic => {
+                let $metric = metrics::Quadratic;
+                $body
+            }
+            DistanceFunction::Minkowski(exponent) => {
+                let $metric = metrics::Minkowski(*exponent);
+                $body
+            }
+            DistanceFunction::Custom(function) => {
+                let $metric = &**function;
+                $body
+            }
+        }
+    };
+}
 
 /// Noise function that outputs Worley noise.
-pub struct Worley {
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "WorleyParams", bound(deserialize = "H: SeedableHasher"))
+)]
+pub struct Worley<H = PermutationTable> {
     /// Specifies the distance function to use when calculating the boundaries of
     /// the cell.
-    pub distance_function: Box<dyn Fn(&[f64], &[f64]) -> f64>,
+    pub distance_function: DistanceFunction,
 
-    /// Signifies whether the distance from the borders of the cell should be returned, or the
-    /// value for the cell.
+    /// Signifies whether the distances to the nearest seed points should be returned, or the
+    /// value for the cell. See [`ReturnType`].
     pub return_type: ReturnType,
 
     /// Frequency of the seed points.
     pub frequency: f64,
 
-    seed: u32,
-    perm_table: PermutationTable,
+    /// How far each seed point may be from its lattice point, from 0 for a
+    /// regular grid to 1 for anywhere within half a cell of it.
+    pub jitter: f64,
+
+    /// How many seed points are placed in each cell.
+    pub seed_points: SeedPoints,
+
+    seed: u64,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    hasher: H,
 }
 
 impl Worley {
-    pub const DEFAULT_SEED: u32 = 0;
+    pub const DEFAULT_SEED: u64 = 0;
     pub const DEFAULT_FREQUENCY: f64 = 1.0;
+    pub const DEFAULT_JITTER: f64 = 1.0;
+
+    pub fn new(seed: u64) -> Self {
+        Self::from_seed(seed)
+    }
+}
 
-    pub fn new(seed: u32) -> Self {
+impl<H: SeedableHasher> Worley<H> {
+    /// Creates the noise function with `seed`, hashing the lattice with any
+    /// [`SeedableHasher`] rather than the default [`PermutationTable`].
+    pub fn from_seed(seed: u64) -> Self {
         Self {
-            perm_table: PermutationTable::new(seed),
+            hasher: H::from_seed(seed),
             seed,
-            distance_function: Box::new(distance_functions::euclidean),
+            distance_function: DistanceFunction::Euclidean,
             return_type: ReturnType::Value,
-            frequency: Self::DEFAULT_FREQUENCY,
+            frequency: Worley::DEFAULT_FREQUENCY,
+            jitter: Worley::DEFAULT_JITTER,
+            seed_points: SeedPoints::One,
         }
     }
+}
 
-    /// Sets the distance function used by the Worley cells.
+impl<H> Worley<H> {
+    /// Sets the distance function used by the Worley cells, either one of the
+    /// built-in [`DistanceFunction`]s or any function taking two points.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the exponent of [`DistanceFunction::Minkowski`] is not finite
+    /// and at least 1.
     pub fn set_distance_function<F>(self, function: F) -> Self
     where
-        F: Fn(&[f64], &[f64]) -> f64 + 'static,
+        F: Into<DistanceFunction>,
     {
+        let distance_function = function.into();
+        if let DistanceFunction::Minkowski(exponent) = distance_function {
+            assert!(
+                exponent >= 1.0 && exponent.is_finite(),
+                "the Minkowski exponent must be finite and at least 1, got {}",
+                exponent
+            );
+        }
+
         Self {
-            distance_function: Box::new(function),
+            distance_function,
             ..self
         }
     }
 
-    /// Enables or disables applying the distance from the nearest seed point
-    /// to the output value.
+    /// Sets whether the output is the value for the cell or computed from the
+    /// distances to the nearest seed points.
     pub fn set_return_type(self, return_type: ReturnType) -> Self {
         Self {
             return_type,
@@ -59,42 +146,714 @@ impl Worley {
     pub fn set_frequency(self, frequency: f64) -> Self {
         Self { frequency, ..self }
     }
+
+    /// Sets how far each seed point may be from its lattice point, clamped to
+    /// the range from 0 for a regular grid to 1 for fully random cells.
+    pub fn set_jitter(self, jitter: f64) -> Self {
+        Self {
+            jitter: jitter.clamp(0.0, 1.0),
+            ..self
+        }
+    }
+
+    /// Sets how many seed points are placed in each cell.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mean of [`SeedPoints::Poisson`] is not positive and
+    /// finite.
+    pub fn set_seed_points(self, seed_points: SeedPoints) -> Self {
+        if let SeedPoints::Poisson(mean) = seed_points {
+            assert!(
+                mean > 0.0 && mean.is_finite(),
+                "the mean number of seed points must be positive and finite, got {}",
+                mean
+            );
+        }
+
+        Self {
+            seed_points,
+            ..self
+        }
+    }
+
+    /// Whether the nearest seed points have to be searched for in all the
+    /// cells around the input value.
+    fn searches_nearby_cells(&self) -> bool {
+        self.seed_points != SeedPoints::One || self.return_type.uses_nearest_distances()
+    }
+}
+
+impl<H: NoiseHasher> Worley<H> {
+    /// Evaluates `worley` at every point in `points`, reusing `hasher`.
+    fn get_batch_with<C, F, const DIM: usize>(
+        &self,
+        mut hasher: C,
+        points: &[[f64; DIM]],
+        output: &mut [f64],
+        worley: F,
+    ) where
+        F: Fn(&mut C, &Self, [f64; DIM]) -> f64,
+    {
+        evaluate_batch(points, output, |mut point| {
+            for coordinate in point.iter_mut() {
+                *coordinate *= self.frequency;
+            }
+            worley(&mut hasher, self, point)
+        });
+    }
+}
+
+impl<H: NoiseHasher> Worley<H> {
+    /// Returns the cell containing a 2-dimensional point.
+    pub fn cell_2d(&self, point: [f64; 2]) -> WorleyCell<2> {
+        self.find_cell(point, get_vec2)
+    }
+
+    /// Returns the cell containing a 3-dimensional point.
+    pub fn cell_3d(&self, point: [f64; 3]) -> WorleyCell<3> {
+        self.find_cell(point, get_vec3)
+    }
+
+    /// Returns the cell containing a 4-dimensional point.
+    pub fn cell_4d(&self, point: [f64; 4]) -> WorleyCell<4> {
+        self.find_cell(point, get_vec4)
+    }
+
+    fn find_cell<const DIM: usize>(
+        &self,
+        point: [f64; DIM],
+        get_vec: fn(usize) -> [f64; DIM],
+    ) -> WorleyCell<DIM> {
+        let mut scaled = point;
+        let mut whole = [0; DIM];
+        let mut near = [0; DIM];
+        for axis in 0..DIM {
+            scaled[axis] *= self.frequency;
+            let cell = scaled[axis].floor();
+            whole[axis] = cell as isize;
+            near[axis] = (scaled[axis] - cell > 0.5) as isize;
+        }
+
+        let mut hasher = DirectHasher::new(&self.hasher);
+        hasher.set_cell(whole);
+        let lattice_point = |offset: [isize; DIM]| {
+            let mut lattice = [0.0; DIM];
+            for axis in 0..DIM {
+                lattice[axis] = (whole[axis] + offset[axis]) as f64;
+            }
+            (hasher.hash(offset), lattice)
+        };
+        let nearest = with_metric!(&self.distance_function, |metric| {
+            find_nearest(self, metric, scaled, whole, near, 1, lattice_point, get_vec)
+        });
+
+        // The border between the nearest seed point and another one is the
+        // plane halfway between them. Any seed point sharing a border with
+        // the cell lies within two lattice steps of its own lattice point.
+        let seed = nearest.seed_point;
+        let mut edge_distance = f64::INFINITY;
+        let mut edge_vector = [0.0; DIM];
+        for_each_offset(nearest.offset, 2, |offset| {
+            let (cell_hash, lattice) = lattice_point(offset);
+            for_each_seed_point(self, cell_hash, lattice, get_vec, |_, other| {
+                let mut normal = [0.0; DIM];
+                for axis in 0..DIM {
+                    normal[axis] = other[axis] - seed[axis];
+                }
+                let length = normal.iter().map(|n| n * n).sum::<f64>().sqrt();
+                if length == 0.0 {
+                    return;
+                }
+
+                let mut distance = 0.0;
+                for axis in 0..DIM {
+                    normal[axis] /= length;
+                    distance += ((seed[axis] + other[axis]) * 0.5 - scaled[axis]) * normal[axis];
+                }
+                if distance < edge_distance {
+                    edge_distance = distance;
+                    for axis in 0..DIM {
+                        edge_vector[axis] = normal[axis] * distance;
+                    }
+                }
+            });
+        });
+
+        let mut cell = whole;
+        let mut seed_point = seed;
+        for axis in 0..DIM {
+            cell[axis] += nearest.offset[axis];
+            seed_point[axis] /= self.frequency;
+            edge_vector[axis] /= self.frequency;
+        }
+
+        WorleyCell {
+            cell,
+            seed_point,
+            value: nearest.hash as f64 / 255.0 * 2.0 - 1.0,
+            edge_distance: edge_distance / self.frequency,
+            edge_vector,
+        }
+    }
 }
 
 impl Default for Worley {
     fn default() -> Self {
-        Self::new(0)
+        Self::from_seed(Self::DEFAULT_SEED)
     }
 }
 
-impl Seedable for Worley {
+impl<H: SeedableHasher> Seedable for Worley<H> {
     /// Sets the seed value used by the Worley cells.
-    fn set_seed(self, seed: u32) -> Self {
+    fn set_seed(self, seed: u64) -> Self {
         // If the new seed is the same as the current seed, just return self.
         if self.seed == seed {
             return self;
         }
 
-        // Otherwise, regenerate the permutation table based on the new seed.
+        // Otherwise, regenerate the hasher based on the new seed.
         Self {
-            perm_table: PermutationTable::new(seed),
+            hasher: H::from_seed(seed),
             seed,
             ..self
         }
     }
 
-    fn seed(&self) -> u32 {
+    fn seed(&self) -> u64 {
         self.seed
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct WorleyParams {
+    distance_function: DistanceFunction,
+    return_type: ReturnType,
+    frequency: f64,
+    jitter: f64,
+    seed_points: SeedPoints,
+    seed: u64,
+}
+
+#[cfg(feature = "serde")]
+impl Default for WorleyParams {
+    fn default() -> Self {
+        Self {
+            distance_function: DistanceFunction::Euclidean,
+            return_type: ReturnType::Value,
+            frequency: Worley::DEFAULT_FREQUENCY,
+            jitter: Worley::DEFAULT_JITTER,
+            seed_points: SeedPoints::One,
+            seed: Worley::DEFAULT_SEED,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<H: SeedableHasher> TryFrom<WorleyParams> for Worley<H> {
+    type Error = String;
+
+    fn try_from(params: WorleyParams) -> Result<Self, Self::Error> {
+        if !params.frequency.is_finite() {
+            return Err(format!(
+                "Worley frequency must be finite, got {}",
+                params.frequency
+            ));
+        }
+
+        if !(0.0..=1.0).contains(&params.jitter) {
+            return Err(format!(
+                "Worley jitter must be between 0 and 1, got {}",
+                params.jitter
+            ));
+        }
+        if let DistanceFunction::Minkowski(exponent) = params.distance_function {
+            if !(exponent >= 1.0 && exponent.is_finite()) {
+                return Err(format!(
+                    "Worley Minkowski exponent must be finite and at least 1, got {}",
+                    exponent
+                ));
+            }
+        }
+        if let SeedPoints::Poisson(mean) = params.seed_points {
+            if !(mean > 0.0 && mean.is_finite()) {
+                return Err(format!(
+                    "Worley mean number of seed points must be positive and finite, got {}",
+                    mean
+                ));
+            }
+        }
+
+        Ok(Self::from_seed(params.seed)
+            .set_distance_function(params.distance_function)
+            .set_return_type(params.return_type)
+            .set_frequency(params.frequency)
+            .set_jitter(params.jitter)
+            .set_seed_points(params.seed_points))
+    }
+}
+
+/// A user-supplied distance function, see [`DistanceFunction::Custom`].
+pub type CustomDistanceFunction = dyn Fn(&[f64], &[f64]) -> f64 + Send + Sync;
+
+/// The distance function used by [`Worley`] to find the nearest seed point.
+///
+/// The built-in functions match those in [`distance_functions`]. Only they can
+/// be serialized; a `Custom` function can't. Worley noise is compiled
+/// separately for each built-in function, so they are much faster than a
+/// `Custom` one.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DistanceFunction {
+    Euclidean,
+    EuclideanSquared,
+    Manhattan,
+    Chebyshev,
+    Quadratic,
+    /// The Minkowski distance with the given exponent, which must be no less
+    /// than one. An exponent of 1 gives the Manhattan distance and 2 the
+    /// Euclidean distance, while larger ones approach the Chebyshev distance.
+    Minkowski(f64),
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(Arc<CustomDistanceFunction>),
+}
+
+impl DistanceFunction {
+    /// Returns the distance between `p1` and `p2`.
+    #[inline]
+    pub fn distance(&self, p1: &[f64], p2: &[f64]) -> f64 {
+        match self {
+            DistanceFunction::Euclidean => distance_functions::euclidean(p1, p2),
+            DistanceFunction::EuclideanSquared => distance_functions::euclidean_squared(p1, p2),
+            DistanceFunction::Manhattan => distance_functions::manhattan(p1, p2),
+            DistanceFunction::Chebyshev => distance_functions::chebyshev(p1, p2),
+            DistanceFunction::Quadratic => distance_functions::quadratic(p1, p2),
+            DistanceFunction::Minkowski(exponent) => {
+                distance_functions::minkowski(p1, p2, *exponent)
+            }
+            DistanceFunction::Custom(function) => function(p1, p2),
+        }
+    }
+}
+
+/// Wraps any function taking two points in [`DistanceFunction::Custom`],
+/// except the functions in [`distance_functions`] themselves, which map to the
+/// matching built-in variant so that they keep their fast path.
+impl<F> From<F> for DistanceFunction
+where
+    F: Fn(&[f64], &[f64]) -> f64 + Send + Sync + 'static,
+{
+    fn from(function: F) -> Self {
+        fn is<F: 'static, G: 'static>(_: G) -> bool {
+            TypeId::of::<F>() == TypeId::of::<G>()
+        }
+
+        if is::<F, _>(distance_functions::euclidean) {
+            DistanceFunction::Euclidean
+        } else if is::<F, _>(distance_functions::euclidean_squared) {
+            DistanceFunction::EuclideanSquared
+        } else if is::<F, _>(distance_functions::manhattan) {
+            DistanceFunction::Manhattan
+        } else if is::<F, _>(distance_functions::chebyshev) {
+            DistanceFunction::Chebyshev
+        } else if is::<F, _>(distance_functions::quadratic) {
+            DistanceFunction::Quadratic
+        } else {
+            DistanceFunction::Custom(Arc::new(function))
+        }
+    }
+}
+
+impl fmt::Debug for DistanceFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DistanceFunction::Euclidean => f.write_str("Euclidean"),
+            DistanceFunction::EuclideanSquared => f.write_str("EuclideanSquared"),
+            DistanceFunction::Manhattan => f.write_str("Manhattan"),
+            DistanceFunction::Chebyshev => f.write_str("Chebyshev"),
+            DistanceFunction::Quadratic => f.write_str("Quadratic"),
+            DistanceFunction::Minkowski(exponent) => {
+                f.debug_tuple("Minkowski").field(exponent).finish()
+            }
+            DistanceFunction::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+/// A distance function picked out of a [`DistanceFunction`] before searching
+/// for the nearest seed points, so that the built-in ones are inlined.
+trait Metric: Copy {
+    fn distance<const DIM: usize>(self, p1: &[f64; DIM], p2: &[f64; DIM]) -> f64;
+}
+
+impl Metric for &(dyn Fn(&[f64], &[f64]) -> f64 + Send + Sync) {
+    #[inline]
+    fn distance<const DIM: usize>(self, p1: &[f64; DIM], p2: &[f64; DIM]) -> f64 {
+        self(p1, p2)
+    }
+}
+
+mod metrics {
+    use super::{distance_functions, Metric};
+
+    macro_rules! impl_metric {
+        ($name:ident, $function:ident) => {
+            #[derive(Clone, Copy)]
+            pub(super) struct $name;
+
+            impl Metric for $name {
+                #[inline(always)]
+                fn distance<const DIM: usize>(self, p1: &[f64; DIM], p2: &[f64; DIM]) -> f64 {
+                    distance_functions::$function(p1, p2)
+                }
+            }
+        };
+    }
+
+    impl_metric!(Euclidean, euclidean);
+    impl_metric!(EuclideanSquared, euclidean_squared);
+    impl_metric!(Manhattan, manhattan);
+    impl_metric!(Chebyshev, chebyshev);
+    impl_metric!(Quadratic, quadratic);
+
+    #[derive(Clone, Copy)]
+    pub(super) struct Minkowski(pub(super) f64);
+
+    impl Metric for Minkowski {
+        #[inline(always)]
+        fn distance<const DIM: usize>(self, p1: &[f64; DIM], p2: &[f64; DIM]) -> f64 {
+            distance_functions::minkowski(p1, p2, self.0)
+        }
+    }
+}
+
+/// The output of [`Worley`], computed from the seed points nearest to the
+/// input value.
+///
+/// The distances to the nearest seed points are commonly called F1, F2 and so
+/// on, F1 being the distance to the nearest one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum ReturnType {
+    /// The distance to the nearest seed point, F1.
     Distance,
+    /// A random value for the cell of the nearest seed point.
     Value,
+    /// The distance to the second nearest seed point, F2.
+    Distance2,
+    /// F2 - F1, which is zero on the borders between cells.
+    Distance2Sub,
+    /// F1 * F2.
+    Distance2Mul,
+    /// The sum of the distances to the [`NEAREST_SEED_POINTS`] nearest seed
+    /// points, each multiplied by the matching weight, from F1 upwards.
+    WeightedDistances([f64; NEAREST_SEED_POINTS]),
+}
+
+/// The number of nearest seed points whose distances
+/// [`ReturnType::WeightedDistances`] combines.
+pub const NEAREST_SEED_POINTS: usize = 4;
+
+impl ReturnType {
+    /// Whether the return type needs more than the nearest seed point.
+    fn uses_nearest_distances(&self) -> bool {
+        !matches!(self, ReturnType::Distance | ReturnType::Value)
+    }
+
+    /// The number of nearest seed points the return type needs.
+    fn needed_seed_points(&self) -> usize {
+        match self {
+            ReturnType::Distance | ReturnType::Value => 1,
+            ReturnType::Distance2 | ReturnType::Distance2Sub | ReturnType::Distance2Mul => 2,
+            ReturnType::WeightedDistances(weights) => weights
+                .iter()
+                .rposition(|&weight| weight != 0.0)
+                .map_or(1, |index| index + 1),
+        }
+    }
+
+    /// Computes the output from the nearest seed points.
+    fn evaluate<const DIM: usize>(&self, nearest: &Nearest<DIM>) -> f64 {
+        let distances = nearest.distances;
+        match self {
+            ReturnType::Distance => distances[0],
+            ReturnType::Distance2 => distances[1],
+            ReturnType::Distance2Sub => distances[1] - distances[0],
+            ReturnType::Distance2Mul => distances[0] * distances[1],
+            // Seed points with a zero weight may not have been searched for,
+            // and infinitely far away.
+            ReturnType::WeightedDistances(weights) => weights
+                .iter()
+                .zip(distances.iter())
+                .filter(|(&weight, _)| weight != 0.0)
+                .map(|(weight, distance)| weight * distance)
+                .sum(),
+            ReturnType::Value => nearest.hash as f64 / 255.0,
+        }
+    }
+}
+
+/// How many seed points [`Worley`] places in each cell.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SeedPoints {
+    /// Exactly one seed point in every cell.
+    One,
+    /// A random number of seed points in each cell, following a Poisson
+    /// distribution with the given mean, as in Worley's original paper. Cells
+    /// hold at most nine seed points, and may hold none at all, in which case
+    /// the nearest seed points are searched for further away.
+    Poisson(f64),
+}
+
+/// The most seed points placed in a cell by [`SeedPoints::Poisson`].
+const MAX_POISSON_SEED_POINTS: usize = 9;
+
+impl SeedPoints {
+    /// The number of seed points in the cell with the given hash.
+    #[inline]
+    fn count(&self, cell_hash: usize) -> usize {
+        match *self {
+            SeedPoints::One => 1,
+            SeedPoints::Poisson(mean) => {
+                // Invert the cumulative distribution function at a uniformly
+                // distributed value between 0 and 1.
+                let uniform = (mix_hash(cell_hash, MAX_POISSON_SEED_POINTS) as f64 + 0.5) / 256.0;
+                let mut probability = (-mean).exp();
+                let mut cumulative = probability;
+                let mut count = 0;
+                while uniform > cumulative && count < MAX_POISSON_SEED_POINTS {
+                    count += 1;
+                    probability *= mean / count as f64;
+                    cumulative += probability;
+                }
+                count
+            }
+        }
+    }
+
+    /// The hash of the seed point with the given index in the cell with the
+    /// given hash, from which its position and value are chosen.
+    #[inline]
+    fn hash(&self, cell_hash: usize, index: usize) -> usize {
+        match self {
+            SeedPoints::One => cell_hash,
+            SeedPoints::Poisson(_) => mix_hash(cell_hash, index),
+        }
+    }
+}
+
+/// Derives a new hash in the range of the permutation table from a hash and
+/// an index.
+#[inline]
+fn mix_hash(hash: usize, index: usize) -> usize {
+    let mut mixed = (hash as u32) ^ (index as u32).wrapping_mul(0x9e37_79b9);
+    mixed ^= mixed >> 16;
+    mixed = mixed.wrapping_mul(0x85eb_ca6b);
+    mixed ^= mixed >> 13;
+    mixed = mixed.wrapping_mul(0xc2b2_ae35);
+    mixed ^= mixed >> 16;
+    (mixed & 0xff) as usize
+}
+
+/// The cell of [`Worley`] noise containing a point: the region of space
+/// closer to one seed point than to any other.
+///
+/// The border of the cell is where the distance to its seed point equals the
+/// distance to a neighbouring one, measured in a straight line. It only
+/// matches the output of the noise for the `euclidean` and
+/// `euclidean_squared` distance functions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WorleyCell<const DIM: usize> {
+    /// The coordinates of the lattice cell the seed point belongs to.
+    pub cell: [isize; DIM],
+
+    /// The position of the seed point.
+    pub seed_point: [f64; DIM],
+
+    /// The value of the cell, as output for [`ReturnType::Value`].
+    pub value: f64,
+
+    /// The distance from the point to the nearest border of the cell.
+    pub edge_distance: f64,
+
+    /// The vector from the point to the nearest point on the border of the
+    /// cell.
+    pub edge_vector: [f64; DIM],
+}
+
+/// The seed points nearest to the input value.
+struct Nearest<const DIM: usize> {
+    /// The distances to the nearest seed points, from the nearest.
+    distances: [f64; NEAREST_SEED_POINTS],
+    /// The hash of the nearest seed point.
+    hash: usize,
+    /// The position of the nearest seed point.
+    seed_point: [f64; DIM],
+    /// The offset of the lattice point of the nearest seed point from the
+    /// cell containing the input value.
+    offset: [isize; DIM],
+}
+
+/// Calls `f` with every offset that differs from `center` by at most `radius`
+/// along each axis.
+#[inline]
+fn for_each_offset<F, const DIM: usize>(center: [isize; DIM], radius: isize, mut f: F)
+where
+    F: FnMut([isize; DIM]),
+{
+    let width = (2 * radius + 1) as usize;
+    for index in 0..width.pow(DIM as u32) {
+        let mut offset = center;
+        let mut digits = index;
+        for coordinate in offset.iter_mut() {
+            *coordinate += (digits % width) as isize - radius;
+            digits /= width;
+        }
+        f(offset);
+    }
+}
+
+/// Calls `f` with the hash and position of every seed point belonging to the
+/// lattice point at `lattice`, whose hash is `cell_hash`. `get_vec` returns the
+/// offset of a seed point from its lattice point.
+#[inline]
+fn for_each_seed_point<T, F, const DIM: usize>(
+    worley: &Worley<T>,
+    cell_hash: usize,
+    lattice: [f64; DIM],
+    get_vec: fn(usize) -> [f64; DIM],
+    mut f: F,
+) where
+    F: FnMut(usize, [f64; DIM]),
+{
+    for index in 0..worley.seed_points.count(cell_hash) {
+        let hash = worley.seed_points.hash(cell_hash, index);
+        let mut seed_point = get_vec(hash);
+        for (coordinate, lattice) in seed_point.iter_mut().zip(lattice.iter()) {
+            *coordinate = *coordinate * worley.jitter + lattice;
+        }
+        f(hash, seed_point);
+    }
+}
+
+impl<const DIM: usize> Nearest<DIM> {
+    /// Adds a seed point at `distance` from the input value.
+    #[inline]
+    fn insert(
+        &mut self,
+        mut distance: f64,
+        hash: usize,
+        seed_point: [f64; DIM],
+        offset: [isize; DIM],
+    ) {
+        if distance < self.distances[0] {
+            self.hash = hash;
+            self.seed_point = seed_point;
+            self.offset = offset;
+        }
+        for nearest in self.distances.iter_mut() {
+            if distance < *nearest {
+                std::mem::swap(&mut distance, nearest);
+            }
+        }
+    }
+}
+
+/// The most lattice points [`find_nearest`] searches when cells hold too few
+/// seed points.
+const MAX_SEARCHED_CELLS: usize = 4096;
+
+/// Finds the [`NEAREST_SEED_POINTS`] seed points nearest to `point`, in the
+/// cell `cell`.
+///
+/// Seed points lie within half a cell of their lattice point, so this searches
+/// the lattice points one step away from `near`, the one nearest to `point`.
+/// This finds every seed point closer than one cell. `lattice_point` returns
+/// the hash and position of the lattice point at an offset from the cell, and
+/// `get_vec` the offset of a seed point from its lattice point.
+///
+/// With [`SeedPoints::Poisson`], cells may hold no seed points at all, so the
+/// search keeps widening one step at a time until at least `needed` seed
+/// points are found, or [`MAX_SEARCHED_CELLS`] lattice points have been
+/// searched.
+#[inline]
+#[allow(clippy::too_many_arguments)]
+fn find_nearest<T, L, M, const DIM: usize>(
+    worley: &Worley<T>,
+    metric: M,
+    point: [f64; DIM],
+    cell: [isize; DIM],
+    near: [isize; DIM],
+    needed: usize,
+    lattice_point: L,
+    get_vec: fn(usize) -> [f64; DIM],
+) -> Nearest<DIM>
+where
+    T: NoiseHasher,
+    L: Fn([isize; DIM]) -> (usize, [f64; DIM]),
+    M: Metric,
+{
+    let mut nearest = Nearest {
+        distances: [f64::INFINITY; NEAREST_SEED_POINTS],
+        hash: 0,
+        seed_point: point,
+        offset: near,
+    };
+
+    for_each_offset(near, 1, |offset| {
+        let (cell_hash, lattice) = lattice_point(offset);
+        for_each_seed_point(worley, cell_hash, lattice, get_vec, |hash, seed_point| {
+            nearest.insert(
+                metric.distance(&point, &seed_point),
+                hash,
+                seed_point,
+                offset,
+            );
+        });
+    });
+
+    // `lattice_point` may only reach one step away from `near`, so the lattice
+    // points further away are hashed directly.
+    let mut radius: usize = 1;
+    while nearest.distances[needed - 1] == f64::INFINITY
+        && (2 * radius + 3).pow(DIM as u32) <= MAX_SEARCHED_CELLS
+    {
+        radius += 1;
+        for_each_offset(near, radius as isize, |offset| {
+            let on_border = offset
+                .iter()
+                .zip(near.iter())
+                .any(|(offset, near)| (offset - near).abs() == radius as isize);
+            if !on_border {
+                return;
+            }
+
+            let mut lattice_cell = cell;
+            let mut lattice = [0.0; DIM];
+            for axis in 0..DIM {
+                lattice_cell[axis] += offset[axis];
+                lattice[axis] = lattice_cell[axis] as f64;
+            }
+            let cell_hash = worley.hasher.hash(&lattice_cell);
+            for_each_seed_point(worley, cell_hash, lattice, get_vec, |hash, seed_point| {
+                nearest.insert(
+                    metric.distance(&point, &seed_point),
+                    hash,
+                    seed_point,
+                    offset,
+                );
+            });
+        });
+    }
+
+    nearest
 }
 
 pub mod distance_functions {
+    #[inline]
     pub fn euclidean(p1: &[f64], p2: &[f64]) -> f64 {
         p1.iter()
             .zip(p2)
@@ -104,6 +863,7 @@ pub mod distance_functions {
             .sqrt()
     }
 
+    #[inline]
     pub fn euclidean_squared(p1: &[f64], p2: &[f64]) -> f64 {
         p1.iter()
             .zip(p2)
@@ -112,6 +872,7 @@ pub mod distance_functions {
             .fold(0.0, |acc, x| acc + x)
     }
 
+    #[inline]
     pub fn manhattan(p1: &[f64], p2: &[f64]) -> f64 {
         p1.iter()
             .zip(p2)
@@ -120,68 +881,242 @@ pub mod distance_functions {
             .fold(0.0, |acc, x| acc + x)
     }
 
+    #[inline]
     pub fn chebyshev(p1: &[f64], p2: &[f64]) -> f64 {
         p1.iter()
             .zip(p2)
             .map(|(a, b)| *a - *b)
             .map(|a| a.abs())
-            .fold(std::f64::MIN, |a, b| a.max(b))
+            .fold(f64::MIN, |a, b| a.max(b))
     }
 
+    #[inline]
     pub fn quadratic(p1: &[f64], p2: &[f64]) -> f64 {
-        let temp: Vec<f64> = p1.iter().zip(p2).map(|(a, b)| *a - *b).collect();
+        let differences = || p1.iter().zip(p2).map(|(a, b)| *a - *b);
 
         let mut result = 0.0;
 
-        for i in &temp {
-            for j in &temp {
-                result += *i * *j;
+        for i in differences() {
+            for j in differences() {
+                result += i * j;
             }
         }
 
         result
     }
+
+    /// The Minkowski distance of order `exponent`, the `exponent`-th root of
+    /// the sum of the `exponent`-th powers of the differences.
+    #[inline]
+    pub fn minkowski(p1: &[f64], p2: &[f64], exponent: f64) -> f64 {
+        p1.iter()
+            .zip(p2)
+            .map(|(a, b)| *a - *b)
+            .map(|a| a.abs().powf(exponent))
+            .fold(0.0, |acc, x| acc + x)
+            .powf(exponent.recip())
+    }
+}
+
+impl<H: NoiseHasher> NoiseFn<f64, 1> for Worley<H> {
+    fn get(&self, point: [f64; 1]) -> f64 {
+        with_metric!(&self.distance_function, |metric| worley_1d(
+            &mut DirectHasher::new(&self.hasher),
+            self,
+            metric,
+            [point[0] * self.frequency],
+        ))
+    }
+
+    fn get_batch(&self, points: &[[f64; 1]], output: &mut [f64]) {
+        // The nearest distances are searched for in the cells around the
+        // nearest lattice point, so cache a wider window of hashes for them.
+        if self.searches_nearby_cells() {
+            let hasher = CachedHasher::<_, 1, 4>::new(&self.hasher, -1);
+            with_metric!(&self.distance_function, |metric| {
+                self.get_batch_with(hasher, points, output, |hasher, worley, point| {
+                    worley_1d(hasher, worley, metric, point)
+                })
+            });
+        } else {
+            let hasher = CachedHasher::<_, 1, 2>::new(&self.hasher, 0);
+            with_metric!(&self.distance_function, |metric| {
+                self.get_batch_with(hasher, points, output, |hasher, worley, point| {
+                    worley_1d(hasher, worley, metric, point)
+                })
+            });
+        }
+    }
+}
+
+#[inline]
+fn worley_1d<H, M, T>(hasher: &mut H, worley: &Worley<T>, metric: M, point: [f64; 1]) -> f64
+where
+    T: NoiseHasher,
+    H: CellHasher<1>,
+    M: Metric,
+{
+    #[inline]
+    fn get_point<H: CellHasher<1>>(
+        hasher: &H,
+        jitter: f64,
+        whole: isize,
+        offset: isize,
+    ) -> [f64; 1] {
+        [get_vec1(hasher.hash([offset]))[0] * jitter + (whole + offset) as f64]
+    }
+
+    let return_type = worley.return_type;
+
+    let cell = point[0].floor();
+    let whole = cell as isize;
+    hasher.set_cell([whole]);
+    let frac = point[0] - cell;
+
+    let near = (frac > 0.5) as isize;
+
+    if worley.searches_nearby_cells() {
+        let lattice_point = |offset: [isize; 1]| {
+            let lattice = [(whole + offset[0]) as f64];
+            (hasher.hash(offset), lattice)
+        };
+        let needed = return_type.needed_seed_points();
+        let nearest = find_nearest(
+            worley,
+            metric,
+            point,
+            [whole],
+            [near],
+            needed,
+            lattice_point,
+            get_vec1,
+        );
+        return return_type.evaluate(&nearest) * 2.0 - 1.0;
+    }
+
+    let far = 1 - near;
+
+    let mut seed_cell = near;
+    let seed_point = get_point(hasher, worley.jitter, whole, near);
+    let mut distance = metric.distance(&point, &seed_point);
+
+    let x_distance = (0.5 - frac) * (0.5 - frac); // x-distance squared to center point
+
+    if x_distance < distance {
+        let cur_point = get_point(hasher, worley.jitter, whole, far);
+        let cur_distance = metric.distance(&point, &cur_point);
+        if cur_distance < distance {
+            distance = cur_distance;
+            seed_cell = far;
+        }
+    }
+
+    let value = match return_type {
+        ReturnType::Distance => distance,
+        ReturnType::Value => hasher.hash([seed_cell]) as f64 / 255.0,
+        _ => unreachable!("the nearest distances are handled above"),
+    };
+
+    value * 2.0 - 1.0
+}
+
+fn get_vec1(index: usize) -> [f64; 1] {
+    let length = ((index & 0xFE) >> 1) as f64 * 0.5 / 127.0;
+
+    if index & 0x01 == 0 {
+        [length]
+    } else {
+        [-length]
+    }
 }
 
-impl NoiseFn<f64, 2> for Worley {
+impl<H: NoiseHasher> NoiseFn<f64, 2> for Worley<H> {
     fn get(&self, point: [f64; 2]) -> f64 {
-        worley_2d(
-            &self.perm_table,
-            &self.distance_function,
-            self.return_type,
+        with_metric!(&self.distance_function, |metric| worley_2d(
+            &mut DirectHasher::new(&self.hasher),
+            self,
+            metric,
             math::mul2(point, self.frequency),
-        )
+        ))
+    }
+
+    fn get_batch(&self, points: &[[f64; 2]], output: &mut [f64]) {
+        // The nearest distances are searched for in the cells around the
+        // nearest lattice point, so cache a wider window of hashes for them.
+        if self.searches_nearby_cells() {
+            let hasher = CachedHasher::<_, 2, 16>::new(&self.hasher, -1);
+            with_metric!(&self.distance_function, |metric| {
+                self.get_batch_with(hasher, points, output, |hasher, worley, point| {
+                    worley_2d(hasher, worley, metric, point)
+                })
+            });
+        } else {
+            let hasher = CachedHasher::<_, 2, 4>::new(&self.hasher, 0);
+            with_metric!(&self.distance_function, |metric| {
+                self.get_batch_with(hasher, points, output, |hasher, worley, point| {
+                    worley_2d(hasher, worley, metric, point)
+                })
+            });
+        }
     }
 }
 
 #[inline]
-fn worley_2d<F>(
-    hasher: &dyn NoiseHasher,
-    distance_function: F,
-    return_type: ReturnType,
-    point: [f64; 2],
-) -> f64
+fn worley_2d<H, M, T>(hasher: &mut H, worley: &Worley<T>, metric: M, point: [f64; 2]) -> f64
 where
-    F: Fn(&[f64], &[f64]) -> f64,
+    T: NoiseHasher,
+    H: CellHasher<2>,
+    M: Metric,
 {
     #[inline]
-    fn get_point(hasher: &dyn NoiseHasher, whole: [isize; 2]) -> [f64; 2] {
-        math::add2(get_vec2(hasher.hash(&whole)), math::to_f64_2(whole))
+    fn get_point<H: CellHasher<2>>(
+        hasher: &H,
+        jitter: f64,
+        whole: [isize; 2],
+        offset: [isize; 2],
+    ) -> [f64; 2] {
+        math::add2(
+            math::mul2(get_vec2(hasher.hash(offset)), jitter),
+            math::to_f64_2(math::add2(whole, offset)),
+        )
     }
 
+    let return_type = worley.return_type;
+
     let cell = math::map2(point, f64::floor);
     let whole = math::to_isize2(cell);
+    hasher.set_cell(whole);
     let frac = math::sub2(point, cell);
 
     let x_half = frac[0] > 0.5;
     let y_half = frac[1] > 0.5;
 
-    let near = [whole[0] + (x_half as isize), whole[1] + (y_half as isize)];
-    let far = [whole[0] + (!x_half as isize), whole[1] + (!y_half as isize)];
+    let near = [x_half as isize, y_half as isize];
+
+    if worley.searches_nearby_cells() {
+        let lattice_point = |offset| {
+            let lattice = math::to_f64_2(math::add2(whole, offset));
+            (hasher.hash(offset), lattice)
+        };
+        let needed = return_type.needed_seed_points();
+        let nearest = find_nearest(
+            worley,
+            metric,
+            point,
+            whole,
+            near,
+            needed,
+            lattice_point,
+            get_vec2,
+        );
+        return return_type.evaluate(&nearest) * 2.0 - 1.0;
+    }
+
+    let far = [!x_half as isize, !y_half as isize];
 
     let mut seed_cell = near;
-    let seed_point = get_point(hasher, near);
-    let mut distance = distance_function(&point, &seed_point);
+    let seed_point = get_point(hasher, worley.jitter, whole, near);
+    let mut distance = metric.distance(&point, &seed_point);
 
     let x_distance = (0.5 - frac[0]) * (0.5 - frac[0]); // x-distance squared to center line
     let y_distance = (0.5 - frac[1]) * (0.5 - frac[1]); // y-distance squared to center line
@@ -189,8 +1124,8 @@ where
     macro_rules! test_point(
             [$x:expr, $y:expr] => {
                 {
-                    let cur_point = get_point(hasher, [$x, $y]);
-                    let cur_distance = distance_function(&point, &cur_point);
+                    let cur_point = get_point(hasher, worley.jitter, whole, [$x, $y]);
+                    let cur_distance = metric.distance(&point, &cur_point);
                     if cur_distance < distance {
                         distance = cur_distance;
                         seed_cell = [$x, $y];
@@ -213,7 +1148,8 @@ where
 
     let value = match return_type {
         ReturnType::Distance => distance,
-        ReturnType::Value => hasher.hash(&seed_cell) as f64 / 255.0,
+        ReturnType::Value => hasher.hash(seed_cell) as f64 / 255.0,
+        _ => unreachable!("the nearest distances are handled above"),
     };
 
     value * 2.0 - 1.0
@@ -237,53 +1173,93 @@ fn get_vec2(index: usize) -> [f64; 2] {
     }
 }
 
-impl NoiseFn<f64, 3> for Worley {
+impl<H: NoiseHasher> NoiseFn<f64, 3> for Worley<H> {
     fn get(&self, point: [f64; 3]) -> f64 {
-        worley_3d(
-            &self.perm_table,
-            &self.distance_function,
-            self.return_type,
+        with_metric!(&self.distance_function, |metric| worley_3d(
+            &mut DirectHasher::new(&self.hasher),
+            self,
+            metric,
             math::mul3(point, self.frequency),
-        )
+        ))
+    }
+
+    fn get_batch(&self, points: &[[f64; 3]], output: &mut [f64]) {
+        // The nearest distances are searched for in the cells around the
+        // nearest lattice point, so cache a wider window of hashes for them.
+        if self.searches_nearby_cells() {
+            let hasher = CachedHasher::<_, 3, 64>::new(&self.hasher, -1);
+            with_metric!(&self.distance_function, |metric| {
+                self.get_batch_with(hasher, points, output, |hasher, worley, point| {
+                    worley_3d(hasher, worley, metric, point)
+                })
+            });
+        } else {
+            let hasher = CachedHasher::<_, 3, 8>::new(&self.hasher, 0);
+            with_metric!(&self.distance_function, |metric| {
+                self.get_batch_with(hasher, points, output, |hasher, worley, point| {
+                    worley_3d(hasher, worley, metric, point)
+                })
+            });
+        }
     }
 }
 
 #[inline]
-fn worley_3d<F>(
-    hasher: &dyn NoiseHasher,
-    distance_function: F,
-    return_type: ReturnType,
-    point: [f64; 3],
-) -> f64
+fn worley_3d<H, M, T>(hasher: &mut H, worley: &Worley<T>, metric: M, point: [f64; 3]) -> f64
 where
-    F: Fn(&[f64], &[f64]) -> f64,
+    T: NoiseHasher,
+    H: CellHasher<3>,
+    M: Metric,
 {
-    fn get_point(hasher: &dyn NoiseHasher, whole: [isize; 3]) -> [f64; 3] {
-        math::add3(get_vec3(hasher.hash(&whole)), math::to_f64_3(whole))
+    fn get_point<H: CellHasher<3>>(
+        hasher: &H,
+        jitter: f64,
+        whole: [isize; 3],
+        offset: [isize; 3],
+    ) -> [f64; 3] {
+        math::add3(
+            math::mul3(get_vec3(hasher.hash(offset)), jitter),
+            math::to_f64_3(math::add3(whole, offset)),
+        )
     }
 
+    let return_type = worley.return_type;
+
     let cell = math::map3(point, f64::floor);
     let whole = math::to_isize3(cell);
+    hasher.set_cell(whole);
     let frac = math::sub3(point, cell);
 
     let x_half = frac[0] > 0.5;
     let y_half = frac[1] > 0.5;
     let z_half = frac[2] > 0.5;
 
-    let near = [
-        whole[0] + (x_half as isize),
-        whole[1] + (y_half as isize),
-        whole[2] + (z_half as isize),
-    ];
-    let far = [
-        whole[0] + (!x_half as isize),
-        whole[1] + (!y_half as isize),
-        whole[2] + (!z_half as isize),
-    ];
+    let near = [x_half as isize, y_half as isize, z_half as isize];
+
+    if worley.searches_nearby_cells() {
+        let lattice_point = |offset| {
+            let lattice = math::to_f64_3(math::add3(whole, offset));
+            (hasher.hash(offset), lattice)
+        };
+        let needed = return_type.needed_seed_points();
+        let nearest = find_nearest(
+            worley,
+            metric,
+            point,
+            whole,
+            near,
+            needed,
+            lattice_point,
+            get_vec3,
+        );
+        return return_type.evaluate(&nearest) * 2.0 - 1.0;
+    }
+
+    let far = [!x_half as isize, !y_half as isize, !z_half as isize];
 
     let mut seed_cell = near;
-    let seed_point = get_point(hasher, near);
-    let mut distance = distance_function(&point, &seed_point);
+    let seed_point = get_point(hasher, worley.jitter, whole, near);
+    let mut distance = metric.distance(&point, &seed_point);
 
     let x_distance = (0.5 - frac[0]) * (0.5 - frac[0]); // x-distance squared to center line
     let y_distance = (0.5 - frac[1]) * (0.5 - frac[1]); // y-distance squared to center line
@@ -292,8 +1268,8 @@ where
     macro_rules! test_point(
             [$x:expr, $y:expr, $z:expr] => {
                 {
-                    let cur_point = get_point(hasher, [$x, $y, $z]);
-                    let cur_distance = distance_function(&point, &cur_point);
+                    let cur_point = get_point(hasher, worley.jitter, whole, [$x, $y, $z]);
+                    let cur_distance = metric.distance(&point, &cur_point);
                     if cur_distance < distance {
                         distance = cur_distance;
                         seed_cell = [$x, $y, $z];
@@ -328,7 +1304,8 @@ where
 
     let value = match return_type {
         ReturnType::Distance => distance,
-        ReturnType::Value => hasher.hash(&seed_cell) as f64 / 255.0,
+        ReturnType::Value => hasher.hash(seed_cell) as f64 / 255.0,
+        _ => unreachable!("the nearest distances are handled above"),
     };
 
     value * 2.0 - 1.0
@@ -363,54 +1340,115 @@ fn get_vec3(index: usize) -> [f64; 3] {
 }
 
 #[allow(clippy::cognitive_complexity)]
-impl NoiseFn<f64, 4> for Worley {
+impl<H: NoiseHasher> NoiseFn<f64, 4> for Worley<H> {
     fn get(&self, point: [f64; 4]) -> f64 {
-        worley_4d(
-            &self.perm_table,
-            &self.distance_function,
-            self.return_type,
+        with_metric!(&self.distance_function, |metric| worley_4d(
+            &mut DirectHasher::new(&self.hasher),
+            self,
+            metric,
             math::mul4(point, self.frequency),
-        )
+        ))
+    }
+
+    fn get_batch(&self, points: &[[f64; 4]], output: &mut [f64]) {
+        // The nearest distances are searched for in the cells around the
+        // nearest lattice point, so cache a wider window of hashes for them.
+        if self.searches_nearby_cells() {
+            let hasher = CachedHasher::<_, 4, 256>::new(&self.hasher, -1);
+            with_metric!(&self.distance_function, |metric| {
+                self.get_batch_with(hasher, points, output, |hasher, worley, point| {
+                    worley_4d(hasher, worley, metric, point)
+                })
+            });
+        } else {
+            let hasher = CachedHasher::<_, 4, 16>::new(&self.hasher, 0);
+            with_metric!(&self.distance_function, |metric| {
+                self.get_batch_with(hasher, points, output, |hasher, worley, point| {
+                    worley_4d(hasher, worley, metric, point)
+                })
+            });
+        }
+    }
+}
+
+impl<H, const DIM: usize> NoiseFn<f32, DIM> for Worley<H>
+where
+    Self: NoiseFn<f64, DIM>,
+{
+    fn get(&self, point: [f32; DIM]) -> f64 {
+        get_widened(self, point)
+    }
+
+    fn get_batch(&self, points: &[[f32; DIM]], output: &mut [f64]) {
+        evaluate_batch_widened(self, points, output);
     }
 }
 
 #[inline]
 #[allow(clippy::cognitive_complexity)]
-fn worley_4d<F>(
-    hasher: &dyn NoiseHasher,
-    distance_function: F,
-    return_type: ReturnType,
-    point: [f64; 4],
-) -> f64
+fn worley_4d<H, M, T>(hasher: &mut H, worley: &Worley<T>, metric: M, point: [f64; 4]) -> f64
 where
-    F: Fn(&[f64], &[f64]) -> f64,
+    T: NoiseHasher,
+    H: CellHasher<4>,
+    M: Metric,
 {
-    fn get_point(hasher: &dyn NoiseHasher, whole: [isize; 4]) -> [f64; 4] {
-        math::add4(get_vec4(hasher.hash(&whole)), math::to_f64_4(whole))
+    fn get_point<H: CellHasher<4>>(
+        hasher: &H,
+        jitter: f64,
+        whole: [isize; 4],
+        offset: [isize; 4],
+    ) -> [f64; 4] {
+        math::add4(
+            math::mul4(get_vec4(hasher.hash(offset)), jitter),
+            math::to_f64_4(math::add4(whole, offset)),
+        )
     }
 
+    let return_type = worley.return_type;
+
     let cell = math::map4(point, f64::floor);
     let whole = math::to_isize4(cell);
+    hasher.set_cell(whole);
     let frac = math::sub4(point, cell);
 
     let half: Vec<bool> = frac.iter().map(|a| *a > 0.5).collect();
 
     let near = [
-        whole[0] + (half[0] as isize),
-        whole[1] + (half[1] as isize),
-        whole[2] + (half[2] as isize),
-        whole[3] + (half[3] as isize),
+        half[0] as isize,
+        half[1] as isize,
+        half[2] as isize,
+        half[3] as isize,
     ];
+
+    if worley.searches_nearby_cells() {
+        let lattice_point = |offset| {
+            let lattice = math::to_f64_4(math::add4(whole, offset));
+            (hasher.hash(offset), lattice)
+        };
+        let needed = return_type.needed_seed_points();
+        let nearest = find_nearest(
+            worley,
+            metric,
+            point,
+            whole,
+            near,
+            needed,
+            lattice_point,
+            get_vec4,
+        );
+        return return_type.evaluate(&nearest) * 2.0 - 1.0;
+    }
+
     let far = [
-        whole[0] + (!half[0] as isize),
-        whole[1] + (!half[0] as isize),
-        whole[2] + (!half[0] as isize),
-        whole[3] + (!half[0] as isize),
+        !half[0] as isize,
+        !half[0] as isize,
+        !half[0] as isize,
+        !half[0] as isize,
     ];
 
     let mut seed_cell = near;
-    let seed_point = get_point(hasher, near);
-    let mut distance = distance_function(&point, &seed_point);
+    let seed_point = get_point(hasher, worley.jitter, whole, near);
+    let mut distance = metric.distance(&point, &seed_point);
 
     // get distance squared to center line for each axis
     let center_distance = frac
@@ -421,8 +1459,8 @@ where
     macro_rules! test_point(
             [$x:expr, $y:expr, $z:expr, $w:expr] => {
                 {
-                    let cur_point = get_point(hasher, [$x, $y, $z, $w]);
-                    let cur_distance = distance_function(&point, &cur_point);
+                    let cur_point = get_point(hasher, worley.jitter, whole, [$x, $y, $z, $w]);
+                    let cur_distance = metric.distance(&point, &cur_point);
                     if cur_distance < distance {
                         distance = cur_distance;
                         seed_cell = [$x, $y, $z, $w];
@@ -498,7 +1536,8 @@ where
 
     let value = match return_type {
         ReturnType::Distance => distance,
-        ReturnType::Value => hasher.hash(&seed_cell) as f64 / 255.0,
+        ReturnType::Value => hasher.hash(seed_cell) as f64 / 255.0,
+        _ => unreachable!("the nearest distances are handled above"),
     };
 
     value * 2.0 - 1.0
@@ -545,3 +1584,311 @@ fn get_vec4(index: usize) -> [f64; 4] {
         _ => panic!("Attempt to access 4D gradient {} of 32", index % 32),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::permutationtable::NoiseHasher;
+
+    /// Finds the nearest distances by checking every lattice point within
+    /// three cells of `point`.
+    fn brute_force_2d(worley: &Worley, point: [f64; 2]) -> [f64; NEAREST_SEED_POINTS] {
+        let whole = math::to_isize2(math::map2(point, f64::floor));
+        let mut distances = Vec::new();
+        for x in -3..=3 {
+            for y in -3..=3 {
+                let lattice = math::add2(whole, [x, y]);
+                let cell_hash = worley.hasher.hash(&lattice);
+                for index in 0..worley.seed_points.count(cell_hash) {
+                    let seed_point = math::add2(
+                        math::mul2(
+                            get_vec2(worley.seed_points.hash(cell_hash, index)),
+                            worley.jitter,
+                        ),
+                        math::to_f64_2(lattice),
+                    );
+                    distances.push(distance_functions::euclidean(&point, &seed_point));
+                }
+            }
+        }
+        distances.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        [distances[0], distances[1], distances[2], distances[3]]
+    }
+
+    #[test]
+    fn test_nearest_distances() {
+        let weights = [0.5, -1.0, 2.0, 0.25];
+        let worley = Worley::new(7);
+        let get = |return_type, point| worley.clone().set_return_type(return_type).get(point);
+
+        let points: Vec<[f64; 2]> = (0..200)
+            .map(|i| [f64::from(i) * 0.137 - 9.0, f64::from(i % 17) * 0.311 - 2.0])
+            .collect();
+
+        for &point in &points {
+            // Only distances below one cell are guaranteed to be found.
+            let [f1, f2, f3, f4] = brute_force_2d(&worley, point);
+            if f2 < 1.0 {
+                assert_eq!(get(ReturnType::Distance2, point), f2 * 2.0 - 1.0);
+                assert_eq!(get(ReturnType::Distance2Sub, point), (f2 - f1) * 2.0 - 1.0);
+                assert_eq!(get(ReturnType::Distance2Mul, point), (f1 * f2) * 2.0 - 1.0);
+            }
+            if f4 < 1.0 {
+                let expected =
+                    f1 * weights[0] + f2 * weights[1] + f3 * weights[2] + f4 * weights[3];
+                let weighted = get(ReturnType::WeightedDistances(weights), point);
+                assert!((weighted - (expected * 2.0 - 1.0)).abs() < 1e-12);
+            }
+        }
+
+        let worley = worley.set_return_type(ReturnType::Distance2Sub);
+        let mut output = vec![0.0; points.len()];
+        worley.get_batch(&points, &mut output);
+        for (&point, &value) in points.iter().zip(&output) {
+            assert_eq!(value, worley.get(point));
+        }
+    }
+
+    #[test]
+    fn test_nearest_distances_1d() {
+        let worley = Worley::new(5);
+        let get = |return_type, point| worley.clone().set_return_type(return_type).get([point]);
+
+        for i in 0..200 {
+            let point = f64::from(i) * 0.173 - 17.0;
+            let whole = point.floor() as isize;
+            let mut distances: Vec<f64> = (whole - 3..=whole + 3)
+                .map(|lattice| {
+                    let seed_point = get_vec1(worley.hasher.hash(&[lattice]))[0] + lattice as f64;
+                    (point - seed_point).abs()
+                })
+                .collect();
+            distances.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            let [f1, f2, f3, f4] = [distances[0], distances[1], distances[2], distances[3]];
+
+            let expected = [
+                (ReturnType::Distance, f1),
+                (ReturnType::Distance2, f2),
+                (ReturnType::Distance2Sub, f2 - f1),
+                (ReturnType::Distance2Mul, f1 * f2),
+                (ReturnType::WeightedDistances([1.0, 0.0, 0.0, 0.0]), f1),
+                (ReturnType::WeightedDistances([0.0, 0.0, 0.0, 1.0]), f4),
+                (
+                    ReturnType::WeightedDistances([0.5, -1.0, 2.0, 0.25]),
+                    f1 * 0.5 - f2 + f3 * 2.0 + f4 * 0.25,
+                ),
+            ];
+            for &(return_type, expected) in expected.iter() {
+                let value = get(return_type, point);
+                assert!((value - (expected * 2.0 - 1.0)).abs() < 1e-12);
+            }
+            assert!((-1.0..=1.0).contains(&get(ReturnType::Value, point)));
+        }
+    }
+
+    #[test]
+    fn test_seed_points() {
+        let grid = Worley::new(2)
+            .set_jitter(0.0)
+            .set_return_type(ReturnType::Distance);
+        assert_eq!(grid.get([0.3, 0.6]), 0.0);
+        assert_eq!(grid.get([5.0, -2.0]), -1.0);
+
+        let worley = Worley::new(2)
+            .set_jitter(0.6)
+            .set_seed_points(SeedPoints::Poisson(3.0))
+            .set_return_type(ReturnType::Distance);
+        let mut counts = [0; MAX_POISSON_SEED_POINTS + 1];
+        for hash in 0..256 {
+            counts[worley.seed_points.count(hash)] += 1;
+        }
+        assert!(counts[0] > 0 && counts[2] > counts[0] && counts[3] > counts[6]);
+
+        for i in 0..200 {
+            let point = [f64::from(i) * 0.173 - 11.0, f64::from(i % 13) * 0.297];
+            let [f1, f2, ..] = brute_force_2d(&worley, point);
+            if f2 < 1.0 {
+                assert_eq!(worley.get(point), f1 * 2.0 - 1.0);
+                let worley = worley.clone().set_return_type(ReturnType::Distance2);
+                assert_eq!(worley.get(point), f2 * 2.0 - 1.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_sparse_seed_points() {
+        fn check<N: NoiseFn<f64, DIM>, const DIM: usize>(worley: &N, points: &[[f64; DIM]]) {
+            let mut output = vec![0.0; points.len()];
+            worley.get_batch(points, &mut output);
+            for (&point, &value) in points.iter().zip(&output) {
+                assert!(value.is_finite());
+                assert_eq!(value, worley.get(point));
+            }
+        }
+
+        // Most cells are empty, so the nearest seed points are often further
+        // than one cell away.
+        let points: Vec<[f64; 4]> = (0..100)
+            .map(|i| {
+                let i = f64::from(i);
+                [i * 0.731 - 30.0, i * -0.417, i * 0.293 + 5.0, i * 0.187]
+            })
+            .collect();
+        for mean in [0.05, 0.2, 1.0] {
+            for return_type in [
+                ReturnType::Distance,
+                ReturnType::Value,
+                ReturnType::Distance2,
+                ReturnType::WeightedDistances([0.5, 0.5, 0.5, 0.5]),
+            ] {
+                let worley = Worley::new(3)
+                    .set_seed_points(SeedPoints::Poisson(mean))
+                    .set_return_type(return_type);
+                let points_1d: Vec<[f64; 1]> = points.iter().map(|p| [p[0]]).collect();
+                let points_2d: Vec<[f64; 2]> = points.iter().map(|p| [p[0], p[1]]).collect();
+                let points_3d: Vec<[f64; 3]> = points.iter().map(|p| [p[0], p[1], p[2]]).collect();
+                check(&worley, &points_1d);
+                check(&worley, &points_2d);
+                check(&worley, &points_3d);
+                check(&worley, &points);
+            }
+        }
+
+        // Samples in an empty cell still take the value of the nearest seed
+        // point, which is never in a cell without one.
+        let worley = Worley::new(3).set_seed_points(SeedPoints::Poisson(0.2));
+        for point in points.iter().map(|p| [p[0], p[1]]) {
+            let cell = worley.cell_2d(point);
+            assert_eq!(cell.value, worley.get(point));
+            assert!(cell.edge_distance.is_finite());
+            assert!(worley.seed_points.count(worley.hasher.hash(&cell.cell)) > 0);
+        }
+    }
+
+    #[test]
+    fn test_cells() {
+        let worley = Worley::new(4)
+            .set_frequency(1.5)
+            .set_jitter(0.8)
+            .set_seed_points(SeedPoints::Poisson(2.0));
+        let get = |return_type, point| worley.clone().set_return_type(return_type).get(point);
+
+        for i in 0..200 {
+            let point = [f64::from(i) * 0.093 - 7.0, f64::from(i % 11) * 0.219 - 1.0];
+            let cell = worley.cell_2d(point);
+
+            assert_eq!(cell.value, get(ReturnType::Value, point));
+            let f1 = distance_functions::euclidean(&point, &cell.seed_point) * 1.5;
+            assert!((get(ReturnType::Distance, point) - (f1 * 2.0 - 1.0)).abs() < 1e-12);
+            for axis in 0..2 {
+                let lattice = (cell.seed_point[axis] * 1.5).round() as isize;
+                assert!((lattice - cell.cell[axis]).abs() <= 1);
+            }
+
+            // The nearest border is where the two nearest seed points are
+            // equally far away.
+            let length = cell.edge_vector[0].hypot(cell.edge_vector[1]);
+            assert!(cell.edge_distance >= 0.0);
+            assert!((length - cell.edge_distance).abs() < 1e-12);
+            let edge = math::add2(point, cell.edge_vector);
+            assert!(get(ReturnType::Distance2Sub, edge) < -1.0 + 1e-9);
+        }
+
+        let worley = worley.set_return_type(ReturnType::Value);
+        let point = [0.3, -1.2, 4.5, 2.2];
+        assert_eq!(
+            worley.cell_3d([0.3, -1.2, 4.5]).value,
+            worley.get([0.3, -1.2, 4.5])
+        );
+        assert_eq!(worley.cell_4d(point).value, worley.get(point));
+    }
+
+    #[test]
+    fn test_distance_functions() {
+        let (p1, p2) = ([1.0, -2.0, 0.5], [0.0, 1.0, 2.5]);
+        let minkowski = |exponent| distance_functions::minkowski(&p1, &p2, exponent);
+        assert_eq!(distance_functions::quadratic(&p1, &p2), 16.0);
+        assert_eq!(minkowski(1.0), distance_functions::manhattan(&p1, &p2));
+        assert!((minkowski(2.0) - distance_functions::euclidean(&p1, &p2)).abs() < 1e-12);
+
+        assert!(matches!(
+            DistanceFunction::from(distance_functions::euclidean),
+            DistanceFunction::Euclidean
+        ));
+        assert!(matches!(
+            DistanceFunction::from(distance_functions::euclidean_squared),
+            DistanceFunction::EuclideanSquared
+        ));
+        assert!(matches!(
+            DistanceFunction::from(distance_functions::manhattan),
+            DistanceFunction::Manhattan
+        ));
+        assert!(matches!(
+            DistanceFunction::from(distance_functions::chebyshev),
+            DistanceFunction::Chebyshev
+        ));
+        assert!(matches!(
+            DistanceFunction::from(distance_functions::quadratic),
+            DistanceFunction::Quadratic
+        ));
+        assert!(matches!(
+            DistanceFunction::from(|p1: &[f64], p2: &[f64]| distance_functions::manhattan(p1, p2)),
+            DistanceFunction::Custom(_)
+        ));
+
+        let points: Vec<[f64; 3]> = (0..100)
+            .map(|i| [f64::from(i) * 0.173 - 8.0, f64::from(i % 7) * 0.41, 1.3])
+            .collect();
+        let mut output = vec![0.0; points.len()];
+
+        for function in [
+            DistanceFunction::Euclidean,
+            DistanceFunction::EuclideanSquared,
+            DistanceFunction::Manhattan,
+            DistanceFunction::Chebyshev,
+            DistanceFunction::Quadratic,
+            DistanceFunction::Minkowski(3.0),
+        ] {
+            for return_type in [ReturnType::Distance, ReturnType::Distance2Sub] {
+                let worley = Worley::new(6)
+                    .set_return_type(return_type)
+                    .set_distance_function(function.clone());
+                let custom = worley.clone().set_distance_function({
+                    let function = function.clone();
+                    move |p1: &[f64], p2: &[f64]| function.distance(p1, p2)
+                });
+
+                worley.get_batch(&points, &mut output);
+                for (&point, &value) in points.iter().zip(&output) {
+                    assert_eq!(value, custom.get(point));
+                    assert_eq!(
+                        worley.get([point[0], point[1]]),
+                        custom.get([point[0], point[1]])
+                    );
+                    assert_eq!(
+                        worley.get([point[2], point[0], point[1], 0.7]),
+                        custom.get([point[2], point[0], point[1], 0.7])
+                    );
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_errors() {
+        assert!(
+            serde_json::from_str::<Worley>(r#"{"distance_function": "Euclid"}"#)
+                .unwrap_err()
+                .to_string()
+                .contains("unknown variant `Euclid`")
+        );
+        assert!(
+            serde_json::from_str::<Worley>(r#"{"distance_function": {"Minkowski": 0.5}}"#)
+                .unwrap_err()
+                .to_string()
+                .contains("at least 1")
+        );
+    }
+}
@@ -1,6 +1,9 @@
-pub use self::{abs::*, clamp::*, curve::*, exponent::*, negate::*, scale_bias::*, terrace::*};
+pub use self::{
+    abs::*, channel::*, clamp::*, curve::*, exponent::*, negate::*, scale_bias::*, terrace::*,
+};
 
 mod abs;
+mod channel;
 mod clamp;
 mod curve;
 mod exponent;
@@ -1,20 +1,42 @@
 use crate::noise_fns::NoiseFn;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Noise function that outputs the absolute value of the output value from the
 /// source function.
-pub struct Abs<'a, T, const DIM: usize> {
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Abs<Source> {
     /// Outputs a value.
-    pub source: &'a dyn NoiseFn<T, DIM>,
+    pub source: Source,
 }
 
-impl<'a, T, const DIM: usize> Abs<'a, T, DIM> {
-    pub fn new(source: &'a dyn NoiseFn<T, DIM>) -> Self {
+impl<Source> Abs<Source> {
+    pub fn new(source: Source) -> Self {
         Self { source }
     }
 }
 
-impl<'a, T, const DIM: usize> NoiseFn<T, DIM> for Abs<'a, T, DIM> {
+impl<Source, T, const DIM: usize> NoiseFn<T, DIM> for Abs<Source>
+where
+    Source: NoiseFn<T, DIM>,
+{
     fn get(&self, point: [T; DIM]) -> f64 {
         (self.source.get(point)).abs()
     }
+
+    fn get_filtered(&self, point: [T; DIM], width: f64) -> f64 {
+        self.source.get_filtered(point, width).abs()
+    }
+
+    fn get_batch_filtered(&self, points: &[[T; DIM]], width: f64, output: &mut [f64])
+    where
+        T: Copy,
+    {
+        self.source.get_batch_filtered(points, width, output);
+        for value in output.iter_mut() {
+            *value = value.abs();
+        }
+    }
 }
@@ -0,0 +1,102 @@
+use crate::noise_fns::{NoiseFn, NoiseFnVector};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+
+/// Noise function that outputs one value of the output vector from a
+/// vector-valued source function.
+///
+/// `N` is the number of values in the output vector of the source function.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "ChannelParams<Source>"))]
+pub struct Channel<Source, const N: usize> {
+    /// Outputs a vector.
+    pub source: Source,
+
+    /// Index of the value of the output vector to output.
+    pub channel: usize,
+}
+
+impl<Source, const N: usize> Channel<Source, N> {
+    /// # Panics
+    ///
+    /// Panics if `channel` is not less than `N`.
+    pub fn new(source: Source, channel: usize) -> Self {
+        assert!(
+            channel < N,
+            "channel {} is out of range for a vector of {} values",
+            channel,
+            N
+        );
+
+        Self { source, channel }
+    }
+}
+
+/// Parameters of a [`Channel`], whose channel is checked against the size of
+/// the vector when deserializing.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ChannelParams<Source> {
+    source: Source,
+    channel: usize,
+}
+
+#[cfg(feature = "serde")]
+impl<Source, const N: usize> TryFrom<ChannelParams<Source>> for Channel<Source, N> {
+    type Error = String;
+
+    fn try_from(params: ChannelParams<Source>) -> Result<Self, Self::Error> {
+        if params.channel >= N {
+            return Err(format!(
+                "channel {} is out of range for a vector of {} values",
+                params.channel, N
+            ));
+        }
+
+        Ok(Channel::new(params.source, params.channel))
+    }
+}
+
+impl<Source, T, const DIM: usize, const N: usize> NoiseFn<T, DIM> for Channel<Source, N>
+where
+    Source: NoiseFnVector<T, DIM, N>,
+{
+    fn get(&self, point: [T; DIM]) -> f64 {
+        self.source.get_vector(point)[self.channel]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "serde")]
+    use crate::Constant;
+    use crate::{NoiseFnVector, Perlin, Simplex, Value};
+
+    #[test]
+    fn test_vector_adapters() {
+        let point = [0.4, 1.7, -2.3];
+        let (x, y, z) = (Perlin::new(1), Simplex::new(2), Value::new());
+
+        let vector = (x, y, z).get_vector(point);
+        assert_eq!(vector, [x.get(point), y.get(point), z.get(point)]);
+        assert_eq!([x, x].get_vector(point), [vector[0]; 2]);
+        assert_eq!(Channel::new((x, y, z), 2).get(point), vector[2]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_channel_out_of_range() {
+        let json = r#"{"source": [{"value": 0.5}, {"value": 1.0}], "channel": 1}"#;
+        let channel: Channel<(Constant, Constant), 2> = serde_json::from_str(json).unwrap();
+        assert_eq!(channel.get([0.0, 0.0]), 1.0);
+
+        let json = r#"{"source": [{"value": 0.5}, {"value": 1.0}], "channel": 2}"#;
+        let error = serde_json::from_str::<Channel<(Constant, Constant), 2>>(json).unwrap_err();
+        assert!(error.to_string().contains("out of range"));
+    }
+}
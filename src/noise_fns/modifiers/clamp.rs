@@ -1,17 +1,26 @@
 use crate::noise_fns::NoiseFn;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Noise function that clamps the output value from the source function to a
 /// range of values.
-pub struct Clamp<'a, T, const DIM: usize> {
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Clamp<Source> {
     /// Outputs a value.
-    pub source: &'a dyn NoiseFn<T, DIM>,
+    pub source: Source,
 
     /// Bound of the clamping range. Default is -1.0 to 1.0.
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "crate::noise_fns::deserialize_bounds")
+    )]
     pub bounds: (f64, f64),
 }
 
-impl<'a, T, const DIM: usize> Clamp<'a, T, DIM> {
-    pub fn new(source: &'a dyn NoiseFn<T, DIM>) -> Self {
+impl<Source> Clamp<Source> {
+    pub fn new(source: Source) -> Self {
         Self {
             source,
             bounds: (-1.0, 1.0),
@@ -40,10 +49,45 @@ impl<'a, T, const DIM: usize> Clamp<'a, T, DIM> {
     }
 }
 
-impl<'a, T, const DIM: usize> NoiseFn<T, DIM> for Clamp<'a, T, DIM> {
+impl<Source, T, const DIM: usize> NoiseFn<T, DIM> for Clamp<Source>
+where
+    Source: NoiseFn<T, DIM>,
+{
     fn get(&self, point: [T; DIM]) -> f64 {
         let value = self.source.get(point);
 
         value.clamp(self.bounds.0, self.bounds.1)
     }
+
+    fn get_filtered(&self, point: [T; DIM], width: f64) -> f64 {
+        let value = self.source.get_filtered(point, width);
+
+        value.clamp(self.bounds.0, self.bounds.1)
+    }
+
+    fn get_batch_filtered(&self, points: &[[T; DIM]], width: f64, output: &mut [f64])
+    where
+        T: Copy,
+    {
+        self.source.get_batch_filtered(points, width, output);
+        for value in output.iter_mut() {
+            *value = value.clamp(self.bounds.0, self.bounds.1);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use super::*;
+    use crate::Constant;
+
+    #[test]
+    fn test_serde_bounds() {
+        assert!(serde_json::from_str::<Clamp<Constant>>(
+            r#"{"source": {"value": 0.0}, "bounds": [1.0, -1.0]}"#
+        )
+        .unwrap_err()
+        .to_string()
+        .contains("lower bound"));
+    }
 }
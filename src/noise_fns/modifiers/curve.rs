@@ -1,4 +1,6 @@
 use crate::{math::interpolate, noise_fns::NoiseFn};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Noise function that maps the output value from the source function onto an
 /// arbitrary function curve.
@@ -14,21 +16,31 @@ use crate::{math::interpolate, noise_fns::NoiseFn};
 /// four control points to the curve. If there is less than four control
 /// points, the get() method panics. Each control point can have any input
 /// and output value, although no two control points can have the same input.
-pub struct Curve<'a, T, const DIM: usize> {
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Curve<Source> {
     /// Outputs a value.
-    pub source: &'a dyn NoiseFn<T, DIM>,
+    pub source: Source,
 
     /// Vec that stores the control points.
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "deserialize_control_points")
+    )]
     control_points: Vec<ControlPoint<f64>>,
 }
 
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
 struct ControlPoint<T> {
     input: T,
     output: T,
 }
 
-impl<'a, T, const DIM: usize> Curve<'a, T, DIM> {
-    pub fn new(source: &'a dyn NoiseFn<T, DIM>) -> Self {
+impl<Source> Curve<Source> {
+    pub fn new(source: Source) -> Self {
         Self {
             source,
             control_points: Vec::with_capacity(4),
@@ -40,7 +52,7 @@ impl<'a, T, const DIM: usize> Curve<'a, T, DIM> {
         if !self
             .control_points
             .iter()
-            .any(|x| (x.input - input_value).abs() < std::f64::EPSILON)
+            .any(|x| (x.input - input_value).abs() < f64::EPSILON)
         {
             // it doesn't, so find the correct position to insert the new
             // control point.
@@ -48,7 +60,7 @@ impl<'a, T, const DIM: usize> Curve<'a, T, DIM> {
                 .control_points
                 .iter()
                 .position(|x| x.input >= input_value)
-                .unwrap_or_else(|| self.control_points.len());
+                .unwrap_or(self.control_points.len());
 
             // add the new control point at the correct position.
             self.control_points.insert(
@@ -62,23 +74,18 @@ impl<'a, T, const DIM: usize> Curve<'a, T, DIM> {
 
         self
     }
-}
 
-impl<'a, T, const DIM: usize> NoiseFn<T, DIM> for Curve<'a, T, DIM> {
-    fn get(&self, point: [T; DIM]) -> f64 {
+    fn apply(&self, source_value: f64) -> f64 {
         // confirm that there's at least 4 control points in the vector.
         assert!(self.control_points.len() >= 4);
 
-        // get output value from the source function
-        let source_value = self.source.get(point);
-
         // Find the first element in the control point array that has a input
         // value larger than the output value from the source function
         let index_pos = self
             .control_points
             .iter()
             .position(|x| x.input > source_value)
-            .unwrap_or_else(|| self.control_points.len());
+            .unwrap_or(self.control_points.len());
 
         // if index_pos < 2 {
         //     println!(
@@ -120,3 +127,48 @@ impl<'a, T, const DIM: usize> NoiseFn<T, DIM> for Curve<'a, T, DIM> {
         )
     }
 }
+
+impl<Source, T, const DIM: usize> NoiseFn<T, DIM> for Curve<Source>
+where
+    Source: NoiseFn<T, DIM>,
+{
+    fn get(&self, point: [T; DIM]) -> f64 {
+        self.apply(self.source.get(point))
+    }
+
+    fn get_filtered(&self, point: [T; DIM], width: f64) -> f64 {
+        self.apply(self.source.get_filtered(point, width))
+    }
+
+    fn get_batch_filtered(&self, points: &[[T; DIM]], width: f64, output: &mut [f64])
+    where
+        T: Copy,
+    {
+        self.source.get_batch_filtered(points, width, output);
+        for value in output.iter_mut() {
+            *value = self.apply(*value);
+        }
+    }
+}
+
+/// Deserializes the control points of a curve, sorting them as
+/// [`Curve::add_control_point`] does and checking that there are enough.
+#[cfg(feature = "serde")]
+fn deserialize_control_points<'de, D>(deserializer: D) -> Result<Vec<ControlPoint<f64>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let points = Vec::<ControlPoint<f64>>::deserialize(deserializer)?;
+    let curve = points.into_iter().fold(Curve::new(()), |curve, point| {
+        curve.add_control_point(point.input, point.output)
+    });
+
+    if curve.control_points.len() < 4 {
+        return Err(serde::de::Error::custom(format!(
+            "a Curve needs at least 4 control points with distinct inputs, got {}",
+            curve.control_points.len()
+        )));
+    }
+
+    Ok(curve.control_points)
+}
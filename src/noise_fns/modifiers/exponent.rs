@@ -1,4 +1,6 @@
 use crate::{math::scale_shift, noise_fns::NoiseFn};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Noise function that maps the output value from the source function onto an
 /// exponential curve.
@@ -7,17 +9,20 @@ use crate::{math::scale_shift, noise_fns::NoiseFn};
 /// this noise function first normalizes the output value (the range becomes 0.0
 /// to 1.0), maps that value onto an exponential curve, then rescales that
 /// value back to the original range.
-pub struct Exponent<'a, T, const DIM: usize> {
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Exponent<Source> {
     /// Outputs a value.
-    pub source: &'a dyn NoiseFn<T, DIM>,
+    pub source: Source,
 
     /// Exponent to apply to the output value from the source function. Default
     /// is 1.0.
     pub exponent: f64,
 }
 
-impl<'a, T, const DIM: usize> Exponent<'a, T, DIM> {
-    pub fn new(source: &'a dyn NoiseFn<T, DIM>) -> Self {
+impl<Source> Exponent<Source> {
+    pub fn new(source: Source) -> Self {
         Self {
             source,
             exponent: 1.0,
@@ -27,14 +32,34 @@ impl<'a, T, const DIM: usize> Exponent<'a, T, DIM> {
     pub fn set_exponent(self, exponent: f64) -> Self {
         Self { exponent, ..self }
     }
-}
 
-impl<'a, T, const DIM: usize> NoiseFn<T, DIM> for Exponent<'a, T, DIM> {
-    fn get(&self, point: [T; DIM]) -> f64 {
-        let mut value = self.source.get(point);
+    fn apply(&self, mut value: f64) -> f64 {
         value = (value + 1.0) / 2.0;
         value = value.abs();
         value = value.powf(self.exponent);
         scale_shift(value, 2.0)
     }
 }
+
+impl<Source, T, const DIM: usize> NoiseFn<T, DIM> for Exponent<Source>
+where
+    Source: NoiseFn<T, DIM>,
+{
+    fn get(&self, point: [T; DIM]) -> f64 {
+        self.apply(self.source.get(point))
+    }
+
+    fn get_filtered(&self, point: [T; DIM], width: f64) -> f64 {
+        self.apply(self.source.get_filtered(point, width))
+    }
+
+    fn get_batch_filtered(&self, points: &[[T; DIM]], width: f64, output: &mut [f64])
+    where
+        T: Copy,
+    {
+        self.source.get_batch_filtered(points, width, output);
+        for value in output.iter_mut() {
+            *value = self.apply(*value);
+        }
+    }
+}
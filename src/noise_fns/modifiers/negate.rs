@@ -1,19 +1,53 @@
-use crate::noise_fns::NoiseFn;
+use crate::{
+    math::dual::Dual,
+    noise_fns::{NoiseFn, NoiseFnGradient},
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Noise function that negates the output value from the source function.
-pub struct Negate<'a, T, const DIM: usize> {
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Negate<Source> {
     /// Outputs a value.
-    pub source: &'a dyn NoiseFn<T, DIM>,
+    pub source: Source,
 }
 
-impl<'a, T, const DIM: usize> Negate<'a, T, DIM> {
-    pub fn new(source: &'a dyn NoiseFn<T, DIM>) -> Self {
+impl<Source> Negate<Source> {
+    pub fn new(source: Source) -> Self {
         Negate { source }
     }
 }
 
-impl<'a, T, const DIM: usize> NoiseFn<T, DIM> for Negate<'a, T, DIM> {
+impl<Source, T, const DIM: usize> NoiseFn<T, DIM> for Negate<Source>
+where
+    Source: NoiseFn<T, DIM>,
+{
     fn get(&self, point: [T; DIM]) -> f64 {
         -self.source.get(point)
     }
+
+    fn get_filtered(&self, point: [T; DIM], width: f64) -> f64 {
+        -self.source.get_filtered(point, width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[T; DIM]], width: f64, output: &mut [f64])
+    where
+        T: Copy,
+    {
+        self.source.get_batch_filtered(points, width, output);
+        for value in output.iter_mut() {
+            *value = -*value;
+        }
+    }
+}
+
+impl<Source, const DIM: usize> NoiseFnGradient<DIM> for Negate<Source>
+where
+    Source: NoiseFnGradient<DIM>,
+{
+    fn get_with_gradient(&self, point: [f64; DIM]) -> (f64, [f64; DIM]) {
+        (-Dual::from(self.source.get_with_gradient(point))).into_tuple()
+    }
 }
@@ -1,13 +1,21 @@
-use crate::noise_fns::NoiseFn;
+use crate::{
+    math::dual::Dual,
+    noise_fns::{NoiseFn, NoiseFnGradient},
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Noise function that applies a scaling factor and a bias to the output value
 /// from the source function.
 ///
 /// The function retrieves the output value from the source function, multiplies
 /// it with the scaling factor, adds the bias to it, then outputs the value.
-pub struct ScaleBias<'a, T, const DIM: usize> {
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct ScaleBias<Source> {
     /// Outputs a value.
-    pub source: &'a dyn NoiseFn<T, DIM>,
+    pub source: Source,
 
     /// Scaling factor to apply to the output value from the source function.
     /// The default value is 1.0.
@@ -18,8 +26,8 @@ pub struct ScaleBias<'a, T, const DIM: usize> {
     pub bias: f64,
 }
 
-impl<'a, T, const DIM: usize> ScaleBias<'a, T, DIM> {
-    pub fn new(source: &'a dyn NoiseFn<T, DIM>) -> Self {
+impl<Source> ScaleBias<Source> {
+    pub fn new(source: Source) -> Self {
         Self {
             source,
             scale: 1.0,
@@ -34,16 +42,58 @@ impl<'a, T, const DIM: usize> ScaleBias<'a, T, DIM> {
     pub fn set_bias(self, bias: f64) -> Self {
         Self { bias, ..self }
     }
-}
 
-impl<'a, T, const DIM: usize> NoiseFn<T, DIM> for ScaleBias<'a, T, DIM> {
     #[cfg(not(target_os = "emscripten"))]
-    fn get(&self, point: [T; DIM]) -> f64 {
-        (self.source.get(point)).mul_add(self.scale, self.bias)
+    fn apply(&self, value: f64) -> f64 {
+        value.mul_add(self.scale, self.bias)
     }
 
     #[cfg(target_os = "emscripten")]
+    fn apply(&self, value: f64) -> f64 {
+        (value * self.scale) + self.bias
+    }
+}
+
+impl<Source, T, const DIM: usize> NoiseFn<T, DIM> for ScaleBias<Source>
+where
+    Source: NoiseFn<T, DIM>,
+{
     fn get(&self, point: [T; DIM]) -> f64 {
-        (self.source.get(point) * self.scale) + self.bias
+        self.apply(self.source.get(point))
+    }
+
+    fn get_filtered(&self, point: [T; DIM], width: f64) -> f64 {
+        self.apply(self.source.get_filtered(point, width))
+    }
+
+    fn get_batch_filtered(&self, points: &[[T; DIM]], width: f64, output: &mut [f64])
+    where
+        T: Copy,
+    {
+        self.source.get_batch_filtered(points, width, output);
+        for value in output.iter_mut() {
+            *value = self.apply(*value);
+        }
+    }
+}
+
+impl<Source, const DIM: usize> NoiseFnGradient<DIM> for ScaleBias<Source>
+where
+    Source: NoiseFnGradient<DIM>,
+{
+    #[cfg(not(target_os = "emscripten"))]
+    fn get_with_gradient(&self, point: [f64; DIM]) -> (f64, [f64; DIM]) {
+        use num_traits::MulAdd;
+
+        let value = Dual::from(self.source.get_with_gradient(point));
+
+        MulAdd::mul_add(value, Dual::constant(self.scale), Dual::constant(self.bias)).into_tuple()
+    }
+
+    #[cfg(target_os = "emscripten")]
+    fn get_with_gradient(&self, point: [f64; DIM]) -> (f64, [f64; DIM]) {
+        let value = Dual::from(self.source.get_with_gradient(point));
+
+        ((value * self.scale) + Dual::constant(self.bias)).into_tuple()
     }
 }
@@ -1,4 +1,6 @@
 use crate::{math::interpolate, noise_fns::NoiseFn};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Noise function that maps the output value from the source function onto a
 /// terrace-forming curve.
@@ -23,20 +25,27 @@ use crate::{math::interpolate, noise_fns::NoiseFn};
 ///
 /// This noise function is often used to generate terrain features such as the
 /// stereotypical desert canyon.
-pub struct Terrace<'a, T, const DIM: usize> {
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Terrace<Source> {
     /// Outputs a value.
-    pub source: &'a dyn NoiseFn<T, DIM>,
+    pub source: Source,
 
     /// Determines if the terrace-forming curve between all control points is
     /// inverted.
     pub invert_terraces: bool,
 
     /// Vec that stores the control points.
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "deserialize_control_points")
+    )]
     control_points: Vec<f64>,
 }
 
-impl<'a, T, const DIM: usize> Terrace<'a, T, DIM> {
-    pub fn new(source: &'a dyn NoiseFn<T, DIM>) -> Self {
+impl<Source> Terrace<Source> {
+    pub fn new(source: Source) -> Self {
         Terrace {
             source,
             invert_terraces: false,
@@ -56,7 +65,7 @@ impl<'a, T, const DIM: usize> Terrace<'a, T, DIM> {
         if !self
             .control_points
             .iter()
-            .any(|&x| (x - control_point).abs() < std::f64::EPSILON)
+            .any(|&x| (x - control_point).abs() < f64::EPSILON)
         {
             // it doesn't, so find the correct position to insert the new
             // control point.
@@ -64,7 +73,7 @@ impl<'a, T, const DIM: usize> Terrace<'a, T, DIM> {
                 .control_points
                 .iter()
                 .position(|&x| x >= control_point)
-                .unwrap_or_else(|| self.control_points.len());
+                .unwrap_or(self.control_points.len());
 
             // add the new control point at the correct position.
             self.control_points.insert(insertion_point, control_point);
@@ -82,23 +91,18 @@ impl<'a, T, const DIM: usize> Terrace<'a, T, DIM> {
             ..self
         }
     }
-}
 
-impl<'a, T, const DIM: usize> NoiseFn<T, DIM> for Terrace<'a, T, DIM> {
-    fn get(&self, point: [T; DIM]) -> f64 {
+    fn apply(&self, source_value: f64) -> f64 {
         // confirm that there's at least 2 control points in the vector.
         assert!(self.control_points.len() >= 2);
 
-        // get output value from the source function
-        let source_value = self.source.get(point);
-
         // Find the first element in the control point array that has a input
         // value larger than the output value from the source function
         let index_pos = self
             .control_points
             .iter()
             .position(|&x| x >= source_value)
-            .unwrap_or_else(|| self.control_points.len());
+            .unwrap_or(self.control_points.len());
 
         // Find the two nearest control points so that we can map their values
         // onto a quadratic curve.
@@ -131,6 +135,51 @@ impl<'a, T, const DIM: usize> NoiseFn<T, DIM> for Terrace<'a, T, DIM> {
     }
 }
 
+impl<Source, T, const DIM: usize> NoiseFn<T, DIM> for Terrace<Source>
+where
+    Source: NoiseFn<T, DIM>,
+{
+    fn get(&self, point: [T; DIM]) -> f64 {
+        self.apply(self.source.get(point))
+    }
+
+    fn get_filtered(&self, point: [T; DIM], width: f64) -> f64 {
+        self.apply(self.source.get_filtered(point, width))
+    }
+
+    fn get_batch_filtered(&self, points: &[[T; DIM]], width: f64, output: &mut [f64])
+    where
+        T: Copy,
+    {
+        self.source.get_batch_filtered(points, width, output);
+        for value in output.iter_mut() {
+            *value = self.apply(*value);
+        }
+    }
+}
+
 fn clamp_index(index: isize, min: usize, max: usize) -> usize {
     index.clamp(min as isize, max as isize) as usize
 }
+
+/// Deserializes the control points of a terrace-forming curve, sorting them as
+/// [`Terrace::add_control_point`] does and checking that there are enough.
+#[cfg(feature = "serde")]
+fn deserialize_control_points<'de, D>(deserializer: D) -> Result<Vec<f64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let points = Vec::<f64>::deserialize(deserializer)?;
+    let terrace = points
+        .into_iter()
+        .fold(Terrace::new(()), Terrace::add_control_point);
+
+    if terrace.control_points.len() < 2 {
+        return Err(serde::de::Error::custom(format!(
+            "a Terrace needs at least 2 distinct control points, got {}",
+            terrace.control_points.len()
+        )));
+    }
+
+    Ok(terrace.control_points)
+}
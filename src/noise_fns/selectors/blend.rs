@@ -1,30 +1,34 @@
-use crate::{math::interpolate, noise_fns::NoiseFn};
+use crate::{
+    math::interpolate,
+    noise_fns::{evaluate_batch, NoiseFn},
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Noise function that outputs a weighted blend of the output values from two
 /// source functions given the output value supplied by a control function.
 ///
 /// This noise function uses linear interpolation to perform the blending
 /// operation.
-pub struct Blend<'a, T, const DIM: usize> {
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Blend<Source1, Source2, Control> {
     /// Outputs one of the values to blend.
-    pub source1: &'a dyn NoiseFn<T, DIM>,
+    pub source1: Source1,
 
     /// Outputs one of the values to blend.
-    pub source2: &'a dyn NoiseFn<T, DIM>,
+    pub source2: Source2,
 
     /// Determines the weight of the blending operation. Negative values weight
     /// the blend towards the output value from the `source1` function. Positive
     /// values weight the blend towards the output value from the `source2`
     /// function.
-    pub control: &'a dyn NoiseFn<T, DIM>,
+    pub control: Control,
 }
 
-impl<'a, T, const DIM: usize> Blend<'a, T, DIM> {
-    pub fn new(
-        source1: &'a dyn NoiseFn<T, DIM>,
-        source2: &'a dyn NoiseFn<T, DIM>,
-        control: &'a dyn NoiseFn<T, DIM>,
-    ) -> Self {
+impl<Source1, Source2, Control> Blend<Source1, Source2, Control> {
+    pub fn new(source1: Source1, source2: Source2, control: Control) -> Self {
         Blend {
             source1,
             source2,
@@ -33,8 +37,12 @@ impl<'a, T, const DIM: usize> Blend<'a, T, DIM> {
     }
 }
 
-impl<'a, T, const DIM: usize> NoiseFn<T, DIM> for Blend<'a, T, DIM>
+impl<Source1, Source2, Control, T, const DIM: usize> NoiseFn<T, DIM>
+    for Blend<Source1, Source2, Control>
 where
+    Source1: NoiseFn<T, DIM>,
+    Source2: NoiseFn<T, DIM>,
+    Control: NoiseFn<T, DIM>,
     T: Copy,
 {
     fn get(&self, point: [T; DIM]) -> f64 {
@@ -44,4 +52,16 @@ where
 
         interpolate::linear(lower, upper, control)
     }
+
+    fn get_filtered(&self, point: [T; DIM], width: f64) -> f64 {
+        let lower = self.source1.get_filtered(point, width);
+        let upper = self.source2.get_filtered(point, width);
+        let control = self.control.get_filtered(point, width);
+
+        interpolate::linear(lower, upper, control)
+    }
+
+    fn get_batch_filtered(&self, points: &[[T; DIM]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.get_filtered(point, width));
+    }
 }
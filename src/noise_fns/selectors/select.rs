@@ -1,36 +1,41 @@
 use crate::{
     math::{interpolate, s_curve::cubic::Cubic},
-    noise_fns::NoiseFn,
+    noise_fns::{evaluate_batch, NoiseFn},
 };
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Noise function that outputs the value selected from one of two source
 /// functions chosen by the output value from a control function.
-pub struct Select<'a, T, const DIM: usize> {
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Select<Source1, Source2, Control> {
     /// Outputs a value.
-    pub source1: &'a dyn NoiseFn<T, DIM>,
+    pub source1: Source1,
 
     /// Outputs a value.
-    pub source2: &'a dyn NoiseFn<T, DIM>,
+    pub source2: Source2,
 
     /// Determines the value to select. If the output value from
     /// the control function is within a range of values know as the _selection
     /// range_, this noise function outputs the value from `source2`.
     /// Otherwise, this noise function outputs the value from `source1`.
-    pub control: &'a dyn NoiseFn<T, DIM>,
+    pub control: Control,
 
     /// Bounds of the selection range. Default is 0.0 to 1.0.
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "crate::noise_fns::deserialize_bounds")
+    )]
     pub bounds: (f64, f64),
 
     /// Edge falloff value. Default is 0.0.
     pub falloff: f64,
 }
 
-impl<'a, T, const DIM: usize> Select<'a, T, DIM> {
-    pub fn new(
-        source1: &'a dyn NoiseFn<T, DIM>,
-        source2: &'a dyn NoiseFn<T, DIM>,
-        control: &'a dyn NoiseFn<T, DIM>,
-    ) -> Self {
+impl<Source1, Source2, Control> Select<Source1, Source2, Control> {
+    pub fn new(source1: Source1, source2: Source2, control: Control) -> Self {
         Select {
             source1,
             source2,
@@ -50,42 +55,134 @@ impl<'a, T, const DIM: usize> Select<'a, T, DIM> {
     pub fn set_falloff(self, falloff: f64) -> Self {
         Select { falloff, ..self }
     }
-}
 
-impl<'a, T, const DIM: usize> NoiseFn<T, DIM> for Select<'a, T, DIM>
-where
-    T: Copy,
-{
-    fn get(&self, point: [T; DIM]) -> f64 {
-        let control_value = self.control.get(point);
+    /// Chooses between the values of the sources, evaluating only those that
+    /// contribute to the output.
+    fn select(
+        &self,
+        control_value: f64,
+        source1: impl Fn() -> f64,
+        source2: impl Fn() -> f64,
+    ) -> f64 {
         let (lower, upper) = self.bounds;
 
         if self.falloff > 0.0 {
             match () {
-                _ if control_value < (lower - self.falloff) => self.source1.get(point),
+                _ if control_value < (lower - self.falloff) => source1(),
                 _ if control_value < (lower + self.falloff) => {
                     let lower_curve = lower - self.falloff;
                     let upper_curve = lower + self.falloff;
                     let alpha =
                         ((control_value - lower_curve) / (upper_curve - lower_curve)).map_cubic();
 
-                    interpolate::linear(self.source1.get(point), self.source2.get(point), alpha)
+                    interpolate::linear(source1(), source2(), alpha)
                 }
-                _ if control_value < (upper - self.falloff) => self.source2.get(point),
+                _ if control_value < (upper - self.falloff) => source2(),
                 _ if control_value < (upper + self.falloff) => {
                     let lower_curve = upper - self.falloff;
                     let upper_curve = upper + self.falloff;
                     let alpha =
                         ((control_value - lower_curve) / (upper_curve - lower_curve)).map_cubic();
 
-                    interpolate::linear(self.source2.get(point), self.source1.get(point), alpha)
+                    interpolate::linear(source2(), source1(), alpha)
                 }
-                _ => self.source1.get(point),
+                _ => source1(),
             }
         } else if control_value < lower || control_value > upper {
-            self.source1.get(point)
+            source1()
         } else {
-            self.source2.get(point)
+            source2()
+        }
+    }
+}
+
+impl<Source1, Source2, Control, T, const DIM: usize> NoiseFn<T, DIM>
+    for Select<Source1, Source2, Control>
+where
+    Source1: NoiseFn<T, DIM>,
+    Source2: NoiseFn<T, DIM>,
+    Control: NoiseFn<T, DIM>,
+    T: Copy,
+{
+    fn get(&self, point: [T; DIM]) -> f64 {
+        self.select(
+            self.control.get(point),
+            || self.source1.get(point),
+            || self.source2.get(point),
+        )
+    }
+
+    fn get_filtered(&self, point: [T; DIM], width: f64) -> f64 {
+        self.select(
+            self.control.get_filtered(point, width),
+            || self.source1.get_filtered(point, width),
+            || self.source2.get_filtered(point, width),
+        )
+    }
+
+    fn get_batch_filtered(&self, points: &[[T; DIM]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.get_filtered(point, width));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Abs, Fbm, Perlin, Seedable, Simplex};
+    #[cfg(feature = "serde")]
+    use crate::{
+        Add, Curve, DistanceFunction, MultiFractal, OctaveRotation, Periodic, RidgedMulti,
+        ScalePoint, Turbulence, Worley,
+    };
+    use std::sync::Arc;
+
+    fn build_graph(seed: u64) -> impl NoiseFn<f64, 3> {
+        let ridges = Abs::new(Perlin::new(seed));
+        let hills: Box<dyn NoiseFn<f64, 3>> = Box::new(Fbm::new().set_seed(seed));
+        let control = Arc::new(Simplex::new(seed));
+
+        Select::new(ridges, hills, control).set_bounds(-0.2, 0.3)
+    }
+
+    #[test]
+    fn test_owned_graph() {
+        let stored: Vec<Box<dyn NoiseFn<f64, 3>>> =
+            vec![Box::new(build_graph(1)), Box::new(build_graph(1))];
+
+        let point = [0.4, 1.7, -2.3];
+        assert_eq!(stored[0].get(point), stored[1].get(point));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        type Graph =
+            Select<Turbulence<Abs<Perlin>>, Curve<Fbm>, ScalePoint<Add<RidgedMulti, Worley>>>;
+
+        let graph: Graph = Select::new(
+            Turbulence::new(Abs::new(Perlin::new(4).set_period([8, 8]))).set_seed(2),
+            Curve::new(
+                Fbm::new()
+                    .set_seed(3)
+                    .set_octaves(4)
+                    .set_rotation(OctaveRotation::Seeded),
+            )
+            .add_control_point(-1.0, -1.0)
+            .add_control_point(-0.2, 0.1)
+            .add_control_point(0.4, 0.3)
+            .add_control_point(1.0, 1.0),
+            ScalePoint::new(Add::new(
+                RidgedMulti::new().set_attenuation(1.5),
+                Worley::new(5).set_distance_function(DistanceFunction::Manhattan),
+            )),
+        )
+        .set_bounds(-0.5, 0.5);
+
+        let json = serde_json::to_string(&graph).unwrap();
+        let loaded: Graph = serde_json::from_str(&json).unwrap();
+
+        for point in [[0.4, 1.7, -2.3], [-5.1, 0.2, 3.3]] {
+            assert_eq!(graph.get(point), loaded.get(point));
         }
     }
 }
@@ -1,112 +1,103 @@
-use crate::noise_fns::NoiseFn;
+use crate::noise_fns::{evaluate_batch, NoiseFn, NoiseFnVector};
+use num_traits::AsPrimitive;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
-/// Noise function that uses multiple source functions to displace each coordinate
-/// of the input value before returning the output value from the `source` function.
-pub struct Displace<Source, XDisplace, YDisplace, ZDisplace, UDisplace> {
+/// Noise function that displaces each coordinate of the input value by the
+/// output of a displacement function before returning the output value from
+/// the `source` function.
+///
+/// The displacement function is a vector noise function with one output value
+/// per coordinate of the input value, such as [`Curl`](crate::Curl). A tuple
+/// of scalar noise functions displaces each coordinate by the output of the
+/// matching function.
+///
+/// ```
+/// use noise::{Checkerboard, Cylinders, Displace, NoiseFn, Perlin};
+///
+/// let displace = Displace::new(Cylinders::new(), (Checkerboard::default(), Perlin::default()));
+/// let value = displace.get([0.5, 1.5]);
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Displace<Source, Displacement> {
     /// Source function that outputs a value
     pub source: Source,
 
-    /// Displacement function that displaces the _x_ coordinate of the input
-    /// value.
-    pub x_displace: XDisplace,
-
-    /// Displacement function that displaces the _y_ coordinate of the input
-    /// value.
-    pub y_displace: YDisplace,
-
-    /// Displacement function that displaces the _z_ coordinate of the input
-    /// value. Only needed for 3d or higher noise.
-    pub z_displace: ZDisplace,
-
-    /// Displacement function that displaces the _u_ coordinate of the input
-    /// value. Only needed for 4d or higher noise.
-    pub u_displace: UDisplace,
+    /// Displacement function that outputs the offset of each coordinate of
+    /// the input value.
+    pub displacement: Displacement,
 }
 
-impl<Source, XDisplace, YDisplace, ZDisplace, UDisplace>
-    Displace<Source, XDisplace, YDisplace, ZDisplace, UDisplace>
-{
-    pub fn new(
-        source: Source,
-        x_displace: XDisplace,
-        y_displace: YDisplace,
-        z_displace: ZDisplace,
-        u_displace: UDisplace,
-    ) -> Self {
+impl<Source, Displacement> Displace<Source, Displacement> {
+    pub fn new(source: Source, displacement: Displacement) -> Self {
         Self {
             source,
-            x_displace,
-            y_displace,
-            z_displace,
-            u_displace,
+            displacement,
         }
     }
-}
 
-impl<Source, XDisplace, YDisplace, ZDisplace, UDisplace> NoiseFn<f64, 2>
-    for Displace<Source, XDisplace, YDisplace, ZDisplace, UDisplace>
-where
-    Source: NoiseFn<f64, 2>,
-    XDisplace: NoiseFn<f64, 2>,
-    YDisplace: NoiseFn<f64, 2>,
-{
-    fn get(&self, point: [f64; 2]) -> f64 {
-        // Get the output values from the displacement functions and add them to
-        // the corresponding coordinate in the input value. Since this is a 2d
-        // function, we only need the x_displace and y_displace functions.
-        let x = point[0] + self.x_displace.get(point);
-        let y = point[1] + self.y_displace.get(point);
+    /// Adds the output values of the displacement function to the
+    /// corresponding coordinates of `point`.
+    fn displace<T, const DIM: usize>(&self, point: [T; DIM]) -> [T; DIM]
+    where
+        Displacement: NoiseFnVector<T, DIM, DIM>,
+        T: AsPrimitive<f64>,
+        f64: AsPrimitive<T>,
+    {
+        // Get the output values from the displacement function and add them to
+        // the corresponding coordinate in the input value.
+        let offsets = self.displacement.get_vector(point);
 
-        // get the output value using the offset input value instead of the
-        // original input value.
-        self.source.get([x, y])
+        let mut displaced = point;
+        for (coordinate, offset) in displaced.iter_mut().zip(offsets.iter()) {
+            *coordinate = (coordinate.as_() + offset).as_();
+        }
+
+        displaced
     }
 }
 
-impl<Source, XDisplace, YDisplace, ZDisplace, UDisplace> NoiseFn<f64, 3>
-    for Displace<Source, XDisplace, YDisplace, ZDisplace, UDisplace>
+impl<Source, Displacement, T, const DIM: usize> NoiseFn<T, DIM> for Displace<Source, Displacement>
 where
-    Source: NoiseFn<f64, 3>,
-    XDisplace: NoiseFn<f64, 3>,
-    YDisplace: NoiseFn<f64, 3>,
-    ZDisplace: NoiseFn<f64, 3>,
+    Source: NoiseFn<T, DIM>,
+    Displacement: NoiseFnVector<T, DIM, DIM>,
+    T: AsPrimitive<f64>,
+    f64: AsPrimitive<T>,
 {
-    fn get(&self, point: [f64; 3]) -> f64 {
-        // Get the output values from the displacement functions and add them to
-        // the corresponding coordinate in the input value. Since this is a 3d
-        // function, we only need the x_displace, y_displace, and z_displace
-        // functions. Also, panic if there is no z_displace function defined.
-        let x = point[0] + self.x_displace.get(point);
-        let y = point[1] + self.y_displace.get(point);
-        let z = point[2] + self.z_displace.get(point);
-
+    fn get(&self, point: [T; DIM]) -> f64 {
         // get the output value using the offset input value instead of the
         // original input value.
-        self.source.get([x, y, z])
+        self.source.get(self.displace(point))
+    }
+
+    fn get_filtered(&self, point: [T; DIM], width: f64) -> f64 {
+        // The displacement function can't be filtered and doesn't report how
+        // far it stretches the space, so the width is passed on unchanged.
+        self.source.get_filtered(self.displace(point), width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[T; DIM]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.get_filtered(point, width));
     }
 }
 
-impl<Source, XDisplace, YDisplace, ZDisplace, UDisplace> NoiseFn<f64, 4>
-    for Displace<Source, XDisplace, YDisplace, ZDisplace, UDisplace>
-where
-    Source: NoiseFn<f64, 4>,
-    XDisplace: NoiseFn<f64, 4>,
-    YDisplace: NoiseFn<f64, 4>,
-    ZDisplace: NoiseFn<f64, 4>,
-    UDisplace: NoiseFn<f64, 4>,
-{
-    fn get(&self, point: [f64; 4]) -> f64 {
-        // Get the output values from the displacement functions and add them to
-        // the corresponding coordinate in the input value. Since this is a 4d
-        // function, we need all of the displace functions. Panic if there is no z-
-        // or u-displace function defined.
-        let x = point[0] + self.x_displace.get(point);
-        let y = point[1] + self.y_displace.get(point);
-        let z = point[2] + self.z_displace.get(point);
-        let u = point[3] + self.u_displace.get(point);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Curl, Perlin};
 
-        // get the output value using the offset input value instead of the
-        // original input value.
-        self.source.get([x, y, z, u])
+    #[test]
+    fn test_vector_displacement() {
+        let point = [0.4, 1.7, -2.3];
+        let source = Perlin::new(1);
+
+        let curl = Curl::new(3);
+        let [dx, dy, dz] = curl.get_vector(point);
+        assert_eq!(
+            Displace::new(source, curl).get(point),
+            source.get([point[0] + dx, point[1] + dy, point[2] + dz])
+        );
     }
 }
@@ -1,4 +1,10 @@
-use crate::noise_fns::NoiseFn;
+use crate::{
+    math,
+    noise_fns::{evaluate_batch, NoiseFn},
+};
+use num_traits::AsPrimitive;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Noise function that rotates the input value around the origin before
 /// returning the output value from the source function.
@@ -8,6 +14,9 @@ use crate::noise_fns::NoiseFn;
 ///
 /// The coordinate system of the input value is assumed to be "right-handed"
 /// (_x_ increases to the right, _y_ increases upward, and _z_ increases inward).
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
 pub struct RotatePoint<Source> {
     /// Source function that outputs a value
     pub source: Source,
@@ -75,13 +84,15 @@ impl<Source> RotatePoint<Source> {
             ..self
         }
     }
-}
 
-impl<Source> NoiseFn<f64, 2> for RotatePoint<Source>
-where
-    Source: NoiseFn<f64, 2>,
-{
-    fn get(&self, point: [f64; 2]) -> f64 {
+    /// Rotates `point` around the origin of the _xy_ plane.
+    fn rotate2<T>(&self, point: [T; 2]) -> [T; 2]
+    where
+        T: AsPrimitive<f64>,
+        f64: AsPrimitive<T>,
+    {
+        let point: [f64; 2] = math::map2(point, AsPrimitive::as_);
+
         // In two dimensions, the plane is _xy_, and we rotate around the
         // z-axis.
         let x = point[0];
@@ -91,17 +102,17 @@ where
         let x2 = x * theta.cos() - y * theta.sin();
         let y2 = x * theta.sin() + y * theta.cos();
 
-        // get the output value using the offset input value instead of the
-        // original input value.
-        self.source.get([x2, y2])
+        math::map2([x2, y2], AsPrimitive::as_)
     }
-}
 
-impl<Source> NoiseFn<f64, 3> for RotatePoint<Source>
-where
-    Source: NoiseFn<f64, 3>,
-{
-    fn get(&self, point: [f64; 3]) -> f64 {
+    /// Rotates `point` around the _x_, _y_ and _z_ axes.
+    fn rotate3<T>(&self, point: [T; 3]) -> [T; 3]
+    where
+        T: AsPrimitive<f64>,
+        f64: AsPrimitive<T>,
+    {
+        let point: [f64; 3] = math::map3(point, AsPrimitive::as_);
+
         // In three dimensions, we could rotate around any of the x, y, or z
         // axes. Need a more complicated function to handle this case.
         let x_cos = self.x_angle.to_radians().cos();
@@ -125,17 +136,57 @@ where
         let y = (x2 * point[0]) + (y2 * point[1]) + (z2 * point[2]);
         let z = (x3 * point[0]) + (y3 * point[1]) + (z3 * point[2]);
 
-        // get the output value using the offset input value instead of the
-        // original input value.
-        self.source.get([x, y, z])
+        math::map3([x, y, z], AsPrimitive::as_)
+    }
+}
+
+impl<Source, T> NoiseFn<T, 2> for RotatePoint<Source>
+where
+    Source: NoiseFn<T, 2>,
+    T: AsPrimitive<f64>,
+    f64: AsPrimitive<T>,
+{
+    fn get(&self, point: [T; 2]) -> f64 {
+        self.source.get(self.rotate2(point))
+    }
+
+    fn get_filtered(&self, point: [T; 2], width: f64) -> f64 {
+        // Rotating a sample doesn't change its width.
+        self.source.get_filtered(self.rotate2(point), width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[T; 2]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.get_filtered(point, width));
+    }
+}
+
+impl<Source, T> NoiseFn<T, 3> for RotatePoint<Source>
+where
+    Source: NoiseFn<T, 3>,
+    T: AsPrimitive<f64>,
+    f64: AsPrimitive<T>,
+{
+    fn get(&self, point: [T; 3]) -> f64 {
+        self.source.get(self.rotate3(point))
+    }
+
+    fn get_filtered(&self, point: [T; 3], width: f64) -> f64 {
+        // Rotating a sample doesn't change its width.
+        self.source.get_filtered(self.rotate3(point), width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[T; 3]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.get_filtered(point, width));
     }
 }
 
-impl<Source> NoiseFn<f64, 4> for RotatePoint<Source>
+impl<Source, T> NoiseFn<T, 4> for RotatePoint<Source>
 where
-    Source: NoiseFn<f64, 4>,
+    Source: NoiseFn<T, 4>,
+    T: AsPrimitive<f64>,
+    f64: AsPrimitive<T>,
 {
-    fn get(&self, _point: [f64; 4]) -> f64 {
+    fn get(&self, _point: [T; 4]) -> f64 {
         // 4d rotations are hard.
         unimplemented!();
     }
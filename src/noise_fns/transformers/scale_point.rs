@@ -1,10 +1,16 @@
-use crate::noise_fns::NoiseFn;
+use crate::noise_fns::{evaluate_batch, NoiseFn};
+use num_traits::AsPrimitive;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Noise function that scales the coordinates of the input value before
 /// returning the output value from the source function.
 ///
 /// The get() method multiplies the coordinates of the input value with a
 /// scaling factor before returning the output value from the source function.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
 pub struct ScalePoint<Source> {
     /// Source function that outputs a value
     pub source: Source,
@@ -83,42 +89,90 @@ impl<Source> ScalePoint<Source> {
             ..self
         }
     }
+
+    /// Multiplies each coordinate of `point` by its scaling factor.
+    fn scale<T, const DIM: usize>(&self, point: [T; DIM]) -> [T; DIM]
+    where
+        T: AsPrimitive<f64>,
+        f64: AsPrimitive<T>,
+    {
+        let scales = [self.x_scale, self.y_scale, self.z_scale, self.u_scale];
+
+        let mut scaled = point;
+        for (coordinate, scale) in scaled.iter_mut().zip(scales.iter()) {
+            *coordinate = (coordinate.as_() * scale).as_();
+        }
+
+        scaled
+    }
+
+    /// Returns the factor by which the scaling stretches the width of a
+    /// sample covering the first `dim` coordinates, which is the largest of
+    /// their scaling factors.
+    fn width_scale(&self, dim: usize) -> f64 {
+        [self.x_scale, self.y_scale, self.z_scale, self.u_scale][..dim]
+            .iter()
+            .fold(0.0, |width_scale, scale| scale.abs().max(width_scale))
+    }
 }
 
-impl<Source> NoiseFn<f64, 2> for ScalePoint<Source>
+impl<Source, T> NoiseFn<T, 2> for ScalePoint<Source>
 where
-    Source: NoiseFn<f64, 2>,
+    Source: NoiseFn<T, 2>,
+    T: AsPrimitive<f64>,
+    f64: AsPrimitive<T>,
 {
-    fn get(&self, point: [f64; 2]) -> f64 {
+    fn get(&self, point: [T; 2]) -> f64 {
+        self.source.get(self.scale(point))
+    }
+
+    fn get_filtered(&self, point: [T; 2], width: f64) -> f64 {
         self.source
-            .get([point[0] * self.x_scale, point[1] * self.y_scale])
+            .get_filtered(self.scale(point), width * self.width_scale(2))
+    }
+
+    fn get_batch_filtered(&self, points: &[[T; 2]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.get_filtered(point, width));
     }
 }
 
-impl<Source> NoiseFn<f64, 3> for ScalePoint<Source>
+impl<Source, T> NoiseFn<T, 3> for ScalePoint<Source>
 where
-    Source: NoiseFn<f64, 3>,
+    Source: NoiseFn<T, 3>,
+    T: AsPrimitive<f64>,
+    f64: AsPrimitive<T>,
 {
-    fn get(&self, point: [f64; 3]) -> f64 {
-        self.source.get([
-            point[0] * self.x_scale,
-            point[1] * self.y_scale,
-            point[2] * self.z_scale,
-        ])
+    fn get(&self, point: [T; 3]) -> f64 {
+        self.source.get(self.scale(point))
+    }
+
+    fn get_filtered(&self, point: [T; 3], width: f64) -> f64 {
+        self.source
+            .get_filtered(self.scale(point), width * self.width_scale(3))
+    }
+
+    fn get_batch_filtered(&self, points: &[[T; 3]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.get_filtered(point, width));
     }
 }
 
-impl<Source> NoiseFn<f64, 4> for ScalePoint<Source>
+impl<Source, T> NoiseFn<T, 4> for ScalePoint<Source>
 where
-    Source: NoiseFn<f64, 4>,
+    Source: NoiseFn<T, 4>,
+    T: AsPrimitive<f64>,
+    f64: AsPrimitive<T>,
 {
-    fn get(&self, point: [f64; 4]) -> f64 {
-        self.source.get([
-            point[0] * self.x_scale,
-            point[1] * self.y_scale,
-            point[2] * self.z_scale,
-            point[3] * self.u_scale,
-        ])
+    fn get(&self, point: [T; 4]) -> f64 {
+        self.source.get(self.scale(point))
+    }
+
+    fn get_filtered(&self, point: [T; 4], width: f64) -> f64 {
+        self.source
+            .get_filtered(self.scale(point), width * self.width_scale(4))
+    }
+
+    fn get_batch_filtered(&self, points: &[[T; 4]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.get_filtered(point, width));
     }
 }
 
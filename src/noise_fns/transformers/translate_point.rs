@@ -1,10 +1,16 @@
-use crate::noise_fns::NoiseFn;
+use crate::noise_fns::{evaluate_batch, NoiseFn};
+use num_traits::AsPrimitive;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 /// Noise function that moves the coordinates of the input value before
 /// returning the output value from the source function.
 ///
 /// The get() method moves the coordinates of the input value by a translation
 /// amount before returning the output value from the source function.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
 pub struct TranslatePoint<Source> {
     /// Source function that outputs a value
     pub source: Source,
@@ -101,41 +107,83 @@ impl<Source> TranslatePoint<Source> {
             ..self
         }
     }
+
+    /// Adds its translation amount to each coordinate of `point`. This moves
+    /// a sample without changing its width.
+    fn translate<T, const DIM: usize>(&self, point: [T; DIM]) -> [T; DIM]
+    where
+        T: AsPrimitive<f64>,
+        f64: AsPrimitive<T>,
+    {
+        let translations = [
+            self.x_translation,
+            self.y_translation,
+            self.z_translation,
+            self.u_translation,
+        ];
+
+        let mut translated = point;
+        for (coordinate, translation) in translated.iter_mut().zip(translations.iter()) {
+            *coordinate = (coordinate.as_() + translation).as_();
+        }
+
+        translated
+    }
 }
 
-impl<Source> NoiseFn<f64, 2> for TranslatePoint<Source>
+impl<Source, T> NoiseFn<T, 2> for TranslatePoint<Source>
 where
-    Source: NoiseFn<f64, 2>,
+    Source: NoiseFn<T, 2>,
+    T: AsPrimitive<f64>,
+    f64: AsPrimitive<T>,
 {
-    fn get(&self, point: [f64; 2]) -> f64 {
-        self.source
-            .get([point[0] + self.x_translation, point[1] + self.y_translation])
+    fn get(&self, point: [T; 2]) -> f64 {
+        self.source.get(self.translate(point))
+    }
+
+    fn get_filtered(&self, point: [T; 2], width: f64) -> f64 {
+        self.source.get_filtered(self.translate(point), width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[T; 2]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.get_filtered(point, width));
     }
 }
 
-impl<Source> NoiseFn<f64, 3> for TranslatePoint<Source>
+impl<Source, T> NoiseFn<T, 3> for TranslatePoint<Source>
 where
-    Source: NoiseFn<f64, 3>,
+    Source: NoiseFn<T, 3>,
+    T: AsPrimitive<f64>,
+    f64: AsPrimitive<T>,
 {
-    fn get(&self, point: [f64; 3]) -> f64 {
-        self.source.get([
-            point[0] + self.x_translation,
-            point[1] + self.y_translation,
-            point[2] + self.z_translation,
-        ])
+    fn get(&self, point: [T; 3]) -> f64 {
+        self.source.get(self.translate(point))
+    }
+
+    fn get_filtered(&self, point: [T; 3], width: f64) -> f64 {
+        self.source.get_filtered(self.translate(point), width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[T; 3]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.get_filtered(point, width));
     }
 }
 
-impl<Source> NoiseFn<f64, 4> for TranslatePoint<Source>
+impl<Source, T> NoiseFn<T, 4> for TranslatePoint<Source>
 where
-    Source: NoiseFn<f64, 4>,
+    Source: NoiseFn<T, 4>,
+    T: AsPrimitive<f64>,
+    f64: AsPrimitive<T>,
 {
-    fn get(&self, point: [f64; 4]) -> f64 {
-        self.source.get([
-            point[0] + self.x_translation,
-            point[1] + self.y_translation,
-            point[2] + self.z_translation,
-            point[3] + self.u_translation,
-        ])
+    fn get(&self, point: [T; 4]) -> f64 {
+        self.source.get(self.translate(point))
+    }
+
+    fn get_filtered(&self, point: [T; 4], width: f64) -> f64 {
+        self.source.get_filtered(self.translate(point), width)
+    }
+
+    fn get_batch_filtered(&self, points: &[[T; 4]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.get_filtered(point, width));
     }
 }
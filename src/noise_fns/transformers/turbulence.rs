@@ -1,4 +1,12 @@
-use crate::noise_fns::{Fbm, MultiFractal, NoiseFn, Seedable};
+use crate::{
+    math,
+    noise_fns::{evaluate_batch, Fbm, MultiFractal, NoiseFn, Seedable},
+};
+use num_traits::AsPrimitive;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
 
 /// Noise function that randomly displaces the input value before returning the
 /// output value from the source function.
@@ -9,6 +17,8 @@ use crate::noise_fns::{Fbm, MultiFractal, NoiseFn, Seedable};
 /// turbulence, an application can modify its frequency, its power, and its
 /// roughness.
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "TurbulenceParams<Source>"))]
 pub struct Turbulence<Source> {
     /// Source function that outputs a value.
     pub source: Source,
@@ -23,15 +33,19 @@ pub struct Turbulence<Source> {
     /// Affects the roughness of the turbulence. Higher values are rougher.
     pub roughness: usize,
 
-    seed: u32,
+    seed: u64,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
     x_distort_function: Fbm,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
     y_distort_function: Fbm,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
     z_distort_function: Fbm,
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
     u_distort_function: Fbm,
 }
 
 impl<Source> Turbulence<Source> {
-    pub const DEFAULT_SEED: u32 = 0;
+    pub const DEFAULT_SEED: u64 = 0;
     pub const DEFAULT_FREQUENCY: f64 = 1.0;
     pub const DEFAULT_POWER: f64 = 1.0;
     pub const DEFAULT_ROUGHNESS: usize = 3;
@@ -87,30 +101,25 @@ impl<Source> Turbulence<Source> {
             ..self
         }
     }
-}
 
-impl<Source> Seedable for Turbulence<Source> {
-    fn set_seed(self, seed: u32) -> Self {
-        Self {
-            seed,
-            x_distort_function: self.x_distort_function.set_seed(seed),
-            y_distort_function: self.y_distort_function.set_seed(seed + 1),
-            z_distort_function: self.z_distort_function.set_seed(seed + 2),
-            u_distort_function: self.u_distort_function.set_seed(seed + 3),
-            ..self
-        }
+    /// Returns the factor by which the distortion stretches the width of a
+    /// sample.
+    ///
+    /// Neighbouring points are moved apart by up to about `power * frequency`
+    /// times their distance, so this is an estimate rather than a bound.
+    fn width_scale(&self) -> f64 {
+        1.0 + self.power.abs() * self.frequency.abs()
     }
 
-    fn seed(&self) -> u32 {
-        self.seed
-    }
-}
+    /// Moves `point` by the distortion functions, evaluating each with
+    /// `distort`.
+    fn distort2<T>(&self, point: [T; 2], distort: impl Fn(&Fbm, [f64; 2]) -> f64) -> [T; 2]
+    where
+        T: AsPrimitive<f64>,
+        f64: AsPrimitive<T>,
+    {
+        let point: [f64; 2] = math::map2(point, AsPrimitive::as_);
 
-impl<Source> NoiseFn<f64, 2> for Turbulence<Source>
-where
-    Source: NoiseFn<f64, 2>,
-{
-    fn get(&self, point: [f64; 2]) -> f64 {
         // First, create offsets based on the input values to keep the sampled
         // points from being near a integer boundary. This is a result of
         // using perlin noise, which returns zero at integer boundaries.
@@ -120,18 +129,21 @@ where
         let x1 = point[0] + 26519.0 / 65536.0;
         let y1 = point[1] + 18128.0 / 65536.0;
 
-        let x_distort = point[0] + (self.x_distort_function.get([x0, y0]) * self.power);
-        let y_distort = point[1] + (self.y_distort_function.get([x1, y1]) * self.power);
+        let x_distort = point[0] + (distort(&self.x_distort_function, [x0, y0]) * self.power);
+        let y_distort = point[1] + (distort(&self.y_distort_function, [x1, y1]) * self.power);
 
-        self.source.get([x_distort, y_distort])
+        math::map2([x_distort, y_distort], AsPrimitive::as_)
     }
-}
 
-impl<Source> NoiseFn<f64, 3> for Turbulence<Source>
-where
-    Source: NoiseFn<f64, 3>,
-{
-    fn get(&self, point: [f64; 3]) -> f64 {
+    /// Moves `point` by the distortion functions, evaluating each with
+    /// `distort`.
+    fn distort3<T>(&self, point: [T; 3], distort: impl Fn(&Fbm, [f64; 3]) -> f64) -> [T; 3]
+    where
+        T: AsPrimitive<f64>,
+        f64: AsPrimitive<T>,
+    {
+        let point: [f64; 3] = math::map3(point, AsPrimitive::as_);
+
         // First, create offsets based on the input values to keep the sampled
         // points from being near a integer boundary. This is a result of
         // using perlin noise, which returns zero at integer boundaries.
@@ -147,19 +159,22 @@ where
         let y2 = point[1] + 11213.0 / 65536.0;
         let z2 = point[2] + 44845.0 / 65536.0;
 
-        let x_distort = point[0] + (self.x_distort_function.get([x0, y0, z0]) * self.power);
-        let y_distort = point[1] + (self.y_distort_function.get([x1, y1, z1]) * self.power);
-        let z_distort = point[2] + (self.z_distort_function.get([x2, y2, z2]) * self.power);
+        let x_distort = point[0] + (distort(&self.x_distort_function, [x0, y0, z0]) * self.power);
+        let y_distort = point[1] + (distort(&self.y_distort_function, [x1, y1, z1]) * self.power);
+        let z_distort = point[2] + (distort(&self.z_distort_function, [x2, y2, z2]) * self.power);
 
-        self.source.get([x_distort, y_distort, z_distort])
+        math::map3([x_distort, y_distort, z_distort], AsPrimitive::as_)
     }
-}
 
-impl<Source> NoiseFn<f64, 4> for Turbulence<Source>
-where
-    Source: NoiseFn<f64, 4>,
-{
-    fn get(&self, point: [f64; 4]) -> f64 {
+    /// Moves `point` by the distortion functions, evaluating each with
+    /// `distort`.
+    fn distort4<T>(&self, point: [T; 4], distort: impl Fn(&Fbm, [f64; 4]) -> f64) -> [T; 4]
+    where
+        T: AsPrimitive<f64>,
+        f64: AsPrimitive<T>,
+    {
+        let point: [f64; 4] = math::map4(point, AsPrimitive::as_);
+
         // First, create offsets based on the input values to keep the sampled
         // points from being near a integer boundary. This is a result of
         // using perlin noise, which returns zero at integer boundaries.
@@ -183,12 +198,135 @@ where
         let z3 = point[2] + 12414.0 / 65536.0;
         let u3 = point[3] + 60943.0 / 65536.0;
 
-        let x_distort = point[0] + (self.x_distort_function.get([x0, y0, z0, u0]) * self.power);
-        let y_distort = point[1] + (self.y_distort_function.get([x1, y1, z1, u1]) * self.power);
-        let z_distort = point[2] + (self.z_distort_function.get([x2, y2, z2, u2]) * self.power);
-        let u_distort = point[3] + (self.u_distort_function.get([x3, y3, z3, u3]) * self.power);
+        let x_distort =
+            point[0] + (distort(&self.x_distort_function, [x0, y0, z0, u0]) * self.power);
+        let y_distort =
+            point[1] + (distort(&self.y_distort_function, [x1, y1, z1, u1]) * self.power);
+        let z_distort =
+            point[2] + (distort(&self.z_distort_function, [x2, y2, z2, u2]) * self.power);
+        let u_distort =
+            point[3] + (distort(&self.u_distort_function, [x3, y3, z3, u3]) * self.power);
+
+        math::map4(
+            [x_distort, y_distort, z_distort, u_distort],
+            AsPrimitive::as_,
+        )
+    }
+}
+
+/// Parameters of a [`Turbulence`], from which its distortion functions are
+/// rebuilt when deserializing.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TurbulenceParams<Source> {
+    source: Source,
+    frequency: f64,
+    power: f64,
+    roughness: usize,
+    seed: u64,
+}
+
+#[cfg(feature = "serde")]
+impl<Source> TryFrom<TurbulenceParams<Source>> for Turbulence<Source> {
+    type Error = String;
+
+    fn try_from(params: TurbulenceParams<Source>) -> Result<Self, Self::Error> {
+        if params.roughness == 0 || params.roughness > Fbm::MAX_OCTAVES {
+            return Err(format!(
+                "Turbulence roughness must be between 1 and {}, got {}",
+                Fbm::MAX_OCTAVES,
+                params.roughness
+            ));
+        }
+
+        Ok(Turbulence::new(params.source)
+            .set_seed(params.seed)
+            .set_frequency(params.frequency)
+            .set_power(params.power)
+            .set_roughness(params.roughness))
+    }
+}
+
+impl<Source> Seedable for Turbulence<Source> {
+    fn set_seed(self, seed: u64) -> Self {
+        Self {
+            seed,
+            x_distort_function: self.x_distort_function.set_seed(seed),
+            y_distort_function: self.y_distort_function.set_seed(seed.wrapping_add(1)),
+            z_distort_function: self.z_distort_function.set_seed(seed.wrapping_add(2)),
+            u_distort_function: self.u_distort_function.set_seed(seed.wrapping_add(3)),
+            ..self
+        }
+    }
+
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl<Source, T> NoiseFn<T, 2> for Turbulence<Source>
+where
+    Source: NoiseFn<T, 2>,
+    T: AsPrimitive<f64>,
+    f64: AsPrimitive<T>,
+{
+    fn get(&self, point: [T; 2]) -> f64 {
+        self.source
+            .get(self.distort2(point, |function, point| function.get(point)))
+    }
+
+    fn get_filtered(&self, point: [T; 2], width: f64) -> f64 {
+        let point = self.distort2(point, |function, point| function.get_filtered(point, width));
+
+        self.source.get_filtered(point, width * self.width_scale())
+    }
+
+    fn get_batch_filtered(&self, points: &[[T; 2]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.get_filtered(point, width));
+    }
+}
+
+impl<Source, T> NoiseFn<T, 3> for Turbulence<Source>
+where
+    Source: NoiseFn<T, 3>,
+    T: AsPrimitive<f64>,
+    f64: AsPrimitive<T>,
+{
+    fn get(&self, point: [T; 3]) -> f64 {
+        self.source
+            .get(self.distort3(point, |function, point| function.get(point)))
+    }
+
+    fn get_filtered(&self, point: [T; 3], width: f64) -> f64 {
+        let point = self.distort3(point, |function, point| function.get_filtered(point, width));
+
+        self.source.get_filtered(point, width * self.width_scale())
+    }
+
+    fn get_batch_filtered(&self, points: &[[T; 3]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.get_filtered(point, width));
+    }
+}
 
+impl<Source, T> NoiseFn<T, 4> for Turbulence<Source>
+where
+    Source: NoiseFn<T, 4>,
+    T: AsPrimitive<f64>,
+    f64: AsPrimitive<T>,
+{
+    fn get(&self, point: [T; 4]) -> f64 {
         self.source
-            .get([x_distort, y_distort, z_distort, u_distort])
+            .get(self.distort4(point, |function, point| function.get(point)))
+    }
+
+    fn get_filtered(&self, point: [T; 4], width: f64) -> f64 {
+        let point = self.distort4(point, |function, point| function.get_filtered(point, width));
+
+        self.source.get_filtered(point, width * self.width_scale())
+    }
+
+    fn get_batch_filtered(&self, points: &[[T; 4]], width: f64, output: &mut [f64]) {
+        evaluate_batch(points, output, |point| self.get_filtered(point, width));
     }
 }
@@ -8,10 +8,20 @@ use std::fmt;
 
 const TABLE_SIZE: usize = 256;
 
+/// Hashes the lattice points that generators place their gradients and values at.
 pub trait NoiseHasher: Send + Sync {
+    /// Hashes the lattice point `to_hash` to a value in `0..256`.
     fn hash(&self, to_hash: &[isize]) -> usize;
 }
 
+/// A [`NoiseHasher`] that generators build from their seed.
+///
+/// Generators use a [`PermutationTable`] unless given another hasher as a type parameter, as in
+/// `Perlin::<IntegerHasher>::from_seed(seed)`.
+pub trait SeedableHasher: NoiseHasher {
+    fn from_seed(seed: u64) -> Self;
+}
+
 /// A seed table, required by all noise functions.
 ///
 /// Table creation is expensive, so in most circumstances you'll only want to
@@ -42,18 +52,20 @@ impl Distribution<PermutationTable> for Standard {
 }
 
 impl PermutationTable {
-    /// Deterministically generates a new permutation table based on a `u32` seed value.
+    /// Deterministically generates a new permutation table based on a `u64` seed value.
     ///
     /// Internally this uses a `XorShiftRng`, but we don't really need to worry
     /// about cryptographic security when working with procedural noise.
-    pub fn new(seed: u32) -> Self {
+    pub fn new(seed: u64) -> Self {
+        // Seeds used to be `u32`, repeated three times. The high half of the seed is mixed into
+        // the last repetition only, so that every `u32` seed still gives the same table.
+        let low = seed as u32;
+        let high = (seed >> 32) as u32;
+
         let mut real = [0; 16];
         real[0] = 1;
-        for i in 1..4 {
-            real[i * 4] = seed as u8;
-            real[(i * 4) + 1] = (seed >> 8) as u8;
-            real[(i * 4) + 2] = (seed >> 16) as u8;
-            real[(i * 4) + 3] = (seed >> 24) as u8;
+        for (i, word) in [low, low, low ^ high].iter().enumerate() {
+            real[(i + 1) * 4..(i + 2) * 4].copy_from_slice(&word.to_le_bytes());
         }
         let mut rng: XorShiftRng = SeedableRng::from_seed(real);
         rng.gen()
@@ -71,6 +83,230 @@ impl NoiseHasher for PermutationTable {
     }
 }
 
+impl SeedableHasher for PermutationTable {
+    fn from_seed(seed: u64) -> Self {
+        Self::new(seed)
+    }
+}
+
+/// A hasher that mixes all the bits of every coordinate with the seed.
+///
+/// A [`PermutationTable`] only looks at the lowest 8 bits of each coordinate, so noise generated
+/// with it repeats every 256 lattice cells along each axis. This hasher doesn't repeat, which
+/// matters when a large world is sampled at a high frequency, and it is cheaper to build, as
+/// there is no table to shuffle. It gives different noise than a permutation table for the same
+/// seed.
+#[derive(Clone, Copy, Debug)]
+pub struct IntegerHasher {
+    seed: u64,
+    key: u64,
+}
+
+impl IntegerHasher {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            key: mix(seed.wrapping_add(MULTIPLIER)),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl NoiseHasher for IntegerHasher {
+    #[inline(always)]
+    fn hash(&self, to_hash: &[isize]) -> usize {
+        let mut hash = self.key;
+        for &coordinate in to_hash {
+            hash = (hash.rotate_left(5) ^ coordinate as u64).wrapping_mul(MULTIPLIER);
+        }
+
+        // Only the top 8 bits are used, so every bit of the input has to reach them.
+        (mix(hash) >> 56) as usize
+    }
+}
+
+impl SeedableHasher for IntegerHasher {
+    fn from_seed(seed: u64) -> Self {
+        Self::new(seed)
+    }
+}
+
+// 2^64 divided by the golden ratio, which spreads consecutive integers apart.
+const MULTIPLIER: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// The finalizer of SplitMix64, which makes every bit of `value` affect every bit of the result.
+#[inline(always)]
+fn mix(mut value: u64) -> u64 {
+    value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    value ^ (value >> 31)
+}
+
+/// Wraps every lattice point into the period of its axis before hashing it, so that the lattice
+/// repeats. An axis with a period of 0 isn't wrapped.
+pub(crate) struct PeriodicHasher<'a, H: ?Sized> {
+    hasher: &'a H,
+    period: [u32; 4],
+}
+
+impl<'a, H: ?Sized> PeriodicHasher<'a, H> {
+    pub(crate) fn new(hasher: &'a H, period: [u32; 4]) -> Self {
+        Self { hasher, period }
+    }
+}
+
+impl<'a, H> NoiseHasher for PeriodicHasher<'a, H>
+where
+    H: NoiseHasher + ?Sized,
+{
+    #[inline(always)]
+    fn hash(&self, to_hash: &[isize]) -> usize {
+        if self.period == [0; 4] {
+            return self.hasher.hash(to_hash);
+        }
+
+        let mut wrapped = [0; 4];
+        for ((wrapped, &coordinate), &period) in
+            wrapped.iter_mut().zip(to_hash).zip(self.period.iter())
+        {
+            *wrapped = if period == 0 {
+                coordinate
+            } else {
+                coordinate.rem_euclid(period as isize)
+            };
+        }
+        self.hasher.hash(&wrapped[..to_hash.len()])
+    }
+}
+
+/// Builds the period of every axis from the periods of the first `N`, for
+/// [`Periodic::set_period`](crate::Periodic::set_period).
+pub(crate) fn expand_period<const N: usize>(period: [u32; N]) -> [u32; 4] {
+    assert!(N <= 4, "a period can have at most 4 axes, got {}", N);
+
+    let mut expanded = [0; 4];
+    expanded[..N].copy_from_slice(&period);
+    expanded
+}
+
+/// Hashes the lattice points surrounding the cell that contains a sample.
+///
+/// Generators first call [`set_cell`](CellHasher::set_cell) with the lattice cell containing the
+/// point being evaluated, then look up the lattice points they need by their offset from that
+/// cell. This lets batch evaluation reuse the hashes of a cell for as long as consecutive points
+/// fall within it.
+pub(crate) trait CellHasher<const DIM: usize> {
+    fn set_cell(&mut self, cell: [isize; DIM]);
+
+    fn hash(&self, offset: [isize; DIM]) -> usize;
+}
+
+/// Hashes every requested lattice point on demand.
+pub(crate) struct DirectHasher<'a, H: ?Sized, const DIM: usize> {
+    hasher: &'a H,
+    cell: [isize; DIM],
+}
+
+impl<'a, H: ?Sized, const DIM: usize> DirectHasher<'a, H, DIM> {
+    #[inline(always)]
+    pub(crate) fn new(hasher: &'a H) -> Self {
+        Self {
+            hasher,
+            cell: [0; DIM],
+        }
+    }
+}
+
+impl<'a, H, const DIM: usize> CellHasher<DIM> for DirectHasher<'a, H, DIM>
+where
+    H: NoiseHasher + ?Sized,
+{
+    #[inline(always)]
+    fn set_cell(&mut self, cell: [isize; DIM]) {
+        self.cell = cell;
+    }
+
+    #[inline(always)]
+    fn hash(&self, offset: [isize; DIM]) -> usize {
+        let mut point = self.cell;
+        for (coordinate, offset) in point.iter_mut().zip(offset.iter()) {
+            *coordinate += offset;
+        }
+        self.hasher.hash(&point)
+    }
+}
+
+/// Hashes all lattice points within a window around a cell up front, and keeps them until a
+/// different cell is set.
+///
+/// The window covers the offsets `min..min + width` along every axis, where `width` is the
+/// `DIM`-th root of `SLOTS`.
+pub(crate) struct CachedHasher<'a, H: ?Sized, const DIM: usize, const SLOTS: usize> {
+    hasher: &'a H,
+    min: isize,
+    cell: Option<[isize; DIM]>,
+    hashes: [usize; SLOTS],
+}
+
+impl<'a, H: ?Sized, const DIM: usize, const SLOTS: usize> CachedHasher<'a, H, DIM, SLOTS> {
+    // Known at compile time, so that looking up a constant offset needs no arithmetic at all.
+    const WIDTH: usize = {
+        let mut width: usize = 1;
+        while width.pow(DIM as u32) < SLOTS {
+            width += 1;
+        }
+        width
+    };
+
+    pub(crate) fn new(hasher: &'a H, min: isize) -> Self {
+        debug_assert_eq!(Self::WIDTH.pow(DIM as u32), SLOTS);
+
+        Self {
+            hasher,
+            min,
+            cell: None,
+            hashes: [0; SLOTS],
+        }
+    }
+}
+
+impl<'a, H, const DIM: usize, const SLOTS: usize> CellHasher<DIM>
+    for CachedHasher<'a, H, DIM, SLOTS>
+where
+    H: NoiseHasher + ?Sized,
+{
+    #[inline(always)]
+    fn set_cell(&mut self, cell: [isize; DIM]) {
+        if self.cell == Some(cell) {
+            return;
+        }
+
+        for (slot, hash) in self.hashes.iter_mut().enumerate() {
+            let mut point = cell;
+            let mut index = slot;
+            for coordinate in point.iter_mut() {
+                *coordinate += self.min + (index % Self::WIDTH) as isize;
+                index /= Self::WIDTH;
+            }
+            *hash = self.hasher.hash(&point);
+        }
+
+        self.cell = Some(cell);
+    }
+
+    #[inline(always)]
+    fn hash(&self, offset: [isize; DIM]) -> usize {
+        let mut slot = 0;
+        for &offset in offset.iter().rev() {
+            slot = slot * Self::WIDTH + (offset - self.min) as usize;
+        }
+        self.hashes[slot]
+    }
+}
+
 impl fmt::Debug for PermutationTable {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "PermutationTable {{ .. }}")
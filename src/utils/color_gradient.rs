@@ -78,7 +78,7 @@ impl ColorGradient {
         self.gradient_points
             .iter()
             .position(|x| x.pos >= pos)
-            .unwrap_or_else(|| self.gradient_points.len())
+            .unwrap_or(self.gradient_points.len())
     }
 
     pub fn clear_gradient(mut self) -> Self {
@@ -126,7 +126,7 @@ impl ColorGradient {
         let mut color = Color::default();
 
         // If there are no colors in the gradient, return black
-        return if self.gradient_points.is_empty() {
+        if self.gradient_points.is_empty() {
             color
         } else {
             match () {
@@ -146,7 +146,7 @@ impl ColorGradient {
             };
 
             color
-        };
+        }
     }
 }
 
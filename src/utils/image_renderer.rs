@@ -2,6 +2,8 @@ use crate::math::interpolate;
 use std::{self, f64::consts::SQRT_2};
 
 use super::{color_gradient::*, noise_image::*, noise_map::*};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 
 pub struct ImageRenderer {
     // The color gradient used to specify the image colors.
@@ -123,73 +125,105 @@ impl ImageRenderer {
 
         let mut destination_image = NoiseImage::new(width, height);
 
+        self.light_source.update_light_values();
+
         for y in 0..height {
             for x in 0..width {
-                let point = noise_map.get_value(x, y);
+                destination_image.set_value(x, y, self.render_pixel(noise_map, x, y));
+            }
+        }
 
-                let source_color = self.gradient.get_color(point);
+        destination_image
+    }
 
-                let mut light_intensity;
-
-                if self.light_enabled {
-                    let mut x_left_offset: isize = -1;
-                    let mut x_right_offset: isize = 1;
-                    let mut y_down_offset: isize = -1;
-                    let mut y_up_offset: isize = 1;
-
-                    if self.wrap_enabled {
-                        if x == 0 {
-                            x_left_offset = width as isize - 1;
-                            x_right_offset = 1;
-                        } else if x == (width as isize - 1) as usize {
-                            x_left_offset = -1;
-                            x_right_offset = width as isize - 1;
-                        }
-
-                        if y == 0 {
-                            y_down_offset = height as isize - 1;
-                            y_up_offset = 1;
-                        } else if y == (height as isize - 1) as usize {
-                            y_down_offset = -1;
-                            y_up_offset = height as isize - 1;
-                        }
-                    } else {
-                        if x == 0 {
-                            x_left_offset = 0;
-                            x_right_offset = 1;
-                        } else if x == (width as isize - 1) as usize {
-                            x_left_offset = -1;
-                            x_right_offset = 0;
-                        }
-
-                        if y == 0 {
-                            y_down_offset = 0;
-                            y_up_offset = 1;
-                        } else if y == (height as isize - 1) as usize {
-                            y_down_offset = -1;
-                            y_up_offset = 0;
-                        }
-                    }
-
-                    let pc = point;
-                    let pl = noise_map.get_value((x as isize + x_left_offset) as usize, y);
-                    let pr = noise_map.get_value((x as isize + x_right_offset) as usize, y);
-                    let pd = noise_map.get_value(x, (y as isize + y_down_offset) as usize);
-                    let pu = noise_map.get_value(x, (y as isize + y_up_offset) as usize);
-
-                    light_intensity = self.light_source.calc_light_intensity(pc, pl, pr, pd, pu);
-                    light_intensity *= self.light_source.brightness;
-                } else {
-                    light_intensity = 1.0;
+    /// Renders the image like [`render`](Self::render), but renders the rows
+    /// in parallel on the rayon thread pool. The result is identical.
+    #[cfg(feature = "rayon")]
+    pub fn render_parallel(&mut self, noise_map: &NoiseMap) -> NoiseImage {
+        let (width, height) = noise_map.size();
+
+        let mut destination_image = NoiseImage::new(width, height);
+        if width == 0 || height == 0 {
+            return destination_image;
+        }
+
+        self.light_source.update_light_values();
+
+        let renderer = &*self;
+        destination_image
+            .values_mut()
+            .par_chunks_exact_mut(width)
+            .enumerate()
+            .for_each(|(y, row)| {
+                for (x, color) in row.iter_mut().enumerate() {
+                    *color = renderer.render_pixel(noise_map, x, y);
                 }
+            });
 
-                let destination_color = self.calc_destination_color(source_color, light_intensity);
+        destination_image
+    }
 
-                destination_image.set_value(x, y, destination_color);
+    fn render_pixel(&self, noise_map: &NoiseMap, x: usize, y: usize) -> Color {
+        let source_color = self.gradient.get_color(noise_map.get_value(x, y));
+
+        let light_intensity = if self.light_enabled {
+            self.calc_light_intensity(noise_map, x, y)
+        } else {
+            1.0
+        };
+
+        self.calc_destination_color(source_color, light_intensity)
+    }
+
+    fn calc_light_intensity(&self, noise_map: &NoiseMap, x: usize, y: usize) -> f64 {
+        let (width, height) = noise_map.size();
+
+        let mut x_left_offset: isize = -1;
+        let mut x_right_offset: isize = 1;
+        let mut y_down_offset: isize = -1;
+        let mut y_up_offset: isize = 1;
+
+        if self.wrap_enabled {
+            if x == 0 {
+                x_left_offset = width as isize - 1;
+                x_right_offset = 1;
+            } else if x == (width as isize - 1) as usize {
+                x_left_offset = -1;
+                x_right_offset = width as isize - 1;
+            }
+
+            if y == 0 {
+                y_down_offset = height as isize - 1;
+                y_up_offset = 1;
+            } else if y == (height as isize - 1) as usize {
+                y_down_offset = -1;
+                y_up_offset = height as isize - 1;
+            }
+        } else {
+            if x == 0 {
+                x_left_offset = 0;
+                x_right_offset = 1;
+            } else if x == (width as isize - 1) as usize {
+                x_left_offset = -1;
+                x_right_offset = 0;
+            }
+
+            if y == 0 {
+                y_down_offset = 0;
+                y_up_offset = 1;
+            } else if y == (height as isize - 1) as usize {
+                y_down_offset = -1;
+                y_up_offset = 0;
             }
         }
 
-        destination_image
+        let pc = noise_map.get_value(x, y);
+        let pl = noise_map.get_value((x as isize + x_left_offset) as usize, y);
+        let pr = noise_map.get_value((x as isize + x_right_offset) as usize, y);
+        let pd = noise_map.get_value(x, (y as isize + y_down_offset) as usize);
+        let pu = noise_map.get_value(x, (y as isize + y_up_offset) as usize);
+
+        self.light_source.calc_light_intensity(pc, pl, pr, pd, pu) * self.light_source.brightness
     }
 
     fn calc_destination_color(&self, source_color: Color, light_value: f64) -> Color {
@@ -212,9 +246,9 @@ impl ImageRenderer {
         }
 
         // Clamp color channels to [0..1]
-        red = red.max(0.0).min(1.0);
-        green = green.max(0.0).min(1.0);
-        blue = blue.max(0.0).min(1.0);
+        red = red.clamp(0.0, 1.0);
+        green = green.clamp(0.0, 1.0);
+        blue = blue.clamp(0.0, 1.0);
 
         // Rescale color channels to u8 [0..255] and return the final color
         [
@@ -235,64 +269,18 @@ impl ImageRenderer {
 
         let mut destination_image = NoiseImage::new(width, height);
 
+        self.light_source.update_light_values();
+
         for y in 0..height {
             for x in 0..width {
                 let point = noise_map.get_value(x, y);
                 let source_color = self.gradient.get_color(point);
 
-                let mut light_intensity;
-
-                if self.light_enabled {
-                    let mut x_left_offset: isize = -1;
-                    let mut x_right_offset: isize = 1;
-                    let mut y_down_offset: isize = -1;
-                    let mut y_up_offset: isize = 1;
-
-                    if self.wrap_enabled {
-                        if x == 0 {
-                            x_left_offset = width as isize - 1;
-                            x_right_offset = 1;
-                        } else if x == (width as isize - 1) as usize {
-                            x_left_offset = -1;
-                            x_right_offset = width as isize - 1;
-                        }
-
-                        if y == 0 {
-                            y_down_offset = height as isize - 1;
-                            y_up_offset = 1;
-                        } else if y == (height as isize - 1) as usize {
-                            y_down_offset = -1;
-                            y_up_offset = height as isize - 1;
-                        }
-                    } else {
-                        if x == 0 {
-                            x_left_offset = 0;
-                            x_right_offset = 1;
-                        } else if x == (width as isize - 1) as usize {
-                            x_left_offset = -1;
-                            x_right_offset = 0;
-                        }
-
-                        if y == 0 {
-                            y_down_offset = 0;
-                            y_up_offset = 1;
-                        } else if y == (height as isize - 1) as usize {
-                            y_down_offset = -1;
-                            y_up_offset = 0;
-                        }
-                    }
-
-                    let pc = point;
-                    let pl = noise_map.get_value((x as isize + x_left_offset) as usize, y);
-                    let pr = noise_map.get_value((x as isize + x_right_offset) as usize, y);
-                    let pd = noise_map.get_value(x, (y as isize + y_down_offset) as usize);
-                    let pu = noise_map.get_value(x, (y as isize + y_up_offset) as usize);
-
-                    light_intensity = self.light_source.calc_light_intensity(pc, pl, pr, pd, pu);
-                    light_intensity *= self.light_source.brightness;
+                let light_intensity = if self.light_enabled {
+                    self.calc_light_intensity(noise_map, x, y)
                 } else {
-                    light_intensity = 1.0;
-                }
+                    1.0
+                };
 
                 let background_color = background.get_value(x, y);
 
@@ -336,9 +324,9 @@ impl ImageRenderer {
         }
 
         // Clamp color channels to [0..1]
-        red = red.max(0.0).min(1.0);
-        green = green.max(0.0).min(1.0);
-        blue = blue.max(0.0).min(1.0);
+        red = red.clamp(0.0, 1.0);
+        green = green.clamp(0.0, 1.0);
+        blue = blue.clamp(0.0, 1.0);
 
         // Rescale color channels to u8 [0..255] and return the final color
         [
@@ -388,11 +376,11 @@ pub struct LightSource {
     // The sine of the elevation of the light source.
     elevation_sine: f64,
 
-    // Used by the calc_light_intensity method to recalculate the light values
+    // Used by the update_light_values method to recalculate the light values
     // only if the light parameters change.
     //
     // When the light parameters change, this value is set to True. When the
-    // calc_light_intensity method is called, this value is set to false.
+    // update_light_values method is called, this value is set to false.
     recalculate_light_values: bool,
 }
 
@@ -446,16 +434,9 @@ impl LightSource {
         self.recalculate_light_values = true;
     }
 
-    fn calc_light_intensity(
-        &mut self,
-        _center: f64,
-        left: f64,
-        right: f64,
-        down: f64,
-        up: f64,
-    ) -> f64 {
-        // Recalculate the sine and cosine of the various light values if necessary so it does not
-        // have to be calculated each time this method is called.
+    // Recalculate the sine and cosine of the various light values if necessary so they do not
+    // have to be calculated for each pixel.
+    fn update_light_values(&mut self) {
         if self.recalculate_light_values {
             self.azimuth_cosine = self.azimuth.to_radians().cos();
             self.azimuth_sine = self.azimuth.to_radians().sin();
@@ -464,7 +445,9 @@ impl LightSource {
 
             self.recalculate_light_values = false;
         }
+    }
 
+    fn calc_light_intensity(&self, _center: f64, left: f64, right: f64, down: f64, up: f64) -> f64 {
         let i_max = 1.0;
 
         let io = i_max * SQRT_2 * self.elevation_sine / 2.0;
@@ -502,4 +485,28 @@ mod tests {
         assert_eq!([0.0; 4], u8_array_to_f64_array([0; 4]));
         assert_eq!([1.0; 4], u8_array_to_f64_array([255; 4]));
     }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn render_parallel() {
+        use crate::{utils::*, Fbm};
+
+        let fbm = Fbm::new();
+        let noise_map = PlaneMapBuilder::new(&fbm).set_size(40, 30).build();
+
+        let mut renderer = ImageRenderer::new()
+            .set_gradient(ColorGradient::new().build_terrain_gradient())
+            .enable_wrap()
+            .set_light_azimuth(30.0);
+        renderer.enable_light();
+
+        let serial = renderer.render(&noise_map);
+        let parallel = renderer.render_parallel(&noise_map);
+
+        for y in 0..30 {
+            for x in 0..40 {
+                assert_eq!(serial.get_value(x, y), parallel.get_value(x, y));
+            }
+        }
+    }
 }
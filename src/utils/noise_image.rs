@@ -63,6 +63,14 @@ impl NoiseImage {
         }
     }
 
+    /// Returns the colors of the image as a slice, one row after another.
+    #[cfg(all(feature = "image", feature = "rayon"))]
+    pub(crate) fn values_mut(&mut self) -> &mut [Color] {
+        let (width, height) = self.size;
+
+        &mut self.map[..width * height]
+    }
+
     pub fn size(&self) -> (usize, usize) {
         self.size
     }
@@ -113,8 +121,8 @@ impl NoiseImage {
         }
 
         let _ = image::save_buffer(
-            &Path::new(&file_path),
-            &*result,
+            Path::new(&file_path),
+            &result,
             self.size.0 as u32,
             self.size.1 as u32,
             image::ColorType::Rgba8,
@@ -70,6 +70,13 @@ impl NoiseMap {
         }
     }
 
+    /// Returns the values of the map as a slice, one row after another.
+    pub(crate) fn values_mut(&mut self) -> &mut [f64] {
+        let (width, height) = self.size;
+
+        &mut self.map[..width * height]
+    }
+
     pub fn get_value(&self, x: usize, y: usize) -> f64 {
         let (width, height) = self.size;
 
@@ -102,8 +109,8 @@ impl NoiseMap {
         }
 
         let _ = image::save_buffer(
-            &Path::new(&file_path),
-            &*pixels,
+            Path::new(&file_path),
+            &pixels,
             self.size.0 as u32,
             self.size.1 as u32,
             image::ColorType::L8,
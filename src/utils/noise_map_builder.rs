@@ -1,24 +1,40 @@
 use crate::{math::interpolate, noise_fns::NoiseFn, utils::noise_map::NoiseMap};
-
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/// Builds a [`NoiseMap`] by sampling a source module over some surface.
+///
+/// The builders borrow their source module, which is a `dyn NoiseFn<f64, 3>`
+/// unless a more specific type is given. With the `rayon` feature enabled,
+/// each builder also has a `build_parallel` method, which builds the rows of
+/// the map on the rayon thread pool and gives exactly the same result as
+/// `build`.
+///
+/// Every sample is taken with [`NoiseFn::get_batch_filtered`], using the
+/// distance between neighbouring pixels on the surface as the filter width, so
+/// fractals leave out the octaves that are too fine for the map's resolution
+/// instead of aliasing.
 pub trait NoiseMapBuilder<'a> {
+    type Source: ?Sized;
+
     fn set_size(self, width: usize, height: usize) -> Self;
 
-    fn set_source_module(self, source_module: &'a dyn NoiseFn<f64, 3>) -> Self;
+    fn set_source_module(self, source_module: &'a Self::Source) -> Self;
 
     fn size(&self) -> (usize, usize);
 
     fn build(&self) -> NoiseMap;
 }
 
-pub struct CylinderMapBuilder<'a> {
+pub struct CylinderMapBuilder<'a, Source: ?Sized = dyn NoiseFn<f64, 3> + 'a> {
     angle_bounds: (f64, f64),
     height_bounds: (f64, f64),
     size: (usize, usize),
-    source_module: &'a dyn NoiseFn<f64, 3>,
+    source_module: &'a Source,
 }
 
-impl<'a> CylinderMapBuilder<'a> {
-    pub fn new(source_module: &'a dyn NoiseFn<f64, 3>) -> Self {
+impl<'a, Source: ?Sized> CylinderMapBuilder<'a, Source> {
+    pub fn new(source_module: &'a Source) -> Self {
         CylinderMapBuilder {
             angle_bounds: (-90.0, 90.0),
             height_bounds: (-1.0, 1.0),
@@ -70,7 +86,12 @@ impl<'a> CylinderMapBuilder<'a> {
     }
 }
 
-impl<'a> NoiseMapBuilder<'a> for CylinderMapBuilder<'a> {
+impl<'a, Source> NoiseMapBuilder<'a> for CylinderMapBuilder<'a, Source>
+where
+    Source: NoiseFn<f64, 3> + ?Sized,
+{
+    type Source = Source;
+
     fn set_size(self, width: usize, height: usize) -> Self {
         CylinderMapBuilder {
             size: (width, height),
@@ -78,7 +99,7 @@ impl<'a> NoiseMapBuilder<'a> for CylinderMapBuilder<'a> {
         }
     }
 
-    fn set_source_module(self, source_module: &'a dyn NoiseFn<f64, 3>) -> Self {
+    fn set_source_module(self, source_module: &'a Source) -> Self {
         CylinderMapBuilder {
             source_module,
             ..self
@@ -90,8 +111,25 @@ impl<'a> NoiseMapBuilder<'a> for CylinderMapBuilder<'a> {
     }
 
     fn build(&self) -> NoiseMap {
-        let mut result_map = NoiseMap::new(self.size.0, self.size.1);
+        build_rows(self.size, |y, points, row| self.build_row(y, points, row))
+    }
+}
+
+impl<'a, Source> CylinderMapBuilder<'a, Source>
+where
+    Source: NoiseFn<f64, 3> + ?Sized,
+{
+    /// Builds the map like [`build`](NoiseMapBuilder::build), but builds the
+    /// rows in parallel on the rayon thread pool.
+    #[cfg(feature = "rayon")]
+    pub fn build_parallel(&self) -> NoiseMap
+    where
+        Source: Sync,
+    {
+        build_rows_parallel(self.size, |y, points, row| self.build_row(y, points, row))
+    }
 
+    fn build_row(&self, y: usize, points: &mut Vec<[f64; 3]>, row: &mut [f64]) {
         let (width, height) = self.size;
 
         let angle_extent = self.angle_bounds.1 - self.angle_bounds.0;
@@ -100,40 +138,37 @@ impl<'a> NoiseMapBuilder<'a> for CylinderMapBuilder<'a> {
         let x_step = angle_extent / width as f64;
         let y_step = height_extent / height as f64;
 
-        for y in 0..height {
-            let current_height = self.height_bounds.0 + y_step * y as f64;
-
-            for x in 0..width {
-                let current_angle = self.angle_bounds.0 + x_step * x as f64;
+        let current_height = self.height_bounds.0 + y_step * y as f64;
 
-                let point_x = current_angle.to_radians().cos();
-                let point_z = current_angle.to_radians().sin();
+        // The cylinder has a radius of 1, so an angle in radians is also the
+        // distance around it.
+        let footprint = x_step.to_radians().abs().max(y_step.abs());
 
-                let value = self.source_module.get([point_x, current_height, point_z]);
+        points.clear();
+        points.extend((0..width).map(|x| {
+            let current_angle = self.angle_bounds.0 + x_step * x as f64;
 
-                println!(
-                    "calculated value {} at {}, {}, {}",
-                    value, point_x, current_height, point_z
-                );
+            let point_x = current_angle.to_radians().cos();
+            let point_z = current_angle.to_radians().sin();
 
-                result_map.set_value(x, y, value);
-            }
-        }
+            [point_x, current_height, point_z]
+        }));
 
-        result_map
+        self.source_module
+            .get_batch_filtered(points, footprint, row);
     }
 }
 
-pub struct PlaneMapBuilder<'a> {
+pub struct PlaneMapBuilder<'a, Source: ?Sized = dyn NoiseFn<f64, 3> + 'a> {
     is_seamless: bool,
     x_bounds: (f64, f64),
     y_bounds: (f64, f64),
     size: (usize, usize),
-    source_module: &'a dyn NoiseFn<f64, 3>,
+    source_module: &'a Source,
 }
 
-impl<'a> PlaneMapBuilder<'a> {
-    pub fn new(source_module: &'a dyn NoiseFn<f64, 3>) -> Self {
+impl<'a, Source: ?Sized> PlaneMapBuilder<'a, Source> {
+    pub fn new(source_module: &'a Source) -> Self {
         PlaneMapBuilder {
             is_seamless: false,
             x_bounds: (-1.0, 1.0),
@@ -143,6 +178,25 @@ impl<'a> PlaneMapBuilder<'a> {
         }
     }
 
+    /// Makes the map tile by blending each sample with the samples one extent
+    /// to the east, north and north-east of it. The blend is linear, so it
+    /// visibly reduces the contrast in the middle of the map.
+    ///
+    /// For a map that tiles exactly, give the source a
+    /// [period](crate::Periodic::set_period) instead, and set the bounds to
+    /// span a whole number of periods:
+    ///
+    /// ```
+    /// use noise::{utils::*, Fbm, MultiFractal, Periodic, Perlin};
+    ///
+    /// // With a frequency of 1, each octave repeats every 4 units.
+    /// let fbm = Fbm::<Perlin>::new().set_lacunarity(2.0).set_period([4, 4]);
+    ///
+    /// let map = PlaneMapBuilder::new(&fbm)
+    ///     .set_x_bounds(0.0, 4.0)
+    ///     .set_y_bounds(0.0, 4.0)
+    ///     .build();
+    /// ```
     pub fn set_is_seamless(self, is_seamless: bool) -> Self {
         PlaneMapBuilder {
             is_seamless,
@@ -173,7 +227,12 @@ impl<'a> PlaneMapBuilder<'a> {
     }
 }
 
-impl<'a> NoiseMapBuilder<'a> for PlaneMapBuilder<'a> {
+impl<'a, Source> NoiseMapBuilder<'a> for PlaneMapBuilder<'a, Source>
+where
+    Source: NoiseFn<f64, 3> + ?Sized,
+{
+    type Source = Source;
+
     fn set_size(self, width: usize, height: usize) -> Self {
         PlaneMapBuilder {
             size: (width, height),
@@ -181,7 +240,7 @@ impl<'a> NoiseMapBuilder<'a> for PlaneMapBuilder<'a> {
         }
     }
 
-    fn set_source_module(self, source_module: &'a dyn NoiseFn<f64, 3>) -> Self {
+    fn set_source_module(self, source_module: &'a Source) -> Self {
         PlaneMapBuilder {
             source_module,
             ..self
@@ -193,9 +252,26 @@ impl<'a> NoiseMapBuilder<'a> for PlaneMapBuilder<'a> {
     }
 
     fn build(&self) -> NoiseMap {
-        let (width, height) = self.size;
+        build_rows(self.size, |y, points, row| self.build_row(y, points, row))
+    }
+}
 
-        let mut result_map = NoiseMap::new(width, height);
+impl<'a, Source> PlaneMapBuilder<'a, Source>
+where
+    Source: NoiseFn<f64, 3> + ?Sized,
+{
+    /// Builds the map like [`build`](NoiseMapBuilder::build), but builds the
+    /// rows in parallel on the rayon thread pool.
+    #[cfg(feature = "rayon")]
+    pub fn build_parallel(&self) -> NoiseMap
+    where
+        Source: Sync,
+    {
+        build_rows_parallel(self.size, |y, points, row| self.build_row(y, points, row))
+    }
+
+    fn build_row(&self, y: usize, points: &mut Vec<[f64; 3]>, row: &mut [f64]) {
+        let (width, height) = self.size;
 
         let x_extent = self.x_bounds.1 - self.x_bounds.0;
         let y_extent = self.y_bounds.1 - self.y_bounds.0;
@@ -203,52 +279,61 @@ impl<'a> NoiseMapBuilder<'a> for PlaneMapBuilder<'a> {
         let x_step = x_extent / width as f64;
         let y_step = y_extent / height as f64;
 
-        for y in 0..height {
-            let current_y = self.y_bounds.0 + y_step * y as f64;
+        let current_y = self.y_bounds.0 + y_step * y as f64;
+        let footprint = x_step.abs().max(y_step.abs());
 
-            for x in 0..width {
+        let fill_points = |points: &mut Vec<[f64; 3]>, x_offset: f64, y_offset: f64| {
+            points.clear();
+            points.extend((0..width).map(|x| {
+                let current_x = self.x_bounds.0 + x_step * x as f64;
+                [current_x + x_offset, current_y + y_offset, 0.0]
+            }));
+        };
+
+        fill_points(points, 0.0, 0.0);
+        self.source_module
+            .get_batch_filtered(points, footprint, row);
+
+        if self.is_seamless {
+            // The seamless blend needs three more samples per pixel, offset by one extent.
+            let mut se_row = vec![0.0; width];
+            let mut nw_row = vec![0.0; width];
+            let mut ne_row = vec![0.0; width];
+
+            fill_points(points, x_extent, 0.0);
+            self.source_module
+                .get_batch_filtered(points, footprint, &mut se_row);
+            fill_points(points, 0.0, y_extent);
+            self.source_module
+                .get_batch_filtered(points, footprint, &mut nw_row);
+            fill_points(points, x_extent, y_extent);
+            self.source_module
+                .get_batch_filtered(points, footprint, &mut ne_row);
+
+            let y_blend = 1.0 - ((current_y - self.y_bounds.0) / y_extent);
+
+            for (x, value) in row.iter_mut().enumerate() {
                 let current_x = self.x_bounds.0 + x_step * x as f64;
+                let x_blend = 1.0 - ((current_x - self.x_bounds.0) / x_extent);
 
-                let final_value = if self.is_seamless {
-                    let sw_value = self.source_module.get([current_x, current_y, 0.0]);
-                    let se_value = self
-                        .source_module
-                        .get([current_x + x_extent, current_y, 0.0]);
-                    let nw_value = self
-                        .source_module
-                        .get([current_x, current_y + y_extent, 0.0]);
-                    let ne_value =
-                        self.source_module
-                            .get([current_x + x_extent, current_y + y_extent, 0.0]);
-
-                    let x_blend = 1.0 - ((current_x - self.x_bounds.0) / x_extent);
-                    let y_blend = 1.0 - ((current_y - self.y_bounds.0) / y_extent);
-
-                    let y0 = interpolate::linear(sw_value, se_value, x_blend);
-                    let y1 = interpolate::linear(nw_value, ne_value, x_blend);
-
-                    interpolate::linear(y0, y1, y_blend)
-                } else {
-                    self.source_module.get([current_x, current_y, 0.0])
-                };
-
-                result_map.set_value(x, y, final_value);
+                let y0 = interpolate::linear(*value, se_row[x], x_blend);
+                let y1 = interpolate::linear(nw_row[x], ne_row[x], x_blend);
+
+                *value = interpolate::linear(y0, y1, y_blend);
             }
         }
-
-        result_map
     }
 }
 
-pub struct SphereMapBuilder<'a> {
+pub struct SphereMapBuilder<'a, Source: ?Sized = dyn NoiseFn<f64, 3> + 'a> {
     latitude_bounds: (f64, f64),
     longitude_bounds: (f64, f64),
     size: (usize, usize),
-    source_module: &'a dyn NoiseFn<f64, 3>,
+    source_module: &'a Source,
 }
 
-impl<'a> SphereMapBuilder<'a> {
-    pub fn new(source_module: &'a dyn NoiseFn<f64, 3>) -> Self {
+impl<'a, Source: ?Sized> SphereMapBuilder<'a, Source> {
+    pub fn new(source_module: &'a Source) -> Self {
         SphereMapBuilder {
             latitude_bounds: (-1.0, 1.0),
             longitude_bounds: (-1.0, 1.0),
@@ -294,7 +379,12 @@ impl<'a> SphereMapBuilder<'a> {
     }
 }
 
-impl<'a> NoiseMapBuilder<'a> for SphereMapBuilder<'a> {
+impl<'a, Source> NoiseMapBuilder<'a> for SphereMapBuilder<'a, Source>
+where
+    Source: NoiseFn<f64, 3> + ?Sized,
+{
+    type Source = Source;
+
     fn set_size(self, width: usize, height: usize) -> Self {
         SphereMapBuilder {
             size: (width, height),
@@ -302,7 +392,7 @@ impl<'a> NoiseMapBuilder<'a> for SphereMapBuilder<'a> {
         }
     }
 
-    fn set_source_module(self, source_module: &'a dyn NoiseFn<f64, 3>) -> Self {
+    fn set_source_module(self, source_module: &'a Source) -> Self {
         SphereMapBuilder {
             source_module,
             ..self
@@ -314,9 +404,26 @@ impl<'a> NoiseMapBuilder<'a> for SphereMapBuilder<'a> {
     }
 
     fn build(&self) -> NoiseMap {
-        let (width, height) = self.size;
+        build_rows(self.size, |y, points, row| self.build_row(y, points, row))
+    }
+}
+
+impl<'a, Source> SphereMapBuilder<'a, Source>
+where
+    Source: NoiseFn<f64, 3> + ?Sized,
+{
+    /// Builds the map like [`build`](NoiseMapBuilder::build), but builds the
+    /// rows in parallel on the rayon thread pool.
+    #[cfg(feature = "rayon")]
+    pub fn build_parallel(&self) -> NoiseMap
+    where
+        Source: Sync,
+    {
+        build_rows_parallel(self.size, |y, points, row| self.build_row(y, points, row))
+    }
 
-        let mut result_map = NoiseMap::new(width, height);
+    fn build_row(&self, y: usize, points: &mut Vec<[f64; 3]>, row: &mut [f64]) {
+        let (width, height) = self.size;
 
         let lon_extent = self.longitude_bounds.1 - self.longitude_bounds.0;
         let lat_extent = self.latitude_bounds.1 - self.latitude_bounds.0;
@@ -324,20 +431,73 @@ impl<'a> NoiseMapBuilder<'a> for SphereMapBuilder<'a> {
         let x_step = lon_extent / width as f64;
         let y_step = lat_extent / height as f64;
 
-        for y in 0..height {
-            let current_lat = self.latitude_bounds.0 + y_step * y as f64;
+        let current_lat = self.latitude_bounds.0 + y_step * y as f64;
 
-            for x in 0..width {
-                let current_lon = self.longitude_bounds.0 + x_step * x as f64;
+        // The sphere has a radius of 1, so an angle in radians is also the
+        // distance along it, and the circles of latitude shrink towards the
+        // poles.
+        let footprint = y_step
+            .to_radians()
+            .abs()
+            .max((x_step.to_radians() * current_lat.to_radians().cos()).abs());
 
-                let point = lat_lon_to_xyz(current_lat, current_lon);
+        points.clear();
+        points.extend((0..width).map(|x| {
+            let current_lon = self.longitude_bounds.0 + x_step * x as f64;
 
-                result_map.set_value(x, y, self.source_module.get(point));
-            }
-        }
+            lat_lon_to_xyz(current_lat, current_lon)
+        }));
+
+        self.source_module
+            .get_batch_filtered(points, footprint, row);
+    }
+}
+
+/// Builds a map of the given size one row at a time, with `build_row` filling
+/// in each row from its index and a reusable buffer for its points.
+fn build_rows<F>(size: (usize, usize), build_row: F) -> NoiseMap
+where
+    F: Fn(usize, &mut Vec<[f64; 3]>, &mut [f64]),
+{
+    let (width, height) = size;
+
+    let mut result_map = NoiseMap::new(width, height);
+    if width == 0 || height == 0 {
+        return result_map;
+    }
 
-        result_map
+    let mut points = Vec::with_capacity(width);
+    for (y, row) in result_map.values_mut().chunks_exact_mut(width).enumerate() {
+        build_row(y, &mut points, row);
     }
+
+    result_map
+}
+
+/// Like [`build_rows`], but builds the rows in parallel. Each row is filled in
+/// exactly as it would be by `build_rows`, so the results are identical.
+#[cfg(feature = "rayon")]
+fn build_rows_parallel<F>(size: (usize, usize), build_row: F) -> NoiseMap
+where
+    F: Fn(usize, &mut Vec<[f64; 3]>, &mut [f64]) + Sync,
+{
+    let (width, height) = size;
+
+    let mut result_map = NoiseMap::new(width, height);
+    if width == 0 || height == 0 {
+        return result_map;
+    }
+
+    result_map
+        .values_mut()
+        .par_chunks_exact_mut(width)
+        .enumerate()
+        .for_each_init(
+            || Vec::with_capacity(width),
+            |points, (y, row)| build_row(y, points, row),
+        );
+
+    result_map
 }
 
 fn lat_lon_to_xyz(lat: f64, lon: f64) -> [f64; 3] {
@@ -348,3 +508,39 @@ fn lat_lon_to_xyz(lat: f64, lon: f64) -> [f64; 3] {
 
     [x, y, z]
 }
+
+#[cfg(all(test, feature = "rayon"))]
+mod tests {
+    use super::*;
+    use crate::{Fbm, Worley};
+
+    fn assert_maps_eq(a: &NoiseMap, b: &NoiseMap) {
+        let (width, height) = a.size();
+        assert_eq!(a.size(), b.size());
+
+        for y in 0..height {
+            for x in 0..width {
+                assert_eq!(a.get_value(x, y).to_bits(), b.get_value(x, y).to_bits());
+            }
+        }
+    }
+
+    #[test]
+    fn test_build_parallel_matches_build() {
+        let fbm = Fbm::new();
+        let worley = Worley::new(3);
+
+        let plane = PlaneMapBuilder::new(&fbm)
+            .set_size(67, 41)
+            .set_is_seamless(true);
+        assert_maps_eq(&plane.build(), &plane.build_parallel());
+
+        let cylinder = CylinderMapBuilder::new(&worley).set_size(33, 20);
+        assert_maps_eq(&cylinder.build(), &cylinder.build_parallel());
+
+        let sphere = SphereMapBuilder::new(&fbm)
+            .set_size(50, 25)
+            .set_bounds(-90.0, 90.0, -180.0, 180.0);
+        assert_maps_eq(&sphere.build(), &sphere.build_parallel());
+    }
+}